@@ -40,6 +40,11 @@ pub struct BangumiSource {
     pub flat_folder: bool,
     pub download_danmaku: bool,
     pub download_subtitle: bool,
+    pub download_speed_limit: i32,
+    pub postprocess_profile: String,
+    pub output_container: String,
+    pub audio_format: String,
+    pub audio_normalize: bool,
     pub ai_rename: bool,
     pub ai_rename_video_prompt: String,
     pub ai_rename_audio_prompt: String,
@@ -539,6 +544,26 @@ impl VideoSource for BangumiSource {
         self.download_subtitle
     }
 
+    fn download_speed_limit(&self) -> u64 {
+        self.download_speed_limit.max(0) as u64
+    }
+
+    fn postprocess_profile(&self) -> &str {
+        &self.postprocess_profile
+    }
+
+    fn output_container(&self) -> &str {
+        &self.output_container
+    }
+
+    fn audio_format(&self) -> &str {
+        &self.audio_format
+    }
+
+    fn audio_normalize(&self) -> bool {
+        self.audio_normalize
+    }
+
     fn ai_rename(&self) -> bool {
         self.ai_rename
     }
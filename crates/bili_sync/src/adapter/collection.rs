@@ -133,6 +133,26 @@ impl VideoSource for collection::Model {
         self.download_subtitle
     }
 
+    fn download_speed_limit(&self) -> u64 {
+        self.download_speed_limit.max(0) as u64
+    }
+
+    fn postprocess_profile(&self) -> &str {
+        &self.postprocess_profile
+    }
+
+    fn output_container(&self) -> &str {
+        &self.output_container
+    }
+
+    fn audio_format(&self) -> &str {
+        &self.audio_format
+    }
+
+    fn audio_normalize(&self) -> bool {
+        self.audio_normalize
+    }
+
     fn ai_rename(&self) -> bool {
         self.ai_rename
     }
@@ -179,6 +199,7 @@ pub(super) async fn collection_from<'a>(
         r#type: Set(collection_info.collection_type.into()),
         name: Set(collection_info.name.clone()),
         path: Set(path.to_string_lossy().to_string()),
+        description: Set(Some(collection_info.description.clone()).filter(|description| !description.is_empty())),
         created_at: Set(crate::utils::time_format::now_standard_string()),
         latest_row_at: Set("1970-01-01 00:00:00".to_string()),
         enabled: Set(true),
@@ -191,7 +212,11 @@ pub(super) async fn collection_from<'a>(
             collection::Column::MId,
             collection::Column::Type,
         ])
-        .update_columns([collection::Column::Name, collection::Column::Path])
+        .update_columns([
+            collection::Column::Name,
+            collection::Column::Path,
+            collection::Column::Description,
+        ])
         .to_owned(),
     )
     .exec(connection)
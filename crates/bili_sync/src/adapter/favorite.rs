@@ -137,6 +137,26 @@ impl VideoSource for favorite::Model {
         self.download_subtitle
     }
 
+    fn download_speed_limit(&self) -> u64 {
+        self.download_speed_limit.max(0) as u64
+    }
+
+    fn postprocess_profile(&self) -> &str {
+        &self.postprocess_profile
+    }
+
+    fn output_container(&self) -> &str {
+        &self.output_container
+    }
+
+    fn audio_format(&self) -> &str {
+        &self.audio_format
+    }
+
+    fn audio_normalize(&self) -> bool {
+        self.audio_normalize
+    }
+
     fn ai_rename(&self) -> bool {
         self.ai_rename
     }
@@ -181,6 +201,8 @@ pub(super) async fn favorite_from<'a>(
         f_id: Set(favorite_info.id),
         name: Set(favorite_info.title.clone()),
         path: Set(path.to_string_lossy().to_string()),
+        description: Set(Some(favorite_info.intro.clone()).filter(|intro| !intro.is_empty())),
+        cover: Set(Some(favorite_info.cover.clone()).filter(|cover| !cover.is_empty())),
         created_at: Set(now_standard_string()),
         latest_row_at: Set("1970-01-01 00:00:00".to_string()),
         enabled: Set(true),
@@ -189,7 +211,12 @@ pub(super) async fn favorite_from<'a>(
     })
     .on_conflict(
         OnConflict::column(favorite::Column::FId)
-            .update_columns([favorite::Column::Name, favorite::Column::Path])
+            .update_columns([
+                favorite::Column::Name,
+                favorite::Column::Path,
+                favorite::Column::Description,
+                favorite::Column::Cover,
+            ])
             .to_owned(),
     )
     .exec(connection)
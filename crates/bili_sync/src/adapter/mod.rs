@@ -166,6 +166,31 @@ pub trait VideoSource {
         true // 默认实现：下载字幕
     }
 
+    /// 获取视频源的下载限速（KB/s），0 表示不单独限速
+    fn download_speed_limit(&self) -> u64 {
+        0
+    }
+
+    /// 获取下载完成后使用的后处理配置名称，空字符串表示不处理
+    fn postprocess_profile(&self) -> &str {
+        ""
+    }
+
+    /// 获取输出容器格式，mkv 时会把字幕、弹幕和封面封装进视频文件
+    fn output_container(&self) -> &str {
+        "mp4"
+    }
+
+    /// 获取仅音频模式的输出格式，m4a 表示保留下载的原始音频
+    fn audio_format(&self) -> &str {
+        "m4a"
+    }
+
+    /// 仅音频模式是否进行响度标准化
+    fn audio_normalize(&self) -> bool {
+        false
+    }
+
     /// 获取是否启用AI重命名（默认为 false）
     fn ai_rename(&self) -> bool {
         false // 默认实现：不启用AI重命名
@@ -276,6 +301,90 @@ impl _ActiveModel {
     }
 }
 
+/// 由数据库中的番剧源记录构建番剧视频源
+fn bangumi_source_from_model(model: bili_sync_entity::video_source::Model, path: &Path) -> BangumiSource {
+    // 解析 selected_seasons JSON 字符串
+    let selected_seasons = if let Some(json_str) = &model.selected_seasons {
+        serde_json::from_str::<Vec<String>>(json_str).ok()
+    } else {
+        None
+    };
+
+    BangumiSource {
+        id: model.id,
+        name: model.name,
+        latest_row_at: model.latest_row_at,
+        season_id: model.season_id,
+        media_id: model.media_id,
+        ep_id: model.ep_id,
+        path: path.to_path_buf(),
+        download_all_seasons: model.download_all_seasons.unwrap_or(false),
+        page_name_template: model.page_name_template,
+        selected_seasons,
+        scan_deleted_videos: model.scan_deleted_videos,
+        keyword_filters: model.keyword_filters,
+        keyword_filter_mode: model.keyword_filter_mode,
+        blacklist_keywords: model.blacklist_keywords,
+        whitelist_keywords: model.whitelist_keywords,
+        keyword_case_sensitive: model.keyword_case_sensitive,
+        audio_only: model.audio_only,
+        audio_only_m4a_only: model.audio_only_m4a_only,
+        flat_folder: model.flat_folder,
+        download_danmaku: model.download_danmaku,
+        download_subtitle: model.download_subtitle,
+        download_speed_limit: model.download_speed_limit,
+        postprocess_profile: model.postprocess_profile,
+        output_container: model.output_container,
+        audio_format: model.audio_format,
+        audio_normalize: model.audio_normalize,
+        ai_rename: model.ai_rename,
+        ai_rename_video_prompt: model.ai_rename_video_prompt,
+        ai_rename_audio_prompt: model.ai_rename_audio_prompt,
+        ai_rename_enable_multi_page: model.ai_rename_enable_multi_page,
+        ai_rename_enable_collection: model.ai_rename_enable_collection,
+        ai_rename_enable_bangumi: model.ai_rename_enable_bangumi,
+    }
+}
+
+/// 根据视频记录找到其所属的视频源，仅读取数据库，不请求视频列表
+pub async fn video_source_of(
+    video: &bili_sync_entity::video::Model,
+    connection: &DatabaseConnection,
+) -> Result<Option<VideoSourceEnum>> {
+    let source = if let Some(id) = video.collection_id {
+        bili_sync_entity::collection::Entity::find_by_id(id)
+            .one(connection)
+            .await?
+            .map(VideoSourceEnum::from)
+    } else if let Some(id) = video.favorite_id {
+        bili_sync_entity::favorite::Entity::find_by_id(id)
+            .one(connection)
+            .await?
+            .map(VideoSourceEnum::from)
+    } else if let Some(id) = video.submission_id {
+        bili_sync_entity::submission::Entity::find_by_id(id)
+            .one(connection)
+            .await?
+            .map(VideoSourceEnum::from)
+    } else if let Some(id) = video.watch_later_id {
+        bili_sync_entity::watch_later::Entity::find_by_id(id)
+            .one(connection)
+            .await?
+            .map(VideoSourceEnum::from)
+    } else if let Some(id) = video.source_id {
+        bili_sync_entity::video_source::Entity::find_by_id(id)
+            .one(connection)
+            .await?
+            .map(|model| {
+                let path = std::path::PathBuf::from(&model.path);
+                VideoSourceEnum::from(bangumi_source_from_model(model, &path))
+            })
+    } else {
+        None
+    };
+    Ok(source)
+}
+
 pub async fn bangumi_from<'a>(
     season_id: &Option<String>,
     media_id: &Option<String>,
@@ -309,42 +418,7 @@ pub async fn bangumi_from<'a>(
 
     // 如果数据库中存在，则使用数据库中的ID；否则使用默认ID
     let bangumi_source = if let Some(model) = bangumi_model {
-        // 解析 selected_seasons JSON 字符串
-        let selected_seasons = if let Some(json_str) = &model.selected_seasons {
-            serde_json::from_str::<Vec<String>>(json_str).ok()
-        } else {
-            None
-        };
-
-        BangumiSource {
-            id: model.id,
-            name: model.name,
-            latest_row_at: model.latest_row_at,
-            season_id: model.season_id,
-            media_id: model.media_id,
-            ep_id: model.ep_id,
-            path: path.to_path_buf(),
-            download_all_seasons: model.download_all_seasons.unwrap_or(false),
-            page_name_template: model.page_name_template,
-            selected_seasons,
-            scan_deleted_videos: model.scan_deleted_videos,
-            keyword_filters: model.keyword_filters,
-            keyword_filter_mode: model.keyword_filter_mode,
-            blacklist_keywords: model.blacklist_keywords,
-            whitelist_keywords: model.whitelist_keywords,
-            keyword_case_sensitive: model.keyword_case_sensitive,
-            audio_only: model.audio_only,
-            audio_only_m4a_only: model.audio_only_m4a_only,
-            flat_folder: model.flat_folder,
-            download_danmaku: model.download_danmaku,
-            download_subtitle: model.download_subtitle,
-            ai_rename: model.ai_rename,
-            ai_rename_video_prompt: model.ai_rename_video_prompt,
-            ai_rename_audio_prompt: model.ai_rename_audio_prompt,
-            ai_rename_enable_multi_page: model.ai_rename_enable_multi_page,
-            ai_rename_enable_collection: model.ai_rename_enable_collection,
-            ai_rename_enable_bangumi: model.ai_rename_enable_bangumi,
-        }
+        bangumi_source_from_model(model, path)
     } else {
         // 如果数据库中不存在，使用默认值并发出警告
         let id_desc = match (season_id, media_id, ep_id) {
@@ -377,6 +451,11 @@ pub async fn bangumi_from<'a>(
             flat_folder: false,
             download_danmaku: true,
             download_subtitle: true,
+            download_speed_limit: 0,
+            postprocess_profile: String::new(),
+            output_container: "mp4".to_string(),
+            audio_format: "m4a".to_string(),
+            audio_normalize: false,
             ai_rename: false,
             ai_rename_video_prompt: String::new(),
             ai_rename_audio_prompt: String::new(),
@@ -237,6 +237,26 @@ impl VideoSource for submission::Model {
         self.download_subtitle
     }
 
+    fn download_speed_limit(&self) -> u64 {
+        self.download_speed_limit.max(0) as u64
+    }
+
+    fn postprocess_profile(&self) -> &str {
+        &self.postprocess_profile
+    }
+
+    fn output_container(&self) -> &str {
+        &self.output_container
+    }
+
+    fn audio_format(&self) -> &str {
+        &self.audio_format
+    }
+
+    fn audio_normalize(&self) -> bool {
+        self.audio_normalize
+    }
+
     fn ai_rename(&self) -> bool {
         self.ai_rename
     }
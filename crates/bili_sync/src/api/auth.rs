@@ -69,6 +69,19 @@ pub async fn auth(headers: HeaderMap, request: Request, next: Next) -> Result<Re
     Ok(next.run(request).await)
 }
 
+/// 逐字节比较，避免通过响应时间猜测令牌；未设置令牌时不允许访问
+pub(crate) fn token_matches(expected: Option<&str>, given: Option<&str>) -> bool {
+    let (Some(expected), Some(given)) = (expected, given) else {
+        return false;
+    };
+    expected.len() == given.len()
+        && expected
+            .bytes()
+            .zip(given.bytes())
+            .fold(0u8, |acc, (a, b)| acc | (a ^ b))
+            == 0
+}
+
 pub(super) struct OpenAPIAuth;
 
 impl Modify for OpenAPIAuth {
@@ -84,3 +97,18 @@ impl Modify for OpenAPIAuth {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_token_matches() {
+        assert!(token_matches(Some("abc"), Some("abc")));
+        assert!(!token_matches(Some("abc"), Some("abd")));
+        assert!(!token_matches(Some("abc"), Some("ab")));
+        assert!(!token_matches(Some("abc"), None));
+        // 未生成令牌时不允许访问
+        assert!(!token_matches(None, Some("")));
+    }
+}
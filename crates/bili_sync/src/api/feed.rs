@@ -0,0 +1,589 @@
+//! 视频源的播客订阅
+//!
+//! 每个视频源提供一个 RSS 2.0（带 iTunes 标签）订阅地址 `/feeds/{source_type}/{id}.xml`，已下载的分页作为节目附件，
+//! 通过 `/feeds/{source_type}/{id}/{page_id}.{ext}` 支持 Range 的方式提供。播客应用无法发送 Authorization 头，
+//! 因此这两个地址不经过认证中间件，改为校验每个订阅独立的 `token` 查询参数。
+
+use std::sync::Arc;
+
+use anyhow::{anyhow, Result};
+use axum::extract::{Path, Query};
+use axum::http::{header, HeaderMap, HeaderValue, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::Extension;
+use bili_sync_entity::entities::{collection, favorite, page, submission, video, video_source, watch_later};
+use chrono::{NaiveDateTime, TimeZone};
+use quick_xml::events::{BytesDecl, BytesText, Event};
+use quick_xml::writer::Writer;
+use sea_orm::sea_query::SimpleExpr;
+use sea_orm::{ActiveValue, ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, QueryOrder};
+use serde::Deserialize;
+use tracing::error;
+
+use crate::api::auth::token_matches;
+use crate::api::video_stream::{downloaded_page_path, get_video_mime_type, serve_file_with_range};
+use crate::utils::time_format::beijing_timezone;
+
+/// 订阅对应的视频源信息
+pub struct FeedSource {
+    pub title: String,
+    pub link: String,
+    pub image: Option<String>,
+    pub token: Option<String>,
+    pub webdav_visible: bool,
+}
+
+#[derive(Deserialize)]
+pub struct FeedQuery {
+    token: Option<String>,
+}
+
+/// 生成新的订阅令牌
+pub fn generate_feed_token() -> String {
+    uuid::Uuid::new_v4().simple().to_string()
+}
+
+/// 订阅地址（不含协议和主机）
+pub fn feed_path(source_type: &str, id: i32, token: &str) -> String {
+    format!("/feeds/{}/{}.xml?token={}", source_type, id, token)
+}
+
+fn collection_source(model: collection::Model) -> FeedSource {
+    FeedSource {
+        title: model.name,
+        link: format!("https://space.bilibili.com/{}", model.m_id),
+        image: model.cover,
+        token: model.feed_token,
+        webdav_visible: model.webdav_visible,
+    }
+}
+
+fn favorite_source(model: favorite::Model) -> FeedSource {
+    FeedSource {
+        title: model.name,
+        link: format!("https://www.bilibili.com/medialist/detail/ml{}", model.f_id),
+        image: None,
+        token: model.feed_token,
+        webdav_visible: model.webdav_visible,
+    }
+}
+
+fn submission_source(model: submission::Model) -> FeedSource {
+    FeedSource {
+        title: format!("{} 的投稿", model.upper_name),
+        link: format!("https://space.bilibili.com/{}", model.upper_id),
+        image: None,
+        token: model.feed_token,
+        webdav_visible: model.webdav_visible,
+    }
+}
+
+fn watch_later_source(model: watch_later::Model) -> FeedSource {
+    FeedSource {
+        title: "稍后再看".to_string(),
+        link: "https://www.bilibili.com/watchlater".to_string(),
+        image: None,
+        token: model.feed_token,
+        webdav_visible: model.webdav_visible,
+    }
+}
+
+fn bangumi_source(model: video_source::Model) -> FeedSource {
+    FeedSource {
+        title: model.name,
+        link: match &model.season_id {
+            Some(season_id) => format!("https://www.bilibili.com/bangumi/play/ss{}", season_id),
+            None => "https://www.bilibili.com/anime".to_string(),
+        },
+        image: None,
+        token: model.feed_token,
+        webdav_visible: model.webdav_visible,
+    }
+}
+
+pub async fn load_feed_source(db: &DatabaseConnection, source_type: &str, id: i32) -> Result<Option<FeedSource>> {
+    let source = match source_type {
+        "collection" => collection::Entity::find_by_id(id).one(db).await?.map(collection_source),
+        "favorite" => favorite::Entity::find_by_id(id).one(db).await?.map(favorite_source),
+        "submission" => submission::Entity::find_by_id(id).one(db).await?.map(submission_source),
+        "watch_later" => watch_later::Entity::find_by_id(id)
+            .one(db)
+            .await?
+            .map(watch_later_source),
+        "bangumi" => video_source::Entity::find_by_id(id).one(db).await?.map(bangumi_source),
+        _ => return Err(anyhow!("不支持的视频源类型: {}", source_type)),
+    };
+    Ok(source)
+}
+
+/// 按类型和 ID 顺序列出全部视频源
+pub async fn list_feed_sources(db: &DatabaseConnection) -> Result<Vec<(&'static str, i32, FeedSource)>> {
+    let mut sources = Vec::new();
+    for model in collection::Entity::find()
+        .order_by_asc(collection::Column::Id)
+        .all(db)
+        .await?
+    {
+        sources.push(("collection", model.id, collection_source(model)));
+    }
+    for model in favorite::Entity::find()
+        .order_by_asc(favorite::Column::Id)
+        .all(db)
+        .await?
+    {
+        sources.push(("favorite", model.id, favorite_source(model)));
+    }
+    for model in submission::Entity::find()
+        .order_by_asc(submission::Column::Id)
+        .all(db)
+        .await?
+    {
+        sources.push(("submission", model.id, submission_source(model)));
+    }
+    for model in watch_later::Entity::find()
+        .order_by_asc(watch_later::Column::Id)
+        .all(db)
+        .await?
+    {
+        sources.push(("watch_later", model.id, watch_later_source(model)));
+    }
+    for model in video_source::Entity::find()
+        .order_by_asc(video_source::Column::Id)
+        .all(db)
+        .await?
+    {
+        sources.push(("bangumi", model.id, bangumi_source(model)));
+    }
+    Ok(sources)
+}
+
+/// 保存视频源的订阅令牌，传入 None 时关闭订阅
+pub async fn save_feed_token(db: &DatabaseConnection, source_type: &str, id: i32, token: Option<String>) -> Result<()> {
+    let token = ActiveValue::Set(token);
+    match source_type {
+        "collection" => {
+            collection::Entity::update(collection::ActiveModel {
+                id: ActiveValue::Unchanged(id),
+                feed_token: token,
+                ..Default::default()
+            })
+            .exec(db)
+            .await?;
+        }
+        "favorite" => {
+            favorite::Entity::update(favorite::ActiveModel {
+                id: ActiveValue::Unchanged(id),
+                feed_token: token,
+                ..Default::default()
+            })
+            .exec(db)
+            .await?;
+        }
+        "submission" => {
+            submission::Entity::update(submission::ActiveModel {
+                id: ActiveValue::Unchanged(id),
+                feed_token: token,
+                ..Default::default()
+            })
+            .exec(db)
+            .await?;
+        }
+        "watch_later" => {
+            watch_later::Entity::update(watch_later::ActiveModel {
+                id: ActiveValue::Unchanged(id),
+                feed_token: token,
+                ..Default::default()
+            })
+            .exec(db)
+            .await?;
+        }
+        "bangumi" => {
+            video_source::Entity::update(video_source::ActiveModel {
+                id: ActiveValue::Unchanged(id),
+                feed_token: token,
+                ..Default::default()
+            })
+            .exec(db)
+            .await?;
+        }
+        _ => return Err(anyhow!("不支持的视频源类型: {}", source_type)),
+    }
+    Ok(())
+}
+
+pub fn source_video_filter(source_type: &str, id: i32) -> Option<SimpleExpr> {
+    let column = match source_type {
+        "collection" => video::Column::CollectionId,
+        "favorite" => video::Column::FavoriteId,
+        "submission" => video::Column::SubmissionId,
+        "watch_later" => video::Column::WatchLaterId,
+        "bangumi" => video::Column::SourceId,
+        _ => return None,
+    };
+    Some(column.eq(id))
+}
+
+/// 解析 `{id}.{ext}` 形式的路径段
+pub fn parse_id_with_ext(segment: &str) -> Option<(i32, &str)> {
+    let (id, ext) = segment.split_once('.')?;
+    Some((id.parse().ok()?, ext))
+}
+
+/// 根据请求头推断外部访问地址，兼容反向代理
+fn base_url(headers: &HeaderMap) -> String {
+    let header_value = |name: &str| {
+        headers
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.split(',').next())
+            .map(|value| value.trim().to_string())
+            .filter(|value| !value.is_empty())
+    };
+    let scheme = header_value("x-forwarded-proto").unwrap_or_else(|| "http".to_string());
+    let host = header_value("x-forwarded-host")
+        .or_else(|| header_value("host"))
+        .unwrap_or_else(|| crate::config::reload_config().bind_address.clone());
+    format!("{}://{}", scheme, host)
+}
+
+/// 播客应用普遍要求图片使用 https
+fn https_url(url: &str) -> String {
+    match url.strip_prefix("http://") {
+        Some(rest) => format!("https://{}", rest),
+        None => url.to_string(),
+    }
+}
+
+struct Channel {
+    title: String,
+    link: String,
+    description: String,
+    image: Option<String>,
+}
+
+struct Episode {
+    guid: String,
+    title: String,
+    author: String,
+    description: String,
+    link: String,
+    pub_date: String,
+    url: String,
+    length: u64,
+    mime: &'static str,
+    duration: u32,
+    image: Option<String>,
+}
+
+fn build_rss(channel: &Channel, episodes: &[Episode]) -> std::io::Result<String> {
+    let mut writer = Writer::new_with_indent(Vec::new(), b' ', 2);
+    writer.write_event(Event::Decl(BytesDecl::new("1.0", Some("UTF-8"), None)))?;
+    writer
+        .create_element("rss")
+        .with_attribute(("version", "2.0"))
+        .with_attribute(("xmlns:itunes", "http://www.itunes.com/dtds/podcast-1.0.dtd"))
+        .write_inner_content(|writer| {
+            writer.create_element("channel").write_inner_content(|writer| {
+                writer
+                    .create_element("title")
+                    .write_text_content(BytesText::new(&channel.title))?;
+                writer
+                    .create_element("link")
+                    .write_text_content(BytesText::new(&channel.link))?;
+                writer
+                    .create_element("description")
+                    .write_text_content(BytesText::new(&channel.description))?;
+                writer
+                    .create_element("itunes:summary")
+                    .write_text_content(BytesText::new(&channel.description))?;
+                writer
+                    .create_element("itunes:explicit")
+                    .write_text_content(BytesText::new("false"))?;
+                if let Some(image) = &channel.image {
+                    writer
+                        .create_element("itunes:image")
+                        .with_attribute(("href", image.as_str()))
+                        .write_empty()?;
+                }
+                for episode in episodes {
+                    write_episode(writer, episode)?;
+                }
+                Ok(())
+            })?;
+            Ok(())
+        })?;
+    Ok(String::from_utf8_lossy(&writer.into_inner()).into_owned())
+}
+
+fn write_episode(writer: &mut Writer<Vec<u8>>, episode: &Episode) -> std::io::Result<()> {
+    writer.create_element("item").write_inner_content(|writer| {
+        writer
+            .create_element("title")
+            .write_text_content(BytesText::new(&episode.title))?;
+        writer
+            .create_element("guid")
+            .with_attribute(("isPermaLink", "false"))
+            .write_text_content(BytesText::new(&episode.guid))?;
+        writer
+            .create_element("link")
+            .write_text_content(BytesText::new(&episode.link))?;
+        writer
+            .create_element("description")
+            .write_text_content(BytesText::new(&episode.description))?;
+        writer
+            .create_element("pubDate")
+            .write_text_content(BytesText::new(&episode.pub_date))?;
+        writer
+            .create_element("enclosure")
+            .with_attribute(("url", episode.url.as_str()))
+            .with_attribute(("length", episode.length.to_string().as_str()))
+            .with_attribute(("type", episode.mime))
+            .write_empty()?;
+        writer
+            .create_element("itunes:author")
+            .write_text_content(BytesText::new(&episode.author))?;
+        writer
+            .create_element("itunes:duration")
+            .write_text_content(BytesText::new(&episode.duration.to_string()))?;
+        if let Some(image) = &episode.image {
+            writer
+                .create_element("itunes:image")
+                .with_attribute(("href", image.as_str()))
+                .write_empty()?;
+        }
+        Ok(())
+    })?;
+    Ok(())
+}
+
+fn error_response(status: StatusCode) -> Response {
+    (status, status.canonical_reason().unwrap_or_default()).into_response()
+}
+
+/// 播客订阅
+pub async fn podcast_feed(
+    Path((source_type, file)): Path<(String, String)>,
+    Query(query): Query<FeedQuery>,
+    headers: HeaderMap,
+    Extension(db): Extension<Arc<DatabaseConnection>>,
+) -> Response {
+    let Some((id, "xml")) = parse_id_with_ext(&file) else {
+        return error_response(StatusCode::NOT_FOUND);
+    };
+    match podcast_feed_impl(&source_type, id, query.token.as_deref(), &headers, &db).await {
+        Ok(response) => response,
+        Err(e) => {
+            error!("生成播客订阅失败: {:#}", e);
+            error_response(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+async fn podcast_feed_impl(
+    source_type: &str,
+    id: i32,
+    token: Option<&str>,
+    headers: &HeaderMap,
+    db: &DatabaseConnection,
+) -> Result<Response> {
+    let Some(filter) = source_video_filter(source_type, id) else {
+        return Ok(error_response(StatusCode::NOT_FOUND));
+    };
+    let Some(source) = load_feed_source(db, source_type, id).await? else {
+        return Ok(error_response(StatusCode::NOT_FOUND));
+    };
+    if !token_matches(source.token.as_deref(), token) {
+        return Ok(error_response(StatusCode::FORBIDDEN));
+    }
+    let token = token.unwrap_or_default();
+
+    let base_url = base_url(headers);
+    let videos = video::Entity::find()
+        .filter(filter)
+        .filter(video::Column::Deleted.eq(0))
+        .order_by_desc(video::Column::Pubtime)
+        .find_with_related(page::Entity)
+        .all(db)
+        .await?;
+
+    let mut episodes = Vec::new();
+    let mut channel_image = source.image.as_deref().map(https_url);
+    for (video_model, mut pages) in videos {
+        pages.sort_by_key(|page_model| page_model.pid);
+        let single_page = pages.len() == 1;
+        for page_model in pages {
+            let Some(path) = downloaded_page_path(&page_model) else {
+                continue;
+            };
+            let Ok(metadata) = tokio::fs::metadata(&path).await else {
+                continue;
+            };
+            let ext = path.extension().and_then(|ext| ext.to_str()).unwrap_or("mp4");
+            let title = if single_page {
+                video_model.name.clone()
+            } else {
+                format!("{} - P{} {}", video_model.name, page_model.pid, page_model.name)
+            };
+            let image = page_model
+                .image
+                .as_deref()
+                .filter(|_| !single_page)
+                .unwrap_or(&video_model.cover);
+            let image = (!image.is_empty()).then(|| https_url(image));
+            if channel_image.is_none() {
+                channel_image = image.clone();
+            }
+            episodes.push(Episode {
+                guid: format!("{}-{}", video_model.bvid, page_model.pid),
+                title,
+                author: video_model.upper_name.clone(),
+                description: video_model.intro.clone(),
+                link: format!(
+                    "https://www.bilibili.com/video/{}?p={}",
+                    video_model.bvid, page_model.pid
+                ),
+                pub_date: rfc2822_pub_date(&video_model.pubtime),
+                url: format!(
+                    "{}/feeds/{}/{}/{}.{}?token={}",
+                    base_url, source_type, id, page_model.id, ext, token
+                ),
+                length: metadata.len(),
+                mime: get_video_mime_type(&path),
+                duration: page_model.duration,
+                image,
+            });
+        }
+    }
+
+    let channel = Channel {
+        description: format!("由 bili-sync 同步的「{}」", source.title),
+        title: source.title,
+        link: source.link,
+        image: channel_image,
+    };
+    let mut response = build_rss(&channel, &episodes)?.into_response();
+    response.headers_mut().insert(
+        header::CONTENT_TYPE,
+        HeaderValue::from_static("application/rss+xml; charset=utf-8"),
+    );
+    Ok(response)
+}
+
+/// 数据库中的发布时间为北京时间，按北京时区格式化为 RSS 要求的 RFC 2822 时间
+fn rfc2822_pub_date(pubtime: &NaiveDateTime) -> String {
+    beijing_timezone()
+        .from_local_datetime(pubtime)
+        .single()
+        .map(|time| time.to_rfc2822())
+        .unwrap_or_default()
+}
+
+/// 播客节目附件
+pub async fn podcast_episode(
+    Path((source_type, id, file)): Path<(String, i32, String)>,
+    Query(query): Query<FeedQuery>,
+    headers: HeaderMap,
+    Extension(db): Extension<Arc<DatabaseConnection>>,
+) -> Response {
+    let Some((page_id, _)) = parse_id_with_ext(&file) else {
+        return error_response(StatusCode::NOT_FOUND);
+    };
+    match podcast_episode_impl(&source_type, id, page_id, query.token.as_deref(), &headers, &db).await {
+        Ok(response) => response,
+        Err(e) => {
+            error!("播客节目传输失败: {:#}", e);
+            error_response(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+async fn podcast_episode_impl(
+    source_type: &str,
+    id: i32,
+    page_id: i32,
+    token: Option<&str>,
+    headers: &HeaderMap,
+    db: &DatabaseConnection,
+) -> Result<Response> {
+    let Some(filter) = source_video_filter(source_type, id) else {
+        return Ok(error_response(StatusCode::NOT_FOUND));
+    };
+    let Some(source) = load_feed_source(db, source_type, id).await? else {
+        return Ok(error_response(StatusCode::NOT_FOUND));
+    };
+    if !token_matches(source.token.as_deref(), token) {
+        return Ok(error_response(StatusCode::FORBIDDEN));
+    }
+    // 分页必须属于该视频源，避免用一个订阅的令牌读取其它视频源的文件
+    let Some(page_model) = page::Entity::find_by_id(page_id).one(db).await? else {
+        return Ok(error_response(StatusCode::NOT_FOUND));
+    };
+    let belongs = video::Entity::find_by_id(page_model.video_id)
+        .filter(filter)
+        .one(db)
+        .await?
+        .is_some();
+    // 与订阅列表一致，只提供已下载完成的分页
+    let Some(path) = downloaded_page_path(&page_model).filter(|_| belongs) else {
+        return Ok(error_response(StatusCode::NOT_FOUND));
+    };
+    if !path.is_file() {
+        return Ok(error_response(StatusCode::NOT_FOUND));
+    }
+    serve_file_with_range(&path, headers).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_id_with_ext() {
+        assert_eq!(parse_id_with_ext("12.xml"), Some((12, "xml")));
+        assert_eq!(parse_id_with_ext("34.m4a"), Some((34, "m4a")));
+        assert_eq!(parse_id_with_ext("abc.xml"), None);
+        assert_eq!(parse_id_with_ext("12"), None);
+    }
+
+    #[test]
+    fn test_rfc2822_pub_date() {
+        let pubtime = NaiveDateTime::parse_from_str("2026-10-17 16:00:00", "%Y-%m-%d %H:%M:%S").unwrap();
+        assert_eq!(rfc2822_pub_date(&pubtime), "Sat, 17 Oct 2026 16:00:00 +0800");
+    }
+
+    #[test]
+    fn test_build_rss() {
+        let channel = Channel {
+            title: "某UP主 的投稿".to_string(),
+            link: "https://space.bilibili.com/1".to_string(),
+            description: "由 bili-sync 同步的「某UP主 的投稿」".to_string(),
+            image: Some("https://i0.hdslb.com/face.jpg".to_string()),
+        };
+        let episodes = vec![Episode {
+            guid: "BV1xx-1".to_string(),
+            title: "标题 <1> & 2".to_string(),
+            author: "某UP主".to_string(),
+            description: "简介".to_string(),
+            link: "https://www.bilibili.com/video/BV1xx?p=1".to_string(),
+            pub_date: beijing_timezone()
+                .with_ymd_and_hms(2026, 10, 17, 16, 0, 0)
+                .unwrap()
+                .to_rfc2822(),
+            url: "http://nas:12345/feeds/submission/1/7.m4a?token=t".to_string(),
+            length: 1024,
+            mime: "audio/mp4",
+            duration: 300,
+            image: None,
+        }];
+        let rss = build_rss(&channel, &episodes).unwrap();
+        assert!(rss.starts_with(r#"<?xml version="1.0" encoding="UTF-8"?>"#));
+        assert!(rss.contains(r#"<rss version="2.0" xmlns:itunes="http://www.itunes.com/dtds/podcast-1.0.dtd">"#));
+        assert!(rss.contains(r#"<itunes:image href="https://i0.hdslb.com/face.jpg"/>"#));
+        assert!(rss.contains("<title>标题 &lt;1&gt; &amp; 2</title>"));
+        assert!(rss.contains(
+            r#"<enclosure url="http://nas:12345/feeds/submission/1/7.m4a?token=t" length="1024" type="audio/mp4"/>"#
+        ));
+        assert!(rss.contains("<pubDate>Sat, 17 Oct 2026 16:00:00 +0800</pubDate>"));
+        assert!(rss.contains("<itunes:duration>300</itunes:duration>"));
+    }
+}
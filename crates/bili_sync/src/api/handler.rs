@@ -26,19 +26,20 @@ use crate::api::auth::OpenAPIAuth;
 use crate::api::error::InnerApiError;
 use crate::api::request::{
     AddVideoSourceRequest, BatchUpdateConfigRequest, ConfigHistoryRequest, ConfigMigrationRequest, QRGenerateRequest,
-    QRPollRequest, ResetSpecificTasksRequest, ResetVideoSourcePathRequest, SetupAuthTokenRequest,
-    SubmissionVideosRequest, UpdateConfigItemRequest, UpdateConfigRequest, UpdateCredentialRequest,
-    UpdateVideoStatusRequest, VideosRequest,
+    QRPollRequest, ReorganizeLibraryRequest, ResetSpecificTasksRequest, ResetVideoSourcePathRequest,
+    RevertReorganizeRequest, SetupAuthTokenRequest, SubmissionVideosRequest, UpdateConfigItemRequest,
+    UpdateConfigRequest, UpdateCredentialRequest, UpdateVideoStatusRequest, VerifyLibraryRequest, VideosRequest,
 };
 use crate::api::response::{
-    AddVideoSourceResponse, BangumiSeasonInfo, BangumiSourceListResponse, BangumiSourceOption,
-    BetaImageUpdateStatusResponse, ConfigChangeInfo, ConfigHistoryResponse, ConfigItemResponse, ConfigReloadResponse,
-    ConfigMigrationReportResponse, ConfigMigrationStatusResponse, ConfigResponse, ConfigValidationResponse,
-    DashBoardResponse, DeleteVideoResponse, DeleteVideoSourceResponse, HotReloadStatusResponse,
-    InitialSetupCheckResponse, MonitoringStatus, PageInfo, QRGenerateResponse, QRPollResponse, QRUserInfo,
-    ResetAllVideosResponse, ResetVideoResponse, ResetVideoSourcePathResponse, SetupAuthTokenResponse,
-    SubmissionVideosResponse, UpdateConfigResponse, UpdateCredentialResponse, UpdateVideoStatusResponse, VideoInfo,
-    VideoResponse, VideoSource, VideoSourcesResponse, VideosResponse,
+    AddVideoSourceResponse, AdoptVideoSourceResponse, BangumiSeasonInfo, BangumiSourceListResponse,
+    BangumiSourceOption, BetaImageUpdateStatusResponse, ConfigChangeInfo, ConfigHistoryResponse, ConfigItemResponse,
+    ConfigMigrationReportResponse, ConfigMigrationStatusResponse, ConfigReloadResponse, ConfigResponse,
+    ConfigValidationResponse, DashBoardResponse, DeleteVideoResponse, DeleteVideoSourceResponse, DownloadNowResponse,
+    HotReloadStatusResponse, InitialSetupCheckResponse, MonitoringStatus, PageInfo, QRGenerateResponse, QRPollResponse,
+    QRUserInfo, ReorganizeLibraryResponse, ResetAllVideosResponse, ResetVideoResponse, ResetVideoSourcePathResponse,
+    SetupAuthTokenResponse, SubmissionVideosResponse, UpdateConfigResponse, UpdateCredentialResponse,
+    UpdateVideoStatusResponse, VerifyLibraryResponse, VideoInfo, VideoResponse, VideoSource, VideoSourcesResponse,
+    VideosResponse,
 };
 use crate::api::wrapper::{ApiError, ApiResponse};
 use crate::utils::status::{PageStatus, VideoStatus};
@@ -655,7 +656,7 @@ mod cleanup_tests {
 
 #[derive(OpenApi)]
 #[openapi(
-    paths(get_video_sources, get_videos, get_video, reset_video, reset_all_videos, reset_specific_tasks, update_video_status, add_video_source, update_video_source_enabled, update_video_source_scan_deleted, reset_video_source_path, delete_video_source, reload_config, get_config, update_config, get_bangumi_seasons, search_bilibili, get_user_favorites, get_user_collections, get_user_followings, get_subscribed_collections, get_submission_videos, get_logs, get_queue_status, proxy_image, get_config_item, get_config_history, get_config_migration_status, migrate_config_schema, validate_config, get_hot_reload_status, check_initial_setup, setup_auth_token, update_credential, generate_qr_code, poll_qr_status, get_current_user, clear_credential, pause_scanning_endpoint, resume_scanning_endpoint, get_task_control_status, get_video_play_info, proxy_video_stream, validate_favorite, get_user_favorites_by_uid, test_notification_handler, get_notification_config, update_notification_config, get_notification_status, test_risk_control_handler, get_beta_image_update_status),
+    paths(get_video_sources, get_videos, get_video, reset_video, download_video_now, reset_all_videos, reset_specific_tasks, verify_library, get_verify_library_status, reorganize_library, revert_reorganize_library, get_reorganize_library_status, adopt_video_source, get_adopt_video_source_status, update_video_status, add_video_source, update_video_source_enabled, update_video_source_scan_deleted, reset_video_source_path, delete_video_source, reload_config, get_config, update_config, get_bangumi_seasons, search_bilibili, get_user_favorites, get_user_collections, get_user_followings, get_subscribed_collections, get_submission_videos, get_logs, get_queue_status, get_cdn_stats, reset_cdn_stats, proxy_image, get_config_item, get_config_history, get_config_migration_status, migrate_config_schema, validate_config, get_hot_reload_status, check_initial_setup, setup_auth_token, update_credential, generate_qr_code, poll_qr_status, get_current_user, clear_credential, pause_scanning_endpoint, resume_scanning_endpoint, get_task_control_status, get_video_play_info, get_video_source_feed, reset_video_source_feed, proxy_video_stream, validate_favorite, get_user_favorites_by_uid, test_notification_handler, test_media_server_handler, get_notification_config, update_notification_config, get_notification_status, test_risk_control_handler, get_beta_image_update_status),
     modifiers(&OpenAPIAuth),
     security(
         ("Token" = []),
@@ -809,6 +810,13 @@ pub async fn get_video_sources(
                 flat_folder: model.flat_folder,
                 download_danmaku: model.download_danmaku,
                 download_subtitle: model.download_subtitle,
+                download_speed_limit: model.download_speed_limit,
+                priority: model.priority,
+                postprocess_profile: model.postprocess_profile,
+                output_container: model.output_container,
+                audio_format: model.audio_format,
+                audio_normalize: model.audio_normalize,
+                webdav_visible: model.webdav_visible,
                 ai_rename: model.ai_rename,
                 ai_rename_video_prompt: model.ai_rename_video_prompt,
                 ai_rename_audio_prompt: model.ai_rename_audio_prompt,
@@ -860,6 +868,13 @@ pub async fn get_video_sources(
                 flat_folder: model.flat_folder,
                 download_danmaku: model.download_danmaku,
                 download_subtitle: model.download_subtitle,
+                download_speed_limit: model.download_speed_limit,
+                priority: model.priority,
+                postprocess_profile: model.postprocess_profile,
+                output_container: model.output_container,
+                audio_format: model.audio_format,
+                audio_normalize: model.audio_normalize,
+                webdav_visible: model.webdav_visible,
                 ai_rename: model.ai_rename,
                 ai_rename_video_prompt: model.ai_rename_video_prompt,
                 ai_rename_audio_prompt: model.ai_rename_audio_prompt,
@@ -911,6 +926,13 @@ pub async fn get_video_sources(
                 flat_folder: model.flat_folder,
                 download_danmaku: model.download_danmaku,
                 download_subtitle: model.download_subtitle,
+                download_speed_limit: model.download_speed_limit,
+                priority: model.priority,
+                postprocess_profile: model.postprocess_profile,
+                output_container: model.output_container,
+                audio_format: model.audio_format,
+                audio_normalize: model.audio_normalize,
+                webdav_visible: model.webdav_visible,
                 ai_rename: model.ai_rename,
                 ai_rename_video_prompt: model.ai_rename_video_prompt,
                 ai_rename_audio_prompt: model.ai_rename_audio_prompt,
@@ -962,6 +984,13 @@ pub async fn get_video_sources(
                 flat_folder: model.flat_folder,
                 download_danmaku: model.download_danmaku,
                 download_subtitle: model.download_subtitle,
+                download_speed_limit: model.download_speed_limit,
+                priority: model.priority,
+                postprocess_profile: model.postprocess_profile,
+                output_container: model.output_container,
+                audio_format: model.audio_format,
+                audio_normalize: model.audio_normalize,
+                webdav_visible: model.webdav_visible,
                 ai_rename: model.ai_rename,
                 ai_rename_video_prompt: model.ai_rename_video_prompt,
                 ai_rename_audio_prompt: model.ai_rename_audio_prompt,
@@ -1032,6 +1061,13 @@ pub async fn get_video_sources(
                 flat_folder: model.flat_folder,
                 download_danmaku: model.download_danmaku,
                 download_subtitle: model.download_subtitle,
+                download_speed_limit: model.download_speed_limit,
+                priority: model.priority,
+                postprocess_profile: model.postprocess_profile,
+                output_container: model.output_container,
+                audio_format: model.audio_format,
+                audio_normalize: model.audio_normalize,
+                webdav_visible: model.webdav_visible,
                 ai_rename: model.ai_rename,
                 ai_rename_video_prompt: model.ai_rename_video_prompt,
                 ai_rename_audio_prompt: model.ai_rename_audio_prompt,
@@ -1418,8 +1454,10 @@ pub async fn get_video(
             page::Column::Name,
             page::Column::DownloadStatus,
             page::Column::Path,
+            page::Column::PostprocessStatus,
+            page::Column::PostprocessError,
         ])
-        .into_tuple::<(i32, i32, String, u32, Option<String>)>()
+        .into_tuple::<(i32, i32, String, u32, Option<String>, i32, Option<String>)>()
         .all(db.as_ref())
         .await?
         .into_iter()
@@ -1577,6 +1615,39 @@ pub async fn reset_video(
     }))
 }
 
+/// 立即下载视频：将视频提升到下载队列最前，并尽可能马上开始下载
+#[utoipa::path(
+    post,
+    path = "/api/videos/{id}/download-now",
+    params(
+        ("id" = i32, Path, description = "Video ID"),
+    ),
+    responses(
+        (status = 200, body = ApiResponse<DownloadNowResponse>),
+    )
+)]
+pub async fn download_video_now(
+    Path(id): Path<i32>,
+    Extension(db): Extension<Arc<DatabaseConnection>>,
+) -> Result<ApiResponse<DownloadNowResponse>, ApiError> {
+    use crate::task::download_queue::DownloadNowOutcome;
+
+    let Some((outcome, priority)) = crate::task::download_queue::download_video_now(db, id).await? else {
+        return Err(InnerApiError::NotFound(id).into());
+    };
+    let (status, message) = match outcome {
+        DownloadNowOutcome::Started => ("started", "已开始下载"),
+        DownloadNowOutcome::Queued => ("queued", "已提升到下载队列最前，将在扫描任务中优先下载"),
+        DownloadNowOutcome::AlreadyCompleted => ("completed", "视频已下载完成"),
+    };
+    Ok(ApiResponse::ok(DownloadNowResponse {
+        video_id: id,
+        status: status.to_string(),
+        priority,
+        message: message.to_string(),
+    }))
+}
+
 /// 重置所有视频和页面的失败状态为未下载状态，这样在下次下载任务中会触发重试
 #[utoipa::path(
     post,
@@ -1840,6 +1911,162 @@ pub async fn reset_all_videos(
     }))
 }
 
+/// 启动全库文件校验任务，校验失败的分页会被重置视频子任务并在下一轮下载中重新下载
+#[utoipa::path(
+    post,
+    path = "/api/videos/verify",
+    params(VerifyLibraryRequest),
+    responses(
+        (status = 200, body = ApiResponse<VerifyLibraryResponse>),
+    )
+)]
+pub async fn verify_library(
+    Extension(db): Extension<Arc<DatabaseConnection>>,
+    Query(params): Query<VerifyLibraryRequest>,
+) -> Result<ApiResponse<VerifyLibraryResponse>, ApiError> {
+    let started = crate::utils::media_verify::start_verify_job(db, params.rehash.unwrap_or(false));
+    Ok(ApiResponse::ok(VerifyLibraryResponse {
+        started,
+        message: if started {
+            "已开始全库文件校验".to_string()
+        } else {
+            "已有校验任务正在运行".to_string()
+        },
+        status: crate::utils::media_verify::verify_job_status(),
+    }))
+}
+
+/// 获取全库文件校验任务的进度
+#[utoipa::path(
+    get,
+    path = "/api/videos/verify/status",
+    responses(
+        (status = 200, body = ApiResponse<crate::utils::media_verify::VerifyJobStatus>),
+    )
+)]
+pub async fn get_verify_library_status() -> Result<ApiResponse<crate::utils::media_verify::VerifyJobStatus>, ApiError> {
+    Ok(ApiResponse::ok(crate::utils::media_verify::verify_job_status()))
+}
+
+/// 按当前路径模板重新整理已下载的文件，默认只预演移动计划
+#[utoipa::path(
+    post,
+    path = "/api/library/reorganize",
+    params(ReorganizeLibraryRequest),
+    responses(
+        (status = 200, body = ApiResponse<ReorganizeLibraryResponse>),
+    )
+)]
+pub async fn reorganize_library(
+    Extension(db): Extension<Arc<DatabaseConnection>>,
+    Query(params): Query<ReorganizeLibraryRequest>,
+) -> Result<ApiResponse<ReorganizeLibraryResponse>, ApiError> {
+    let dry_run = params.dry_run.unwrap_or(true);
+    // 扫描下载过程中移动文件会与正在写入的文件冲突
+    let (started, message) = if !dry_run && crate::task::is_scanning() {
+        (false, "正在扫描下载，请等待本轮扫描结束后再整理媒体库".to_string())
+    } else if crate::utils::reorganize::start_reorganize_job(db, dry_run) {
+        let message = if dry_run {
+            "已开始预演媒体库整理"
+        } else {
+            "已开始整理媒体库"
+        };
+        (true, message.to_string())
+    } else {
+        (false, "已有整理任务正在运行".to_string())
+    };
+    Ok(ApiResponse::ok(ReorganizeLibraryResponse {
+        started,
+        message,
+        status: crate::utils::reorganize::reorganize_job_status(),
+    }))
+}
+
+/// 按移动日志回滚一次媒体库整理
+#[utoipa::path(
+    post,
+    path = "/api/library/reorganize/revert",
+    params(RevertReorganizeRequest),
+    responses(
+        (status = 200, body = ApiResponse<ReorganizeLibraryResponse>),
+    )
+)]
+pub async fn revert_reorganize_library(
+    Extension(db): Extension<Arc<DatabaseConnection>>,
+    Query(params): Query<RevertReorganizeRequest>,
+) -> Result<ApiResponse<ReorganizeLibraryResponse>, ApiError> {
+    let (started, message) = if crate::task::is_scanning() {
+        (false, "正在扫描下载，请等待本轮扫描结束后再回滚".to_string())
+    } else if crate::utils::reorganize::start_revert_job(db, params.log_file) {
+        (true, "已开始回滚媒体库整理".to_string())
+    } else {
+        (false, "已有整理任务正在运行".to_string())
+    };
+    Ok(ApiResponse::ok(ReorganizeLibraryResponse {
+        started,
+        message,
+        status: crate::utils::reorganize::reorganize_job_status(),
+    }))
+}
+
+/// 获取媒体库整理任务的进度和移动计划
+#[utoipa::path(
+    get,
+    path = "/api/library/reorganize/status",
+    responses(
+        (status = 200, body = ApiResponse<crate::utils::reorganize::ReorganizeJobStatus>),
+    )
+)]
+pub async fn get_reorganize_library_status(
+) -> Result<ApiResponse<crate::utils::reorganize::ReorganizeJobStatus>, ApiError> {
+    Ok(ApiResponse::ok(crate::utils::reorganize::reorganize_job_status()))
+}
+
+/// 导入视频源目录下已有的文件：拉取视频列表后按 bvid/aid/cid 匹配文件，匹配到的分页标记为已完成，不再重新下载
+///
+/// 建议添加视频源后先将其禁用，导入完成并确认未匹配文件后再启用
+#[utoipa::path(
+    post,
+    path = "/api/video-sources/{source_type}/{id}/adopt",
+    params(
+        ("source_type" = String, Path, description = "视频源类型"),
+        ("id" = i32, Path, description = "视频源ID"),
+    ),
+    responses(
+        (status = 200, body = ApiResponse<AdoptVideoSourceResponse>),
+    )
+)]
+pub async fn adopt_video_source(
+    Extension(db): Extension<Arc<DatabaseConnection>>,
+    Path((source_type, id)): Path<(String, i32)>,
+) -> Result<ApiResponse<AdoptVideoSourceResponse>, ApiError> {
+    let (started, message) = if crate::task::is_scanning() {
+        (false, "正在扫描下载，请等待本轮扫描结束后再导入".to_string())
+    } else if crate::utils::library_adopt::start_adopt_job(db, source_type, id) {
+        (true, "已开始导入已有媒体库".to_string())
+    } else {
+        (false, "已有导入任务正在运行".to_string())
+    };
+    Ok(ApiResponse::ok(AdoptVideoSourceResponse {
+        started,
+        message,
+        status: crate::utils::library_adopt::adopt_job_status(),
+    }))
+}
+
+/// 获取导入任务的进度与未匹配的文件
+#[utoipa::path(
+    get,
+    path = "/api/video-sources/adopt/status",
+    responses(
+        (status = 200, body = ApiResponse<crate::utils::library_adopt::AdoptJobStatus>),
+    )
+)]
+pub async fn get_adopt_video_source_status(
+) -> Result<ApiResponse<crate::utils::library_adopt::AdoptJobStatus>, ApiError> {
+    Ok(ApiResponse::ok(crate::utils::library_adopt::adopt_job_status()))
+}
+
 /// 强制重置特定任务状态（不管当前状态）
 #[utoipa::path(
     post,
@@ -1860,9 +2087,9 @@ pub async fn reset_specific_tasks(
         return Err(crate::api::error::InnerApiError::BadRequest("至少需要选择一个任务".to_string()).into());
     }
 
-    // 验证任务索引范围
+    // 验证任务索引范围，分页比视频多一个进度条预览图任务
     for &index in task_indexes {
-        if index > 4 {
+        if index > 5 {
             return Err(crate::api::error::InnerApiError::BadRequest(format!("无效的任务索引: {}", index)).into());
         }
     }
@@ -2019,7 +2246,7 @@ pub async fn reset_specific_tasks(
 
             // 重置指定的任务索引：默认仅重置失败任务；force=true 时重置所有非 0 状态
             for &task_index in task_indexes {
-                if task_index < 5 {
+                if task_index < 6 {
                     let current_status = page_status.get(task_index);
                     let should_reset = if force_reset {
                         current_status != 0
@@ -2359,7 +2586,7 @@ pub async fn update_video_status(
         if let Some(page_info) = page_id_map.remove(&page_update.page_id) {
             let mut page_status = PageStatus::from(page_info.download_status);
             for update in &page_update.updates {
-                if update.status_index < 5 {
+                if update.status_index < 6 {
                     page_status.set(update.status_index, update.status_value);
                 }
             }
@@ -2628,6 +2855,7 @@ pub async fn add_video_source_internal(
                 enabled: sea_orm::Set(true),
                 scan_deleted_videos: sea_orm::Set(false),
                 cover: sea_orm::Set(cover_url),
+                description: sea_orm::Set(None),
                 keyword_filters: sea_orm::Set(keyword_filters_json),
                 keyword_filter_mode: sea_orm::Set(keyword_filter_mode),
                 blacklist_keywords: sea_orm::Set(None),
@@ -2638,6 +2866,14 @@ pub async fn add_video_source_internal(
                 flat_folder: sea_orm::Set(params.flat_folder.unwrap_or(false)),
                 download_danmaku: sea_orm::Set(params.download_danmaku.unwrap_or(true)),
                 download_subtitle: sea_orm::Set(params.download_subtitle.unwrap_or(true)),
+                download_speed_limit: sea_orm::Set(0),
+                priority: sea_orm::Set(0),
+                postprocess_profile: sea_orm::Set(String::new()),
+                output_container: sea_orm::Set("mp4".to_string()),
+                audio_format: sea_orm::Set("m4a".to_string()),
+                audio_normalize: sea_orm::Set(false),
+                feed_token: sea_orm::Set(None),
+                webdav_visible: sea_orm::Set(true),
                 ai_rename: sea_orm::Set(params.ai_rename.unwrap_or(false)),
                 ai_rename_video_prompt: sea_orm::Set(params.ai_rename_video_prompt.clone().unwrap_or_default()),
                 ai_rename_audio_prompt: sea_orm::Set(params.ai_rename_audio_prompt.clone().unwrap_or_default()),
@@ -2707,6 +2943,16 @@ pub async fn add_video_source_internal(
                 flat_folder: sea_orm::Set(params.flat_folder.unwrap_or(false)),
                 download_danmaku: sea_orm::Set(params.download_danmaku.unwrap_or(true)),
                 download_subtitle: sea_orm::Set(params.download_subtitle.unwrap_or(true)),
+                download_speed_limit: sea_orm::Set(0),
+                priority: sea_orm::Set(0),
+                postprocess_profile: sea_orm::Set(String::new()),
+                output_container: sea_orm::Set("mp4".to_string()),
+                audio_format: sea_orm::Set("m4a".to_string()),
+                audio_normalize: sea_orm::Set(false),
+                feed_token: sea_orm::Set(None),
+                webdav_visible: sea_orm::Set(true),
+                description: sea_orm::Set(None),
+                cover: sea_orm::Set(None),
                 ai_rename: sea_orm::Set(params.ai_rename.unwrap_or(false)),
                 ai_rename_video_prompt: sea_orm::Set(params.ai_rename_video_prompt.clone().unwrap_or_default()),
                 ai_rename_audio_prompt: sea_orm::Set(params.ai_rename_audio_prompt.clone().unwrap_or_default()),
@@ -2782,6 +3028,14 @@ pub async fn add_video_source_internal(
                 audio_only: sea_orm::Set(params.audio_only.unwrap_or(false)),
                 download_danmaku: sea_orm::Set(params.download_danmaku.unwrap_or(true)),
                 download_subtitle: sea_orm::Set(params.download_subtitle.unwrap_or(true)),
+                download_speed_limit: sea_orm::Set(0),
+                priority: sea_orm::Set(0),
+                postprocess_profile: sea_orm::Set(String::new()),
+                output_container: sea_orm::Set("mp4".to_string()),
+                audio_format: sea_orm::Set("m4a".to_string()),
+                audio_normalize: sea_orm::Set(false),
+                feed_token: sea_orm::Set(None),
+                webdav_visible: sea_orm::Set(true),
                 ai_rename: sea_orm::Set(params.ai_rename.unwrap_or(false)),
                 ai_rename_video_prompt: sea_orm::Set(params.ai_rename_video_prompt.clone().unwrap_or_default()),
                 ai_rename_audio_prompt: sea_orm::Set(params.ai_rename_audio_prompt.clone().unwrap_or_default()),
@@ -3169,6 +3423,14 @@ pub async fn add_video_source_internal(
                 audio_only: sea_orm::Set(params.audio_only.unwrap_or(false)),
                 download_danmaku: sea_orm::Set(params.download_danmaku.unwrap_or(true)),
                 download_subtitle: sea_orm::Set(params.download_subtitle.unwrap_or(true)),
+                download_speed_limit: sea_orm::Set(0),
+                priority: sea_orm::Set(0),
+                postprocess_profile: sea_orm::Set(String::new()),
+                output_container: sea_orm::Set("mp4".to_string()),
+                audio_format: sea_orm::Set("m4a".to_string()),
+                audio_normalize: sea_orm::Set(false),
+                feed_token: sea_orm::Set(None),
+                webdav_visible: sea_orm::Set(true),
                 ai_rename: sea_orm::Set(params.ai_rename.unwrap_or(false)),
                 ai_rename_video_prompt: sea_orm::Set(params.ai_rename_video_prompt.clone().unwrap_or_default()),
                 ai_rename_audio_prompt: sea_orm::Set(params.ai_rename_audio_prompt.clone().unwrap_or_default()),
@@ -3723,6 +3985,9 @@ async fn delete_video_files_from_pages(conn: &impl ConnectionTrait, video_id: i3
                                 }
                             }
                         }
+
+                        // 删除进度条预览图（.trickplay 目录或 .bif 文件）
+                        deleted_count += crate::utils::trickplay::remove_trickplay(parent_dir, &file_stem_str).await;
                     }
                 }
             }
@@ -4826,6 +5091,32 @@ pub async fn update_video_source_download_options_internal(
     id: i32,
     params: crate::api::request::UpdateVideoSourceDownloadOptionsRequest,
 ) -> Result<crate::api::response::UpdateVideoSourceDownloadOptionsResponse, ApiError> {
+    if let Some(profile) = params.postprocess_profile.as_deref().filter(|name| !name.is_empty()) {
+        if crate::task::postprocess::find_profile(profile).is_none() {
+            return Err(
+                crate::api::error::InnerApiError::BadRequest(format!("未找到名为「{}」的后处理配置", profile)).into(),
+            );
+        }
+    }
+    if let Some(container) = params.output_container.as_deref() {
+        if !matches!(container, "mp4" | "mkv") {
+            return Err(crate::api::error::InnerApiError::BadRequest(format!(
+                "不支持的输出容器「{}」，可选值为 mp4 或 mkv",
+                container
+            ))
+            .into());
+        }
+    }
+    if let Some(format) = params.audio_format.as_deref() {
+        if crate::utils::audio_transcode::AudioFormat::parse(format).is_none() {
+            return Err(crate::api::error::InnerApiError::BadRequest(format!(
+                "不支持的音频格式「{}」，可选值为 m4a、mp3、opus 或 flac",
+                format
+            ))
+            .into());
+        }
+    }
+
     let txn = db.begin().await?;
 
     let result = match source_type.as_str() {
@@ -4840,6 +5131,22 @@ pub async fn update_video_source_download_options_internal(
             let flat_folder = params.flat_folder.unwrap_or(collection.flat_folder);
             let download_danmaku = params.download_danmaku.unwrap_or(collection.download_danmaku);
             let download_subtitle = params.download_subtitle.unwrap_or(collection.download_subtitle);
+            let download_speed_limit = params
+                .download_speed_limit
+                .map(|v| v as i32)
+                .unwrap_or(collection.download_speed_limit);
+            let priority = params.priority.unwrap_or(collection.priority);
+            let postprocess_profile = params
+                .postprocess_profile
+                .clone()
+                .unwrap_or(collection.postprocess_profile.clone());
+            let output_container = params
+                .output_container
+                .clone()
+                .unwrap_or(collection.output_container.clone());
+            let audio_format = params.audio_format.clone().unwrap_or(collection.audio_format.clone());
+            let audio_normalize = params.audio_normalize.unwrap_or(collection.audio_normalize);
+            let webdav_visible = params.webdav_visible.unwrap_or(collection.webdav_visible);
             let ai_rename = params.ai_rename.unwrap_or(collection.ai_rename);
             let ai_rename_video_prompt = params
                 .ai_rename_video_prompt
@@ -4866,6 +5173,13 @@ pub async fn update_video_source_download_options_internal(
                 flat_folder: sea_orm::Set(flat_folder),
                 download_danmaku: sea_orm::Set(download_danmaku),
                 download_subtitle: sea_orm::Set(download_subtitle),
+                download_speed_limit: sea_orm::Set(download_speed_limit),
+                priority: sea_orm::Set(priority),
+                postprocess_profile: sea_orm::Set(postprocess_profile.clone()),
+                output_container: sea_orm::Set(output_container.clone()),
+                audio_format: sea_orm::Set(audio_format.clone()),
+                audio_normalize: sea_orm::Set(audio_normalize),
+                webdav_visible: sea_orm::Set(webdav_visible),
                 ai_rename: sea_orm::Set(ai_rename),
                 ai_rename_video_prompt: sea_orm::Set(ai_rename_video_prompt.clone()),
                 ai_rename_audio_prompt: sea_orm::Set(ai_rename_audio_prompt.clone()),
@@ -4886,6 +5200,13 @@ pub async fn update_video_source_download_options_internal(
                 flat_folder,
                 download_danmaku,
                 download_subtitle,
+                download_speed_limit,
+                priority,
+                postprocess_profile,
+                output_container,
+                audio_format,
+                audio_normalize,
+                webdav_visible,
                 ai_rename,
                 ai_rename_video_prompt,
                 ai_rename_audio_prompt,
@@ -4907,6 +5228,22 @@ pub async fn update_video_source_download_options_internal(
             let flat_folder = params.flat_folder.unwrap_or(favorite.flat_folder);
             let download_danmaku = params.download_danmaku.unwrap_or(favorite.download_danmaku);
             let download_subtitle = params.download_subtitle.unwrap_or(favorite.download_subtitle);
+            let download_speed_limit = params
+                .download_speed_limit
+                .map(|v| v as i32)
+                .unwrap_or(favorite.download_speed_limit);
+            let priority = params.priority.unwrap_or(favorite.priority);
+            let postprocess_profile = params
+                .postprocess_profile
+                .clone()
+                .unwrap_or(favorite.postprocess_profile.clone());
+            let output_container = params
+                .output_container
+                .clone()
+                .unwrap_or(favorite.output_container.clone());
+            let audio_format = params.audio_format.clone().unwrap_or(favorite.audio_format.clone());
+            let audio_normalize = params.audio_normalize.unwrap_or(favorite.audio_normalize);
+            let webdav_visible = params.webdav_visible.unwrap_or(favorite.webdav_visible);
             let ai_rename = params.ai_rename.unwrap_or(favorite.ai_rename);
             let ai_rename_video_prompt = params
                 .ai_rename_video_prompt
@@ -4933,6 +5270,13 @@ pub async fn update_video_source_download_options_internal(
                 flat_folder: sea_orm::Set(flat_folder),
                 download_danmaku: sea_orm::Set(download_danmaku),
                 download_subtitle: sea_orm::Set(download_subtitle),
+                download_speed_limit: sea_orm::Set(download_speed_limit),
+                priority: sea_orm::Set(priority),
+                postprocess_profile: sea_orm::Set(postprocess_profile.clone()),
+                output_container: sea_orm::Set(output_container.clone()),
+                audio_format: sea_orm::Set(audio_format.clone()),
+                audio_normalize: sea_orm::Set(audio_normalize),
+                webdav_visible: sea_orm::Set(webdav_visible),
                 ai_rename: sea_orm::Set(ai_rename),
                 ai_rename_video_prompt: sea_orm::Set(ai_rename_video_prompt.clone()),
                 ai_rename_audio_prompt: sea_orm::Set(ai_rename_audio_prompt.clone()),
@@ -4953,6 +5297,13 @@ pub async fn update_video_source_download_options_internal(
                 flat_folder,
                 download_danmaku,
                 download_subtitle,
+                download_speed_limit,
+                priority,
+                postprocess_profile,
+                output_container,
+                audio_format,
+                audio_normalize,
+                webdav_visible,
                 ai_rename,
                 ai_rename_video_prompt,
                 ai_rename_audio_prompt,
@@ -4974,6 +5325,22 @@ pub async fn update_video_source_download_options_internal(
             let flat_folder = params.flat_folder.unwrap_or(submission.flat_folder);
             let download_danmaku = params.download_danmaku.unwrap_or(submission.download_danmaku);
             let download_subtitle = params.download_subtitle.unwrap_or(submission.download_subtitle);
+            let download_speed_limit = params
+                .download_speed_limit
+                .map(|v| v as i32)
+                .unwrap_or(submission.download_speed_limit);
+            let priority = params.priority.unwrap_or(submission.priority);
+            let postprocess_profile = params
+                .postprocess_profile
+                .clone()
+                .unwrap_or(submission.postprocess_profile.clone());
+            let output_container = params
+                .output_container
+                .clone()
+                .unwrap_or(submission.output_container.clone());
+            let audio_format = params.audio_format.clone().unwrap_or(submission.audio_format.clone());
+            let audio_normalize = params.audio_normalize.unwrap_or(submission.audio_normalize);
+            let webdav_visible = params.webdav_visible.unwrap_or(submission.webdav_visible);
             let ai_rename = params.ai_rename.unwrap_or(submission.ai_rename);
             let ai_rename_video_prompt = params
                 .ai_rename_video_prompt
@@ -5012,6 +5379,13 @@ pub async fn update_video_source_download_options_internal(
                 flat_folder: sea_orm::Set(flat_folder),
                 download_danmaku: sea_orm::Set(download_danmaku),
                 download_subtitle: sea_orm::Set(download_subtitle),
+                download_speed_limit: sea_orm::Set(download_speed_limit),
+                priority: sea_orm::Set(priority),
+                postprocess_profile: sea_orm::Set(postprocess_profile.clone()),
+                output_container: sea_orm::Set(output_container.clone()),
+                audio_format: sea_orm::Set(audio_format.clone()),
+                audio_normalize: sea_orm::Set(audio_normalize),
+                webdav_visible: sea_orm::Set(webdav_visible),
                 ai_rename: sea_orm::Set(ai_rename),
                 ai_rename_video_prompt: sea_orm::Set(ai_rename_video_prompt.clone()),
                 ai_rename_audio_prompt: sea_orm::Set(ai_rename_audio_prompt.clone()),
@@ -5038,6 +5412,13 @@ pub async fn update_video_source_download_options_internal(
                 flat_folder,
                 download_danmaku,
                 download_subtitle,
+                download_speed_limit,
+                priority,
+                postprocess_profile,
+                output_container,
+                audio_format,
+                audio_normalize,
+                webdav_visible,
                 ai_rename,
                 ai_rename_video_prompt,
                 ai_rename_audio_prompt,
@@ -5059,6 +5440,22 @@ pub async fn update_video_source_download_options_internal(
             let flat_folder = params.flat_folder.unwrap_or(watch_later.flat_folder);
             let download_danmaku = params.download_danmaku.unwrap_or(watch_later.download_danmaku);
             let download_subtitle = params.download_subtitle.unwrap_or(watch_later.download_subtitle);
+            let download_speed_limit = params
+                .download_speed_limit
+                .map(|v| v as i32)
+                .unwrap_or(watch_later.download_speed_limit);
+            let priority = params.priority.unwrap_or(watch_later.priority);
+            let postprocess_profile = params
+                .postprocess_profile
+                .clone()
+                .unwrap_or(watch_later.postprocess_profile.clone());
+            let output_container = params
+                .output_container
+                .clone()
+                .unwrap_or(watch_later.output_container.clone());
+            let audio_format = params.audio_format.clone().unwrap_or(watch_later.audio_format.clone());
+            let audio_normalize = params.audio_normalize.unwrap_or(watch_later.audio_normalize);
+            let webdav_visible = params.webdav_visible.unwrap_or(watch_later.webdav_visible);
             let ai_rename = params.ai_rename.unwrap_or(watch_later.ai_rename);
             let ai_rename_video_prompt = params
                 .ai_rename_video_prompt
@@ -5085,6 +5482,13 @@ pub async fn update_video_source_download_options_internal(
                 flat_folder: sea_orm::Set(flat_folder),
                 download_danmaku: sea_orm::Set(download_danmaku),
                 download_subtitle: sea_orm::Set(download_subtitle),
+                download_speed_limit: sea_orm::Set(download_speed_limit),
+                priority: sea_orm::Set(priority),
+                postprocess_profile: sea_orm::Set(postprocess_profile.clone()),
+                output_container: sea_orm::Set(output_container.clone()),
+                audio_format: sea_orm::Set(audio_format.clone()),
+                audio_normalize: sea_orm::Set(audio_normalize),
+                webdav_visible: sea_orm::Set(webdav_visible),
                 ai_rename: sea_orm::Set(ai_rename),
                 ai_rename_video_prompt: sea_orm::Set(ai_rename_video_prompt.clone()),
                 ai_rename_audio_prompt: sea_orm::Set(ai_rename_audio_prompt.clone()),
@@ -5105,6 +5509,13 @@ pub async fn update_video_source_download_options_internal(
                 flat_folder,
                 download_danmaku,
                 download_subtitle,
+                download_speed_limit,
+                priority,
+                postprocess_profile,
+                output_container,
+                audio_format,
+                audio_normalize,
+                webdav_visible,
                 ai_rename,
                 ai_rename_video_prompt,
                 ai_rename_audio_prompt,
@@ -5126,6 +5537,22 @@ pub async fn update_video_source_download_options_internal(
             let flat_folder = params.flat_folder.unwrap_or(video_source.flat_folder);
             let download_danmaku = params.download_danmaku.unwrap_or(video_source.download_danmaku);
             let download_subtitle = params.download_subtitle.unwrap_or(video_source.download_subtitle);
+            let download_speed_limit = params
+                .download_speed_limit
+                .map(|v| v as i32)
+                .unwrap_or(video_source.download_speed_limit);
+            let priority = params.priority.unwrap_or(video_source.priority);
+            let postprocess_profile = params
+                .postprocess_profile
+                .clone()
+                .unwrap_or(video_source.postprocess_profile.clone());
+            let output_container = params
+                .output_container
+                .clone()
+                .unwrap_or(video_source.output_container.clone());
+            let audio_format = params.audio_format.clone().unwrap_or(video_source.audio_format.clone());
+            let audio_normalize = params.audio_normalize.unwrap_or(video_source.audio_normalize);
+            let webdav_visible = params.webdav_visible.unwrap_or(video_source.webdav_visible);
             let ai_rename = params.ai_rename.unwrap_or(video_source.ai_rename);
             let ai_rename_video_prompt = params
                 .ai_rename_video_prompt
@@ -5152,6 +5579,13 @@ pub async fn update_video_source_download_options_internal(
                 flat_folder: sea_orm::Set(flat_folder),
                 download_danmaku: sea_orm::Set(download_danmaku),
                 download_subtitle: sea_orm::Set(download_subtitle),
+                download_speed_limit: sea_orm::Set(download_speed_limit),
+                priority: sea_orm::Set(priority),
+                postprocess_profile: sea_orm::Set(postprocess_profile.clone()),
+                output_container: sea_orm::Set(output_container.clone()),
+                audio_format: sea_orm::Set(audio_format.clone()),
+                audio_normalize: sea_orm::Set(audio_normalize),
+                webdav_visible: sea_orm::Set(webdav_visible),
                 ai_rename: sea_orm::Set(ai_rename),
                 ai_rename_video_prompt: sea_orm::Set(ai_rename_video_prompt.clone()),
                 ai_rename_audio_prompt: sea_orm::Set(ai_rename_audio_prompt.clone()),
@@ -5172,6 +5606,13 @@ pub async fn update_video_source_download_options_internal(
                 flat_folder,
                 download_danmaku,
                 download_subtitle,
+                download_speed_limit,
+                priority,
+                postprocess_profile,
+                output_container,
+                audio_format,
+                audio_normalize,
+                webdav_visible,
                 ai_rename,
                 ai_rename_video_prompt,
                 ai_rename_audio_prompt,
@@ -5279,6 +5720,62 @@ pub async fn reset_video_source_path(
     }
 }
 
+/// 获取视频源的播客订阅地址，首次获取时生成访问令牌
+#[utoipa::path(
+    get,
+    path = "/api/video-sources/{source_type}/{id}/feed",
+    responses(
+        (status = 200, body = ApiResponse<crate::api::response::VideoSourceFeedResponse>),
+    )
+)]
+pub async fn get_video_source_feed(
+    Path((source_type, id)): Path<(String, i32)>,
+    Extension(db): Extension<Arc<DatabaseConnection>>,
+) -> Result<ApiResponse<crate::api::response::VideoSourceFeedResponse>, ApiError> {
+    let source = crate::api::feed::load_feed_source(&db, &source_type, id)
+        .await?
+        .ok_or(InnerApiError::NotFound(id))?;
+    let token = match source.token {
+        Some(token) => token,
+        None => {
+            let token = crate::api::feed::generate_feed_token();
+            crate::api::feed::save_feed_token(&db, &source_type, id, Some(token.clone())).await?;
+            info!("已为视频源 {} {} 生成播客订阅令牌", source_type, id);
+            token
+        }
+    };
+    Ok(ApiResponse::ok(crate::api::response::VideoSourceFeedResponse {
+        feed_path: crate::api::feed::feed_path(&source_type, id, &token),
+        source_id: id,
+        source_type,
+    }))
+}
+
+/// 重新生成视频源的播客订阅令牌，旧的订阅地址随即失效
+#[utoipa::path(
+    post,
+    path = "/api/video-sources/{source_type}/{id}/feed/reset",
+    responses(
+        (status = 200, body = ApiResponse<crate::api::response::VideoSourceFeedResponse>),
+    )
+)]
+pub async fn reset_video_source_feed(
+    Path((source_type, id)): Path<(String, i32)>,
+    Extension(db): Extension<Arc<DatabaseConnection>>,
+) -> Result<ApiResponse<crate::api::response::VideoSourceFeedResponse>, ApiError> {
+    crate::api::feed::load_feed_source(&db, &source_type, id)
+        .await?
+        .ok_or(InnerApiError::NotFound(id))?;
+    let token = crate::api::feed::generate_feed_token();
+    crate::api::feed::save_feed_token(&db, &source_type, id, Some(token.clone())).await?;
+    info!("已重新生成视频源 {} {} 的播客订阅令牌", source_type, id);
+    Ok(ApiResponse::ok(crate::api::response::VideoSourceFeedResponse {
+        feed_path: crate::api::feed::feed_path(&source_type, id, &token),
+        source_id: id,
+        source_type,
+    }))
+}
+
 /// 验证路径重设操作的安全性
 async fn validate_path_reset_safety(
     txn: &sea_orm::DatabaseTransaction,
@@ -5319,6 +5816,12 @@ async fn validate_path_reset_safety(
                 download_status: 0,
                 created_at: now_standard_string(),
                 ai_renamed: None,
+                file_size: None,
+                file_hash: None,
+                verified_at: None,
+                postprocess_status: 0,
+                postprocess_error: None,
+                postprocess_copy_path: None,
             };
 
             let api_title = if let Some(current_path) = std::path::Path::new(&video.path).parent() {
@@ -9306,6 +9809,31 @@ pub async fn get_queue_status() -> Result<ApiResponse<QueueStatusResponse>, ApiE
     Ok(ApiResponse::ok(response))
 }
 
+/// 获取各 CDN 节点的下载统计和黑名单状态
+#[utoipa::path(
+    get,
+    path = "/api/cdn/stats",
+    responses(
+        (status = 200, body = ApiResponse<crate::utils::cdn_health::CdnHealthReport>),
+    )
+)]
+pub async fn get_cdn_stats() -> Result<ApiResponse<crate::utils::cdn_health::CdnHealthReport>, ApiError> {
+    Ok(ApiResponse::ok(crate::utils::cdn_health::health_report()))
+}
+
+/// 清空 CDN 节点统计，同时解除所有节点的黑名单
+#[utoipa::path(
+    post,
+    path = "/api/cdn/stats/reset",
+    responses(
+        (status = 200, body = ApiResponse<crate::utils::cdn_health::CdnHealthReport>),
+    )
+)]
+pub async fn reset_cdn_stats() -> Result<ApiResponse<crate::utils::cdn_health::CdnHealthReport>, ApiError> {
+    crate::utils::cdn_health::reset();
+    Ok(ApiResponse::ok(crate::utils::cdn_health::health_report()))
+}
+
 /// 代理B站图片请求，解决防盗链问题
 #[utoipa::path(
     get,
@@ -9439,6 +9967,7 @@ pub async fn update_config_item_internal(
 
     // 创建配置管理器
     let manager = ConfigManager::new(db.as_ref().clone());
+    let trickplay_was_enabled = crate::config::reload_config().trickplay.enabled;
 
     // 更新配置项
     if let Err(e) = manager.update_config_item(&key, request.value.clone()).await {
@@ -9450,6 +9979,7 @@ pub async fn update_config_item_internal(
     if let Err(e) = crate::config::reload_config_bundle().await {
         warn!("重新加载配置包失败: {}", e);
     }
+    reset_trickplay_if_enabled(db, trickplay_was_enabled);
 
     // 返回响应
     let response = ConfigItemResponse {
@@ -9470,6 +10000,7 @@ pub async fn batch_update_config_internal(
     use crate::config::ConfigManager;
 
     let manager = ConfigManager::new(db.as_ref().clone());
+    let trickplay_was_enabled = crate::config::reload_config().trickplay.enabled;
 
     // 批量更新配置项
     for (key, value) in request.items {
@@ -9484,6 +10015,7 @@ pub async fn batch_update_config_internal(
         warn!("重新加载配置包失败: {}", e);
         return Err(ApiError::from(anyhow!("重新加载配置包失败: {}", e)));
     }
+    reset_trickplay_if_enabled(db, trickplay_was_enabled);
 
     let response = ConfigReloadResponse {
         success: true,
@@ -10455,7 +10987,9 @@ pub async fn get_video_play_info(
     Path(video_id): Path<String>,
     Extension(db): Extension<Arc<DatabaseConnection>>,
 ) -> Result<ApiResponse<crate::api::response::VideoPlayInfoResponse>, ApiError> {
-    use crate::api::response::{AudioStreamInfo, SubtitleStreamInfo, VideoPlayInfoResponse, VideoStreamInfo};
+    use crate::api::response::{
+        AudioStreamInfo, ChapterInfo, SubtitleStreamInfo, VideoPlayInfoResponse, VideoStreamInfo,
+    };
     use crate::bilibili::{BestStream, BiliClient, PageInfo, Stream, Video};
 
     // 查找视频信息
@@ -10548,6 +11082,7 @@ pub async fn get_video_play_info(
             video_streams: Vec::new(),
             audio_streams: Vec::new(),
             subtitle_streams: Vec::new(),
+            chapters: Vec::new(),
             video_title: video_title.clone(),
             video_duration: Some(page_info.duration),
             video_quality_description: "获取失败".to_string(),
@@ -10666,24 +11201,46 @@ pub async fn get_video_play_info(
         }
     }
 
+    // 字幕和分段章节来自同一个播放器接口，只请求一次
+    let player_info = match video.get_player_info(&page_info).await {
+        Ok(player_info) => Some(player_info),
+        Err(e) => {
+            warn!("获取播放器信息失败: {}", e);
+            None
+        }
+    };
+
     // 获取字幕信息
-    let subtitle_streams = match video.get_subtitles(&page_info).await {
-        Ok(subtitles) => {
-            subtitles
+    let subtitle_streams = match player_info.as_ref() {
+        Some(player_info) => match video.get_subtitles(player_info).await {
+            Ok(subtitles) => subtitles
                 .into_iter()
                 .map(|subtitle| SubtitleStreamInfo {
                     language: subtitle.lan.clone(),
                     language_doc: subtitle.lan.clone(), // 暂时使用language作为language_doc
                     url: format!("/api/videos/{}/subtitles/{}", video_id, subtitle.lan),
                 })
-                .collect()
-        }
-        Err(e) => {
-            warn!("获取字幕失败: {}", e);
-            Vec::new()
-        }
+                .collect(),
+            Err(e) => {
+                warn!("获取字幕失败: {}", e);
+                Vec::new()
+            }
+        },
+        None => Vec::new(),
     };
 
+    // 获取分段章节
+    let chapters = player_info
+        .map(|player_info| player_info.chapters())
+        .unwrap_or_default()
+        .into_iter()
+        .map(|chapter| ChapterInfo {
+            title: chapter.content,
+            start: chapter.from,
+            end: chapter.to,
+        })
+        .collect();
+
     let quality_desc = if !video_streams.is_empty() {
         video_streams[0].quality_description.clone()
     } else {
@@ -10696,6 +11253,7 @@ pub async fn get_video_play_info(
         video_streams,
         audio_streams,
         subtitle_streams,
+        chapters,
         video_title,
         video_duration: Some(page_info.duration),
         video_quality_description: quality_desc,
@@ -11657,6 +12215,12 @@ async fn update_bangumi_video_path_in_database(
             download_status: 0,
             created_at: now_standard_string(),
             ai_renamed: None,
+            file_size: None,
+            file_hash: None,
+            verified_at: None,
+            postprocess_status: 0,
+            postprocess_error: None,
+            postprocess_copy_path: None,
         };
 
         // 🚨 修复路径提取逻辑：处理混合路径分隔符问题
@@ -11791,6 +12355,12 @@ async fn move_bangumi_files_to_new_path(
             download_status: 0,
             created_at: now_standard_string(),
             ai_renamed: None,
+            file_size: None,
+            file_hash: None,
+            verified_at: None,
+            postprocess_status: 0,
+            postprocess_error: None,
+            postprocess_copy_path: None,
         };
 
         // 修复路径提取逻辑：处理混合路径分隔符问题
@@ -12268,6 +12838,111 @@ async fn reset_nfo_tasks_for_config_change(db: Arc<DatabaseConnection>) -> Resul
     Ok((resetted_videos_count, resetted_pages_count))
 }
 
+/// 配置项更新后，如果进度条预览图从关闭变为开启，在后台重置缺少预览图的分页的预览图任务
+fn reset_trickplay_if_enabled(db: Arc<DatabaseConnection>, was_enabled: bool) {
+    if was_enabled || !crate::config::reload_config().trickplay.enabled {
+        return;
+    }
+    tokio::spawn(async move {
+        match reset_trickplay_tasks_for_config_change(db).await {
+            Ok((videos_count, pages_count)) => info!(
+                "进度条预览图已开启，重置了 {} 个视频的 {} 个分页的预览图任务",
+                videos_count, pages_count
+            ),
+            Err(e) => error!("重置进度条预览图任务状态时出错: {:#}", e),
+        }
+    });
+}
+
+/// 预览图关闭期间完成的分页会把预览图任务（索引5）记为完成，开启后需要重新执行。
+/// 只重置视频文件存在但预览图不存在的分页，仅音频的分页不会生成预览图，不做处理
+async fn reset_trickplay_tasks_for_config_change(db: Arc<DatabaseConnection>) -> Result<(usize, usize)> {
+    use sea_orm::*;
+    use std::collections::HashSet;
+
+    let trickplay_config = crate::config::reload_config().trickplay;
+    let pages = page::Entity::find()
+        .inner_join(video::Entity)
+        .filter(video::Column::Deleted.eq(0))
+        .filter(page::Column::Path.is_not_null())
+        .select_only()
+        .columns([
+            page::Column::Id,
+            page::Column::VideoId,
+            page::Column::Path,
+            page::Column::DownloadStatus,
+        ])
+        .into_tuple::<(i32, i32, Option<String>, u32)>()
+        .all(db.as_ref())
+        .await?;
+
+    let resetted_pages = tokio::task::spawn_blocking(move || {
+        pages
+            .into_iter()
+            .filter_map(|(id, video_id, path, download_status)| {
+                let mut page_status = PageStatus::from(download_status);
+                if page_status.get(5) != crate::utils::status::STATUS_OK {
+                    return None;
+                }
+                let path = std::path::PathBuf::from(path?);
+                let is_video = path
+                    .extension()
+                    .and_then(|ext| ext.to_str())
+                    .is_some_and(|ext| matches!(ext, "mp4" | "mkv"));
+                if !is_video
+                    || !path.is_file()
+                    || crate::utils::trickplay::output_path(&trickplay_config, &path).exists()
+                {
+                    return None;
+                }
+                page_status.set(5, 0);
+                Some((id, video_id, u32::from(page_status)))
+            })
+            .collect::<Vec<_>>()
+    })
+    .await?;
+    if resetted_pages.is_empty() {
+        return Ok((0, 0));
+    }
+    let video_ids = resetted_pages
+        .iter()
+        .map(|(_, video_id, _)| *video_id)
+        .collect::<HashSet<_>>();
+    let videos = video::Entity::find()
+        .filter(video::Column::Id.is_in(video_ids.iter().copied()))
+        .select_only()
+        .columns([video::Column::Id, video::Column::DownloadStatus])
+        .into_tuple::<(i32, u32)>()
+        .all(db.as_ref())
+        .await?;
+
+    let txn = db.begin().await?;
+    for (id, _, download_status) in &resetted_pages {
+        page::Entity::update(page::ActiveModel {
+            id: sea_orm::ActiveValue::Unchanged(*id),
+            download_status: sea_orm::Set(*download_status),
+            ..Default::default()
+        })
+        .exec(&txn)
+        .await?;
+    }
+    // 分页状态变化后需要同时重置视频的分P下载任务（索引4），视频才会重新进入下载流程
+    for (id, download_status) in videos {
+        let mut video_status = VideoStatus::from(download_status);
+        video_status.set(4, 0);
+        video::Entity::update(video::ActiveModel {
+            id: sea_orm::ActiveValue::Unchanged(id),
+            download_status: sea_orm::Set(video_status.into()),
+            ..Default::default()
+        })
+        .exec(&txn)
+        .await?;
+    }
+    txn.commit().await?;
+
+    Ok((video_ids.len(), resetted_pages.len()))
+}
+
 /// 从全局缓存中获取番剧季标题
 /// 如果缓存中没有，返回None（避免在API响应中阻塞）
 async fn get_cached_season_title(season_id: &str) -> Option<String> {
@@ -12416,6 +13091,7 @@ ORDER BY
         last_scan_time: task_status.last_run.map(to_standard_string),
         next_scan_time: task_status.next_run.map(to_standard_string),
         is_scanning,
+        schedule: crate::task::schedule::schedule_status(),
     };
 
     Ok(ApiResponse::ok(crate::api::response::DashBoardResponse {
@@ -12521,6 +13197,47 @@ pub async fn test_notification_handler(
     }
 }
 
+/// 测试媒体服务器连接
+#[utoipa::path(
+    post,
+    path = "/api/media-server/test",
+    responses(
+        (status = 200, description = "测试连接结果", body = ApiResponse<crate::api::response::TestMediaServerResponse>),
+        (status = 400, description = "配置错误", body = String),
+        (status = 500, description = "服务器内部错误", body = String)
+    )
+)]
+pub async fn test_media_server_handler() -> Result<ApiResponse<crate::api::response::TestMediaServerResponse>, ApiError>
+{
+    let config = crate::config::reload_config().media_server;
+
+    // 不要求已启用，便于在开启前先验证地址和密钥
+    let client = match crate::utils::media_server::MediaServerClient::new(config) {
+        Ok(client) => client,
+        Err(e) => {
+            return Ok(ApiResponse::bad_request(
+                crate::api::response::TestMediaServerResponse {
+                    success: false,
+                    message: e.to_string(),
+                },
+            ));
+        }
+    };
+
+    match client.test_connection().await {
+        Ok(description) => Ok(ApiResponse::ok(crate::api::response::TestMediaServerResponse {
+            success: true,
+            message: format!("连接成功: {}", description),
+        })),
+        Err(e) => Ok(ApiResponse::bad_request(
+            crate::api::response::TestMediaServerResponse {
+                success: false,
+                message: format!("连接失败: {:#}", e),
+            },
+        )),
+    }
+}
+
 /// 获取推送配置
 #[utoipa::path(
     get,
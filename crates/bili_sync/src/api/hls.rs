@@ -0,0 +1,1037 @@
+//! 网页播放器的 HLS 转码播放
+//!
+//! `stream_video` 直接提供原始文件，浏览器不支持 HEVC、AV1、杜比视界等编码时无法播放，网络较慢时也无法降低码率。
+//! 这里按需用 ffmpeg 把本地文件切成 fMP4 分片：客户端支持的视频编码直接复制，其余实时转码为 H.264，
+//! 并按源分辨率提供多个码率档位。每个档位对应一个 ffmpeg 会话，同时运行的会话总数和单个客户端的会话数受配置限制，
+//! 空闲的会话由后台任务结束并删除分片。
+//!
+//! 档位播放列表一次性列出全部分片，播放器可以直接跳转到任意位置。转码档位的关键帧按固定的分片时长强制对齐，
+//! 请求的分片离 ffmpeg 当前的进度太远（或在本次转码的起点之前）时，从该分片对应的时间点重新启动 ffmpeg，
+//! 并保留原始时间戳使分片能够衔接。直接复制视频流时只能在源文件的关键帧处切分，播放列表按 ffprobe 读出的关键帧
+//! 时间推算 ffmpeg 的切分位置；此时重新定位会吸附到之前的关键帧、与播放列表错位，因此始终从头复制，
+//! 复制不需要编码，很快就能到达请求的分片。
+//!
+//! 原生 HLS 播放器无法附加请求头，主播放列表通过 Authorization 头或 `token` 查询参数认证，认证后签发一个随机的
+//! 播放密钥放在档位地址的路径中，档位播放列表和分片使用的相对地址会自动带上密钥，不需要在地址中重复传递 auth_token。
+
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
+use std::path::{Path as FsPath, PathBuf};
+use std::process::Stdio;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, LazyLock};
+use std::time::{Duration, Instant};
+
+use anyhow::{bail, Context, Result};
+use axum::extract::{ConnectInfo, Path, Query};
+use axum::http::{header, HeaderMap, HeaderValue, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::Extension;
+use sea_orm::DatabaseConnection;
+use serde::Deserialize;
+use tokio::fs;
+use tokio::process::Child;
+use tokio::sync::Mutex;
+use tracing::{debug, error, info, warn};
+
+use crate::api::auth::token_matches;
+use crate::api::video_stream::find_video_file;
+use crate::config::HlsConfig;
+
+/// 转码档位：高度和视频码率（kbps）
+const LADDER: [(u32, u32); 4] = [(1080, 5000), (720, 2800), (480, 1400), (360, 800)];
+
+/// 浏览器普遍支持直接复制的音频编码
+const COPY_AUDIO_CODECS: [&str; 2] = ["aac", "mp3"];
+
+static SESSIONS: LazyLock<Mutex<HashMap<SessionKey, HlsSession>>> = LazyLock::new(|| Mutex::new(HashMap::new()));
+static SESSION_COUNTER: AtomicU64 = AtomicU64::new(0);
+static PLAYBACK_KEYS: LazyLock<Mutex<HashMap<String, PlaybackKey>>> = LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// 播放密钥闲置多久后失效，需要长于暂停播放的时间
+const PLAYBACK_KEY_TTL: Duration = Duration::from_secs(6 * 3600);
+
+/// 请求的分片超出 ffmpeg 下一个分片多少个时重新定位，而不是等待 ffmpeg 转码到该位置
+const SEEK_AHEAD_SEGMENTS: u32 = 3;
+
+/// ffmpeg 自己写出的播放列表，只用来判断转码进度，返回给播放器的播放列表单独生成
+const FFMPEG_PLAYLIST: &str = "ffmpeg.m3u8";
+
+/// 同一档位名称在不同客户端下可能是复制或转码，会话按输入文件和完整的档位区分
+type SessionKey = (PathBuf, Variant);
+
+struct HlsSession {
+    dir: PathBuf,
+    /// 丢弃会话时结束 ffmpeg 进程
+    child: Child,
+    /// 启动会话的客户端
+    client: IpAddr,
+    last_access: Instant,
+    variant: Variant,
+    /// 启动会话时的媒体信息，用来判断会话的档位是否与后续请求的客户端编码一致
+    probe: MediaProbe,
+    /// 分片总数
+    segment_count: u32,
+    /// 当前 ffmpeg 进程开始转码的分片序号
+    start_segment: u32,
+}
+
+/// 主播放列表认证通过后签发的播放密钥，只能访问对应视频
+struct PlaybackKey {
+    video_id: String,
+    client: IpAddr,
+    last_access: Instant,
+}
+
+#[derive(Deserialize)]
+pub struct HlsQuery {
+    /// 客户端可直接播放的视频编码，逗号分隔，例如 `avc,hevc,av1`
+    codecs: Option<String>,
+    /// 无法附加请求头的播放器使用的 auth_token
+    token: Option<String>,
+}
+
+/// ffprobe 得到的媒体信息
+#[derive(Debug, Clone, Default, PartialEq)]
+struct MediaProbe {
+    /// 视频编码族：avc、hevc、av1、vp9 或 dovi（杜比视界）
+    video_family: Option<String>,
+    width: u32,
+    height: u32,
+    /// 整体码率（bps），未知时为 0
+    bit_rate: u64,
+    /// 时长（秒）
+    duration: f64,
+    audio_codec: Option<String>,
+}
+
+/// 一个码率档位的转码方式
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct Variant {
+    name: String,
+    /// 为 None 时直接复制视频流
+    height: Option<u32>,
+    width: u32,
+    /// 写入主播放列表的带宽（bps）
+    bandwidth: u64,
+    video_bitrate: Option<u32>,
+    copy_audio: bool,
+}
+
+fn hls_root() -> PathBuf {
+    std::env::temp_dir().join("bili-sync-hls")
+}
+
+fn parse_probe(json: &serde_json::Value) -> MediaProbe {
+    let mut probe = MediaProbe {
+        bit_rate: json["format"]["bit_rate"]
+            .as_str()
+            .and_then(|rate| rate.parse().ok())
+            .unwrap_or(0),
+        duration: json["format"]["duration"]
+            .as_str()
+            .and_then(|duration| duration.parse().ok())
+            .unwrap_or(0.0),
+        ..Default::default()
+    };
+    let Some(streams) = json["streams"].as_array() else {
+        return probe;
+    };
+    if let Some(video) = streams.iter().find(|stream| {
+        stream["codec_type"] == "video" && stream["disposition"]["attached_pic"].as_i64().unwrap_or(0) == 0
+    }) {
+        let tag = video["codec_tag_string"].as_str().unwrap_or_default();
+        let family = match (video["codec_name"].as_str().unwrap_or_default(), tag) {
+            // 浏览器无法解码杜比视界的增强层，当作单独的编码处理
+            (_, "dvh1" | "dvhe" | "dav1") => "dovi",
+            ("h264", _) => "avc",
+            ("hevc", _) => "hevc",
+            ("av1", _) => "av1",
+            ("vp9", _) => "vp9",
+            (other, _) => other,
+        };
+        probe.video_family = Some(family.to_string());
+        probe.width = video["width"].as_u64().unwrap_or(0) as u32;
+        probe.height = video["height"].as_u64().unwrap_or(0) as u32;
+    }
+    probe.audio_codec = streams
+        .iter()
+        .find(|stream| stream["codec_type"] == "audio")
+        .and_then(|stream| stream["codec_name"].as_str())
+        .map(str::to_string);
+    probe
+}
+
+async fn probe_media(path: &FsPath) -> Result<MediaProbe> {
+    let output = tokio::process::Command::new("ffprobe")
+        .args(["-v", "error", "-print_format", "json", "-show_format", "-show_streams"])
+        .arg(path)
+        .output()
+        .await
+        .context("无法执行 ffprobe")?;
+    if !output.status.success() {
+        bail!(
+            "ffprobe 无法解析文件: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).context("ffprobe 输出格式错误")?;
+    Ok(parse_probe(&json))
+}
+
+/// 根据媒体信息和客户端支持的编码规划码率档位
+///
+/// 第一个档位保持源分辨率，客户端支持源编码时直接复制；之后是低于源分辨率的转码档位
+fn plan_variants(probe: &MediaProbe, client_codecs: &[&str]) -> Vec<Variant> {
+    let copy_audio = probe
+        .audio_codec
+        .as_deref()
+        .is_none_or(|codec| COPY_AUDIO_CODECS.contains(&codec));
+    let Some(family) = probe.video_family.as_deref() else {
+        // 没有视频轨时只提供一个档位
+        return vec![Variant {
+            name: "source".to_string(),
+            height: None,
+            width: 0,
+            bandwidth: probe.bit_rate.max(192_000),
+            video_bitrate: None,
+            copy_audio,
+        }];
+    };
+    let scaled_width = |height: u32| {
+        if probe.height == 0 {
+            return 0;
+        }
+        // libx264 要求宽度为偶数
+        ((probe.width as u64 * height as u64 / probe.height as u64) as u32 + 1) & !1
+    };
+    let source_bitrate = LADDER
+        .iter()
+        .rev()
+        .find(|(height, _)| *height >= probe.height)
+        .unwrap_or(&LADDER[0])
+        .1;
+
+    let mut variants = Vec::new();
+    if client_codecs.contains(&family) {
+        variants.push(Variant {
+            name: "source".to_string(),
+            height: None,
+            width: probe.width,
+            bandwidth: if probe.bit_rate > 0 {
+                probe.bit_rate
+            } else {
+                source_bitrate as u64 * 1000
+            },
+            video_bitrate: None,
+            copy_audio,
+        });
+    } else {
+        variants.push(Variant {
+            name: "source".to_string(),
+            height: Some(probe.height),
+            width: probe.width,
+            bandwidth: (source_bitrate as u64 + 192) * 1000,
+            video_bitrate: Some(source_bitrate),
+            copy_audio,
+        });
+    }
+    for (height, bitrate) in LADDER {
+        if height >= probe.height {
+            continue;
+        }
+        variants.push(Variant {
+            name: format!("{}p", height),
+            height: Some(height),
+            width: scaled_width(height),
+            bandwidth: (bitrate as u64 + 192) * 1000,
+            video_bitrate: Some(bitrate),
+            copy_audio,
+        });
+    }
+    variants
+}
+
+fn master_playlist(variants: &[Variant], key: &str, codecs_query: &str) -> String {
+    let mut playlist = String::from("#EXTM3U\n#EXT-X-VERSION:7\n");
+    for variant in variants {
+        playlist.push_str(&format!("#EXT-X-STREAM-INF:BANDWIDTH={}", variant.bandwidth));
+        if let Some(height) = variant.height.filter(|_| variant.width > 0) {
+            playlist.push_str(&format!(",RESOLUTION={}x{}", variant.width, height));
+        }
+        playlist.push_str(&format!(
+            ",NAME=\"{}\"\n{}/{}/index.m3u8?codecs={}\n",
+            variant.name, key, variant.name, codecs_query
+        ));
+    }
+    playlist
+}
+
+/// 档位播放列表，按各分片的时长列出全部分片，分片地址带上客户端支持的编码，会话被清理后可以据此重新启动
+fn variant_playlist(segments: &[f64], codecs_query: &str) -> String {
+    let target_duration = segments.iter().fold(1.0_f64, |max, length| max.max(length.round()));
+    let mut playlist = format!(
+        "#EXTM3U\n#EXT-X-VERSION:7\n#EXT-X-TARGETDURATION:{}\n#EXT-X-PLAYLIST-TYPE:VOD\n#EXT-X-MEDIA-SEQUENCE:0\n\
+         #EXT-X-INDEPENDENT-SEGMENTS\n#EXT-X-MAP:URI=\"init.mp4?codecs={}\"\n",
+        target_duration as u32, codecs_query
+    );
+    for (index, length) in segments.iter().enumerate() {
+        playlist.push_str(&format!(
+            "#EXTINF:{:.3},\nseg{:05}.m4s?codecs={}\n",
+            length.max(0.001),
+            index,
+            codecs_query
+        ));
+    }
+    playlist.push_str("#EXT-X-ENDLIST\n");
+    playlist
+}
+
+/// 转码档位的分片时长：关键帧按固定时长强制对齐，只有最后一个分片较短
+fn fixed_segments(duration: f64, segment_duration: u32) -> Vec<f64> {
+    let segment_duration = segment_duration.max(1);
+    let count = ((duration / segment_duration as f64).ceil() as u32).max(1);
+    (0..count)
+        .map(|index| (duration - (index * segment_duration) as f64).min(segment_duration as f64))
+        .collect()
+}
+
+/// 直接复制视频流时的分片时长，与 ffmpeg HLS 封装的切分规则一致：
+/// 第 n 个分片在距第一个视频包不少于 n 倍分片时长的第一个关键帧处结束
+fn keyframe_segments(first_pts: f64, keyframes: &[f64], duration: f64, segment_duration: u32) -> Vec<f64> {
+    let segment_duration = segment_duration.max(1) as f64;
+    let mut segments = Vec::new();
+    let mut segment_start = 0.0;
+    for keyframe in keyframes.iter().map(|keyframe| keyframe - first_pts) {
+        if keyframe >= (segments.len() + 1) as f64 * segment_duration {
+            segments.push(keyframe - segment_start);
+            segment_start = keyframe;
+        }
+    }
+    segments.push((duration - segment_start).max(0.001));
+    segments
+}
+
+/// 读取视频流第一个包和所有关键帧的时间戳（秒），只读取包信息，不需要解码
+async fn probe_keyframes(path: &FsPath) -> Result<(f64, Vec<f64>)> {
+    let output = tokio::process::Command::new("ffprobe")
+        .args([
+            "-v",
+            "error",
+            "-select_streams",
+            "V:0",
+            "-show_entries",
+            "packet=pts_time,flags",
+            "-of",
+            "csv=p=0",
+        ])
+        .arg(path)
+        .output()
+        .await
+        .context("无法执行 ffprobe")?;
+    if !output.status.success() {
+        bail!(
+            "ffprobe 无法读取关键帧: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    parse_keyframes(&String::from_utf8_lossy(&output.stdout)).context("ffprobe 没有输出视频包")
+}
+
+fn parse_keyframes(output: &str) -> Option<(f64, Vec<f64>)> {
+    let mut packets = output.lines().filter_map(|line| {
+        let (pts, flags) = line.trim().split_once(',')?;
+        Some((pts.parse::<f64>().ok()?, flags.contains('K')))
+    });
+    let (first_pts, _) = packets.next()?;
+    let keyframes = std::iter::once((first_pts, true))
+        .chain(packets)
+        .filter(|(_, key)| *key)
+        .map(|(pts, _)| pts)
+        .collect();
+    Some((first_pts, keyframes))
+}
+
+/// 规划档位的分片时长，直接复制视频流时按关键帧推算
+async fn plan_segments(input: &FsPath, probe: &MediaProbe, variant: &Variant, config: &HlsConfig) -> Result<Vec<f64>> {
+    if variant.height.is_none() && probe.video_family.is_some() {
+        let (first_pts, keyframes) = probe_keyframes(input).await?;
+        return Ok(keyframe_segments(
+            first_pts,
+            &keyframes,
+            probe.duration,
+            config.segment_duration,
+        ));
+    }
+    Ok(fixed_segments(probe.duration, config.segment_duration))
+}
+
+/// 从分片文件名中取出序号
+fn segment_index(name: &str) -> Option<u32> {
+    name.strip_prefix("seg")?.strip_suffix(".m4s")?.parse().ok()
+}
+
+/// 请求的分片在本次转码的起点之前，或者离 ffmpeg 下一个要写出的分片太远时，需要从该分片重新启动 ffmpeg
+fn needs_seek(start_segment: u32, latest: Option<u32>, requested: u32) -> bool {
+    let next = latest.map_or(start_segment, |latest| latest + 1);
+    requested < start_segment || requested > next + SEEK_AHEAD_SEGMENTS
+}
+
+/// ffmpeg 本次转码已经写出的最后一个分片
+async fn latest_segment(dir: &FsPath) -> Option<u32> {
+    let playlist = fs::read_to_string(dir.join(FFMPEG_PLAYLIST)).await.ok()?;
+    playlist.lines().rev().find_map(segment_index)
+}
+
+fn ffmpeg_args(input: &FsPath, dir: &FsPath, variant: &Variant, config: &HlsConfig, start_segment: u32) -> Vec<String> {
+    let mut args: Vec<String> = ["-hide_banner", "-loglevel", "error", "-nostdin", "-y"]
+        .map(String::from)
+        .into();
+    // 只有转码档位会从中间启动，见 `seek_session`
+    if start_segment > 0 {
+        args.extend(["-ss".to_string(), (start_segment * config.segment_duration).to_string()]);
+    }
+    // 保留原始时间戳，重新定位后写出的分片与播放列表中的位置一致
+    args.extend(["-copyts", "-i"].map(String::from));
+    args.push(input.to_string_lossy().into_owned());
+    args.extend(["-map", "0:V:0?", "-map", "0:a:0?"].map(String::from));
+    match (variant.height, variant.video_bitrate) {
+        (Some(height), Some(bitrate)) => {
+            args.extend(["-c:v".to_string(), config.video_encoder.clone()]);
+            if config.video_encoder == "libx264" {
+                args.extend(["-preset", "veryfast"].map(String::from));
+            }
+            args.extend([
+                "-b:v".to_string(),
+                format!("{}k", bitrate),
+                "-maxrate".to_string(),
+                format!("{}k", bitrate * 3 / 2),
+                "-bufsize".to_string(),
+                format!("{}k", bitrate * 2),
+                "-vf".to_string(),
+                format!("scale=-2:{}", height),
+                "-pix_fmt".to_string(),
+                "yuv420p".to_string(),
+                // 关键帧与分片边界对齐，各档位之间可以无缝切换
+                "-force_key_frames".to_string(),
+                format!("expr:gte(t,(n_forced+{})*{})", start_segment, config.segment_duration),
+            ]);
+        }
+        _ => {
+            args.extend(["-c:v", "copy"].map(String::from));
+        }
+    }
+    if variant.copy_audio {
+        args.extend(["-c:a", "copy"].map(String::from));
+    } else {
+        args.extend(["-c:a", "aac", "-b:a", "192k", "-ac", "2"].map(String::from));
+    }
+    args.extend([
+        "-f".to_string(),
+        "hls".to_string(),
+        "-hls_time".to_string(),
+        config.segment_duration.to_string(),
+        "-hls_playlist_type".to_string(),
+        "event".to_string(),
+        "-hls_segment_type".to_string(),
+        "fmp4".to_string(),
+        "-start_number".to_string(),
+        start_segment.to_string(),
+        "-hls_fmp4_init_filename".to_string(),
+        "init.mp4".to_string(),
+        "-hls_flags".to_string(),
+        "temp_file+independent_segments".to_string(),
+        "-hls_segment_filename".to_string(),
+        dir.join("seg%05d.m4s").to_string_lossy().into_owned(),
+    ]);
+    args.push(dir.join(FFMPEG_PLAYLIST).to_string_lossy().into_owned());
+    args
+}
+
+/// 档位名称和分片文件名只允许 ffmpeg 生成的字符，避免读取会话目录之外的文件
+fn is_valid_name(name: &str) -> bool {
+    !name.is_empty()
+        && !name.starts_with('.')
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '.' || c == '_' || c == '-')
+}
+
+fn is_segment_file(name: &str) -> bool {
+    is_valid_name(name) && (name == "init.mp4" || (name.starts_with("seg") && name.ends_with(".m4s")))
+}
+
+fn parse_client_codecs(query: &HlsQuery) -> Vec<String> {
+    let codecs: Vec<String> = query
+        .codecs
+        .as_deref()
+        .unwrap_or_default()
+        .split(',')
+        .map(|codec| codec.trim().to_ascii_lowercase())
+        .filter(|codec| !codec.is_empty())
+        .collect();
+    if codecs.is_empty() {
+        // 所有浏览器都支持 H.264
+        vec!["avc".to_string()]
+    } else {
+        codecs
+    }
+}
+
+/// 客户端的会话已满时结束它最久未访问的会话（通常是切换清晰度或视频后不再使用的会话）；
+/// 总会话已满时结束最久未访问的会话，仍无法腾出名额时返回错误
+fn make_room(sessions: &mut HashMap<SessionKey, HlsSession>, client: IpAddr, config: &HlsConfig) -> Result<()> {
+    loop {
+        let client_sessions = sessions.values().filter(|session| session.client == client).count();
+        if client_sessions < config.max_sessions_per_client.max(1) {
+            break;
+        }
+        let Some(oldest) = sessions
+            .iter()
+            .filter(|(_, session)| session.client == client)
+            .min_by_key(|(_, session)| session.last_access)
+            .map(|(key, _)| key.clone())
+        else {
+            break;
+        };
+        if let Some(session) = sessions.remove(&oldest) {
+            info!("客户端 {} 的转码会话已满，结束其最久未访问的会话: {:?}", client, oldest);
+            spawn_remove_dir(session.dir);
+        }
+    }
+    let max_sessions = config.max_sessions;
+    while sessions.len() >= max_sessions.max(1) {
+        let Some(oldest) = sessions
+            .iter()
+            .filter(|(_, session)| session.last_access.elapsed() > Duration::from_secs(10))
+            .min_by_key(|(_, session)| session.last_access)
+            .map(|(key, _)| key.clone())
+        else {
+            bail!("同时转码的会话已达上限 {}，请稍后重试", max_sessions);
+        };
+        if let Some(session) = sessions.remove(&oldest) {
+            info!("转码会话已满，结束最久未访问的会话: {:?}", oldest);
+            spawn_remove_dir(session.dir);
+        }
+    }
+    Ok(())
+}
+
+fn spawn_remove_dir(dir: PathBuf) {
+    tokio::spawn(async move {
+        if let Err(e) = fs::remove_dir_all(&dir).await {
+            debug!("删除 HLS 会话目录 {:?} 失败: {:#}", dir, e);
+        }
+    });
+}
+
+fn spawn_ffmpeg(
+    input: &FsPath,
+    dir: &FsPath,
+    variant: &Variant,
+    config: &HlsConfig,
+    start_segment: u32,
+) -> Result<Child> {
+    let log = std::fs::File::create(dir.join("ffmpeg.log"))?;
+    tokio::process::Command::new("ffmpeg")
+        .args(ffmpeg_args(input, dir, variant, config, start_segment))
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(log)
+        .kill_on_drop(true)
+        .spawn()
+        .context("无法启动 ffmpeg")
+}
+
+/// 获取或启动会话，返回会话目录；新会话从 `start_segment` 开始转码，直接复制视频流时始终从头开始
+async fn ensure_session(
+    input: &FsPath,
+    probe: &MediaProbe,
+    variant: &Variant,
+    segment_count: u32,
+    start_segment: u32,
+    client: IpAddr,
+    config: &HlsConfig,
+) -> Result<PathBuf> {
+    let key = (input.to_path_buf(), variant.clone());
+    let mut sessions = SESSIONS.lock().await;
+    if let Some(session) = sessions.get_mut(&key) {
+        session.last_access = Instant::now();
+        return Ok(session.dir.clone());
+    }
+    make_room(&mut sessions, client, config)?;
+
+    let start_segment = if variant.height.is_none() { 0 } else { start_segment };
+    let dir = hls_root().join(format!(
+        "{}-{}",
+        SESSION_COUNTER.fetch_add(1, Ordering::Relaxed),
+        variant.name
+    ));
+    fs::create_dir_all(&dir).await?;
+    let child = spawn_ffmpeg(input, &dir, variant, config, start_segment)?;
+    info!("开始 HLS 会话 {:?}（{}）", input, variant.name);
+    sessions.insert(
+        key,
+        HlsSession {
+            dir: dir.clone(),
+            child,
+            client,
+            last_access: Instant::now(),
+            variant: variant.clone(),
+            probe: probe.clone(),
+            segment_count,
+            start_segment,
+        },
+    );
+    Ok(dir)
+}
+
+/// 会话的档位按请求的客户端编码重新规划后仍然相同时才能复用，避免只支持 H.264 的客户端拿到复制的 HEVC 分片
+fn session_matches(variant: &Variant, probe: &MediaProbe, variant_name: &str, client_codecs: &[&str]) -> bool {
+    variant.name == variant_name && plan_variants(probe, client_codecs).contains(variant)
+}
+
+/// 查找与客户端编码匹配的会话，返回会话目录和档位
+async fn touch_session(input: &FsPath, variant_name: &str, client_codecs: &[&str]) -> Option<(PathBuf, Variant)> {
+    let mut sessions = SESSIONS.lock().await;
+    let (_, session) = sessions.iter_mut().find(|((path, variant), session)| {
+        path == input && session_matches(variant, &session.probe, variant_name, client_codecs)
+    })?;
+    session.last_access = Instant::now();
+    Some((session.dir.clone(), session.variant.clone()))
+}
+
+async fn remove_session(input: &FsPath, variant: &Variant) {
+    if let Some(session) = SESSIONS.lock().await.remove(&(input.to_path_buf(), variant.clone())) {
+        spawn_remove_dir(session.dir);
+    }
+}
+
+/// 准备转码请求的分片，超出分片总数时返回 false；离当前进度太远时从该分片重新启动 ffmpeg，已写出的分片保留在目录中
+///
+/// 直接复制视频流的会话从中间启动时会吸附到之前的关键帧，分片与播放列表无法对应，只等待 ffmpeg 复制到该分片
+async fn seek_session(input: &FsPath, variant: &Variant, index: u32, config: &HlsConfig) -> Result<bool> {
+    let mut sessions = SESSIONS.lock().await;
+    let Some(session) = sessions.get_mut(&(input.to_path_buf(), variant.clone())) else {
+        bail!("HLS 会话已结束");
+    };
+    if index >= session.segment_count {
+        return Ok(false);
+    }
+    if session.variant.height.is_none() || !needs_seek(session.start_segment, latest_segment(&session.dir).await, index)
+    {
+        return Ok(true);
+    }
+    debug!("HLS 会话 {:?}（{}）跳转到第 {} 个分片", input, variant.name, index);
+    // 等旧进程退出后再删除进度记录，避免它写回过期的播放列表
+    let _ = session.child.kill().await;
+    let _ = fs::remove_file(session.dir.join(FFMPEG_PLAYLIST)).await;
+    session.child = spawn_ffmpeg(input, &session.dir, &session.variant, config, index)?;
+    session.start_segment = index;
+    Ok(true)
+}
+
+/// 等待 ffmpeg 写出分片或初始化分片，转码结束仍没有该文件时返回 None
+async fn wait_for_file(input: &FsPath, variant: &Variant, dir: &FsPath, file: &str) -> Result<Option<Vec<u8>>> {
+    let path = dir.join(file);
+    for _ in 0..300 {
+        if let Ok(content) = fs::read(&path).await {
+            return Ok(Some(content));
+        }
+        let exit_status = {
+            let mut sessions = SESSIONS.lock().await;
+            match sessions.get_mut(&(input.to_path_buf(), variant.clone())) {
+                Some(session) => session.child.try_wait()?,
+                None => bail!("HLS 会话已结束"),
+            }
+        };
+        match exit_status {
+            // ffmpeg 退出但没有产出分片，说明转码失败
+            Some(status) if !status.success() => {
+                let log = fs::read_to_string(dir.join("ffmpeg.log")).await.unwrap_or_default();
+                remove_session(input, variant).await;
+                bail!("ffmpeg error: {}", log.trim());
+            }
+            Some(_) if fs::metadata(&path).await.is_err() => return Ok(None),
+            _ => {}
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    bail!("等待 HLS 分片 {} 超时", file)
+}
+
+fn text_response(status: StatusCode, message: impl Into<String>) -> Response {
+    (status, message.into()).into_response()
+}
+
+fn playlist_response(content: String) -> Response {
+    let mut response = content.into_response();
+    let headers = response.headers_mut();
+    headers.insert(
+        header::CONTENT_TYPE,
+        HeaderValue::from_static("application/vnd.apple.mpegurl"),
+    );
+    headers.insert(header::CACHE_CONTROL, HeaderValue::from_static("no-cache"));
+    response
+}
+
+/// 请求头或查询参数中的令牌与 auth_token 一致，与管理页接口的认证规则相同；未设置 auth_token 时拒绝访问
+fn authorized(headers: &HeaderMap, query: &HlsQuery) -> bool {
+    let token = crate::config::reload_config().auth_token.unwrap_or_default();
+    if token.is_empty() {
+        return false;
+    }
+    let header_token = headers.get(header::AUTHORIZATION).and_then(|value| value.to_str().ok());
+    token_matches(Some(&token), header_token) || token_matches(Some(&token), query.token.as_deref())
+}
+
+/// 签发播放密钥
+async fn issue_playback_key(video_id: &str, client: IpAddr) -> String {
+    let key = uuid::Uuid::new_v4().simple().to_string();
+    PLAYBACK_KEYS.lock().await.insert(
+        key.clone(),
+        PlaybackKey {
+            video_id: video_id.to_string(),
+            client,
+            last_access: Instant::now(),
+        },
+    );
+    key
+}
+
+/// 校验播放密钥，返回签发时的客户端
+async fn check_playback_key(key: &str, video_id: &str) -> Option<IpAddr> {
+    let mut keys = PLAYBACK_KEYS.lock().await;
+    let playback_key = keys.get_mut(key)?;
+    if playback_key.video_id != video_id || playback_key.last_access.elapsed() >= PLAYBACK_KEY_TTL {
+        return None;
+    }
+    playback_key.last_access = Instant::now();
+    Some(playback_key.client)
+}
+
+/// HLS 主播放列表
+pub async fn hls_master_playlist(
+    Path(video_id): Path<String>,
+    Query(query): Query<HlsQuery>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    Extension(db): Extension<Arc<DatabaseConnection>>,
+) -> Response {
+    if !authorized(&headers, &query) {
+        return text_response(StatusCode::UNAUTHORIZED, "Unauthorized");
+    }
+    let result = async {
+        let input = find_video_file(&video_id, &db).await?;
+        let probe = probe_media(&input).await?;
+        let client_codecs = parse_client_codecs(&query);
+        let client_codecs: Vec<&str> = client_codecs.iter().map(String::as_str).collect();
+        let variants = plan_variants(&probe, &client_codecs);
+        let key = issue_playback_key(&video_id, addr.ip()).await;
+        Ok::<_, anyhow::Error>(master_playlist(&variants, &key, &client_codecs.join(",")))
+    }
+    .await;
+    match result {
+        Ok(content) => playlist_response(content),
+        Err(e) => {
+            error!("生成 HLS 主播放列表失败: {:#}", e);
+            text_response(StatusCode::INTERNAL_SERVER_ERROR, format!("{:#}", e))
+        }
+    }
+}
+
+/// HLS 档位播放列表和分片
+pub async fn hls_variant_file(
+    Path((video_id, key, variant_name, file)): Path<(String, String, String, String)>,
+    Query(query): Query<HlsQuery>,
+    Extension(db): Extension<Arc<DatabaseConnection>>,
+) -> Response {
+    let Some(client) = check_playback_key(&key, &video_id).await else {
+        return text_response(StatusCode::UNAUTHORIZED, "Unauthorized");
+    };
+    match hls_variant_file_impl(&video_id, &variant_name, &file, &query, client, &db).await {
+        Ok(response) => response,
+        Err(e) => {
+            error!("HLS 请求 {}/{}/{} 失败: {:#}", video_id, variant_name, file, e);
+            text_response(StatusCode::SERVICE_UNAVAILABLE, format!("{:#}", e))
+        }
+    }
+}
+
+async fn hls_variant_file_impl(
+    video_id: &str,
+    variant_name: &str,
+    file: &str,
+    query: &HlsQuery,
+    client: IpAddr,
+    db: &DatabaseConnection,
+) -> Result<Response> {
+    if !is_valid_name(variant_name) || (file != "index.m3u8" && !is_segment_file(file)) {
+        return Ok(text_response(StatusCode::NOT_FOUND, "Not Found"));
+    }
+    let input = find_video_file(video_id, db).await?;
+    let config = crate::config::reload_config().hls;
+    let client_codecs = parse_client_codecs(query);
+    let client_codecs: Vec<&str> = client_codecs.iter().map(String::as_str).collect();
+    let index = segment_index(file);
+
+    // 播放列表请求时从头开始转码；会话已被清理时，分片请求从该分片开始重新建立会话
+    let (dir, variant) = match touch_session(&input, variant_name, &client_codecs).await {
+        Some(session) if file != "index.m3u8" => session,
+        _ => {
+            let probe = probe_media(&input).await?;
+            let Some(variant) = plan_variants(&probe, &client_codecs)
+                .into_iter()
+                .find(|variant| variant.name == variant_name)
+            else {
+                return Ok(text_response(StatusCode::NOT_FOUND, "Not Found"));
+            };
+            let segments = plan_segments(&input, &probe, &variant, &config).await?;
+            let segment_count = segments.len() as u32;
+            if file == "index.m3u8" {
+                ensure_session(&input, &probe, &variant, segment_count, 0, client, &config).await?;
+                return Ok(playlist_response(variant_playlist(&segments, &client_codecs.join(","))));
+            }
+            let start_segment = index.unwrap_or_default();
+            let dir = ensure_session(&input, &probe, &variant, segment_count, start_segment, client, &config).await?;
+            (dir, variant)
+        }
+    };
+
+    if let Some(index) = index {
+        if fs::metadata(dir.join(file)).await.is_err() && !seek_session(&input, &variant, index, &config).await? {
+            return Ok(text_response(StatusCode::NOT_FOUND, "Not Found"));
+        }
+    }
+    let Some(content) = wait_for_file(&input, &variant, &dir, file).await? else {
+        return Ok(text_response(StatusCode::NOT_FOUND, "Not Found"));
+    };
+    let mut response = Response::new(content.into());
+    response
+        .headers_mut()
+        .insert(header::CONTENT_TYPE, HeaderValue::from_static("video/mp4"));
+    Ok(response)
+}
+
+/// 启动时清理残留的分片目录，并定期结束空闲的会话
+pub fn spawn_idle_cleanup() {
+    tokio::spawn(async {
+        let root = hls_root();
+        if fs::metadata(&root).await.is_ok() {
+            if let Err(e) = fs::remove_dir_all(&root).await {
+                warn!("清理残留的 HLS 分片目录 {:?} 失败: {:#}", root, e);
+            }
+        }
+        let mut interval = tokio::time::interval(Duration::from_secs(15));
+        loop {
+            interval.tick().await;
+            let idle_timeout = Duration::from_secs(crate::config::reload_config().hls.idle_timeout);
+            let mut sessions = SESSIONS.lock().await;
+            let idle: Vec<SessionKey> = sessions
+                .iter()
+                .filter(|(_, session)| session.last_access.elapsed() > idle_timeout)
+                .map(|(key, _)| key.clone())
+                .collect();
+            for key in idle {
+                if let Some(session) = sessions.remove(&key) {
+                    debug!("结束空闲的 HLS 会话 {:?}", key);
+                    spawn_remove_dir(session.dir);
+                }
+            }
+            drop(sessions);
+            PLAYBACK_KEYS
+                .lock()
+                .await
+                .retain(|_, key| key.last_access.elapsed() < PLAYBACK_KEY_TTL);
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hevc_4k() -> MediaProbe {
+        MediaProbe {
+            video_family: Some("hevc".to_string()),
+            width: 3840,
+            height: 2160,
+            bit_rate: 20_000_000,
+            duration: 600.0,
+            audio_codec: Some("eac3".to_string()),
+        }
+    }
+
+    #[test]
+    fn test_parse_probe() {
+        let json = serde_json::json!({
+            "streams": [
+                {"codec_type": "video", "codec_name": "mjpeg", "disposition": {"attached_pic": 1}},
+                {"codec_type": "video", "codec_name": "hevc", "codec_tag_string": "dvh1", "width": 1920, "height": 1080},
+                {"codec_type": "audio", "codec_name": "eac3"}
+            ],
+            "format": {"bit_rate": "8000000", "duration": "12.5"}
+        });
+        assert_eq!(
+            parse_probe(&json),
+            MediaProbe {
+                video_family: Some("dovi".to_string()),
+                width: 1920,
+                height: 1080,
+                bit_rate: 8_000_000,
+                duration: 12.5,
+                audio_codec: Some("eac3".to_string()),
+            }
+        );
+    }
+
+    #[test]
+    fn test_plan_variants() {
+        // 客户端支持 HEVC 时源档位直接复制，杜比音频仍需转码
+        let variants = plan_variants(&hevc_4k(), &["avc", "hevc"]);
+        assert_eq!(
+            variants.iter().map(|v| v.name.as_str()).collect::<Vec<_>>(),
+            ["source", "1080p", "720p", "480p", "360p"]
+        );
+        assert_eq!(variants[0].height, None);
+        assert_eq!(variants[0].bandwidth, 20_000_000);
+        assert!(!variants[0].copy_audio);
+        assert_eq!(variants[2].width, 1280);
+
+        // 不支持时源档位按原分辨率转码
+        let variants = plan_variants(&hevc_4k(), &["avc"]);
+        assert_eq!(variants[0].height, Some(2160));
+        assert_eq!(variants[0].video_bitrate, Some(5000));
+
+        let probe = MediaProbe {
+            video_family: Some("avc".to_string()),
+            width: 854,
+            height: 480,
+            bit_rate: 0,
+            duration: 60.0,
+            audio_codec: Some("aac".to_string()),
+        };
+        let variants = plan_variants(&probe, &["avc"]);
+        assert_eq!(
+            variants.iter().map(|v| v.name.as_str()).collect::<Vec<_>>(),
+            ["source", "360p"]
+        );
+        assert_eq!(variants[0].bandwidth, 1_400_000);
+        assert_eq!(variants[1].width, 640);
+        assert!(variants[1].copy_audio);
+    }
+
+    #[test]
+    fn test_session_per_client_codecs() {
+        let probe = hevc_4k();
+        let hevc_client = ["avc", "hevc"];
+        let avc_client = ["avc"];
+        let copy = plan_variants(&probe, &hevc_client).remove(0);
+        let transcode = plan_variants(&probe, &avc_client).remove(0);
+        // 同名的源档位在两个客户端下分别是复制和转码，对应不同的会话
+        assert_eq!(copy.name, transcode.name);
+        let input = PathBuf::from("in.mp4");
+        assert_ne!((input.clone(), copy.clone()), (input, transcode.clone()));
+        // 只支持 H.264 的客户端不能复用复制 HEVC 的会话，反之亦然
+        assert!(session_matches(&copy, &probe, "source", &hevc_client));
+        assert!(!session_matches(&copy, &probe, "source", &avc_client));
+        assert!(session_matches(&transcode, &probe, "source", &avc_client));
+        assert!(!session_matches(&transcode, &probe, "source", &hevc_client));
+        // 转码档位与客户端编码无关，可以共用
+        let scaled = &plan_variants(&probe, &avc_client)[1];
+        assert!(session_matches(scaled, &probe, "1080p", &hevc_client));
+        assert!(!session_matches(scaled, &probe, "720p", &hevc_client));
+    }
+
+    #[test]
+    fn test_master_playlist() {
+        let playlist = master_playlist(&plan_variants(&hevc_4k(), &["avc"])[..2], "key", "avc");
+        assert_eq!(
+            playlist,
+            "#EXTM3U\n#EXT-X-VERSION:7\n\
+             #EXT-X-STREAM-INF:BANDWIDTH=5192000,RESOLUTION=3840x2160,NAME=\"source\"\nkey/source/index.m3u8?codecs=avc\n\
+             #EXT-X-STREAM-INF:BANDWIDTH=5192000,RESOLUTION=1920x1080,NAME=\"1080p\"\nkey/1080p/index.m3u8?codecs=avc\n"
+        );
+    }
+
+    #[test]
+    fn test_variant_playlist() {
+        assert_eq!(
+            variant_playlist(&fixed_segments(13.5, 6), "avc"),
+            "#EXTM3U\n#EXT-X-VERSION:7\n#EXT-X-TARGETDURATION:6\n#EXT-X-PLAYLIST-TYPE:VOD\n#EXT-X-MEDIA-SEQUENCE:0\n\
+             #EXT-X-INDEPENDENT-SEGMENTS\n#EXT-X-MAP:URI=\"init.mp4?codecs=avc\"\n\
+             #EXTINF:6.000,\nseg00000.m4s?codecs=avc\n\
+             #EXTINF:6.000,\nseg00001.m4s?codecs=avc\n\
+             #EXTINF:1.500,\nseg00002.m4s?codecs=avc\n\
+             #EXT-X-ENDLIST\n"
+        );
+    }
+
+    #[test]
+    fn test_keyframe_segments() {
+        let output = "0.080000,K__\n0.120000,___\n2.080000,K__\n5.080000,K__\n7.080000,K__\n\
+                      9.080000,K__\n14.080000,K__\n15.080000,K__\n";
+        let (first_pts, keyframes) = parse_keyframes(output).unwrap();
+        assert_eq!(first_pts, 0.08);
+        assert_eq!(keyframes, [0.08, 2.08, 5.08, 7.08, 9.08, 14.08, 15.08]);
+        // 分片在距起点 6、12、18 秒之后的第一个关键帧处结束，超出目标时长的分片会拉长
+        let segments = keyframe_segments(first_pts, &keyframes, 20.0, 6);
+        let rounded: Vec<f64> = segments
+            .iter()
+            .map(|length| (length * 1000.0).round() / 1000.0)
+            .collect();
+        assert_eq!(rounded, [7.0, 7.0, 6.0]);
+        assert!(variant_playlist(&segments, "hevc").contains("#EXT-X-TARGETDURATION:7\n"));
+        assert_eq!(parse_keyframes(""), None);
+    }
+
+    #[test]
+    fn test_needs_seek() {
+        assert_eq!(segment_index("seg00012.m4s"), Some(12));
+        assert_eq!(segment_index("init.mp4"), None);
+        // 刚启动还没有分片时，起点附近的分片等待即可
+        assert!(!needs_seek(0, None, 2));
+        assert!(needs_seek(0, None, 10));
+        // 已经转码到第 20 个分片，稍后的分片等待，跳到前面或远处时重新定位
+        assert!(!needs_seek(10, Some(20), 23));
+        assert!(needs_seek(10, Some(20), 30));
+        assert!(needs_seek(10, Some(20), 5));
+    }
+
+    #[test]
+    fn test_ffmpeg_args_seek() {
+        let config = HlsConfig {
+            segment_duration: 6,
+            video_encoder: "libx264".to_string(),
+            ..Default::default()
+        };
+        let variant = &plan_variants(&hevc_4k(), &["avc"])[1];
+        let args = ffmpeg_args(FsPath::new("in.mp4"), FsPath::new("out"), variant, &config, 10).join(" ");
+        assert!(args.contains("-ss 60 -copyts -i in.mp4"));
+        assert!(args.contains("expr:gte(t,(n_forced+10)*6)"));
+        assert!(args.contains("-start_number 10"));
+        assert!(args.ends_with("ffmpeg.m3u8"));
+        let args = ffmpeg_args(FsPath::new("in.mp4"), FsPath::new("out"), variant, &config, 0).join(" ");
+        assert!(!args.contains("-ss"));
+    }
+
+    #[tokio::test]
+    async fn test_playback_key() {
+        let client: IpAddr = "192.168.1.2".parse().unwrap();
+        let key = issue_playback_key("42", client).await;
+        assert_eq!(check_playback_key(&key, "42").await, Some(client));
+        // 密钥只能访问签发时的视频
+        assert_eq!(check_playback_key(&key, "43").await, None);
+        assert_eq!(check_playback_key("unknown", "42").await, None);
+    }
+
+    #[test]
+    fn test_is_segment_file() {
+        assert!(is_segment_file("seg00001.m4s"));
+        assert!(is_segment_file("init.mp4"));
+        assert!(!is_segment_file("ffmpeg.log"));
+        assert!(!is_segment_file("seg/../x.m4s"));
+        assert!(!is_valid_name(".."));
+        assert!(!is_valid_name(""));
+    }
+}
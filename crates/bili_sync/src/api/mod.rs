@@ -1,8 +1,11 @@
 pub mod auth;
+pub mod feed;
 pub mod handler;
+pub mod hls;
 pub mod request;
 pub mod response;
 pub mod video_stream;
+pub mod webdav;
 pub mod ws;
 
 mod error;
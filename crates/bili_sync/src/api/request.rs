@@ -21,6 +21,24 @@ pub struct VideosRequest {
     pub sort_order: Option<String>, // "asc", "desc"
 }
 
+#[derive(Deserialize, IntoParams, Default)]
+pub struct VerifyLibraryRequest {
+    /// 是否对已记录哈希的文件重新计算哈希
+    pub rehash: Option<bool>,
+}
+
+#[derive(Deserialize, IntoParams, Default)]
+pub struct ReorganizeLibraryRequest {
+    /// 是否只预演移动计划，默认为 true，需要显式传入 false 才会移动文件
+    pub dry_run: Option<bool>,
+}
+
+#[derive(Deserialize, IntoParams, Default)]
+pub struct RevertReorganizeRequest {
+    /// 要回滚的移动日志文件名，留空时回滚最近一次整理
+    pub log_file: Option<String>,
+}
+
 #[derive(Deserialize, IntoParams)]
 pub struct SubmissionVideosRequest {
     pub page: Option<i32>,
@@ -117,6 +135,20 @@ pub struct UpdateVideoSourceDownloadOptionsRequest {
     pub download_danmaku: Option<bool>,
     /// 是否下载字幕文件（SRT）
     pub download_subtitle: Option<bool>,
+    /// 下载限速（KB/s），0 表示不单独限速
+    pub download_speed_limit: Option<u32>,
+    /// 下载优先级，数值越大越先扫描和下载
+    pub priority: Option<i32>,
+    /// 后处理配置名称，空字符串表示不处理
+    pub postprocess_profile: Option<String>,
+    /// 输出容器格式：mp4 或 mkv
+    pub output_container: Option<String>,
+    /// 仅音频模式的输出格式：m4a、mp3、opus 或 flac
+    pub audio_format: Option<String>,
+    /// 仅音频模式是否进行响度标准化
+    pub audio_normalize: Option<bool>,
+    /// 是否在 WebDAV 目录中显示
+    pub webdav_visible: Option<bool>,
     /// 是否启用AI重命名
     pub ai_rename: Option<bool>,
     /// AI重命名视频提示词（覆盖全局配置）
@@ -299,7 +331,7 @@ fn default_page_size() -> u32 {
 // 状态更新结构
 #[derive(Deserialize, ToSchema)]
 pub struct StatusUpdate {
-    pub status_index: usize, // 状态位索引 (0-4)
+    pub status_index: usize, // 状态位索引 (视频 0-4，分页 0-5)
     pub status_value: u32,   // 状态值 (0, 1, 2, 3)
 }
 
@@ -345,7 +377,7 @@ pub struct UpdateVideoStatusRequest {
 // 选择性重置任务请求
 #[derive(Deserialize, ToSchema)]
 pub struct ResetSpecificTasksRequest {
-    pub task_indexes: Vec<usize>, // 要重置的任务索引列表 (0-4)
+    pub task_indexes: Vec<usize>, // 要重置的任务索引列表 (0-5，5 仅对分页有效)
     pub collection: Option<i32>,
     pub favorite: Option<i32>,
     pub submission: Option<i32>,
@@ -44,6 +44,15 @@ pub struct ResetVideoResponse {
     pub pages: Vec<PageInfo>,
 }
 
+#[derive(Serialize, ToSchema)]
+pub struct DownloadNowResponse {
+    pub video_id: i32,
+    /// started: 已开始下载；queued: 已提升优先级，等待扫描任务处理；completed: 已下载完成
+    pub status: String,
+    pub priority: i32,
+    pub message: String,
+}
+
 #[derive(Serialize, ToSchema)]
 pub struct UpdateVideoStatusResponse {
     pub success: bool,
@@ -58,6 +67,27 @@ pub struct ResetAllVideosResponse {
     pub resetted_pages_count: usize,
 }
 
+#[derive(Serialize, ToSchema)]
+pub struct VerifyLibraryResponse {
+    pub started: bool,
+    pub message: String,
+    pub status: crate::utils::media_verify::VerifyJobStatus,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct ReorganizeLibraryResponse {
+    pub started: bool,
+    pub message: String,
+    pub status: crate::utils::reorganize::ReorganizeJobStatus,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct AdoptVideoSourceResponse {
+    pub started: bool,
+    pub message: String,
+    pub status: crate::utils::library_adopt::AdoptJobStatus,
+}
+
 #[derive(Serialize, ToSchema)]
 pub struct AddVideoSourceResponse {
     pub success: bool,
@@ -129,6 +159,13 @@ pub struct UpdateVideoSourceDownloadOptionsResponse {
     pub flat_folder: bool,
     pub download_danmaku: bool,
     pub download_subtitle: bool,
+    pub download_speed_limit: i32,
+    pub priority: i32,
+    pub postprocess_profile: String,
+    pub output_container: String,
+    pub audio_format: String,
+    pub audio_normalize: bool,
+    pub webdav_visible: bool,
     pub ai_rename: bool,
     pub ai_rename_video_prompt: String,
     pub ai_rename_audio_prompt: String,
@@ -160,6 +197,15 @@ pub struct ResetVideoSourcePathResponse {
     pub message: String,
 }
 
+/// 视频源的播客订阅信息
+#[derive(Serialize, ToSchema)]
+pub struct VideoSourceFeedResponse {
+    pub source_id: i32,
+    pub source_type: String,
+    /// 订阅地址（不含协议和主机），包含访问令牌
+    pub feed_path: String,
+}
+
 /// 番剧源简化信息（用于合并选择）
 #[derive(Serialize, ToSchema, Debug)]
 pub struct BangumiSourceOption {
@@ -213,6 +259,13 @@ pub struct VideoSource {
     pub flat_folder: bool,                 // 是否启用平铺目录模式
     pub download_danmaku: bool,            // 是否下载弹幕文件
     pub download_subtitle: bool,           // 是否下载字幕文件
+    pub download_speed_limit: i32,         // 下载限速（KB/s），0 表示不单独限速
+    pub priority: i32,                     // 下载优先级，数值越大越先扫描和下载
+    pub postprocess_profile: String,       // 后处理配置名称，空字符串表示不处理
+    pub output_container: String,          // 输出容器格式：mp4 或 mkv
+    pub audio_format: String,              // 仅音频模式的输出格式：m4a、mp3、opus 或 flac
+    pub audio_normalize: bool,             // 仅音频模式是否进行响度标准化
+    pub webdav_visible: bool,              // 是否在 WebDAV 目录中显示
     pub ai_rename: bool,                   // 是否启用AI重命名
     pub ai_rename_video_prompt: String,    // AI重命名视频提示词
     pub ai_rename_audio_prompt: String,    // AI重命名音频提示词
@@ -228,8 +281,11 @@ pub struct PageInfo {
     pub id: i32,
     pub pid: i32,
     pub name: String,
-    pub download_status: [u32; 5],
+    pub download_status: [u32; 6],
     pub path: Option<String>,
+    /// 后处理状态：0 无，1 排队中，2 处理中，3 完成，4 失败，5 无需处理
+    pub postprocess_status: i32,
+    pub postprocess_error: Option<String>,
 }
 
 impl From<(i32, i32, String, u32)> for PageInfo {
@@ -240,6 +296,8 @@ impl From<(i32, i32, String, u32)> for PageInfo {
             name,
             download_status: PageStatus::from(download_status).into(),
             path: None,
+            postprocess_status: 0,
+            postprocess_error: None,
         }
     }
 }
@@ -252,6 +310,32 @@ impl From<(i32, i32, String, u32, Option<String>)> for PageInfo {
             name,
             download_status: PageStatus::from(download_status).into(),
             path,
+            postprocess_status: 0,
+            postprocess_error: None,
+        }
+    }
+}
+
+impl From<(i32, i32, String, u32, Option<String>, i32, Option<String>)> for PageInfo {
+    fn from(
+        (id, pid, name, download_status, path, postprocess_status, postprocess_error): (
+            i32,
+            i32,
+            String,
+            u32,
+            Option<String>,
+            i32,
+            Option<String>,
+        ),
+    ) -> Self {
+        Self {
+            id,
+            pid,
+            name,
+            download_status: PageStatus::from(download_status).into(),
+            path,
+            postprocess_status,
+            postprocess_error,
         }
     }
 }
@@ -688,6 +772,7 @@ pub struct VideoPlayInfoResponse {
     pub video_streams: Vec<VideoStreamInfo>,
     pub audio_streams: Vec<AudioStreamInfo>,
     pub subtitle_streams: Vec<SubtitleStreamInfo>,
+    pub chapters: Vec<ChapterInfo>,
     pub video_title: String,
     pub video_duration: Option<u32>,
     pub video_quality_description: String,
@@ -728,6 +813,14 @@ pub struct SubtitleStreamInfo {
     pub url: String,
 }
 
+/// 分段章节信息，时间单位为秒
+#[derive(Serialize, ToSchema)]
+pub struct ChapterInfo {
+    pub title: String,
+    pub start: u32,
+    pub end: u32,
+}
+
 /// 验证收藏夹响应
 #[derive(Serialize, ToSchema)]
 pub struct ValidateFavoriteResponse {
@@ -764,6 +857,8 @@ pub struct MonitoringStatus {
     pub last_scan_time: Option<String>,
     pub next_scan_time: Option<String>,
     pub is_scanning: bool,
+    /// 时间窗口状态，未启用时间窗口时为空
+    pub schedule: Option<crate::task::schedule::ScheduleStatus>,
 }
 
 /// 每日视频计数
@@ -809,6 +904,13 @@ pub struct TestNotificationResponse {
     pub message: String,
 }
 
+// 测试媒体服务器连接响应
+#[derive(Serialize, ToSchema)]
+pub struct TestMediaServerResponse {
+    pub success: bool,
+    pub message: String,
+}
+
 // 推送状态响应
 #[derive(Serialize, ToSchema)]
 pub struct NotificationStatusResponse {
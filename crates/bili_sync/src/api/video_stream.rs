@@ -9,10 +9,13 @@ use std::io::{Read, Seek, SeekFrom};
 use std::path::PathBuf;
 use std::sync::Arc;
 use tokio::fs;
+use tokio::io::{AsyncReadExt, AsyncSeekExt};
 use tracing::{debug, error, info, warn};
 
 use bili_sync_entity::entities::{page, video};
 
+use crate::utils::status::{PageStatus, STATUS_OK};
+
 /// Range请求参数
 #[derive(Debug)]
 pub struct RangeSpec {
@@ -109,6 +112,58 @@ async fn stream_video_impl(video_id: String, headers: HeaderMap, db: Arc<Databas
     }
 }
 
+/// 按 Range 头流式提供文件片段，没有 Range 头时流式返回完整文件
+///
+/// 播客应用和 DLNA 电视等客户端会直接下载整个文件或请求 `bytes=0-` 后顺序读取，
+/// 不能像网页播放器那样每次只返回开头的 10MB
+pub async fn serve_file_with_range(path: &PathBuf, headers: &HeaderMap) -> Result<Response> {
+    let file_size = fs::metadata(path).await.context("无法获取文件大小")?.len();
+    let range = headers
+        .get(header::RANGE)
+        .and_then(|value| value.to_str().ok())
+        .filter(|_| file_size > 0)
+        .and_then(|range_str| match parse_range_header(range_str, file_size) {
+            Ok(range) => Some(range),
+            Err(e) => {
+                warn!("Range解析失败: {:#}, 返回完整文件", e);
+                None
+            }
+        });
+    let (start, end) = match &range {
+        Some(range) => (range.start, range.end.unwrap_or(file_size - 1)),
+        None => (0, file_size.saturating_sub(1)),
+    };
+    let content_length = if file_size == 0 { 0 } else { end - start + 1 };
+
+    let mut file = fs::File::open(path)
+        .await
+        .with_context(|| format!("无法打开文件: {:?}", path))?;
+    if start > 0 {
+        file.seek(SeekFrom::Start(start)).await.context("文件定位失败")?;
+    }
+    let mut response = Response::new(axum::body::Body::from_stream(tokio_util::io::ReaderStream::new(
+        file.take(content_length),
+    )));
+    if range.is_some() {
+        *response.status_mut() = StatusCode::PARTIAL_CONTENT;
+        response.headers_mut().insert(
+            header::CONTENT_RANGE,
+            HeaderValue::from_str(&format!("bytes {}-{}/{}", start, end, file_size))?,
+        );
+    }
+    let headers = response.headers_mut();
+    headers.insert(
+        header::CONTENT_TYPE,
+        HeaderValue::from_static(get_video_mime_type(path)),
+    );
+    headers.insert(
+        header::CONTENT_LENGTH,
+        HeaderValue::from_str(&content_length.to_string())?,
+    );
+    headers.insert(header::ACCEPT_RANGES, HeaderValue::from_static("bytes"));
+    Ok(response)
+}
+
 fn file_starts_with_flv(video_path: &PathBuf) -> Result<bool> {
     let mut file = File::open(video_path).with_context(|| format!("无法打开视频文件: {:?}", video_path))?;
     let mut header = [0u8; 3];
@@ -165,7 +220,7 @@ async fn maybe_remux_flv_in_mp4(video_path: &PathBuf) -> Result<()> {
 }
 
 /// 查找视频文件路径
-async fn find_video_file(video_id: &str, db: &DatabaseConnection) -> Result<PathBuf> {
+pub async fn find_video_file(video_id: &str, db: &DatabaseConnection) -> Result<PathBuf> {
     debug!("查找视频文件: {}", video_id);
 
     // 首先尝试作为分页ID查找
@@ -311,8 +366,16 @@ async fn find_video_file_in_directory(dir_path: &PathBuf) -> Result<PathBuf> {
     bail!("文件夹中未找到视频文件: {:?}", dir_path);
 }
 
+/// 已下载完成且记录了文件路径的分页，文件可能已被手动删除，使用前需自行检查
+pub fn downloaded_page_path(page_model: &page::Model) -> Option<PathBuf> {
+    if PageStatus::from(page_model.download_status).get(1) != STATUS_OK {
+        return None;
+    }
+    page_model.path.as_deref().map(PathBuf::from)
+}
+
 /// 根据文件扩展名获取MIME类型
-fn get_video_mime_type(path: &std::path::Path) -> &'static str {
+pub fn get_video_mime_type(path: &std::path::Path) -> &'static str {
     match path.extension().and_then(|ext| ext.to_str()) {
         Some("mp4") => "video/mp4",
         Some("webm") => "video/webm",
@@ -322,6 +385,10 @@ fn get_video_mime_type(path: &std::path::Path) -> &'static str {
         Some("flv") => "video/x-flv",
         Some("wmv") => "video/x-ms-wmv",
         Some("m4v") => "video/x-m4v",
+        Some("m4a") => "audio/mp4",
+        Some("mp3") => "audio/mpeg",
+        Some("opus") => "audio/ogg",
+        Some("flac") => "audio/flac",
         _ => "video/mp4", // 默认
     }
 }
@@ -0,0 +1,636 @@
+//! 只读 WebDAV 媒体库
+//!
+//! 在 `/webdav/` 下按 视频源 → 视频 → 文件 的层级提供已下载的文件，目录和文件名取自数据库中记录的标题，
+//! 与磁盘上的实际路径无关。字幕、弹幕等与分页文件同名的附属文件一并列出。
+//! 挂载客户端普遍只支持 HTTP Basic 认证，因此密码使用 auth_token，用户名任意；未设置 auth_token 时拒绝访问。
+//! 目录列表缓存一小段时间，播放时的分段读取（Range GET）无需每次重新查询数据库和扫描目录。
+
+use std::collections::{HashMap, HashSet};
+use std::future::Future;
+use std::hash::Hash;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, LazyLock};
+use std::time::{Duration, Instant};
+
+use anyhow::Result;
+use axum::extract::Request;
+use axum::http::{header, HeaderMap, HeaderValue, Method, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::Extension;
+use base64::prelude::BASE64_STANDARD;
+use base64::Engine;
+use bili_sync_entity::entities::{page, video};
+use chrono::{DateTime, NaiveDateTime, TimeZone, Utc};
+use quick_xml::events::{BytesDecl, BytesText, Event};
+use quick_xml::writer::Writer;
+use sea_orm::{ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, QueryOrder};
+use tokio::sync::Mutex;
+use tracing::error;
+
+use crate::api::auth::token_matches;
+use crate::api::feed::{list_feed_sources, source_video_filter};
+use crate::api::video_stream::{downloaded_page_path, get_video_mime_type, serve_file_with_range};
+use crate::utils::filenamify::filenamify;
+use crate::utils::time_format::beijing_timezone;
+
+const PREFIX: &str = "/webdav";
+/// 目录列表的缓存时间，新下载的文件最多延迟这么久出现
+const LISTING_TTL: Duration = Duration::from_secs(30);
+
+type ListingCache<K, T> = LazyLock<Mutex<HashMap<K, (Instant, Arc<Vec<T>>)>>>;
+
+static SOURCES: ListingCache<(), SourceEntry> = LazyLock::new(|| Mutex::new(HashMap::new()));
+static VIDEOS: ListingCache<(&'static str, i32), VideoEntry> = LazyLock::new(|| Mutex::new(HashMap::new()));
+static FILES: ListingCache<i32, FileEntry> = LazyLock::new(|| Mutex::new(HashMap::new()));
+
+#[derive(Clone)]
+struct SourceEntry {
+    name: String,
+    source_type: &'static str,
+    id: i32,
+}
+
+#[derive(Clone)]
+struct VideoEntry {
+    name: String,
+    video: video::Model,
+    pages: Vec<page::Model>,
+}
+
+#[derive(Clone)]
+struct FileEntry {
+    name: String,
+    path: PathBuf,
+}
+
+enum Resource {
+    Root,
+    Source(SourceEntry),
+    Video(Box<VideoEntry>),
+    File(FileEntry),
+}
+
+/// PROPFIND 响应中的一项
+struct PropEntry {
+    href: String,
+    name: String,
+    collection: bool,
+    size: u64,
+    modified: Option<DateTime<Utc>>,
+    mime: Option<&'static str>,
+}
+
+/// 同一目录下出现重名时追加后缀区分，先出现的保留原名
+fn unique_name(used: &mut HashSet<String>, name: &str, suffix: impl std::fmt::Display) -> String {
+    let name = filenamify(name);
+    let name = if used.contains(&name) {
+        format!("{} ({})", name, suffix)
+    } else {
+        name
+    };
+    used.insert(name.clone());
+    name
+}
+
+fn percent_encode(segment: &str) -> String {
+    let mut encoded = String::with_capacity(segment.len());
+    for byte in segment.bytes() {
+        if byte.is_ascii_alphanumeric() || matches!(byte, b'-' | b'_' | b'.' | b'~') {
+            encoded.push(byte as char);
+        } else {
+            encoded.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    encoded
+}
+
+fn percent_decode(segment: &str) -> Option<String> {
+    let bytes = segment.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            let hex = std::str::from_utf8(bytes.get(i + 1..i + 3)?).ok()?;
+            decoded.push(u8::from_str_radix(hex, 16).ok()?);
+            i += 3;
+        } else {
+            decoded.push(bytes[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8(decoded).ok()
+}
+
+/// 将请求路径拆分为解码后的路径段，拒绝 `..` 等特殊路径段
+fn parse_path(path: &str) -> Option<Vec<String>> {
+    let rest = path.strip_prefix(PREFIX)?;
+    if !rest.is_empty() && !rest.starts_with('/') {
+        return None;
+    }
+    rest.split('/')
+        .filter(|segment| !segment.is_empty())
+        .map(|segment| percent_decode(segment).filter(|segment| segment != "." && segment != ".."))
+        .collect()
+}
+
+fn href_for(segments: &[String], collection: bool) -> String {
+    let mut href = PREFIX.to_string();
+    for segment in segments {
+        href.push('/');
+        href.push_str(&percent_encode(segment));
+    }
+    if collection {
+        href.push('/');
+    }
+    href
+}
+
+/// 密码与 auth_token 相同即可，同时兼容管理页使用的裸 Authorization 头
+fn authorized(headers: &HeaderMap) -> bool {
+    let token = crate::config::reload_config().auth_token.unwrap_or_default();
+    if token.is_empty() {
+        return false;
+    }
+    let Some(value) = headers.get(header::AUTHORIZATION).and_then(|value| value.to_str().ok()) else {
+        return false;
+    };
+    if token_matches(Some(&token), Some(value)) {
+        return true;
+    }
+    value
+        .strip_prefix("Basic ")
+        .and_then(|encoded| BASE64_STANDARD.decode(encoded.trim()).ok())
+        .and_then(|decoded| String::from_utf8(decoded).ok())
+        .is_some_and(|credentials| {
+            credentials
+                .split_once(':')
+                .is_some_and(|(_, password)| token_matches(Some(&token), Some(password)))
+        })
+}
+
+/// 读取未过期的缓存列表，否则重新加载；加载期间不持有锁，并发请求至多重复加载一次
+async fn cached_listing<K, T, F>(cache: &ListingCache<K, T>, key: K, load: F) -> Result<Arc<Vec<T>>>
+where
+    K: Eq + Hash,
+    F: Future<Output = Result<Vec<T>>>,
+{
+    if let Some((loaded_at, listing)) = cache.lock().await.get(&key) {
+        if loaded_at.elapsed() < LISTING_TTL {
+            return Ok(listing.clone());
+        }
+    }
+    let listing = Arc::new(load.await?);
+    let mut cache = cache.lock().await;
+    cache.retain(|_, (loaded_at, _)| loaded_at.elapsed() < LISTING_TTL);
+    cache.insert(key, (Instant::now(), listing.clone()));
+    Ok(listing)
+}
+
+async fn cached_sources(db: &DatabaseConnection) -> Result<Arc<Vec<SourceEntry>>> {
+    cached_listing(&SOURCES, (), list_sources(db)).await
+}
+
+async fn cached_videos(db: &DatabaseConnection, source: &SourceEntry) -> Result<Arc<Vec<VideoEntry>>> {
+    cached_listing(&VIDEOS, (source.source_type, source.id), list_videos(db, source)).await
+}
+
+async fn cached_files(entry: &VideoEntry) -> Result<Arc<Vec<FileEntry>>> {
+    cached_listing(&FILES, entry.video.id, async { Ok(list_files(entry).await) }).await
+}
+
+async fn list_sources(db: &DatabaseConnection) -> Result<Vec<SourceEntry>> {
+    let mut used = HashSet::new();
+    Ok(list_feed_sources(db)
+        .await?
+        .into_iter()
+        .filter(|(_, _, source)| source.webdav_visible)
+        .map(|(source_type, id, source)| SourceEntry {
+            name: unique_name(&mut used, &source.title, format!("{}-{}", source_type, id)),
+            source_type,
+            id,
+        })
+        .collect())
+}
+
+/// 视频源下至少有一个已下载分页的视频
+async fn list_videos(db: &DatabaseConnection, source: &SourceEntry) -> Result<Vec<VideoEntry>> {
+    let Some(filter) = source_video_filter(source.source_type, source.id) else {
+        return Ok(Vec::new());
+    };
+    let videos = video::Entity::find()
+        .filter(filter)
+        .filter(video::Column::Deleted.eq(0))
+        .order_by_desc(video::Column::Pubtime)
+        .find_with_related(page::Entity)
+        .all(db)
+        .await?;
+    let mut used = HashSet::new();
+    Ok(videos
+        .into_iter()
+        .filter(|(_, pages)| {
+            pages
+                .iter()
+                .any(|page_model| downloaded_page_path(page_model).is_some())
+        })
+        .map(|(video_model, mut pages)| {
+            pages.sort_by_key(|page_model| page_model.pid);
+            VideoEntry {
+                name: unique_name(&mut used, &video_model.name, &video_model.bvid),
+                video: video_model,
+                pages,
+            }
+        })
+        .collect())
+}
+
+/// 分页文件及与其同名的字幕、弹幕等附属文件，文件名中的磁盘文件名部分替换为数据库中的标题
+async fn list_files(entry: &VideoEntry) -> Vec<FileEntry> {
+    let page_paths: Vec<PathBuf> = entry.pages.iter().filter_map(downloaded_page_path).collect();
+    let page_path_set: HashSet<&Path> = page_paths.iter().map(PathBuf::as_path).collect();
+    let mut dir_cache: HashMap<PathBuf, Vec<PathBuf>> = HashMap::new();
+    let single_page = entry.pages.len() == 1;
+    let mut used = HashSet::new();
+    let mut files = Vec::new();
+    for page_model in &entry.pages {
+        let Some(path) = downloaded_page_path(page_model) else {
+            continue;
+        };
+        let (Some(dir), Some(stem)) = (path.parent(), path.file_stem().and_then(|stem| stem.to_str())) else {
+            continue;
+        };
+        if !dir_cache.contains_key(dir) {
+            let mut siblings = Vec::new();
+            if let Ok(mut read_dir) = tokio::fs::read_dir(dir).await {
+                while let Ok(Some(dir_entry)) = read_dir.next_entry().await {
+                    siblings.push(dir_entry.path());
+                }
+            }
+            siblings.sort();
+            dir_cache.insert(dir.to_path_buf(), siblings);
+        }
+        let display_stem = if single_page {
+            entry.name.clone()
+        } else {
+            filenamify(format!("P{:02} {}", page_model.pid, page_model.name))
+        };
+        for sibling in &dir_cache[dir] {
+            let Some(file_name) = sibling.file_name().and_then(|name| name.to_str()) else {
+                continue;
+            };
+            let Some(suffix) = file_name
+                .strip_prefix(stem)
+                .filter(|suffix| suffix.starts_with('.') || suffix.starts_with('-'))
+            else {
+                continue;
+            };
+            // 同目录下其它分页的文件名可能以本分页文件名开头
+            if sibling != &path && page_path_set.contains(sibling.as_path()) {
+                continue;
+            }
+            if !sibling.is_file() {
+                continue;
+            }
+            files.push(FileEntry {
+                name: unique_name(&mut used, &format!("{}{}", display_stem, suffix), page_model.pid),
+                path: sibling.clone(),
+            });
+        }
+    }
+    files
+}
+
+async fn resolve(db: &DatabaseConnection, segments: &[String]) -> Result<Option<Resource>> {
+    let mut resource = Resource::Root;
+    for segment in segments {
+        let next = match &resource {
+            Resource::Root => cached_sources(db)
+                .await?
+                .iter()
+                .find(|source| &source.name == segment)
+                .cloned()
+                .map(Resource::Source),
+            Resource::Source(source) => cached_videos(db, source)
+                .await?
+                .iter()
+                .find(|video_entry| &video_entry.name == segment)
+                .map(|video_entry| Resource::Video(Box::new(video_entry.clone()))),
+            Resource::Video(video_entry) => cached_files(video_entry)
+                .await?
+                .iter()
+                .find(|file| &file.name == segment)
+                .cloned()
+                .map(Resource::File),
+            Resource::File(_) => None,
+        };
+        match next {
+            Some(next) => resource = next,
+            None => return Ok(None),
+        }
+    }
+    Ok(Some(resource))
+}
+
+/// 数据库中的时间为北京时间，转换为 UTC
+fn beijing_to_utc(time: &NaiveDateTime) -> Option<DateTime<Utc>> {
+    beijing_timezone()
+        .from_local_datetime(time)
+        .single()
+        .map(|time| time.with_timezone(&Utc))
+}
+
+fn collection_entry(href: String, name: String, modified: Option<NaiveDateTime>) -> PropEntry {
+    PropEntry {
+        href,
+        name,
+        collection: true,
+        size: 0,
+        modified: modified.as_ref().and_then(beijing_to_utc),
+        mime: None,
+    }
+}
+
+async fn file_entry(href: String, file: &FileEntry) -> Option<PropEntry> {
+    let metadata = tokio::fs::metadata(&file.path).await.ok()?;
+    Some(PropEntry {
+        href,
+        name: file.name.clone(),
+        collection: false,
+        size: metadata.len(),
+        modified: metadata.modified().ok().map(DateTime::<Utc>::from),
+        mime: Some(match file.path.extension().and_then(|ext| ext.to_str()) {
+            Some("nfo") | Some("xml") => "application/xml",
+            Some("srt") | Some("ass") => "text/plain",
+            Some("jpg") | Some("jpeg") => "image/jpeg",
+            Some("png") => "image/png",
+            _ => get_video_mime_type(&file.path),
+        }),
+    })
+}
+
+async fn prop_entries(
+    db: &DatabaseConnection,
+    segments: &[String],
+    resource: &Resource,
+    with_children: bool,
+) -> Result<Vec<PropEntry>> {
+    let name = segments.last().cloned().unwrap_or_else(|| "webdav".to_string());
+    let mut entries = Vec::new();
+    match resource {
+        Resource::File(file) => {
+            entries.extend(file_entry(href_for(segments, false), file).await);
+            return Ok(entries);
+        }
+        Resource::Video(video_entry) => entries.push(collection_entry(
+            href_for(segments, true),
+            name,
+            Some(video_entry.video.pubtime),
+        )),
+        _ => entries.push(collection_entry(href_for(segments, true), name, None)),
+    }
+    if !with_children {
+        return Ok(entries);
+    }
+
+    let child_href = |child: &str, collection: bool| {
+        let mut child_segments = segments.to_vec();
+        child_segments.push(child.to_string());
+        href_for(&child_segments, collection)
+    };
+    match resource {
+        Resource::Root => {
+            for source in cached_sources(db).await?.iter() {
+                entries.push(collection_entry(
+                    child_href(&source.name, true),
+                    source.name.clone(),
+                    None,
+                ));
+            }
+        }
+        Resource::Source(source) => {
+            for video_entry in cached_videos(db, source).await?.iter() {
+                entries.push(collection_entry(
+                    child_href(&video_entry.name, true),
+                    video_entry.name.clone(),
+                    Some(video_entry.video.pubtime),
+                ));
+            }
+        }
+        Resource::Video(video_entry) => {
+            for file in cached_files(video_entry).await?.iter() {
+                entries.extend(file_entry(child_href(&file.name, false), file).await);
+            }
+        }
+        Resource::File(_) => {}
+    }
+    Ok(entries)
+}
+
+fn build_multistatus(entries: &[PropEntry]) -> std::io::Result<String> {
+    let mut writer = Writer::new(Vec::new());
+    writer.write_event(Event::Decl(BytesDecl::new("1.0", Some("utf-8"), None)))?;
+    writer
+        .create_element("D:multistatus")
+        .with_attribute(("xmlns:D", "DAV:"))
+        .write_inner_content(|writer| {
+            for entry in entries {
+                writer.create_element("D:response").write_inner_content(|writer| {
+                    writer
+                        .create_element("D:href")
+                        .write_text_content(BytesText::new(&entry.href))?;
+                    writer.create_element("D:propstat").write_inner_content(|writer| {
+                        writer
+                            .create_element("D:prop")
+                            .write_inner_content(|writer| write_props(writer, entry))?;
+                        writer
+                            .create_element("D:status")
+                            .write_text_content(BytesText::new("HTTP/1.1 200 OK"))?;
+                        Ok(())
+                    })?;
+                    Ok(())
+                })?;
+            }
+            Ok(())
+        })?;
+    Ok(String::from_utf8_lossy(&writer.into_inner()).into_owned())
+}
+
+fn write_props(writer: &mut Writer<Vec<u8>>, entry: &PropEntry) -> std::io::Result<()> {
+    writer
+        .create_element("D:displayname")
+        .write_text_content(BytesText::new(&entry.name))?;
+    if entry.collection {
+        writer.create_element("D:resourcetype").write_inner_content(|writer| {
+            writer.create_element("D:collection").write_empty()?;
+            Ok(())
+        })?;
+    } else {
+        writer.create_element("D:resourcetype").write_empty()?;
+        writer
+            .create_element("D:getcontentlength")
+            .write_text_content(BytesText::new(&entry.size.to_string()))?;
+    }
+    if let Some(mime) = entry.mime {
+        writer
+            .create_element("D:getcontenttype")
+            .write_text_content(BytesText::new(mime))?;
+    }
+    if let Some(modified) = entry.modified {
+        writer
+            .create_element("D:getlastmodified")
+            .write_text_content(BytesText::new(
+                &modified.format("%a, %d %b %Y %H:%M:%S GMT").to_string(),
+            ))?;
+        if !entry.collection {
+            writer
+                .create_element("D:getetag")
+                .write_text_content(BytesText::new(&format!(
+                    "\"{:x}-{:x}\"",
+                    entry.size,
+                    modified.timestamp()
+                )))?;
+        }
+    }
+    Ok(())
+}
+
+fn status_response(status: StatusCode) -> Response {
+    (status, status.canonical_reason().unwrap_or_default()).into_response()
+}
+
+fn unauthorized() -> Response {
+    let mut response = status_response(StatusCode::UNAUTHORIZED);
+    response.headers_mut().insert(
+        header::WWW_AUTHENTICATE,
+        HeaderValue::from_static(r#"Basic realm="bili-sync", charset="UTF-8""#),
+    );
+    response
+}
+
+fn with_dav_headers(mut response: Response) -> Response {
+    let headers = response.headers_mut();
+    headers.insert("dav", HeaderValue::from_static("1"));
+    headers.insert(header::ALLOW, HeaderValue::from_static("OPTIONS, GET, HEAD, PROPFIND"));
+    response
+}
+
+async fn propfind(db: &DatabaseConnection, segments: &[String], headers: &HeaderMap) -> Result<Response> {
+    let Some(resource) = resolve(db, segments).await? else {
+        return Ok(status_response(StatusCode::NOT_FOUND));
+    };
+    // 不支持无限深度的遍历，按 1 处理
+    let with_children = headers
+        .get("depth")
+        .and_then(|value| value.to_str().ok())
+        .is_none_or(|depth| depth.trim() != "0");
+    let entries = prop_entries(db, segments, &resource, with_children).await?;
+    let mut response = (StatusCode::MULTI_STATUS, build_multistatus(&entries)?).into_response();
+    response.headers_mut().insert(
+        header::CONTENT_TYPE,
+        HeaderValue::from_static("application/xml; charset=utf-8"),
+    );
+    Ok(response)
+}
+
+async fn get_file(db: &DatabaseConnection, segments: &[String], headers: &HeaderMap) -> Result<Response> {
+    match resolve(db, segments).await? {
+        Some(Resource::File(file)) => serve_file_with_range(&file.path, headers).await,
+        Some(_) => Ok(with_dav_headers(status_response(StatusCode::METHOD_NOT_ALLOWED))),
+        None => Ok(status_response(StatusCode::NOT_FOUND)),
+    }
+}
+
+/// WebDAV 入口，处理 `/webdav` 下的全部请求
+pub async fn webdav(Extension(db): Extension<Arc<DatabaseConnection>>, request: Request) -> Response {
+    let (parts, _) = request.into_parts();
+    if !authorized(&parts.headers) {
+        return unauthorized();
+    }
+    let Some(segments) = parse_path(parts.uri.path()) else {
+        return status_response(StatusCode::NOT_FOUND);
+    };
+    let result = match parts.method {
+        Method::OPTIONS => Ok(with_dav_headers(StatusCode::OK.into_response())),
+        Method::GET | Method::HEAD => get_file(&db, &segments, &parts.headers).await,
+        ref method if method.as_str() == "PROPFIND" => propfind(&db, &segments, &parts.headers).await,
+        _ => Ok(with_dav_headers(status_response(StatusCode::METHOD_NOT_ALLOWED))),
+    };
+    result.unwrap_or_else(|e| {
+        error!("WebDAV 请求 {} 失败: {:#}", parts.uri.path(), e);
+        status_response(StatusCode::INTERNAL_SERVER_ERROR)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_path() {
+        assert_eq!(parse_path("/webdav"), Some(vec![]));
+        assert_eq!(parse_path("/webdav/"), Some(vec![]));
+        assert_eq!(
+            parse_path("/webdav/%E6%94%B6%E8%97%8F/a%20b/"),
+            Some(vec!["收藏".to_string(), "a b".to_string()])
+        );
+        assert_eq!(parse_path("/webdav/../etc"), None);
+        assert_eq!(parse_path("/webdav/%2E%2E/etc"), None);
+        assert_eq!(parse_path("/webdav/%zz"), None);
+        assert_eq!(parse_path("/webdavx"), None);
+        assert_eq!(
+            href_for(&["收藏".to_string(), "a b.mp4".to_string()], false),
+            "/webdav/%E6%94%B6%E8%97%8F/a%20b.mp4"
+        );
+    }
+
+    #[test]
+    fn test_unique_name() {
+        let mut used = HashSet::new();
+        assert_eq!(unique_name(&mut used, "标题", "BV1"), "标题");
+        assert_eq!(unique_name(&mut used, "标题", "BV2"), "标题 (BV2)");
+        assert_eq!(unique_name(&mut used, "a/b", "BV3"), "a_b");
+    }
+
+    #[tokio::test]
+    async fn test_cached_listing() {
+        static CACHE: ListingCache<i32, i32> = LazyLock::new(|| Mutex::new(HashMap::new()));
+        let first = cached_listing(&CACHE, 1, async { Ok(vec![1]) }).await.unwrap();
+        // 有效期内不再加载
+        let second = cached_listing(&CACHE, 1, async { Ok(vec![2]) }).await.unwrap();
+        assert_eq!(*first, vec![1]);
+        assert_eq!(*second, vec![1]);
+        let other = cached_listing(&CACHE, 2, async { Ok(vec![3]) }).await.unwrap();
+        assert_eq!(*other, vec![3]);
+        // 加载失败不写入缓存
+        assert!(cached_listing(&CACHE, 3, async { Err(anyhow::anyhow!("失败")) })
+            .await
+            .is_err());
+        assert!(!CACHE.lock().await.contains_key(&3));
+    }
+
+    #[test]
+    fn test_beijing_to_utc() {
+        let time = NaiveDateTime::parse_from_str("2026-10-17 16:00:00", "%Y-%m-%d %H:%M:%S").unwrap();
+        let entry = collection_entry("/webdav/a/".to_string(), "a".to_string(), Some(time));
+        assert_eq!(entry.modified, DateTime::from_timestamp(1_792_224_000, 0));
+    }
+
+    #[test]
+    fn test_build_multistatus() {
+        let entries = vec![
+            collection_entry("/webdav/".to_string(), "webdav".to_string(), None),
+            PropEntry {
+                href: "/webdav/a/b.mp4".to_string(),
+                name: "b.mp4".to_string(),
+                collection: false,
+                size: 1024,
+                modified: DateTime::from_timestamp(1_792_224_000, 0),
+                mime: Some("video/mp4"),
+            },
+        ];
+        let xml = build_multistatus(&entries).unwrap();
+        assert!(xml.contains(r#"<D:multistatus xmlns:D="DAV:">"#));
+        assert!(xml.contains("<D:resourcetype><D:collection/></D:resourcetype>"));
+        assert!(xml.contains("<D:getcontentlength>1024</D:getcontentlength>"));
+        assert!(xml.contains("<D:getlastmodified>Sat, 17 Oct 2026 08:00:00 GMT</D:getlastmodified>"));
+    }
+}
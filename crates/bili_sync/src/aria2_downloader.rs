@@ -4,6 +4,7 @@ use reqwest::Client as ReqwestClient;
 use reqwest::Method;
 use std::io::Read;
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 use std::sync::OnceLock;
 use std::time::Duration;
@@ -12,8 +13,10 @@ use tokio::time::{sleep, timeout};
 use tracing::{debug, error, info, warn};
 
 use crate::bilibili::Client;
-use crate::config::CONFIG_DIR;
+use crate::config::{ExternalAria2Config, CONFIG_DIR};
 use crate::http::headers::{create_api_headers, create_aria2_headers};
+use crate::utils::bandwidth::Throttle;
+use crate::utils::cdn_health;
 
 /// 嵌入的aria2二进制文件 (编译时自动下载对应平台版本)
 #[cfg(target_os = "windows")]
@@ -30,8 +33,10 @@ static ARIA2_BINARY_DECOMPRESSED: OnceLock<Vec<u8>> = OnceLock::new();
 /// 单个aria2进程实例
 #[derive(Debug)]
 pub struct Aria2Instance {
-    process: tokio::process::Child,
+    /// 外部守护进程模式下为 None，进程不由本程序管理
+    process: Option<tokio::process::Child>,
     rpc_port: u16,
+    rpc_url: String,
     rpc_secret: String,
     active_downloads: std::sync::atomic::AtomicUsize,
     last_used: std::sync::Arc<std::sync::Mutex<std::time::Instant>>,
@@ -41,8 +46,26 @@ pub struct Aria2Instance {
 impl Aria2Instance {
     pub fn new(process: tokio::process::Child, rpc_port: u16, rpc_secret: String) -> Self {
         Self {
-            process,
+            process: Some(process),
             rpc_port,
+            rpc_url: format!("http://127.0.0.1:{}/jsonrpc", rpc_port),
+            rpc_secret,
+            active_downloads: std::sync::atomic::AtomicUsize::new(0),
+            last_used: std::sync::Arc::new(std::sync::Mutex::new(std::time::Instant::now())),
+            health_check_failures: std::sync::atomic::AtomicUsize::new(0),
+        }
+    }
+
+    /// 连接已有的aria2守护进程，只通过RPC通信
+    pub fn external(rpc_url: String, rpc_secret: String) -> Self {
+        let rpc_port = reqwest::Url::parse(&rpc_url)
+            .ok()
+            .and_then(|url| url.port_or_known_default())
+            .unwrap_or(0);
+        Self {
+            process: None,
+            rpc_port,
+            rpc_url,
             rpc_secret,
             active_downloads: std::sync::atomic::AtomicUsize::new(0),
             last_used: std::sync::Arc::new(std::sync::Mutex::new(std::time::Instant::now())),
@@ -66,8 +89,12 @@ impl Aria2Instance {
     }
 
     pub fn is_healthy(&mut self) -> bool {
+        // 外部守护进程只能通过RPC判断健康状态
+        let Some(process) = self.process.as_mut() else {
+            return true;
+        };
         // 检查进程是否还在运行
-        match self.process.try_wait() {
+        match process.try_wait() {
             Ok(Some(_)) => {
                 // 进程已退出
                 debug!("aria2进程已退出 (端口: {})", self.rpc_port);
@@ -111,6 +138,10 @@ pub struct Aria2Downloader {
     aria2_instances: Arc<Mutex<Vec<Aria2Instance>>>,
     aria2_binary_path: PathBuf,
     instance_count: usize,
+    /// 当前已下发给aria2实例的全局限速（KB/s），用于配置热重载时判断是否需要更新
+    applied_speed_limit: AtomicU64,
+    /// 连接外部aria2守护进程时的配置，此时不启动、也不终止任何aria2进程
+    external: Option<ExternalAria2Config>,
 }
 
 impl Aria2Downloader {
@@ -125,6 +156,11 @@ impl Aria2Downloader {
 
     /// 创建新的aria2下载器实例，支持多进程
     pub async fn new(client: Client) -> Result<Self> {
+        let external = crate::config::with_config(|bundle| bundle.config.external_aria2.clone());
+        if external.enabled {
+            return Self::new_external(client, external).await;
+        }
+
         tracing::info!("初始化aria2下载器...");
 
         // 启动前先清理所有旧的aria2进程
@@ -145,6 +181,8 @@ impl Aria2Downloader {
             aria2_instances: Arc::new(Mutex::new(Vec::new())),
             aria2_binary_path,
             instance_count,
+            applied_speed_limit: AtomicU64::new(Self::configured_speed_limit()),
+            external: None,
         };
 
         // 启动所有aria2进程实例
@@ -266,6 +304,53 @@ impl Aria2Downloader {
         Ok(downloader)
     }
 
+    /// 连接外部aria2守护进程（例如另一个容器中的aria2），复用其RPC服务
+    async fn new_external(client: Client, external: ExternalAria2Config) -> Result<Self> {
+        let rpc_url = external.rpc_url.trim().to_string();
+        if rpc_url.is_empty() {
+            bail!("已启用外部aria2，但未配置RPC地址");
+        }
+        tracing::info!("连接外部aria2守护进程: {}", rpc_url);
+
+        let downloader = Self {
+            client,
+            rpc_client: Self::build_rpc_client()?,
+            aria2_instances: Arc::new(Mutex::new(Vec::new())),
+            aria2_binary_path: PathBuf::new(),
+            instance_count: 1,
+            applied_speed_limit: AtomicU64::new(Self::configured_speed_limit()),
+            external: Some(external.clone()),
+        };
+        downloader
+            .test_instance_connection(&rpc_url, &external.rpc_secret)
+            .await
+            .with_context(|| format!("无法连接外部aria2守护进程: {}", rpc_url))?;
+        *downloader.aria2_instances.lock().await = vec![Aria2Instance::external(rpc_url, external.rpc_secret)];
+        tracing::info!("外部aria2守护进程连接成功");
+
+        Ok(downloader)
+    }
+
+    /// 把本程序看到的下载目录转换为aria2所在环境中的路径
+    ///
+    /// 只在路径位于本地前缀之下时替换，前缀按路径组件匹配
+    fn translate_dir(dir: &Path, local_prefix: &str, remote_prefix: &str) -> Option<String> {
+        if local_prefix.is_empty() {
+            return None;
+        }
+        let rest = dir.strip_prefix(local_prefix).ok()?;
+        let mut translated = remote_prefix.trim_end_matches(['/', '\\']).to_string();
+        // aria2 通常运行在 Linux 容器中，统一使用 / 分隔
+        for component in rest.components() {
+            translated.push('/');
+            translated.push_str(&component.as_os_str().to_string_lossy());
+        }
+        if translated.is_empty() {
+            translated.push('/');
+        }
+        Some(translated)
+    }
+
     /// 计算最优的aria2进程数量
     fn calculate_optimal_instance_count() -> usize {
         // Force single aria2 instance; use internal split/connection for parallelism.
@@ -430,6 +515,7 @@ impl Aria2Downloader {
 
     fn is_media_like_file(file_name: &str) -> bool {
         let name = file_name.to_ascii_lowercase();
+        let name = name.strip_suffix(".part").unwrap_or(&name);
         name.ends_with(".tmp_video")
             || name.ends_with(".tmp_audio")
             || name.ends_with(".m4s")
@@ -466,7 +552,10 @@ impl Aria2Downloader {
             tokio::time::sleep(Duration::from_secs(3)).await;
 
             // 验证连接（带重试）
-            if let Err(e) = self.test_instance_connection(rpc_port, &instance.rpc_secret).await {
+            if let Err(e) = self
+                .test_instance_connection(&instance.rpc_url, &instance.rpc_secret)
+                .await
+            {
                 warn!("aria2实例 {} 连接测试失败: {:#}", i + 1, e);
                 continue;
             }
@@ -797,7 +886,10 @@ impl Aria2Downloader {
             "--enable-async-dns6=false".to_string(),
             // 网络优化配置
             "--lowest-speed-limit=1K".to_string(),
-            "--max-overall-download-limit=0".to_string(),
+            format!(
+                "--max-overall-download-limit={}K",
+                Self::per_instance_speed_limit(Self::configured_speed_limit(), self.instance_count)
+            ),
             "--stream-piece-selector=geom".to_string(),
             "--piece-length=1M".to_string(),
             "--summary-interval=0".to_string(),
@@ -880,8 +972,8 @@ impl Aria2Downloader {
     }
 
     /// 测试单个实例的连接（带重试机制）
-    async fn test_instance_connection(&self, rpc_port: u16, rpc_secret: &str) -> Result<()> {
-        let url = format!("http://127.0.0.1:{}/jsonrpc", rpc_port);
+    async fn test_instance_connection(&self, rpc_url: &str, rpc_secret: &str) -> Result<()> {
+        let url = rpc_url.to_string();
         let payload = serde_json::json!({
             "jsonrpc": "2.0",
             "method": "aria2.getVersion",
@@ -908,7 +1000,7 @@ impl Aria2Downloader {
     }
 
     /// 选择最佳aria2实例（负载均衡+健康检查）
-    async fn select_best_instance(&self) -> Result<(usize, u16, String)> {
+    async fn select_best_instance(&self) -> Result<(usize, String, String)> {
         let instances = self.aria2_instances.lock().await;
 
         if instances.is_empty() {
@@ -929,7 +1021,7 @@ impl Aria2Downloader {
         if healthy_instances.is_empty() {
             warn!("所有aria2实例都不健康，尝试使用第一个实例");
             let instance = &instances[0];
-            return Ok((0, instance.rpc_port, instance.rpc_secret.clone()));
+            return Ok((0, instance.rpc_url.clone(), instance.rpc_secret.clone()));
         }
 
         // 找到负载最低的健康实例
@@ -938,17 +1030,115 @@ impl Aria2Downloader {
             .min_by_key(|(_, instance)| instance.get_load())
             .ok_or_else(|| anyhow::anyhow!("无法找到可用实例"))?;
 
-        Ok((*best_index, best_instance.rpc_port, best_instance.rpc_secret.clone()))
+        Ok((
+            *best_index,
+            best_instance.rpc_url.clone(),
+            best_instance.rpc_secret.clone(),
+        ))
+    }
+
+    /// 配置中的全局下载限速（KB/s）
+    fn configured_speed_limit() -> u64 {
+        crate::config::with_config(|bundle| bundle.config.concurrent_limit.download_speed_limit)
+    }
+
+    /// 全局限速由所有实例平分，保证多个实例的总速度不超过配置值
+    fn per_instance_speed_limit(total_kbps: u64, instance_count: usize) -> u64 {
+        if total_kbps == 0 {
+            return 0;
+        }
+        total_kbps.div_ceil(instance_count.max(1) as u64)
+    }
+
+    /// 单个下载任务的限速（KB/s），0 表示不限速
+    ///
+    /// aria2 只支持按单个下载任务限速，`source_share` 为该任务分到的视频源限速；
+    /// 外部守护进程不下发全局限速，取两者中更严格的一个作为任务限速
+    fn task_speed_limit(&self, source_share: u64) -> u64 {
+        if self.external.is_none() {
+            return source_share;
+        }
+        match (source_share, Self::configured_speed_limit()) {
+            (0, global) => global,
+            (source, 0) => source,
+            (source, global) => source.min(global),
+        }
+    }
+
+    /// 通过 changeOption 调整正在进行的任务的限速
+    async fn change_task_speed_limit(&self, gid: &str, rpc_url: &str, rpc_secret: &str, task_speed_limit: u64) {
+        let payload = serde_json::json!({
+            "jsonrpc": "2.0",
+            "method": "aria2.changeOption",
+            "id": "change_task_speed_limit",
+            "params": [
+                format!("token:{}", rpc_secret),
+                gid,
+                { "max-download-limit": format!("{}K", task_speed_limit) }
+            ]
+        });
+        match self.rpc_client.post(rpc_url).json(&payload).send().await {
+            Ok(_) => debug!("已将任务限速调整为 {} KB/s (GID: {})", task_speed_limit, gid),
+            Err(e) => debug!("调整任务限速失败 (GID: {}): {:#}", gid, e),
+        }
+    }
+
+    /// 配置热重载后，通过 changeGlobalOption 把新的全局限速下发给正在运行的实例
+    async fn sync_overall_speed_limit(&self) {
+        // 外部守护进程可能还在为其它程序服务，不修改它的全局选项，全局限速改为按任务下发
+        if self.external.is_some() {
+            return;
+        }
+        let configured = Self::configured_speed_limit();
+        if self.applied_speed_limit.load(Ordering::Relaxed) == configured {
+            return;
+        }
+        let limit = format!("{}K", Self::per_instance_speed_limit(configured, self.instance_count));
+        let endpoints: Vec<(u16, String, String)> = {
+            let instances = self.aria2_instances.lock().await;
+            instances
+                .iter()
+                .map(|i| (i.rpc_port, i.rpc_url.clone(), i.rpc_secret.clone()))
+                .collect()
+        };
+        let mut all_ok = true;
+        for (rpc_port, rpc_url, rpc_secret) in endpoints {
+            let payload = serde_json::json!({
+                "jsonrpc": "2.0",
+                "method": "aria2.changeGlobalOption",
+                "id": "change_speed_limit",
+                "params": [
+                    format!("token:{}", rpc_secret),
+                    { "max-overall-download-limit": limit }
+                ]
+            });
+            let result = self
+                .rpc_client
+                .post(&rpc_url)
+                .json(&payload)
+                .send()
+                .await
+                .and_then(|resp| resp.error_for_status());
+            if let Err(e) = result {
+                warn!("更新aria2实例(端口: {})限速失败: {:#}", rpc_port, e);
+                all_ok = false;
+            }
+        }
+        if all_ok {
+            info!("aria2全局限速已更新为 {} KB/s", configured);
+            self.applied_speed_limit.store(configured, Ordering::Relaxed);
+        }
     }
 
     /// 使用aria2下载文件，支持多个URL备选和多进程
-    pub async fn fetch_with_aria2_fallback(&self, urls: &[&str], path: &Path) -> Result<()> {
+    pub async fn fetch_with_aria2_fallback(&self, urls: &[&str], path: &Path, throttle: &Throttle) -> Result<()> {
         if urls.is_empty() {
             bail!("No URLs provided");
         }
         if crate::task::TASK_CONTROLLER.is_paused() {
             bail!("用户主动暂停任务");
         }
+        self.sync_overall_speed_limit().await;
 
         // 尝试删除已存在的文件以确保重新下载（忽略文件不存在的错误）
         // 使用 remove_file 而不是 exists() 检查，避免Windows路径混合斜杠导致的问题
@@ -968,21 +1158,34 @@ impl Aria2Downloader {
             tokio::fs::create_dir_all(parent).await?;
         }
 
-        let file_name = path.file_name().and_then(|n| n.to_str()).context("Invalid file name")?;
+        // aria2 写入 .part 文件并在旁边维护 .aria2 控制文件，中断后再次下载会自动续传，
+        // 完成后再重命名为目标文件，避免留下被误认为已完成的半截文件
+        let part_path = crate::downloader::part_path(path);
+        let file_name = part_path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .context("Invalid file name")?;
         let dir = path
             .parent()
             .and_then(|p| p.to_str())
             .context("Invalid directory path")?;
+        if part_path.exists() {
+            info!("检测到未完成的下载，aria2将尝试续传: {}", part_path.display());
+        }
+        // 外部aria2看到的目录可能与本程序不同，按配置的前缀转换
+        let remote_dir = self.external.as_ref().and_then(|external| {
+            Self::translate_dir(
+                Path::new(dir),
+                &external.local_path_prefix,
+                &external.remote_path_prefix,
+            )
+        });
+        let dir = remote_dir.as_deref().unwrap_or(dir);
 
         // 选择最佳的aria2实例
-        let (instance_index, rpc_port, rpc_secret) = self.select_best_instance().await?;
+        let (instance_index, rpc_url, rpc_secret) = self.select_best_instance().await?;
 
-        info!(
-            "使用aria2实例 {} (端口: {}) 下载: {}",
-            instance_index + 1,
-            rpc_port,
-            file_name
-        );
+        info!("使用aria2实例 {} ({}) 下载: {}", instance_index + 1, rpc_url, file_name);
 
         // 增加该实例的负载计数
         {
@@ -992,16 +1195,23 @@ impl Aria2Downloader {
             }
         }
 
-        // 构建aria2 RPC请求
+        // 构建aria2 RPC请求，视频源限速由同一视频源正在进行的任务平分
+        let _source_share = throttle.register_external_task();
+        let task_speed_limit = self.task_speed_limit(throttle.external_task_kbps());
         let gid = self
-            .add_download_task_to_instance(urls, dir, file_name, rpc_port, &rpc_secret)
+            .add_download_task_to_instance(urls, dir, file_name, &rpc_url, &rpc_secret, task_speed_limit)
             .await?;
 
         // 等待下载完成
         let result = self
-            .wait_for_download_on_instance(&gid, rpc_port, &rpc_secret, instance_index)
+            .wait_for_download_on_instance(&gid, &rpc_url, &rpc_secret, instance_index, throttle, task_speed_limit)
             .await;
 
+        // 外部守护进程不会随本程序关闭，放弃的任务需要主动移除，避免它在后台继续下载
+        if result.is_err() && self.external.is_some() {
+            self.remove_task(&gid, &rpc_url, &rpc_secret).await;
+        }
+
         // 减少该实例的负载计数
         {
             let instances = self.aria2_instances.lock().await;
@@ -1014,7 +1224,10 @@ impl Aria2Downloader {
         result?;
 
         // 增强的文件验证逻辑
-        self.verify_downloaded_file(path).await?;
+        self.verify_downloaded_file(&part_path).await?;
+        tokio::fs::rename(&part_path, path)
+            .await
+            .with_context(|| format!("重命名 {} -> {} 失败", part_path.display(), path.display()))?;
 
         Ok(())
     }
@@ -1025,10 +1238,11 @@ impl Aria2Downloader {
         urls: &[&str],
         dir: &str,
         file_name: &str,
-        rpc_port: u16,
+        rpc_url: &str,
         rpc_secret: &str,
+        task_speed_limit: u64,
     ) -> Result<String> {
-        let url = format!("http://127.0.0.1:{}/jsonrpc", rpc_port);
+        let url = rpc_url.to_string();
 
         // 智能计算当前实例的线程数
         let current_config = crate::config::reload_config();
@@ -1095,6 +1309,10 @@ impl Aria2Downloader {
             "header": create_aria2_headers()
         });
 
+        if task_speed_limit > 0 {
+            options["max-download-limit"] = serde_json::Value::String(format!("{}K", task_speed_limit));
+        }
+
         // 添加SSL/TLS相关配置
         if cfg!(target_os = "linux") {
             let ca_paths = [
@@ -1180,11 +1398,13 @@ impl Aria2Downloader {
     async fn wait_for_download_on_instance(
         &self,
         gid: &str,
-        rpc_port: u16,
+        rpc_url: &str,
         rpc_secret: &str,
         _instance_index: usize,
+        throttle: &Throttle,
+        mut applied_speed_limit: u64,
     ) -> Result<()> {
-        let url = format!("http://127.0.0.1:{}/jsonrpc", rpc_port);
+        let url = rpc_url.to_string();
         let mut consecutive_failures = 0;
         const MAX_CONSECUTIVE_FAILURES: u32 = 5;
 
@@ -1222,7 +1442,7 @@ impl Aria2Downloader {
                 "params": [
                     format!("token:{}", rpc_secret),
                     gid,
-                    ["status", "totalLength", "completedLength", "downloadSpeed", "errorMessage"]
+                    ["status", "totalLength", "completedLength", "downloadSpeed", "errorMessage", "files"]
                 ]
             });
 
@@ -1277,6 +1497,7 @@ impl Aria2Downloader {
 
             let result = &json["result"];
             let status = result["status"].as_str().unwrap_or("unknown");
+            let used_uris = Self::used_uris(result);
 
             match status {
                 "complete" => {
@@ -1285,6 +1506,12 @@ impl Aria2Downloader {
                     let completed_length = result["completedLength"].as_str().unwrap_or("0");
                     let download_speed = result["downloadSpeed"].as_str().unwrap_or("0");
 
+                    // 同时使用了多个节点时平均分摊下载量
+                    let completed_bytes = completed_length.parse::<u64>().unwrap_or(0);
+                    for uri in &used_uris {
+                        cdn_health::record_success(uri, completed_bytes / used_uris.len() as u64, start_time.elapsed());
+                    }
+
                     if let (Ok(total), Ok(completed), Ok(speed)) = (
                         total_length.parse::<u64>(),
                         completed_length.parse::<u64>(),
@@ -1320,6 +1547,9 @@ impl Aria2Downloader {
                         info!("aria2下载因用户暂停而失败 (GID: {})：{}", gid, error_msg);
                     } else {
                         error!("aria2下载失败 (GID: {})：{}", gid, error_msg);
+                        for uri in &used_uris {
+                            cdn_health::record_failure(uri, error_msg);
+                        }
                     }
 
                     bail!("下载失败: {}", error_msg);
@@ -1351,6 +1581,12 @@ impl Aria2Downloader {
                         .parse::<u64>()
                         .unwrap_or(0);
 
+                    if last_completed_length == 0 && completed_length > 0 {
+                        for uri in &used_uris {
+                            cdn_health::record_first_byte(uri, start_time.elapsed());
+                        }
+                    }
+
                     // 检查下载是否停滞
                     if completed_length == last_completed_length {
                         stall_count += 1;
@@ -1396,11 +1632,49 @@ impl Aria2Downloader {
                 }
             }
 
+            // 同一视频源的任务开始或结束后重新平分视频源限速
+            let task_speed_limit = self.task_speed_limit(throttle.external_task_kbps());
+            if task_speed_limit != applied_speed_limit {
+                self.change_task_speed_limit(gid, rpc_url, rpc_secret, task_speed_limit)
+                    .await;
+                applied_speed_limit = task_speed_limit;
+            }
+
             // 使用动态检查间隔
             sleep(check_interval).await;
         }
     }
 
+    /// tellStatus 结果中正在使用（或最终使用过）的下载链接
+    fn used_uris(result: &serde_json::Value) -> Vec<String> {
+        result["files"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|file| file["uris"].as_array())
+            .flatten()
+            .filter(|uri| uri["status"].as_str() == Some("used"))
+            .filter_map(|uri| uri["uri"].as_str().map(str::to_string))
+            .collect::<std::collections::BTreeSet<_>>()
+            .into_iter()
+            .collect()
+    }
+
+    /// 强制移除下载任务并清除其结果记录，失败时只记录日志
+    async fn remove_task(&self, gid: &str, rpc_url: &str, rpc_secret: &str) {
+        for method in ["aria2.forceRemove", "aria2.removeDownloadResult"] {
+            let payload = serde_json::json!({
+                "jsonrpc": "2.0",
+                "method": method,
+                "id": "remove_task",
+                "params": [format!("token:{}", rpc_secret), gid]
+            });
+            if let Err(e) = self.rpc_client.post(rpc_url).json(&payload).send().await {
+                debug!("移除aria2任务失败 (GID: {}, {}): {:#}", gid, method, e);
+            }
+        }
+    }
+
     /// 合并视频和音频文件
     pub async fn merge(&self, video_path: &Path, audio_path: &Path, output_path: &Path) -> Result<()> {
         use crate::downloader::Downloader;
@@ -1412,19 +1686,29 @@ impl Aria2Downloader {
 
     /// 优雅关闭所有aria2进程
     pub async fn shutdown(&self) -> Result<()> {
+        if self.external.is_some() {
+            // 外部守护进程由其所在环境管理，这里只断开连接
+            self.aria2_instances.lock().await.clear();
+            info!("已断开外部aria2守护进程");
+            return Ok(());
+        }
+
         info!("正在关闭所有aria2实例...");
 
         let mut instances = self.aria2_instances.lock().await;
         let mut shutdown_futures = Vec::new();
 
         for (i, instance) in instances.iter_mut().enumerate() {
-            let rpc_port = instance.rpc_port;
+            let Some(process) = instance.process.as_mut() else {
+                continue;
+            };
+            let rpc_url = instance.rpc_url.clone();
             let rpc_secret = instance.rpc_secret.clone();
             let rpc_client = self.rpc_client.clone();
 
             // 尝试优雅关闭aria2实例
             let shutdown_future = async move {
-                let url = format!("http://127.0.0.1:{}/jsonrpc", rpc_port);
+                let url = rpc_url;
                 let payload = serde_json::json!({
                     "jsonrpc": "2.0",
                     "method": "aria2.shutdown",
@@ -1439,13 +1723,13 @@ impl Aria2Downloader {
             shutdown_futures.push(shutdown_future);
 
             // 强制终止进程 - Windows兼容性改进
-            if let Err(e) = instance.process.kill().await {
+            if let Err(e) = process.kill().await {
                 warn!("终止aria2实例 {} 失败: {}", i + 1, e);
 
                 // 如果普通kill失败，尝试使用系统命令强制终止
                 #[cfg(target_os = "windows")]
                 {
-                    if let Some(pid) = instance.process.id() {
+                    if let Some(pid) = process.id() {
                         let _ = tokio::process::Command::new("taskkill")
                             .args(["/F", "/PID", &pid.to_string()])
                             .output()
@@ -1456,7 +1740,7 @@ impl Aria2Downloader {
 
                 #[cfg(target_os = "linux")]
                 {
-                    if let Some(pid) = instance.process.id() {
+                    if let Some(pid) = process.id() {
                         let _ = tokio::process::Command::new("kill")
                             .args(["-9", &pid.to_string()])
                             .output()
@@ -1467,7 +1751,7 @@ impl Aria2Downloader {
 
                 #[cfg(any(target_os = "macos", target_os = "ios"))]
                 {
-                    if let Some(pid) = instance.process.id() {
+                    if let Some(pid) = process.id() {
                         let _ = tokio::process::Command::new("kill")
                             .args(["-9", &pid.to_string()])
                             .output()
@@ -1547,7 +1831,7 @@ impl Aria2Downloader {
             }
 
             // 对于空闲实例，进行RPC健康检查
-            let rpc_healthy = Self::check_instance_rpc_health(client, instance.rpc_port, &instance.rpc_secret).await;
+            let rpc_healthy = Self::check_instance_rpc_health(client, &instance.rpc_url, &instance.rpc_secret).await;
             if !rpc_healthy {
                 warn!("aria2实例 {} RPC连接不健康，准备重启", i + 1);
                 unhealthy_indices.push(i);
@@ -1597,8 +1881,9 @@ impl Aria2Downloader {
     }
 
     /// 检查实例的RPC健康状态
-    async fn check_instance_rpc_health(client: &ReqwestClient, rpc_port: u16, rpc_secret: &str) -> bool {
+    async fn check_instance_rpc_health(client: &ReqwestClient, rpc_url: &str, rpc_secret: &str) -> bool {
         let client_clone = client.clone();
+        let rpc_url_clone = rpc_url.to_string();
         let rpc_secret_clone = rpc_secret.to_string();
 
         let result = Self::retry_with_backoff_static(
@@ -1609,9 +1894,9 @@ impl Aria2Downloader {
             Duration::from_secs(10), // 增加RPC健康检查超时时间到10秒
             move || {
                 let client = client_clone.clone();
+                let url = rpc_url_clone.clone();
                 let rpc_secret = rpc_secret_clone.clone();
                 async move {
-                    let url = format!("http://127.0.0.1:{}/jsonrpc", rpc_port);
                     let payload = serde_json::json!({
                         "jsonrpc": "2.0",
                         "method": "aria2.getVersion",
@@ -1652,7 +1937,9 @@ impl Aria2Downloader {
         tokio::time::sleep(Duration::from_secs(3)).await;
 
         // 验证连接
-        temp_downloader.test_instance_connection(rpc_port, &rpc_secret).await?;
+        temp_downloader
+            .test_instance_connection(&instance.rpc_url, &rpc_secret)
+            .await?;
 
         info!("新aria2实例创建成功，端口: {}", rpc_port);
         Ok(instance)
@@ -1667,6 +1954,8 @@ impl Aria2Downloader {
             aria2_instances: Arc::new(Mutex::new(Vec::new())),
             aria2_binary_path,
             instance_count: 1,
+            applied_speed_limit: AtomicU64::new(Self::configured_speed_limit()),
+            external: None,
         })
     }
 
@@ -1935,3 +2224,26 @@ impl Drop for Aria2Downloader {
         // 如果后续需要清理，可在显式卸载/清理路径中处理，而不是在Drop中
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_translate_dir() {
+        let translate =
+            |dir: &str, local: &str, remote: &str| Aria2Downloader::translate_dir(Path::new(dir), local, remote);
+        assert_eq!(
+            translate("/downloads/收藏夹/视频", "/downloads", "/data"),
+            Some("/data/收藏夹/视频".to_string())
+        );
+        assert_eq!(
+            translate("/downloads", "/downloads/", "/data/"),
+            Some("/data".to_string())
+        );
+        assert_eq!(translate("/downloads/a", "/downloads", "/"), Some("/a".to_string()));
+        // 前缀按路径组件匹配
+        assert_eq!(translate("/downloads2/a", "/downloads", "/data"), None);
+        assert_eq!(translate("/downloads/a", "", "/data"), None);
+    }
+}
@@ -163,13 +163,11 @@ impl Stream {
         match self {
             Self::Flv(url) | Self::Html5Mp4(url) | Self::EpisodeTryMp4(url) => vec![url],
             Self::DashVideo { url, backup_url, .. } | Self::DashAudio { url, backup_url, .. } => {
-                let mut urls = std::iter::once(url.as_str())
+                let mut urls: Vec<&str> = std::iter::once(url.as_str())
                     .chain(backup_url.iter().map(|s| s.as_str()))
                     .collect();
                 let current_config = crate::config::reload_config();
-                if !current_config.cdn_sorting {
-                    urls
-                } else {
+                if current_config.cdn_sorting {
                     urls.sort_by_key(|u| {
                         if u.contains("upos-") {
                             0 // 服务商 cdn
@@ -181,8 +179,10 @@ impl Stream {
                             3 // pcdn 或者其它
                         }
                     });
-                    urls
                 }
+                // 再按各节点实际的下载表现排序，上面的静态优先级只决定评分相同（如尚无统计）的节点之间的顺序
+                crate::utils::cdn_health::sort_urls(&mut urls);
+                urls
             }
         }
     }
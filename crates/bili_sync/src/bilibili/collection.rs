@@ -65,6 +65,7 @@ pub struct CollectionInfo {
     pub mid: i64,
     pub sid: i64,
     pub collection_type: CollectionType,
+    pub description: String,
 }
 
 impl<'de> Deserialize<'de> for CollectionInfo {
@@ -78,6 +79,8 @@ impl<'de> Deserialize<'de> for CollectionInfo {
             name: String,
             season_id: Option<i64>,
             series_id: Option<i64>,
+            #[serde(default)]
+            description: String,
         }
         let raw = CollectionInfoRaw::deserialize(deserializer)?;
         let (sid, collection_type) = match (raw.season_id, raw.series_id) {
@@ -90,6 +93,7 @@ impl<'de> Deserialize<'de> for CollectionInfo {
             name: raw.name,
             sid,
             collection_type,
+            description: raw.description,
         })
     }
 }
@@ -338,6 +342,7 @@ mod tests {
                     name: "合集·【命运方舟全剧情解说】".to_owned(),
                     sid: 1987140,
                     collection_type: CollectionType::Season,
+                    description: String::new(),
                 },
             ),
             (
@@ -365,6 +370,7 @@ mod tests {
                     name: "提瓦特冒险记".to_owned(),
                     sid: 387212,
                     collection_type: CollectionType::Series,
+                    description: "原神沙雕般的游戏体验".to_owned(),
                 },
             ),
         ];
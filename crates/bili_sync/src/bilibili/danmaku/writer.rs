@@ -6,6 +6,7 @@ use tokio::fs::{self, File};
 use crate::bilibili::danmaku::canvas::{CanvasConfig, DanmakuOption};
 use crate::bilibili::danmaku::{AssWriter, Danmu};
 use crate::bilibili::PageInfo;
+use crate::utils::atomic_write::{commit_temp, temp_path_for};
 
 pub struct DanmakuWriter<'a> {
     page: &'a PageInfo,
@@ -29,15 +30,29 @@ impl<'a> DanmakuWriter<'a> {
             let static_option: &'static DanmakuOption = Box::leak(Box::new(danmaku_option));
             CanvasConfig::new(static_option, self.page)
         });
-        let mut writer =
-            AssWriter::construct(File::create(path).await?, self.page.name.clone(), canvas_config.clone()).await?;
-        let mut canvas = canvas_config.canvas();
-        for danmuku in self.danmaku {
-            if let Some(drawable) = canvas.draw(danmuku)? {
-                writer.write(drawable).await?;
+        // 先写入临时文件，全部写完后再重命名，避免中断时留下不完整的弹幕文件
+        let tmp_path = temp_path_for(&path);
+        let write_result = async {
+            let mut writer = AssWriter::construct(
+                File::create(&tmp_path).await?,
+                self.page.name.clone(),
+                canvas_config.clone(),
+            )
+            .await?;
+            let mut canvas = canvas_config.canvas();
+            for danmuku in self.danmaku {
+                if let Some(drawable) = canvas.draw(danmuku)? {
+                    writer.write(drawable).await?;
+                }
             }
+            writer.flush().await?;
+            Ok::<_, anyhow::Error>(())
         }
-        writer.flush().await?;
-        Ok(())
+        .await;
+        if let Err(e) = write_result {
+            let _ = fs::remove_file(&tmp_path).await;
+            return Err(e);
+        }
+        commit_temp(&tmp_path, &path).await
     }
 }
@@ -14,6 +14,10 @@ pub struct FavoriteList<'a> {
 pub struct FavoriteListInfo {
     pub id: i64,
     pub title: String,
+    #[serde(default)]
+    pub intro: String,
+    #[serde(default)]
+    pub cover: String,
 }
 
 #[derive(Debug, serde::Deserialize)]
@@ -19,7 +19,7 @@ use once_cell::sync::Lazy;
 pub use risk_control::{CaptchaInfo, CaptchaResult, GeetestInfo, RiskControl};
 pub use submission::Submission;
 pub use verification_coordinator::{VerificationRequest, VERIFICATION_COORDINATOR};
-pub use video::{bvid_to_aid, Dimension, PageInfo, Video};
+pub use video::{bvid_to_aid, try_bvid_to_aid, Chapter, Dimension, PageInfo, PlayerInfo, Video, VideoShot};
 pub use watch_later::WatchLater;
 pub mod bangumi;
 
@@ -75,6 +75,51 @@ pub struct PageInfo {
     pub dimension: Option<Dimension>,
 }
 
+/// 视频的分段章节（view points），时间单位为秒
+#[derive(Debug, Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct Chapter {
+    pub from: u32,
+    pub to: u32,
+    pub content: String,
+}
+
+/// 从播放器接口的 `data.view_points` 中提取章节，按起始时间排序；逐条解析，格式异常或无效的条目单独丢弃
+fn parse_chapters(view_points: &serde_json::Value) -> Vec<Chapter> {
+    let mut chapters = view_points
+        .as_array()
+        .map(Vec::as_slice)
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|view_point| serde_json::from_value::<Chapter>(view_point.clone()).ok())
+        .filter(|chapter| chapter.to > chapter.from && !chapter.content.trim().is_empty())
+        .collect::<Vec<_>>();
+    chapters.sort_by_key(|chapter| chapter.from);
+    chapters
+}
+
+/// 播放器接口（`x/player/wbi/v2`）返回的分页信息，字幕和分段章节都从同一次请求中解析
+pub struct PlayerInfo(serde_json::Value);
+
+impl PlayerInfo {
+    /// 分页的分段章节，没有章节时返回空列表
+    pub fn chapters(&self) -> Vec<Chapter> {
+        parse_chapters(&self.0["data"]["view_points"])
+    }
+}
+
+/// 视频快照（进度条预览）雪碧图，每张雪碧图按行排列 `img_x_len × img_y_len` 个缩略图
+#[derive(Debug, Clone, Default, serde::Deserialize)]
+pub struct VideoShot {
+    /// 雪碧图地址
+    #[serde(default)]
+    pub image: Vec<String>,
+    /// 每个缩略图对应的时间（秒），与雪碧图中的排列顺序一致
+    #[serde(default)]
+    pub index: Vec<u32>,
+    pub img_x_len: u32,
+    pub img_y_len: u32,
+}
+
 #[derive(Debug, serde::Deserialize, Default)]
 pub struct Dimension {
     pub width: u32,
@@ -1528,7 +1573,8 @@ impl<'a> Video<'a> {
         Ok(PageAnalyzer::new(validated_res["result"].take()))
     }
 
-    pub async fn get_subtitles(&self, page: &PageInfo) -> Result<Vec<SubTitle>> {
+    /// 播放器接口，包含字幕和分段章节等信息；同一分页只需请求一次
+    pub async fn get_player_info(&self, page: &PageInfo) -> Result<PlayerInfo> {
         let res = self
             .client
             .request(Method::GET, "https://api.bilibili.com/x/player/wbi/v2")
@@ -1543,9 +1589,44 @@ impl<'a> Video<'a> {
             .json::<serde_json::Value>()
             .await?
             .validate()?;
+        Ok(PlayerInfo(res))
+    }
+
+    /// 获取分页的视频快照，视频没有快照时返回 None
+    pub async fn get_videoshot(&self, page: &PageInfo) -> Result<Option<VideoShot>> {
+        let mut res = self
+            .client
+            .request(Method::GET, "https://api.bilibili.com/x/player/videoshot")
+            .await
+            .query(&[
+                ("bvid", self.bvid.as_str()),
+                ("cid", &page.cid.to_string()),
+                ("index", "1"),
+            ])
+            .send()
+            .await?
+            .error_for_status()?
+            .json::<serde_json::Value>()
+            .await?
+            .validate()?;
+        let mut shot: VideoShot = match serde_json::from_value(res["data"].take()) {
+            Ok(shot) => shot,
+            Err(_) => return Ok(None),
+        };
+        if shot.image.is_empty() || shot.index.is_empty() || shot.img_x_len == 0 || shot.img_y_len == 0 {
+            return Ok(None);
+        }
+        for url in shot.image.iter_mut() {
+            if url.starts_with("//") {
+                *url = format!("https:{}", url);
+            }
+        }
+        Ok(Some(shot))
+    }
 
+    pub async fn get_subtitles(&self, player_info: &PlayerInfo) -> Result<Vec<SubTitle>> {
         // 检查字幕数据是否存在
-        let subtitle_data = &res["data"]["subtitle"];
+        let subtitle_data = &player_info.0["data"]["subtitle"];
         if subtitle_data.is_null() {
             debug!("视频没有字幕数据");
             return Ok(Vec::new());
@@ -1721,14 +1802,43 @@ pub fn bvid_to_aid(bvid: &str) -> u64 {
     (tmp & MASK_CODE) ^ XOR_CODE
 }
 
+/// 校验格式后再转换，用于数据库中可能存在占位 bvid 的场景
+pub fn try_bvid_to_aid(bvid: &str) -> Option<u64> {
+    let valid = bvid.len() == 12 && bvid.starts_with("BV") && bvid.chars().skip(3).all(|c| DATA.contains(&c));
+    valid.then(|| bvid_to_aid(bvid))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_parse_chapters() {
+        let view_points = serde_json::json!([
+            {"type": 2, "from": 95, "to": 300, "content": "正片", "imgUrl": ""},
+            {"type": 2, "from": 0, "to": 95, "content": "开场", "imgUrl": ""},
+            {"type": 2, "from": 300, "to": 300, "content": "空章节", "imgUrl": ""},
+            {"type": 2, "from": "300", "to": 400, "content": "格式异常", "imgUrl": ""},
+            {"type": 2, "from": 400, "to": 500, "imgUrl": ""}
+        ]);
+        let chapters = parse_chapters(&view_points);
+        assert_eq!(
+            chapters
+                .iter()
+                .map(|c| (c.from, c.content.as_str()))
+                .collect::<Vec<_>>(),
+            vec![(0, "开场"), (95, "正片")]
+        );
+        assert!(parse_chapters(&serde_json::Value::Null).is_empty());
+    }
+
     #[test]
     fn test_bvid_to_aid() {
         assert_eq!(bvid_to_aid("BV1Tr421n746"), 1401752220u64);
         assert_eq!(bvid_to_aid("BV1sH4y1s7fe"), 1051892992u64);
+        assert_eq!(try_bvid_to_aid("BV1Tr421n746"), Some(1401752220u64));
+        assert_eq!(try_bvid_to_aid("BV0000000000"), None);
+        assert_eq!(try_bvid_to_aid("ep12345"), None);
     }
 
     #[test]
@@ -5,6 +5,7 @@ use handlebars::Handlebars;
 use leaky_bucket::RateLimiter;
 
 use crate::config::Config;
+use crate::utils::bandwidth::SpeedLimiter;
 
 /// 配置包，包含所有需要热重载的组件
 /// 使用 ArcSwap<ConfigBundle> 确保原子性更新
@@ -17,6 +18,8 @@ pub struct ConfigBundle {
     /// HTTP 请求限流器
     #[allow(dead_code)]
     pub rate_limiter: Arc<RateLimiter>,
+    /// 全局下载带宽限速器，未设置限速时为 None
+    pub bandwidth_limiter: Option<Arc<SpeedLimiter>>,
 }
 
 impl ConfigBundle {
@@ -24,11 +27,13 @@ impl ConfigBundle {
     pub fn from_config(config: Config) -> Result<Self> {
         let handlebars = Self::build_handlebars(&config)?;
         let rate_limiter = Self::build_rate_limiter(&config);
+        let bandwidth_limiter = Self::build_bandwidth_limiter(&config);
 
         Ok(Self {
             config,
             handlebars,
             rate_limiter: Arc::new(rate_limiter),
+            bandwidth_limiter,
         })
     }
 
@@ -129,6 +134,14 @@ impl ConfigBundle {
         }
     }
 
+    /// 构建下载带宽限速器
+    fn build_bandwidth_limiter(config: &Config) -> Option<Arc<SpeedLimiter>> {
+        match config.concurrent_limit.download_speed_limit {
+            0 => None,
+            kbps => Some(Arc::new(SpeedLimiter::new(kbps))),
+        }
+    }
+
     /// 检查配置是否有效
     #[cfg(not(test))]
     pub fn validate(&self) -> bool {
@@ -252,6 +265,10 @@ impl std::fmt::Debug for ConfigBundle {
             .field("config", &"<Config instance>")
             .field("handlebars", &"<Handlebars instance>")
             .field("rate_limiter", &"<RateLimiter instance>")
+            .field(
+                "bandwidth_limiter",
+                &self.bandwidth_limiter.as_ref().map(|limiter| limiter.kbps()),
+            )
             .finish()
     }
 }
@@ -350,6 +367,22 @@ mod tests {
         assert_ne!(result1, result2, "不同配置应该产生不同的渲染结果");
     }
 
+    #[test]
+    fn test_bandwidth_limiter_follows_config() {
+        let bundle = ConfigBundle::from_config(Config::default()).unwrap();
+        assert!(bundle.bandwidth_limiter.is_none());
+
+        let config = Config {
+            concurrent_limit: crate::config::item::ConcurrentLimit {
+                download_speed_limit: 2048,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let bundle = ConfigBundle::from_config(config).unwrap();
+        assert_eq!(bundle.bandwidth_limiter.map(|limiter| limiter.kbps()), Some(2048));
+    }
+
     #[test]
     fn test_template_render_consistency() {
         let config = Config {
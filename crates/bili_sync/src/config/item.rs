@@ -25,6 +25,31 @@ pub enum NFOFormatType {
     Detailed,
 }
 
+/// NFO 输出方案，按目标媒体服务器调整同一份元数据的写法
+#[derive(Serialize, Deserialize, Default, Clone, Copy, PartialEq, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum NFOProfile {
+    /// Kodi（默认，年份和日期均使用配置的时间类型）
+    #[default]
+    Kodi,
+    /// Jellyfin：首映日期使用发布时间，配置的时间类型写入 dateadded，演员头像引用本地缓存
+    Jellyfin,
+    /// Emby：读取 NFO 的方式与 Jellyfin 相同，沿用 Jellyfin 的输出
+    Emby,
+    /// Plex（XBMCnfo 导入器）：纯文本简介并额外写入 id 元素，单P视频同样按剧集写入
+    Plex,
+}
+
+impl NFOProfile {
+    /// 普通单P视频是否写为电影
+    ///
+    /// Plex 的资料库不能同时包含电影和剧集，多P、合集和番剧都按剧集组织，
+    /// 因此单P视频也写为一集，并在视频文件夹中补充 tvshow.nfo
+    pub fn single_page_as_movie(self) -> bool {
+        self != NFOProfile::Plex
+    }
+}
+
 /// 空UP主信息处理策略
 #[derive(Serialize, Deserialize, Default, Clone, PartialEq, Debug)]
 #[serde(rename_all = "lowercase")]
@@ -47,6 +72,9 @@ pub struct NFOConfig {
     /// NFO 文件格式类型
     #[serde(default)]
     pub format_type: NFOFormatType,
+    /// 目标媒体服务器
+    #[serde(default)]
+    pub profile: NFOProfile,
     /// NFO 文件使用的时间类型
     #[serde(default)]
     pub time_type: NFOTimeType,
@@ -74,6 +102,12 @@ pub struct NFOConfig {
     /// 空UP主的默认名称（当策略为Default时使用）
     #[serde(default = "default_empty_upper_default_name")]
     pub empty_upper_default_name: String,
+    /// 是否把收藏夹、合集作为媒体服务器合集写入 NFO 的 set 和 tag
+    #[serde(default = "default_collection_set")]
+    pub collection_set: bool,
+    /// 合集文件夹的存放目录，留空表示不生成；每个收藏夹/合集生成一个带封面和 NFO 的文件夹
+    #[serde(default)]
+    pub box_set_path: String,
 }
 
 fn default_nfo_enabled() -> bool {
@@ -108,11 +142,16 @@ fn default_empty_upper_default_name() -> String {
     "哔哩哔哩".to_string()
 }
 
+fn default_collection_set() -> bool {
+    true
+}
+
 impl Default for NFOConfig {
     fn default() -> Self {
         Self {
             enabled: default_nfo_enabled(),
             format_type: NFOFormatType::default(),
+            profile: NFOProfile::default(),
             time_type: NFOTimeType::default(),
             include_bilibili_info: default_include_bilibili_info(),
             include_actor_info: default_include_actor_info(),
@@ -122,6 +161,8 @@ impl Default for NFOConfig {
             empty_upper_strategy: EmptyUpperStrategy::default(),
             empty_upper_placeholder: default_empty_upper_placeholder(),
             empty_upper_default_name: default_empty_upper_default_name(),
+            collection_set: default_collection_set(),
+            box_set_path: String::new(),
         }
     }
 }
@@ -162,6 +203,448 @@ impl Default for ParallelDownloadConfig {
     }
 }
 
+/// CDN 节点健康评分配置
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CdnHealthConfig {
+    /// 是否根据各节点的下载表现调整下载链接顺序
+    #[serde(default = "default_cdn_health_enabled")]
+    pub enabled: bool,
+    /// 连续失败多少次后暂时拉黑该节点
+    #[serde(default = "default_cdn_blacklist_threshold")]
+    pub blacklist_threshold: u32,
+    /// 拉黑时长（分钟），期间该节点的链接排在最后
+    #[serde(default = "default_cdn_blacklist_minutes")]
+    pub blacklist_minutes: u64,
+}
+
+fn default_cdn_health_enabled() -> bool {
+    true
+}
+
+fn default_cdn_blacklist_threshold() -> u32 {
+    3
+}
+
+fn default_cdn_blacklist_minutes() -> u64 {
+    30
+}
+
+impl Default for CdnHealthConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_cdn_health_enabled(),
+            blacklist_threshold: default_cdn_blacklist_threshold(),
+            blacklist_minutes: default_cdn_blacklist_minutes(),
+        }
+    }
+}
+
+/// 下载完成后的后处理配置
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PostProcessConfig {
+    /// 同时运行的后处理任务数（修改后重启生效）
+    #[serde(default = "default_postprocess_max_workers")]
+    pub max_workers: usize,
+    /// 可供视频源选择的后处理配置
+    #[serde(default)]
+    pub profiles: Vec<PostProcessProfile>,
+}
+
+fn default_postprocess_max_workers() -> usize {
+    1
+}
+
+impl Default for PostProcessConfig {
+    fn default() -> Self {
+        Self {
+            max_workers: default_postprocess_max_workers(),
+            profiles: Vec::new(),
+        }
+    }
+}
+
+/// 一个命名的后处理配置，由 ffmpeg 在视频合并完成后执行
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PostProcessProfile {
+    /// 配置名称，视频源通过名称引用
+    pub name: String,
+    /// 仅处理这些编码的视频（ffprobe 的 codec_name，如 av1、hevc），为空且未设置最大高度时处理所有视频
+    #[serde(default)]
+    pub match_codecs: Vec<String>,
+    /// 视频编码器
+    #[serde(default = "default_postprocess_video_codec")]
+    pub video_codec: String,
+    /// 质量参数（CRF），为空时使用编码器默认值
+    #[serde(default)]
+    pub crf: Option<u32>,
+    /// 编码预设，如 veryfast、medium
+    #[serde(default)]
+    pub preset: Option<String>,
+    /// 最大高度（像素），超过时等比缩小，0 表示不缩放
+    #[serde(default)]
+    pub max_height: u32,
+    /// 音频编码器，为空时直接复制音频流
+    #[serde(default)]
+    pub audio_codec: Option<String>,
+    /// 保留原文件，另存一份兼容副本
+    #[serde(default)]
+    pub keep_original: bool,
+    /// 兼容副本的文件名后缀，例如 `标题 - compat.mp4`
+    #[serde(default = "default_postprocess_copy_suffix")]
+    pub copy_suffix: String,
+    /// 追加到 ffmpeg 输出参数中的额外参数
+    #[serde(default)]
+    pub extra_args: Vec<String>,
+}
+
+fn default_postprocess_video_codec() -> String {
+    "libx264".to_string()
+}
+
+fn default_postprocess_copy_suffix() -> String {
+    "compat".to_string()
+}
+
+/// 向下载完成的 mp4/m4a 文件写入元数据标签的配置
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct MetadataEmbedConfig {
+    /// 是否写入标题、UP主、发布日期、简介和标签
+    #[serde(default = "default_metadata_embed_enabled")]
+    pub enabled: bool,
+    /// 是否同时嵌入封面图片
+    #[serde(default = "default_metadata_embed_cover")]
+    pub embed_cover: bool,
+}
+
+fn default_metadata_embed_enabled() -> bool {
+    true
+}
+
+fn default_metadata_embed_cover() -> bool {
+    true
+}
+
+impl Default for MetadataEmbedConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_metadata_embed_enabled(),
+            embed_cover: default_metadata_embed_cover(),
+        }
+    }
+}
+
+/// 仅音频模式响度标准化（EBU R128）的目标值
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct LoudnessConfig {
+    /// 目标综合响度（LUFS）
+    #[serde(default = "default_loudness_integrated")]
+    pub integrated: f64,
+    /// 最大真峰值（dBTP）
+    #[serde(default = "default_loudness_true_peak")]
+    pub true_peak: f64,
+    /// 目标响度范围（LU）
+    #[serde(default = "default_loudness_range")]
+    pub range: f64,
+}
+
+fn default_loudness_integrated() -> f64 {
+    -16.0
+}
+
+fn default_loudness_true_peak() -> f64 {
+    -1.5
+}
+
+fn default_loudness_range() -> f64 {
+    11.0
+}
+
+impl Default for LoudnessConfig {
+    fn default() -> Self {
+        Self {
+            integrated: default_loudness_integrated(),
+            true_peak: default_loudness_true_peak(),
+            range: default_loudness_range(),
+        }
+    }
+}
+
+/// 进度条预览图的来源
+#[derive(Serialize, Deserialize, Default, Clone, Copy, PartialEq, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum TrickplaySource {
+    /// 下载 B 站的视频快照雪碧图，没有快照时改为本地生成
+    #[default]
+    Bilibili,
+    /// 使用 ffmpeg 从已下载的视频中截取
+    Ffmpeg,
+}
+
+/// 进度条预览图的输出格式
+#[derive(Serialize, Deserialize, Default, Clone, Copy, PartialEq, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum TrickplayLayout {
+    /// Jellyfin 的 `{名称}.trickplay/{宽度} - {列}x{行}/{序号}.jpg` 拼图
+    #[default]
+    Jellyfin,
+    /// Roku BIF 文件 `{名称}-{宽度}-{间隔}.bif`，Emby 和 Plex 等使用
+    Bif,
+}
+
+/// 进度条预览图（trickplay）配置
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct TrickplayConfig {
+    /// 是否为分页生成预览图
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default)]
+    pub source: TrickplaySource,
+    #[serde(default)]
+    pub layout: TrickplayLayout,
+    /// 相邻两张预览图的间隔（秒），Jellyfin 需要与服务端的设置一致
+    #[serde(default = "default_trickplay_interval")]
+    pub interval: u32,
+    /// 单张预览图的宽度（像素）
+    #[serde(default = "default_trickplay_width")]
+    pub width: u32,
+    /// Jellyfin 拼图每张的列数
+    #[serde(default = "default_trickplay_tile")]
+    pub tile_columns: u32,
+    /// Jellyfin 拼图每张的行数
+    #[serde(default = "default_trickplay_tile")]
+    pub tile_rows: u32,
+}
+
+fn default_trickplay_interval() -> u32 {
+    10
+}
+
+fn default_trickplay_width() -> u32 {
+    320
+}
+
+fn default_trickplay_tile() -> u32 {
+    10
+}
+
+impl Default for TrickplayConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            source: TrickplaySource::default(),
+            layout: TrickplayLayout::default(),
+            interval: default_trickplay_interval(),
+            width: default_trickplay_width(),
+            tile_columns: default_trickplay_tile(),
+            tile_rows: default_trickplay_tile(),
+        }
+    }
+}
+
+/// 网页播放器 HLS 转码播放配置
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct HlsConfig {
+    /// 同时运行的 ffmpeg 进程上限，每个正在播放的清晰度占用一个
+    #[serde(default = "default_hls_max_sessions")]
+    pub max_sessions: usize,
+    /// 单个客户端（按 IP 区分）同时占用的会话上限，超出时结束该客户端最久未访问的会话
+    #[serde(default = "default_hls_max_sessions_per_client")]
+    pub max_sessions_per_client: usize,
+    /// 会话无请求多少秒后结束 ffmpeg 并删除分片
+    #[serde(default = "default_hls_idle_timeout")]
+    pub idle_timeout: u64,
+    /// 分片时长（秒）
+    #[serde(default = "default_hls_segment_duration")]
+    pub segment_duration: u32,
+    /// 转码使用的 H.264 编码器，例如 libx264、h264_nvenc、h264_qsv
+    #[serde(default = "default_hls_video_encoder")]
+    pub video_encoder: String,
+}
+
+fn default_hls_max_sessions() -> usize {
+    2
+}
+
+fn default_hls_max_sessions_per_client() -> usize {
+    1
+}
+
+fn default_hls_idle_timeout() -> u64 {
+    60
+}
+
+fn default_hls_segment_duration() -> u32 {
+    6
+}
+
+fn default_hls_video_encoder() -> String {
+    "libx264".to_string()
+}
+
+impl Default for HlsConfig {
+    fn default() -> Self {
+        Self {
+            max_sessions: default_hls_max_sessions(),
+            max_sessions_per_client: default_hls_max_sessions_per_client(),
+            idle_timeout: default_hls_idle_timeout(),
+            segment_duration: default_hls_segment_duration(),
+            video_encoder: default_hls_video_encoder(),
+        }
+    }
+}
+
+/// 内置 DLNA/UPnP 媒体服务器配置
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct DlnaConfig {
+    /// 是否启用 DLNA 媒体服务器
+    #[serde(default)]
+    pub enabled: bool,
+    /// 绑定的网卡 IPv4 地址，留空时自动选择默认路由所在的网卡
+    #[serde(default)]
+    pub interface: String,
+    /// 设备描述、目录浏览和媒体文件使用的 HTTP 端口
+    #[serde(default = "default_dlna_port")]
+    pub port: u16,
+    /// 电视等设备上显示的服务器名称
+    #[serde(default = "default_dlna_friendly_name")]
+    pub friendly_name: String,
+}
+
+fn default_dlna_port() -> u16 {
+    12346
+}
+
+fn default_dlna_friendly_name() -> String {
+    "bili-sync".to_string()
+}
+
+impl Default for DlnaConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            interface: String::new(),
+            port: default_dlna_port(),
+            friendly_name: default_dlna_friendly_name(),
+        }
+    }
+}
+
+/// 扫描结束后通知媒体服务器刷新新增视频所在的目录
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct MediaServerConfig {
+    /// 是否在每轮扫描结束后通知媒体服务器
+    #[serde(default)]
+    pub enabled: bool,
+    /// 媒体服务器类型：jellyfin、emby 或 plex
+    #[serde(default = "default_media_server_kind")]
+    pub kind: String,
+    /// 服务器地址，例如 http://jellyfin:8096
+    #[serde(default)]
+    pub url: String,
+    /// Jellyfin/Emby 的 API 密钥，或 Plex 的 X-Plex-Token
+    #[serde(default)]
+    pub api_key: String,
+    /// 本程序看到的下载目录前缀，例如 /downloads，留空表示两边路径相同
+    #[serde(default)]
+    pub local_path_prefix: String,
+    /// 媒体服务器中对应的目录前缀，例如 /media/bilibili
+    #[serde(default)]
+    pub remote_path_prefix: String,
+    /// 请求超时（秒）
+    #[serde(default = "default_media_server_timeout")]
+    pub timeout: u64,
+}
+
+fn default_media_server_kind() -> String {
+    "jellyfin".to_string()
+}
+
+fn default_media_server_timeout() -> u64 {
+    10
+}
+
+impl Default for MediaServerConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            kind: default_media_server_kind(),
+            url: String::new(),
+            api_key: String::new(),
+            local_path_prefix: String::new(),
+            remote_path_prefix: String::new(),
+            timeout: default_media_server_timeout(),
+        }
+    }
+}
+
+/// 媒体库图片布局，按 Kodi/Jellyfin 识别的文件名补齐剧集根目录的图片
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ArtworkConfig {
+    /// 是否生成额外的图片文件
+    #[serde(default)]
+    pub enabled: bool,
+    /// 根目录 poster.jpg（竖版封面）
+    #[serde(default = "default_artwork_item")]
+    pub poster: bool,
+    /// 根目录 fanart.jpg（背景图）
+    #[serde(default = "default_artwork_item")]
+    pub fanart: bool,
+    /// 根目录 landscape.jpg（横版封面）
+    #[serde(default = "default_artwork_item")]
+    pub landscape: bool,
+    /// 根目录 seasonXX-poster.jpg（季度封面）
+    #[serde(default = "default_artwork_item")]
+    pub season_poster: bool,
+    /// 在演员目录中放置 UP 主头像
+    #[serde(default = "default_artwork_item")]
+    pub actor_thumbs: bool,
+    /// 演员头像目录名，Kodi 默认读取 .actors
+    #[serde(default = "default_actors_folder")]
+    pub actors_folder: String,
+}
+
+fn default_artwork_item() -> bool {
+    true
+}
+
+fn default_actors_folder() -> String {
+    ".actors".to_string()
+}
+
+impl Default for ArtworkConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            poster: true,
+            fanart: true,
+            landscape: true,
+            season_poster: true,
+            actor_thumbs: true,
+            actors_folder: default_actors_folder(),
+        }
+    }
+}
+
+/// 外部aria2守护进程配置，用于aria2运行在其它容器等场景
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct ExternalAria2Config {
+    /// 是否连接已有的aria2守护进程，启用后不再启动或终止任何aria2c进程
+    #[serde(default)]
+    pub enabled: bool,
+    /// JSON-RPC 地址，例如 http://aria2:6800/jsonrpc
+    #[serde(default)]
+    pub rpc_url: String,
+    /// RPC 密钥（aria2 的 --rpc-secret），未设置时留空
+    #[serde(default)]
+    pub rpc_secret: String,
+    /// 本程序看到的下载目录前缀，例如 /downloads，留空表示两边路径相同
+    #[serde(default)]
+    pub local_path_prefix: String,
+    /// aria2 所在环境中对应的目录前缀，例如 /data
+    #[serde(default)]
+    pub remote_path_prefix: String,
+}
+
 /// 并发下载相关的配置
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct ConcurrentLimit {
@@ -170,6 +653,9 @@ pub struct ConcurrentLimit {
     pub rate_limit: Option<RateLimit>,
     #[serde(default)]
     pub parallel_download: ParallelDownloadConfig,
+    /// 全局下载限速（KB/s），0 表示不限速
+    #[serde(default)]
+    pub download_speed_limit: u64,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -189,10 +675,120 @@ impl Default for ConcurrentLimit {
                 duration: 250,
             }),
             parallel_download: ParallelDownloadConfig::default(),
+            download_speed_limit: 0,
+        }
+    }
+}
+
+/// 下载后文件校验配置
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct MediaVerifyConfig {
+    /// 是否在分页视频下载完成后立即校验
+    #[serde(default = "default_media_verify_enabled")]
+    pub enabled: bool,
+    /// 允许的时长误差下限（秒）
+    #[serde(default = "default_media_verify_tolerance_secs")]
+    pub duration_tolerance_secs: u32,
+    /// 允许的时长误差比例（百分比），与秒数取较大值
+    #[serde(default = "default_media_verify_tolerance_percent")]
+    pub duration_tolerance_percent: u32,
+    /// 是否计算并保存文件哈希（大文件会额外占用磁盘IO）
+    #[serde(default = "default_media_verify_compute_hash")]
+    pub compute_hash: bool,
+}
+
+fn default_media_verify_enabled() -> bool {
+    true
+}
+
+fn default_media_verify_tolerance_secs() -> u32 {
+    5
+}
+
+fn default_media_verify_tolerance_percent() -> u32 {
+    2
+}
+
+fn default_media_verify_compute_hash() -> bool {
+    true
+}
+
+impl Default for MediaVerifyConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_media_verify_enabled(),
+            duration_tolerance_secs: default_media_verify_tolerance_secs(),
+            duration_tolerance_percent: default_media_verify_tolerance_percent(),
+            compute_hash: default_media_verify_compute_hash(),
         }
     }
 }
 
+/// 磁盘空间保护配置
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct DiskSpaceGuardConfig {
+    /// 是否在下载前检查视频源所在卷的剩余空间
+    #[serde(default = "default_disk_space_guard_enabled")]
+    pub enabled: bool,
+    /// 剩余空间低于该值（MB）时暂停下载，空间释放后自动继续
+    #[serde(default = "default_disk_space_min_free_mb")]
+    pub min_free_mb: u64,
+    /// 空间不足时重新检查的间隔（秒）
+    #[serde(default = "default_disk_space_check_interval_secs")]
+    pub check_interval_secs: u64,
+}
+
+fn default_disk_space_guard_enabled() -> bool {
+    true
+}
+
+fn default_disk_space_min_free_mb() -> u64 {
+    2048
+}
+
+fn default_disk_space_check_interval_secs() -> u64 {
+    60
+}
+
+impl Default for DiskSpaceGuardConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_disk_space_guard_enabled(),
+            min_free_mb: default_disk_space_min_free_mb(),
+            check_interval_secs: default_disk_space_check_interval_secs(),
+        }
+    }
+}
+
+/// 时间窗口，时间均为北京时间
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct TimeWindow {
+    /// 开始时间（HH:MM）
+    pub start: String,
+    /// 结束时间（HH:MM），早于开始时间表示跨越午夜，与开始时间相同表示全天
+    pub end: String,
+    /// 生效的星期（1=周一 ... 7=周日），跨午夜的窗口按开始当天计算，为空表示每天
+    #[serde(default)]
+    pub weekdays: Vec<u8>,
+}
+
+/// 扫描与下载的时间窗口配置
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct ScheduleConfig {
+    /// 是否启用时间窗口，关闭时任何时间都可以扫描和下载
+    #[serde(default)]
+    pub enabled: bool,
+    /// 允许扫描的时间窗口，为空表示任何时间
+    #[serde(default)]
+    pub scan_windows: Vec<TimeWindow>,
+    /// 允许下载媒体文件的时间窗口，为空表示任何时间；下载发生在扫描过程中，需与扫描窗口有重叠
+    #[serde(default)]
+    pub download_windows: Vec<TimeWindow>,
+    /// 静默时段，期间既不扫描也不下载，优先级高于上面两项
+    #[serde(default)]
+    pub quiet_windows: Vec<TimeWindow>,
+}
+
 /// UP主投稿风控配置
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct SubmissionRiskControlConfig {
@@ -89,6 +89,20 @@ fn describe_config_key(key: &str) -> &'static str {
         "version" => "旧版配置版本号",
         "risk_control" => "风控验证配置",
         "ai_rename" => "AI重命名配置",
+        "media_verify" => "下载后文件校验配置",
+        "schedule" => "扫描与下载时间窗口配置",
+        "disk_space_guard" => "磁盘空间保护配置",
+        "external_aria2" => "外部aria2守护进程配置",
+        "cdn_health" => "CDN节点健康评分配置",
+        "builtin_muxer" => "内置音视频合并",
+        "postprocess" => "后处理配置",
+        "metadata_embed" => "元数据标签写入配置",
+        "trickplay" => "进度条预览图配置",
+        "loudness" => "响度标准化配置",
+        "hls" => "HLS 转码播放配置",
+        "dlna" => "DLNA 媒体服务器配置",
+        "media_server" => "媒体服务器刷新配置",
+        "artwork" => "媒体库图片布局配置",
         _ => "未知/未定义",
     }
 }
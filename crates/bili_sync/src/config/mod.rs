@@ -20,8 +20,11 @@ pub use crate::config::global::{
 };
 use crate::config::item::ConcurrentLimit;
 pub use crate::config::item::{
-    EmptyUpperStrategy, NFOConfig, NFOTimeType, PathSafeTemplate, RateLimit, SubmissionRiskControlConfig,
-    SubmissionScanStrategyConfig,
+    ArtworkConfig, CdnHealthConfig, DiskSpaceGuardConfig, DlnaConfig, EmptyUpperStrategy, ExternalAria2Config,
+    HlsConfig, LoudnessConfig, MediaServerConfig, MediaVerifyConfig, MetadataEmbedConfig, NFOConfig, NFOProfile,
+    NFOTimeType, PathSafeTemplate, PostProcessConfig, PostProcessProfile, RateLimit, ScheduleConfig,
+    SubmissionRiskControlConfig, SubmissionScanStrategyConfig, TimeWindow, TrickplayConfig, TrickplayLayout,
+    TrickplaySource,
 };
 pub use crate::config::manager::ConfigManager;
 
@@ -226,6 +229,62 @@ pub struct Config {
     /// AI 自动重命名配置（OpenAI 兼容接口）
     #[serde(default)]
     pub ai_rename: crate::utils::ai_rename::AiRenameConfig,
+
+    /// 下载后文件校验配置
+    #[serde(default)]
+    pub media_verify: MediaVerifyConfig,
+
+    /// 扫描与下载时间窗口配置
+    #[serde(default)]
+    pub schedule: ScheduleConfig,
+
+    /// 磁盘空间保护配置
+    #[serde(default)]
+    pub disk_space_guard: DiskSpaceGuardConfig,
+
+    /// 外部aria2守护进程配置
+    #[serde(default)]
+    pub external_aria2: ExternalAria2Config,
+
+    /// CDN 节点健康评分配置
+    #[serde(default)]
+    pub cdn_health: CdnHealthConfig,
+
+    /// 合并音视频时优先使用内置的 fMP4 封装，无法处理时再调用 ffmpeg
+    #[serde(default = "default_builtin_muxer")]
+    pub builtin_muxer: bool,
+
+    /// 下载完成后的后处理配置
+    #[serde(default)]
+    pub postprocess: PostProcessConfig,
+
+    /// 向 mp4/m4a 文件写入元数据标签和封面
+    #[serde(default)]
+    pub metadata_embed: MetadataEmbedConfig,
+
+    /// 进度条预览图（trickplay）
+    #[serde(default)]
+    pub trickplay: TrickplayConfig,
+
+    /// 仅音频模式响度标准化的目标值
+    #[serde(default)]
+    pub loudness: LoudnessConfig,
+
+    /// 网页播放器 HLS 转码播放
+    #[serde(default)]
+    pub hls: HlsConfig,
+
+    /// 内置 DLNA 媒体服务器
+    #[serde(default)]
+    pub dlna: DlnaConfig,
+
+    /// 扫描后通知 Jellyfin/Emby/Plex 刷新媒体库
+    #[serde(default)]
+    pub media_server: MediaServerConfig,
+
+    /// 媒体库图片布局（fanart、landscape、季度封面、演员头像）
+    #[serde(default)]
+    pub artwork: ArtworkConfig,
 }
 
 fn default_skip_bangumi_preview() -> bool {
@@ -248,6 +307,10 @@ fn default_bangumi_use_season_structure() -> bool {
     true // 默认使用Season结构（同时启用系列名标准化）
 }
 
+fn default_builtin_muxer() -> bool {
+    true
+}
+
 fn default_cdn_sorting() -> bool {
     true // 默认启用CDN排序
 }
@@ -609,6 +672,20 @@ impl Clone for Config {
             enable_cid_population: self.enable_cid_population,
             risk_control: self.risk_control.clone(),
             ai_rename: self.ai_rename.clone(),
+            media_verify: self.media_verify.clone(),
+            schedule: self.schedule.clone(),
+            disk_space_guard: self.disk_space_guard.clone(),
+            external_aria2: self.external_aria2.clone(),
+            cdn_health: self.cdn_health.clone(),
+            builtin_muxer: self.builtin_muxer,
+            postprocess: self.postprocess.clone(),
+            metadata_embed: self.metadata_embed.clone(),
+            trickplay: self.trickplay.clone(),
+            loudness: self.loudness.clone(),
+            hls: self.hls.clone(),
+            dlna: self.dlna.clone(),
+            media_server: self.media_server.clone(),
+            artwork: self.artwork.clone(),
         }
     }
 }
@@ -651,6 +728,20 @@ impl Default for Config {
             enable_cid_population: false,   // 默认关闭，减少不必要的日志
             risk_control: RiskControlConfig::default(),
             ai_rename: crate::utils::ai_rename::AiRenameConfig::default(),
+            media_verify: MediaVerifyConfig::default(),
+            schedule: ScheduleConfig::default(),
+            disk_space_guard: DiskSpaceGuardConfig::default(),
+            external_aria2: ExternalAria2Config::default(),
+            cdn_health: CdnHealthConfig::default(),
+            builtin_muxer: default_builtin_muxer(),
+            postprocess: PostProcessConfig::default(),
+            metadata_embed: MetadataEmbedConfig::default(),
+            trickplay: TrickplayConfig::default(),
+            loudness: LoudnessConfig::default(),
+            hls: HlsConfig::default(),
+            dlna: DlnaConfig::default(),
+            media_server: MediaServerConfig::default(),
+            artwork: ArtworkConfig::default(),
         }
     }
 }
@@ -1,15 +1,72 @@
 use core::str;
-use std::path::Path;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Instant;
 
 use anyhow::{bail, ensure, Context, Result};
-use futures::TryStreamExt;
 use reqwest::{header, Method, StatusCode};
+use serde::{Deserialize, Serialize};
 use tokio::fs::{self, File, OpenOptions};
 use tokio::io::{AsyncSeekExt, AsyncWriteExt};
-use tokio_util::io::StreamReader;
 use tracing::{debug, error, info, warn};
 
 use crate::bilibili::Client;
+use crate::utils::atomic_write::{append_suffix, write_atomic};
+use crate::utils::bandwidth::{copy_throttled, Throttle};
+use crate::utils::cdn_health;
+use crate::utils::fmp4_mux::merge_dash_tracks;
+
+/// 下载过程中写入的临时文件：`xxx.mp4` -> `xxx.mp4.part`，完整下载后才会重命名为目标文件
+pub fn part_path(path: &Path) -> PathBuf {
+    append_suffix(path, ".part")
+}
+
+/// `.part` 文件对应的断点续传控制信息
+fn part_meta_path(path: &Path) -> PathBuf {
+    append_suffix(path, ".part.json")
+}
+
+/// 断点续传控制信息
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct PartMeta {
+    /// 文件总大小，0 表示服务器未返回
+    total_size: u64,
+    /// 是否由多线程分片下载产生（预分配了完整大小，不能按文件长度续传）
+    #[serde(default)]
+    parallel: bool,
+    /// 多线程模式下已完成的分片（闭区间）
+    #[serde(default)]
+    completed: Vec<(u64, u64)>,
+}
+
+impl PartMeta {
+    async fn load(path: &Path) -> Option<Self> {
+        let content = fs::read(part_meta_path(path)).await.ok()?;
+        serde_json::from_slice(&content).ok()
+    }
+
+    async fn save(&self, path: &Path) -> Result<()> {
+        write_atomic(&part_meta_path(path), serde_json::to_vec(self)?).await
+    }
+}
+
+/// 清理目标文件对应的 `.part` 及其控制信息
+pub async fn discard_part(path: &Path) {
+    let _ = fs::remove_file(part_path(path)).await;
+    let _ = fs::remove_file(part_meta_path(path)).await;
+}
+
+/// 下载完成后将 `.part` 重命名为目标文件
+async fn finalize_part(path: &Path) -> Result<()> {
+    let part = part_path(path);
+    fs::rename(&part, path)
+        .await
+        .with_context(|| format!("重命名 {} -> {} 失败", part.display(), path.display()))?;
+    let _ = fs::remove_file(part_meta_path(path)).await;
+    Ok(())
+}
+
 pub struct Downloader {
     client: Client,
 }
@@ -22,23 +79,34 @@ impl Downloader {
         Self { client }
     }
 
-    pub async fn fetch(&self, url: &str, path: &Path) -> Result<()> {
+    pub async fn fetch(&self, url: &str, path: &Path, throttle: &Throttle) -> Result<()> {
         let config = crate::config::reload_config();
         let parallel = &config.concurrent_limit.parallel_download;
 
-        if parallel.enabled && parallel.threads > 1 {
-            match self.fetch_parallel(url, path, parallel.threads).await {
+        // 已有单线程下载留下的 .part 时直接续传，避免分片下载把已下载的内容丢掉
+        let meta = PartMeta::load(path).await;
+        let single_part_exists = part_path(path).exists() && !meta.as_ref().is_some_and(|m| m.parallel);
+
+        if parallel.enabled && parallel.threads > 1 && !single_part_exists {
+            match self.fetch_parallel(url, path, parallel.threads, throttle).await {
                 Ok(()) => return Ok(()),
                 Err(e) => {
+                    // 已经有分片下载完成时保留进度，交给下一个备用URL或下次重试继续
+                    if PartMeta::load(path)
+                        .await
+                        .is_some_and(|m| m.parallel && !m.completed.is_empty())
+                    {
+                        return Err(e);
+                    }
                     debug!("原生多线程下载不可用，回退到单线程下载: {:#}", e);
                 }
             }
         }
 
-        self.fetch_single(url, path).await
+        self.fetch_single(url, path, throttle).await
     }
 
-    async fn fetch_single(&self, url: &str, path: &Path) -> Result<()> {
+    async fn fetch_single(&self, url: &str, path: &Path, throttle: &Throttle) -> Result<()> {
         // 创建父目录
         if let Some(parent) = path.parent() {
             if !parent.exists() {
@@ -46,32 +114,98 @@ impl Downloader {
             }
         }
 
-        let mut file = match File::create(path).await {
-            Ok(f) => f,
+        let part = part_path(path);
+        let meta = PartMeta::load(path).await;
+        // 分片下载的 .part 预分配了完整大小，文件长度不代表已下载量，无法按长度续传
+        if meta.as_ref().is_some_and(|m| m.parallel) {
+            discard_part(path).await;
+        }
+        let resume_from = match fs::metadata(&part).await {
+            Ok(metadata) => metadata.len(),
+            Err(_) => 0,
+        };
+
+        let mut request = self.client.request(Method::GET, url, None);
+        if resume_from > 0 {
+            request = request
+                .header(header::RANGE, format!("bytes={}-", resume_from))
+                .header(header::ACCEPT_ENCODING, "identity");
+        }
+        let request_started = Instant::now();
+        let resp = match request.send().await {
+            Ok(r) => r,
+            Err(e) => {
+                error!("HTTP请求失败: {:#}", e);
+                return Err(e.into());
+            }
+        };
+        cdn_health::record_first_byte(url, request_started.elapsed());
+
+        // .part 已经不小于服务器上的文件（例如之前下载完成但未来得及重命名），重新下载最稳妥
+        if resume_from > 0 && resp.status() == StatusCode::RANGE_NOT_SATISFIABLE {
+            warn!("续传位置超出文件范围，重新下载: {}", path.display());
+            discard_part(path).await;
+            return Box::pin(self.fetch_single(url, path, throttle)).await;
+        }
+
+        let resp = match resp.error_for_status() {
+            Ok(r) => r,
             Err(e) => {
-                error!("创建文件失败: {:#}", e);
+                error!("HTTP状态码错误: {:#}", e);
                 return Err(e.into());
             }
         };
 
-        let resp = match self.client.request(Method::GET, url, None).send().await {
-            Ok(r) => match r.error_for_status() {
-                Ok(r) => r,
+        let resumed = resume_from > 0 && resp.status() == StatusCode::PARTIAL_CONTENT;
+        let total_size = if resumed {
+            resp.header_file_size().unwrap_or_default()
+        } else {
+            resp.header_content_length().unwrap_or_default()
+        };
+
+        // 备用URL返回的文件大小与之前记录的不一致时，说明不是同一份内容，不能拼接
+        if resumed {
+            if let Some(previous) = meta.as_ref().map(|m| m.total_size).filter(|size| *size > 0) {
+                if total_size > 0 && previous != total_size {
+                    warn!(
+                        "续传文件大小不一致({} != {})，重新下载: {}",
+                        previous,
+                        total_size,
+                        path.display()
+                    );
+                    drop(resp);
+                    discard_part(path).await;
+                    return Box::pin(self.fetch_single(url, path, throttle)).await;
+                }
+            }
+        }
+
+        let (mut file, offset) = if resumed {
+            debug!("从 {} 字节处续传: {}", resume_from, path.display());
+            (OpenOptions::new().append(true).open(&part).await?, resume_from)
+        } else {
+            // 服务器忽略了 Range 请求，只能从头开始
+            match File::create(&part).await {
+                Ok(f) => (f, 0),
                 Err(e) => {
-                    error!("HTTP状态码错误: {:#}", e);
+                    error!("创建文件失败: {:#}", e);
                     return Err(e.into());
                 }
-            },
-            Err(e) => {
-                error!("HTTP请求失败: {:#}", e);
-                return Err(e.into());
             }
         };
 
-        let expected = resp.header_content_length().unwrap_or_default();
+        PartMeta {
+            total_size,
+            ..Default::default()
+        }
+        .save(path)
+        .await?;
 
-        let mut stream_reader = StreamReader::new(resp.bytes_stream().map_err(std::io::Error::other));
-        let received = match tokio::io::copy(&mut stream_reader, &mut file).await {
+        let copy_result = copy_throttled(resp.bytes_stream(), &mut file, throttle).await;
+        // 无论成功与否都把已收到的数据落盘，供下次续传使用
+        file.flush().await?;
+        file.sync_all().await?;
+        let received = match copy_result {
             Ok(size) => size,
             Err(e) => {
                 error!("下载过程中出错: {:#}", e);
@@ -79,19 +213,19 @@ impl Downloader {
             }
         };
 
-        file.flush().await?;
-
+        let downloaded = offset + received;
         ensure!(
-            received >= expected,
-            "received {} bytes, expected {} bytes",
-            received,
-            expected
+            total_size == 0 || downloaded == total_size,
+            cdn_health::IncompleteBody {
+                received: downloaded,
+                expected: total_size,
+            }
         );
 
-        Ok(())
+        finalize_part(path).await
     }
 
-    async fn fetch_parallel(&self, url: &str, path: &Path, threads: usize) -> Result<()> {
+    async fn fetch_parallel(&self, url: &str, path: &Path, threads: usize, throttle: &Throttle) -> Result<()> {
         const MIN_PARALLEL_SIZE: u64 = 4 * 1024 * 1024; // 4MB 以下不分片，避免小文件开销
         const MIN_SEGMENT_SIZE: u64 = 1 * 1024 * 1024; // 每片至少 1MB，避免过多分片
 
@@ -122,15 +256,39 @@ impl Downloader {
             total_mb, segment_count, threads
         );
 
-        // 预创建并设置目标文件大小，便于随机写入
-        {
-            let file = File::create(path).await?;
-            file.set_len(total_size).await?;
-        }
+        let part = part_path(path);
+
+        // 同一文件、同样大小的分片下载记录可以复用，已完成的分片直接跳过
+        let part_len = fs::metadata(&part).await.map(|m| m.len()).ok();
+        let meta = match PartMeta::load(path).await {
+            Some(meta) if meta.parallel && meta.total_size == total_size && part_len == Some(total_size) => {
+                if !meta.completed.is_empty() {
+                    info!(
+                        "检测到未完成的分片下载，已完成 {} 个分片，继续下载",
+                        meta.completed.len()
+                    );
+                }
+                meta
+            }
+            _ => {
+                // 预创建并设置目标文件大小，便于随机写入
+                let file = File::create(&part).await?;
+                file.set_len(total_size).await?;
+                let meta = PartMeta {
+                    total_size,
+                    parallel: true,
+                    completed: Vec::new(),
+                };
+                meta.save(path).await?;
+                meta
+            }
+        };
+        let completed: HashSet<(u64, u64)> = meta.completed.iter().copied().collect();
+        let meta = Arc::new(tokio::sync::Mutex::new(meta));
 
         let url_owned = url.to_string();
-        let path_owned = path.to_path_buf();
         let mut tasks = Vec::with_capacity(segment_count);
+        let mut skipped = 0u64;
 
         let base = total_size / segment_count as u64;
         let mut start = 0u64;
@@ -141,33 +299,51 @@ impl Downloader {
                 start + base - 1
             };
 
+            if completed.contains(&(start, end)) {
+                skipped += end - start + 1;
+                start = end + 1;
+                continue;
+            }
+
             let client = self.client.clone();
             let url = url_owned.clone();
-            let path = path_owned.clone();
+            let part = part.clone();
+            let meta = meta.clone();
+            let target = path.to_path_buf();
+            let throttle = throttle.clone();
             let part_start = start;
             let part_end = end;
 
-            tasks.push(async move { download_range_to_file(client, &url, &path, part_start, part_end).await });
+            tasks.push(async move {
+                let received = download_range_to_file(client, &url, &part, part_start, part_end, &throttle).await?;
+                // 每完成一个分片就记录一次，进程崩溃后可以从这里继续
+                let mut meta = meta.lock().await;
+                meta.completed.push((part_start, part_end));
+                meta.save(&target).await?;
+                Ok::<u64, anyhow::Error>(received)
+            });
 
             start = end + 1;
         }
 
         let results = futures::future::try_join_all(tasks).await?;
-        let downloaded: u64 = results.into_iter().sum();
+        let downloaded: u64 = results.into_iter().sum::<u64>() + skipped;
         ensure!(
             downloaded == total_size,
-            "分片下载大小不一致: {} != {}",
-            downloaded,
-            total_size
+            cdn_health::IncompleteBody {
+                received: downloaded,
+                expected: total_size,
+            }
         );
 
-        Ok(())
+        finalize_part(path).await
     }
 
     async fn get_size_and_range_support(&self, url: &str) -> Result<(u64, bool)> {
         let mut total_size = None;
         let mut range_supported = false;
 
+        let request_started = Instant::now();
         let head_resp = self
             .client
             .request(Method::HEAD, url, None)
@@ -176,6 +352,7 @@ impl Downloader {
             .await;
 
         if let Ok(resp) = head_resp {
+            cdn_health::record_first_byte(url, request_started.elapsed());
             if let Ok(resp) = resp.error_for_status() {
                 total_size = resp.header_content_length().filter(|size| *size > 0);
 
@@ -218,19 +395,26 @@ impl Downloader {
         }
     }
 
-    pub async fn fetch_with_fallback(&self, urls: &[&str], path: &Path) -> Result<()> {
+    pub async fn fetch_with_fallback(&self, urls: &[&str], path: &Path, throttle: &Throttle) -> Result<()> {
         if urls.is_empty() {
             bail!("no urls provided");
         }
 
         let mut last_error = None;
         for url in urls.iter() {
-            match self.fetch(url, path).await {
+            let started = Instant::now();
+            let resumed_bytes = fs::metadata(part_path(path)).await.map(|m| m.len()).unwrap_or(0);
+            match self.fetch(url, path, throttle).await {
                 Ok(_) => {
+                    let size = fs::metadata(path).await.map(|m| m.len()).unwrap_or(0);
+                    cdn_health::record_success(url, size.saturating_sub(resumed_bytes), started.elapsed());
                     return Ok(());
                 }
                 Err(err) => {
                     warn!("下载失败: {:#}", err);
+                    if cdn_health::is_host_error(&err) {
+                        cdn_health::record_failure(url, &format!("{:#}", err));
+                    }
                     last_error = Some(err);
                 }
             }
@@ -255,15 +439,12 @@ impl Downloader {
             bail!("音频文件不存在: {}", audio_path.display());
         }
 
-        // 增强的文件完整性检查
-        if let Err(e) = self.validate_media_file(video_path, "视频").await {
-            error!("视频文件完整性检查失败: {:#}", e);
-            bail!("视频文件损坏或不完整: {}", e);
-        }
-
-        if let Err(e) = self.validate_media_file(audio_path, "音频").await {
-            error!("音频文件完整性检查失败: {:#}", e);
-            bail!("音频文件损坏或不完整: {}", e);
+        // 基本的文件完整性检查，内置封装会自行解析文件结构，不依赖 ffprobe
+        for (path, file_type) in [(video_path, "视频"), (audio_path, "音频")] {
+            if let Err(e) = check_media_file_size(path, file_type).await {
+                error!("{}文件完整性检查失败: {:#}", file_type, e);
+                bail!("{}文件损坏或不完整: {}", file_type, e);
+            }
         }
 
         // 确保输出目录存在
@@ -273,12 +454,47 @@ impl Downloader {
             }
         }
 
+        // 先输出到临时文件，合并完成后再重命名，避免进程中断时留下半截的成品文件
+        let tmp_output_path = part_path(output_path);
+
+        // DASH 流是单轨道 fMP4，优先用内置封装合并，不需要 ffmpeg
+        let builtin_muxer = crate::config::with_config(|bundle| bundle.config.builtin_muxer);
+        if builtin_muxer && ffmpeg_muxer_for(output_path) == "mp4" {
+            let (video, audio, output) = (
+                video_path.to_path_buf(),
+                audio_path.to_path_buf(),
+                tmp_output_path.clone(),
+            );
+            let result = tokio::task::spawn_blocking(move || merge_dash_tracks(&video, &audio, &output))
+                .await
+                .context("内置封装任务异常退出")
+                .and_then(|result| result);
+            match result {
+                Ok(()) => {
+                    fs::rename(&tmp_output_path, output_path).await?;
+                    return Ok(());
+                }
+                Err(e) => {
+                    let _ = fs::remove_file(&tmp_output_path).await;
+                    warn!("内置封装无法合并该视频，回退到 ffmpeg: {:#}", e);
+                }
+            }
+        }
+
+        // 回退到 ffmpeg 时才用 ffprobe 进一步检查输入文件
+        for (path, file_type) in [(video_path, "视频"), (audio_path, "音频")] {
+            if let Err(e) = self.validate_media_file(path, file_type).await {
+                error!("{}文件完整性检查失败: {:#}", file_type, e);
+                bail!("{}文件损坏或不完整: {}", file_type, e);
+            }
+        }
+
         // 将Path转换为字符串，防止临时值过早释放
         let video_path_str = video_path.to_string_lossy().to_string();
         let audio_path_str = audio_path.to_string_lossy().to_string();
-        let output_path_str = output_path.to_string_lossy().to_string();
+        let output_path_str = tmp_output_path.to_string_lossy().to_string();
 
-        // 构建FFmpeg命令
+        // 构建FFmpeg命令（临时文件没有可识别的扩展名，需要显式指定封装格式）
         let args = [
             "-i",
             &video_path_str,
@@ -288,6 +504,8 @@ impl Downloader {
             "copy",
             "-strict",
             "unofficial",
+            "-f",
+            ffmpeg_muxer_for(output_path),
             "-y",
             &output_path_str,
         ];
@@ -295,36 +513,19 @@ impl Downloader {
         let output = tokio::process::Command::new("ffmpeg").args(args).output().await?;
 
         if !output.status.success() {
+            let _ = fs::remove_file(&tmp_output_path).await;
             let stderr = str::from_utf8(&output.stderr).unwrap_or("unknown");
             error!("FFmpeg错误: {}", stderr);
             bail!("ffmpeg error: {}", stderr);
         }
 
+        fs::rename(&tmp_output_path, output_path).await?;
+
         Ok(())
     }
 
-    /// 验证媒体文件的完整性
+    /// 使用 ffprobe 验证媒体文件的格式，文件大小已由 `check_media_file_size` 检查
     async fn validate_media_file(&self, file_path: &Path, file_type: &str) -> Result<()> {
-        // 检查文件大小
-        let metadata = tokio::fs::metadata(file_path)
-            .await
-            .with_context(|| format!("无法读取{}文件元数据: {}", file_type, file_path.display()))?;
-
-        let file_size = metadata.len();
-        if file_size == 0 {
-            bail!("{}文件为空: {}", file_type, file_path.display());
-        }
-
-        if file_size < 1024 {
-            // 小于1KB很可能是损坏的
-            bail!(
-                "{}文件过小({}字节)，可能损坏: {}",
-                file_type,
-                file_size,
-                file_path.display()
-            );
-        }
-
         // 使用ffprobe快速验证文件格式
         let file_path_str = file_path.to_string_lossy().to_string();
         let result = tokio::process::Command::new("ffprobe")
@@ -363,7 +564,37 @@ impl Downloader {
     }
 }
 
-async fn download_range_to_file(client: Client, url: &str, path: &Path, start: u64, end: u64) -> Result<u64> {
+/// 检查媒体文件的大小，空文件或过小的文件视为损坏
+async fn check_media_file_size(file_path: &Path, file_type: &str) -> Result<()> {
+    let metadata = tokio::fs::metadata(file_path)
+        .await
+        .with_context(|| format!("无法读取{}文件元数据: {}", file_type, file_path.display()))?;
+
+    let file_size = metadata.len();
+    if file_size == 0 {
+        bail!("{}文件为空: {}", file_type, file_path.display());
+    }
+
+    if file_size < 1024 {
+        // 小于1KB很可能是损坏的
+        bail!(
+            "{}文件过小({}字节)，可能损坏: {}",
+            file_type,
+            file_size,
+            file_path.display()
+        );
+    }
+    Ok(())
+}
+
+async fn download_range_to_file(
+    client: Client,
+    url: &str,
+    path: &Path,
+    start: u64,
+    end: u64,
+    throttle: &Throttle,
+) -> Result<u64> {
     let expected = end.saturating_sub(start) + 1;
 
     let mut file = OpenOptions::new().write(true).open(path).await?;
@@ -386,16 +617,15 @@ async fn download_range_to_file(client: Client, url: &str, path: &Path, start: u
 
     let resp = resp.error_for_status().context("Range状态码错误")?;
 
-    let mut stream_reader = StreamReader::new(resp.bytes_stream().map_err(std::io::Error::other));
-    let received = tokio::io::copy(&mut stream_reader, &mut file).await?;
+    let received = copy_throttled(resp.bytes_stream(), &mut file, throttle).await?;
     file.flush().await?;
+    file.sync_data().await?;
 
-    ensure!(
-        received == expected,
-        "Range分片下载不完整: received {} bytes, expected {} bytes",
-        received,
-        expected
-    );
+    if received != expected {
+        return Err(
+            anyhow::Error::new(cdn_health::IncompleteBody { received, expected }).context("Range分片下载不完整")
+        );
+    }
 
     Ok(received)
 }
@@ -430,9 +660,11 @@ pub async fn remux_with_ffmpeg(input_path: &Path, output_path: &Path) -> Result<
         }
     }
 
+    let tmp_output_path = part_path(output_path);
+
     // 将Path转换为字符串，防止临时值过早释放
     let input_path_str = input_path.to_string_lossy().to_string();
-    let output_path_str = output_path.to_string_lossy().to_string();
+    let output_path_str = tmp_output_path.to_string_lossy().to_string();
 
     let args = [
         "-i",
@@ -441,15 +673,35 @@ pub async fn remux_with_ffmpeg(input_path: &Path, output_path: &Path) -> Result<
         "copy",
         "-movflags",
         "+faststart",
+        "-f",
+        ffmpeg_muxer_for(output_path),
         "-y",
         &output_path_str,
     ];
 
     let output = tokio::process::Command::new("ffmpeg").args(args).output().await?;
     if !output.status.success() {
+        let _ = fs::remove_file(&tmp_output_path).await;
         let stderr = str::from_utf8(&output.stderr).unwrap_or("unknown");
         bail!("ffmpeg error: {}", stderr.trim());
     }
 
+    fs::rename(&tmp_output_path, output_path).await?;
+
     Ok(())
 }
+
+/// 根据目标文件扩展名选择 ffmpeg 封装格式
+pub fn ffmpeg_muxer_for(output_path: &Path) -> &'static str {
+    match output_path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| ext.to_ascii_lowercase())
+        .as_deref()
+    {
+        Some("mkv") => "matroska",
+        Some("m4a") => "ipod",
+        Some("flv") => "flv",
+        _ => "mp4",
+    }
+}
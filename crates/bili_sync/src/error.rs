@@ -259,6 +259,7 @@ impl ErrorClassifier {
             io::ErrorKind::NotFound => ClassifiedError::new(ErrorType::FileSystem, "文件或目录不存在".to_string()),
             io::ErrorKind::ConnectionRefused => ClassifiedError::new(ErrorType::Network, "连接被拒绝".to_string()),
             io::ErrorKind::TimedOut => ClassifiedError::new(ErrorType::Timeout, "操作超时".to_string()),
+            io::ErrorKind::StorageFull => ClassifiedError::new(ErrorType::FileSystem, "磁盘空间不足".to_string()),
             io::ErrorKind::WriteZero | io::ErrorKind::UnexpectedEof => {
                 ClassifiedError::new(ErrorType::Network, "网络连接中断".to_string())
             }
@@ -15,6 +15,8 @@ mod http;
 mod ingest_log;
 mod initialization;
 mod task;
+#[cfg(test)]
+mod test_utils;
 mod unified_downloader;
 mod utils;
 mod workflow;
@@ -24,7 +26,8 @@ use std::future::Future;
 use std::sync::Arc;
 
 // 移除未使用的Lazy导入
-use task::{http_server, video_downloader};
+use task::schedule::schedule_watcher;
+use task::{dlna_server, http_server, video_downloader};
 use tokio_util::sync::CancellationToken;
 use tokio_util::task::TaskTracker;
 
@@ -116,13 +119,22 @@ async fn main() -> Result<()> {
         }
     }
 
+    // 重新排队上次退出前未完成的后处理任务
+    match crate::task::postprocess::resume_pending_jobs(connection.as_ref()).await {
+        Ok(count) if count > 0 => info!("已重新排队 {} 个未完成的后处理任务", count),
+        Ok(_) => {}
+        Err(e) => warn!("恢复后处理任务失败: {:#}", e),
+    }
+
     // SQLite配置已经在database::setup_database中设置了mmap，不再需要额外的初始化
 
     let token = CancellationToken::new();
     let tracker = TaskTracker::new();
 
     spawn_task("HTTP 服务", http_server(connection.clone()), &tracker, token.clone());
+    spawn_task("DLNA 服务", dlna_server(connection.clone()), &tracker, token.clone());
     spawn_task("定时下载", video_downloader(connection), &tracker, token.clone());
+    spawn_task("时间窗口", schedule_watcher(), &tracker, token.clone());
 
     tracker.close();
     handle_shutdown(tracker, token).await;
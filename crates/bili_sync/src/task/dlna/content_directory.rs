@@ -0,0 +1,492 @@
+//! ContentDirectory 服务：以 视频源 → 视频 → 分页 三层浏览已下载的媒体库
+
+use std::collections::HashMap;
+use std::fmt;
+
+use anyhow::Result;
+use bili_sync_entity::entities::{page, video};
+use quick_xml::events::BytesText;
+use quick_xml::writer::Writer;
+use sea_orm::{ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, QueryOrder};
+use tracing::error;
+
+use super::soap::{SoapFault, ACTION_FAILED, INVALID_ARGS, NO_SUCH_OBJECT};
+use crate::api::feed::{list_feed_sources, load_feed_source, source_video_filter};
+use crate::api::video_stream::{downloaded_page_path, get_video_mime_type};
+
+/// 允许拖动进度条的 DLNA 传输标志，同时用于目录中的 protocolInfo 和媒体响应头
+pub const DLNA_FEATURES: &str = "DLNA.ORG_OP=01;DLNA.ORG_CI=0;DLNA.ORG_FLAGS=01700000000000000000000000000000";
+
+/// 目录对象 ID，根目录固定为 `0`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ObjectId {
+    Root,
+    Source { source_type: String, id: i32 },
+    Video(i32),
+    Page(i32),
+}
+
+impl ObjectId {
+    pub fn parse(value: &str) -> Option<Self> {
+        if value == "0" {
+            return Some(Self::Root);
+        }
+        let parts: Vec<&str> = value.split('/').collect();
+        match parts.as_slice() {
+            ["source", source_type, id] if source_video_filter(source_type, 0).is_some() => Some(Self::Source {
+                source_type: source_type.to_string(),
+                id: id.parse().ok()?,
+            }),
+            ["video", id] => Some(Self::Video(id.parse().ok()?)),
+            ["page", id] => Some(Self::Page(id.parse().ok()?)),
+            _ => None,
+        }
+    }
+
+    /// 视频所属的视频源，多个来源字段同时存在时按视频源类型的固定顺序取第一个
+    fn source_of(video_model: &video::Model) -> Self {
+        [
+            ("collection", video_model.collection_id),
+            ("favorite", video_model.favorite_id),
+            ("submission", video_model.submission_id),
+            ("watch_later", video_model.watch_later_id),
+            ("bangumi", video_model.source_id),
+        ]
+        .into_iter()
+        .find_map(|(source_type, id)| {
+            id.map(|id| Self::Source {
+                source_type: source_type.to_string(),
+                id,
+            })
+        })
+        .unwrap_or(Self::Root)
+    }
+}
+
+impl fmt::Display for ObjectId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Root => write!(f, "0"),
+            Self::Source { source_type, id } => write!(f, "source/{}/{}", source_type, id),
+            Self::Video(id) => write!(f, "video/{}", id),
+            Self::Page(id) => write!(f, "page/{}", id),
+        }
+    }
+}
+
+pub struct Container {
+    pub id: String,
+    pub parent_id: String,
+    pub title: String,
+    pub child_count: Option<usize>,
+    pub album_art: Option<String>,
+}
+
+pub struct MediaItem {
+    pub id: String,
+    pub parent_id: String,
+    pub title: String,
+    pub creator: String,
+    pub date: String,
+    pub album_art: Option<String>,
+    pub url: String,
+    pub mime: &'static str,
+    pub size: u64,
+    pub duration: u32,
+}
+
+pub enum DidlObject {
+    Container(Container),
+    Item(MediaItem),
+}
+
+/// 老旧电视通常不支持 https 图片
+fn http_url(url: &str) -> Option<String> {
+    if url.is_empty() {
+        return None;
+    }
+    Some(match url.strip_prefix("https://") {
+        Some(rest) => format!("http://{}", rest),
+        None => url.to_string(),
+    })
+}
+
+/// DIDL-Lite 要求的 `H:MM:SS.000` 时长格式
+fn format_duration(seconds: u32) -> String {
+    format!("{}:{:02}:{:02}.000", seconds / 3600, seconds / 60 % 60, seconds % 60)
+}
+
+fn video_container(video_model: &video::Model, child_count: usize) -> Container {
+    Container {
+        id: ObjectId::Video(video_model.id).to_string(),
+        parent_id: ObjectId::source_of(video_model).to_string(),
+        title: video_model.name.clone(),
+        child_count: Some(child_count),
+        album_art: http_url(&video_model.cover),
+    }
+}
+
+async fn media_item(
+    base_url: &str,
+    video_model: &video::Model,
+    page_model: &page::Model,
+    single_page: bool,
+) -> Option<MediaItem> {
+    let path = downloaded_page_path(page_model)?;
+    let metadata = tokio::fs::metadata(&path).await.ok()?;
+    let ext = path.extension().and_then(|ext| ext.to_str()).unwrap_or("mp4");
+    let title = if single_page {
+        video_model.name.clone()
+    } else {
+        format!("P{} {}", page_model.pid, page_model.name)
+    };
+    let image = page_model
+        .image
+        .as_deref()
+        .filter(|_| !single_page)
+        .unwrap_or(&video_model.cover);
+    Some(MediaItem {
+        id: ObjectId::Page(page_model.id).to_string(),
+        parent_id: ObjectId::Video(video_model.id).to_string(),
+        title,
+        creator: video_model.upper_name.clone(),
+        date: video_model.pubtime.format("%Y-%m-%d").to_string(),
+        album_art: http_url(image),
+        url: format!("{}/dlna/media/{}.{}", base_url, page_model.id, ext),
+        mime: get_video_mime_type(&path),
+        size: metadata.len(),
+        duration: page_model.duration,
+    })
+}
+
+/// 查找可浏览的视频及其分页：与按视频源浏览时相同，视频未删除且所属视频源仍然存在
+async fn find_video_with_pages(
+    db: &DatabaseConnection,
+    video_id: i32,
+) -> Result<Option<(video::Model, Vec<page::Model>)>> {
+    let Some(video_model) = video::Entity::find_by_id(video_id)
+        .filter(video::Column::Deleted.eq(0))
+        .one(db)
+        .await?
+    else {
+        return Ok(None);
+    };
+    let ObjectId::Source { source_type, id } = ObjectId::source_of(&video_model) else {
+        return Ok(None);
+    };
+    if load_feed_source(db, &source_type, id).await?.is_none() {
+        return Ok(None);
+    }
+    let pages = page::Entity::find()
+        .filter(page::Column::VideoId.eq(video_id))
+        .order_by_asc(page::Column::Pid)
+        .all(db)
+        .await?;
+    Ok(Some((video_model, pages)))
+}
+
+/// 媒体文件只提供目录中能浏览到的分页，已删除视频或已移除视频源的文件不能再按 ID 访问
+pub async fn find_browsable_page(db: &DatabaseConnection, page_id: i32) -> Result<Option<page::Model>> {
+    let Some(page_model) = page::Entity::find_by_id(page_id).one(db).await? else {
+        return Ok(None);
+    };
+    Ok(find_video_with_pages(db, page_model.video_id)
+        .await?
+        .map(|_| page_model))
+}
+
+async fn children(db: &DatabaseConnection, base_url: &str, object_id: &ObjectId) -> Result<Option<Vec<DidlObject>>> {
+    let objects = match object_id {
+        ObjectId::Root => list_feed_sources(db)
+            .await?
+            .into_iter()
+            .map(|(source_type, id, source)| {
+                DidlObject::Container(Container {
+                    id: ObjectId::Source {
+                        source_type: source_type.to_string(),
+                        id,
+                    }
+                    .to_string(),
+                    parent_id: ObjectId::Root.to_string(),
+                    title: source.title,
+                    child_count: None,
+                    album_art: source.image.as_deref().and_then(http_url),
+                })
+            })
+            .collect(),
+        ObjectId::Source { source_type, id } => {
+            let Some(filter) = source_video_filter(source_type, *id) else {
+                return Ok(None);
+            };
+            if load_feed_source(db, source_type, *id).await?.is_none() {
+                return Ok(None);
+            }
+            let videos = video::Entity::find()
+                .filter(filter)
+                .filter(video::Column::Deleted.eq(0))
+                .order_by_desc(video::Column::Pubtime)
+                .find_with_related(page::Entity)
+                .all(db)
+                .await?;
+            videos
+                .into_iter()
+                .filter_map(|(video_model, pages)| {
+                    let playable = pages
+                        .iter()
+                        .filter(|page_model| downloaded_page_path(page_model).is_some())
+                        .count();
+                    (playable > 0).then(|| DidlObject::Container(video_container(&video_model, playable)))
+                })
+                .collect()
+        }
+        ObjectId::Video(video_id) => {
+            let Some((video_model, pages)) = find_video_with_pages(db, *video_id).await? else {
+                return Ok(None);
+            };
+            let single_page = pages.len() == 1;
+            let mut items = Vec::new();
+            for page_model in &pages {
+                if let Some(item) = media_item(base_url, &video_model, page_model, single_page).await {
+                    items.push(DidlObject::Item(item));
+                }
+            }
+            items
+        }
+        ObjectId::Page(_) => Vec::new(),
+    };
+    Ok(Some(objects))
+}
+
+async fn metadata(db: &DatabaseConnection, base_url: &str, object_id: &ObjectId) -> Result<Option<DidlObject>> {
+    let object = match object_id {
+        ObjectId::Root => Some(DidlObject::Container(Container {
+            id: ObjectId::Root.to_string(),
+            parent_id: "-1".to_string(),
+            title: "bili-sync".to_string(),
+            child_count: None,
+            album_art: None,
+        })),
+        ObjectId::Source { source_type, id } => load_feed_source(db, source_type, *id).await?.map(|source| {
+            DidlObject::Container(Container {
+                id: object_id.to_string(),
+                parent_id: ObjectId::Root.to_string(),
+                title: source.title,
+                child_count: None,
+                album_art: source.image.as_deref().and_then(http_url),
+            })
+        }),
+        ObjectId::Video(video_id) => find_video_with_pages(db, *video_id).await?.map(|(video_model, pages)| {
+            let playable = pages
+                .iter()
+                .filter(|page_model| downloaded_page_path(page_model).is_some())
+                .count();
+            DidlObject::Container(video_container(&video_model, playable))
+        }),
+        ObjectId::Page(page_id) => {
+            let Some(page_model) = page::Entity::find_by_id(*page_id).one(db).await? else {
+                return Ok(None);
+            };
+            let Some((video_model, pages)) = find_video_with_pages(db, page_model.video_id).await? else {
+                return Ok(None);
+            };
+            media_item(base_url, &video_model, &page_model, pages.len() == 1)
+                .await
+                .map(DidlObject::Item)
+        }
+    };
+    Ok(object)
+}
+
+fn write_container(writer: &mut Writer<Vec<u8>>, container: &Container) -> std::io::Result<()> {
+    let child_count = container.child_count.map(|count| count.to_string());
+    let mut element = writer
+        .create_element("container")
+        .with_attribute(("id", container.id.as_str()))
+        .with_attribute(("parentID", container.parent_id.as_str()))
+        .with_attribute(("restricted", "1"));
+    if let Some(child_count) = &child_count {
+        element = element.with_attribute(("childCount", child_count.as_str()));
+    }
+    element.write_inner_content(|writer| {
+        writer
+            .create_element("dc:title")
+            .write_text_content(BytesText::new(&container.title))?;
+        writer
+            .create_element("upnp:class")
+            .write_text_content(BytesText::new("object.container.storageFolder"))?;
+        if let Some(album_art) = &container.album_art {
+            writer
+                .create_element("upnp:albumArtURI")
+                .write_text_content(BytesText::new(album_art))?;
+        }
+        Ok(())
+    })?;
+    Ok(())
+}
+
+fn write_item(writer: &mut Writer<Vec<u8>>, item: &MediaItem) -> std::io::Result<()> {
+    let class = if item.mime.starts_with("audio/") {
+        "object.item.audioItem.musicTrack"
+    } else {
+        "object.item.videoItem"
+    };
+    writer
+        .create_element("item")
+        .with_attribute(("id", item.id.as_str()))
+        .with_attribute(("parentID", item.parent_id.as_str()))
+        .with_attribute(("restricted", "1"))
+        .write_inner_content(|writer| {
+            writer
+                .create_element("dc:title")
+                .write_text_content(BytesText::new(&item.title))?;
+            writer
+                .create_element("upnp:class")
+                .write_text_content(BytesText::new(class))?;
+            writer
+                .create_element("dc:creator")
+                .write_text_content(BytesText::new(&item.creator))?;
+            writer
+                .create_element("dc:date")
+                .write_text_content(BytesText::new(&item.date))?;
+            if let Some(album_art) = &item.album_art {
+                writer
+                    .create_element("upnp:albumArtURI")
+                    .write_text_content(BytesText::new(album_art))?;
+            }
+            writer
+                .create_element("res")
+                .with_attribute((
+                    "protocolInfo",
+                    format!("http-get:*:{}:{}", item.mime, DLNA_FEATURES).as_str(),
+                ))
+                .with_attribute(("size", item.size.to_string().as_str()))
+                .with_attribute(("duration", format_duration(item.duration).as_str()))
+                .write_text_content(BytesText::new(&item.url))?;
+            Ok(())
+        })?;
+    Ok(())
+}
+
+pub fn build_didl(objects: &[DidlObject]) -> std::io::Result<String> {
+    let mut writer = Writer::new(Vec::new());
+    writer
+        .create_element("DIDL-Lite")
+        .with_attribute(("xmlns", "urn:schemas-upnp-org:metadata-1-0/DIDL-Lite/"))
+        .with_attribute(("xmlns:dc", "http://purl.org/dc/elements/1.1/"))
+        .with_attribute(("xmlns:upnp", "urn:schemas-upnp-org:metadata-1-0/upnp/"))
+        .with_attribute(("xmlns:dlna", "urn:schemas-dlna-org:metadata-1-0/"))
+        .write_inner_content(|writer| {
+            for object in objects {
+                match object {
+                    DidlObject::Container(container) => write_container(writer, container)?,
+                    DidlObject::Item(item) => write_item(writer, item)?,
+                }
+            }
+            Ok(())
+        })?;
+    Ok(String::from_utf8_lossy(&writer.into_inner()).into_owned())
+}
+
+fn parse_index(arguments: &HashMap<String, String>, name: &str) -> std::result::Result<usize, SoapFault> {
+    match arguments.get(name).map(|value| value.trim()) {
+        None | Some("") => Ok(0),
+        Some(value) => value.parse().map_err(|_| INVALID_ARGS),
+    }
+}
+
+/// Browse 动作，RequestedCount 为 0 时返回全部结果
+pub async fn browse(
+    db: &DatabaseConnection,
+    base_url: &str,
+    update_id: u32,
+    arguments: &HashMap<String, String>,
+) -> std::result::Result<Vec<(&'static str, String)>, SoapFault> {
+    let object_id = arguments
+        .get("ObjectID")
+        .and_then(|value| ObjectId::parse(value))
+        .ok_or(NO_SUCH_OBJECT)?;
+    let starting_index = parse_index(arguments, "StartingIndex")?;
+    let requested_count = match parse_index(arguments, "RequestedCount")? {
+        0 => usize::MAX,
+        count => count,
+    };
+    let objects = match arguments.get("BrowseFlag").map(String::as_str) {
+        Some("BrowseMetadata") => metadata(db, base_url, &object_id)
+            .await
+            .map(|object| object.map(|object| vec![object])),
+        Some("BrowseDirectChildren") => children(db, base_url, &object_id).await,
+        _ => return Err(INVALID_ARGS),
+    }
+    .map_err(|e| {
+        error!("DLNA 浏览 {} 失败: {:#}", object_id, e);
+        ACTION_FAILED
+    })?
+    .ok_or(NO_SUCH_OBJECT)?;
+
+    let total_matches = objects.len();
+    let returned: Vec<DidlObject> = objects.into_iter().skip(starting_index).take(requested_count).collect();
+    let result = build_didl(&returned).map_err(|e| {
+        error!("生成 DIDL-Lite 失败: {:#}", e);
+        ACTION_FAILED
+    })?;
+    Ok(vec![
+        ("Result", result),
+        ("NumberReturned", returned.len().to_string()),
+        ("TotalMatches", total_matches.to_string()),
+        ("UpdateID", update_id.to_string()),
+    ])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_object_id() {
+        for value in ["0", "source/favorite/3", "source/watch_later/1", "video/42", "page/7"] {
+            assert_eq!(ObjectId::parse(value).unwrap().to_string(), value);
+        }
+        assert_eq!(ObjectId::parse("video/42"), Some(ObjectId::Video(42)));
+        assert_eq!(ObjectId::parse("source/unknown/3"), None);
+        assert_eq!(ObjectId::parse("video/abc"), None);
+        assert_eq!(ObjectId::parse("page/1/2"), None);
+        assert_eq!(ObjectId::parse(""), None);
+    }
+
+    #[test]
+    fn test_build_didl() {
+        let objects = vec![
+            DidlObject::Container(Container {
+                id: "video/42".to_string(),
+                parent_id: "source/favorite/3".to_string(),
+                title: "合集 & 其它".to_string(),
+                child_count: Some(2),
+                album_art: http_url("https://i0.hdslb.com/cover.jpg"),
+            }),
+            DidlObject::Item(MediaItem {
+                id: "page/7".to_string(),
+                parent_id: "video/42".to_string(),
+                title: "P1 开头".to_string(),
+                creator: "某UP主".to_string(),
+                date: "2026-10-17".to_string(),
+                album_art: None,
+                url: "http://192.168.1.2:12346/dlna/media/7.mp4".to_string(),
+                mime: "video/mp4",
+                size: 1024,
+                duration: 3725,
+            }),
+        ];
+        let didl = build_didl(&objects).unwrap();
+        assert!(didl.starts_with(r#"<DIDL-Lite xmlns="urn:schemas-upnp-org:metadata-1-0/DIDL-Lite/""#));
+        assert!(
+            didl.contains(r#"<container id="video/42" parentID="source/favorite/3" restricted="1" childCount="2">"#)
+        );
+        assert!(didl.contains("<dc:title>合集 &amp; 其它</dc:title>"));
+        assert!(didl.contains("<upnp:albumArtURI>http://i0.hdslb.com/cover.jpg</upnp:albumArtURI>"));
+        assert!(didl.contains("<upnp:class>object.item.videoItem</upnp:class>"));
+        assert!(didl.contains(&format!(
+            r#"<res protocolInfo="http-get:*:video/mp4:{}" size="1024" duration="1:02:05.000">http://192.168.1.2:12346/dlna/media/7.mp4</res>"#,
+            DLNA_FEATURES
+        )));
+    }
+}
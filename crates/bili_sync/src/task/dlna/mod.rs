@@ -0,0 +1,335 @@
+//! 内置 DLNA/UPnP 媒体服务器
+//!
+//! 通过 SSDP 在局域网内宣告一个 MediaServer 设备，提供 ContentDirectory 和 ConnectionManager 服务，
+//! 按 视频源 → 视频 → 分页 的层级浏览已下载的文件，媒体文件与网页播放器使用同一套 Range 传输逻辑。
+//! DLNA 协议本身没有认证，因此默认关闭，使用独立的端口并只绑定到指定的局域网网卡。
+
+mod content_directory;
+mod description;
+mod soap;
+mod ssdp;
+
+use std::collections::HashMap;
+use std::net::{IpAddr, Ipv4Addr};
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use anyhow::{bail, Context, Result};
+use axum::extract::Path;
+use axum::http::{header, HeaderMap, HeaderValue, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{any, get, post};
+use axum::{Extension, Router};
+use sea_orm::DatabaseConnection;
+use tokio::net::TcpListener;
+use tokio::task::JoinHandle;
+use tokio_util::sync::{CancellationToken, DropGuard};
+use tracing::{error, info, warn};
+
+use crate::api::feed::parse_id_with_ext;
+use crate::api::video_stream::{downloaded_page_path, serve_file_with_range};
+use crate::config::DlnaConfig;
+
+pub const DEVICE_TYPE: &str = "urn:schemas-upnp-org:device:MediaServer:1";
+pub const CONTENT_DIRECTORY: &str = "urn:schemas-upnp-org:service:ContentDirectory:1";
+pub const CONNECTION_MANAGER: &str = "urn:schemas-upnp-org:service:ConnectionManager:1";
+
+/// 配置检查间隔（秒），启用状态、网卡或端口变化后重启服务
+const CONFIG_CHECK_INTERVAL_SECS: u64 = 10;
+
+/// 启动失败后的最长重试间隔（秒）
+const MAX_RETRY_DELAY_SECS: u64 = 300;
+
+/// 支持提供的媒体类型
+const SOURCE_PROTOCOL_INFO: &str = "http-get:*:video/mp4:*,http-get:*:video/x-matroska:*,http-get:*:video/x-flv:*,\
+http-get:*:audio/mp4:*,http-get:*:audio/mpeg:*,http-get:*:audio/ogg:*,http-get:*:audio/flac:*";
+
+pub struct DeviceInfo {
+    pub uuid: String,
+    pub friendly_name: String,
+    /// 媒体文件地址的前缀，例如 `http://192.168.1.2:12346`
+    pub base_url: String,
+    /// 每次启动服务时变化，提示客户端丢弃缓存的目录
+    pub system_update_id: u32,
+}
+
+impl DeviceInfo {
+    fn new(config: &DlnaConfig, ip: Ipv4Addr) -> Self {
+        // 同一网卡和端口上的设备 ID 保持不变，避免电视在每次重启后列出新的服务器
+        let digest = md5::compute(format!("bili-sync-dlna:{}:{}", ip, config.port));
+        Self {
+            uuid: uuid::Uuid::from_bytes(digest.0).to_string(),
+            friendly_name: config.friendly_name.clone(),
+            base_url: format!("http://{}:{}", ip, config.port),
+            system_update_id: chrono::Utc::now().timestamp() as u32,
+        }
+    }
+
+    pub fn location(&self) -> String {
+        format!("{}/dlna/description.xml", self.base_url)
+    }
+}
+
+/// 解析绑定网卡地址，留空时取发往 SSDP 组播地址所使用的网卡
+fn resolve_interface(interface: &str) -> Result<Ipv4Addr> {
+    let interface = interface.trim();
+    if !interface.is_empty() && interface != "0.0.0.0" {
+        return interface
+            .parse()
+            .with_context(|| format!("DLNA 绑定网卡地址无效，需要填写 IPv4 地址: {}", interface));
+    }
+    // UDP connect 只选择路由，不会发送数据
+    let socket = std::net::UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0))?;
+    socket.connect((ssdp::SSDP_ADDR, 1900))?;
+    match socket.local_addr()?.ip() {
+        IpAddr::V4(ip) if !ip.is_unspecified() => Ok(ip),
+        _ => bail!("无法自动确定局域网地址，请在 DLNA 配置中指定绑定网卡地址"),
+    }
+}
+
+fn xml_response(body: impl Into<String>) -> Response {
+    let mut response = body.into().into_response();
+    response.headers_mut().insert(
+        header::CONTENT_TYPE,
+        HeaderValue::from_static(r#"text/xml; charset="utf-8""#),
+    );
+    response
+}
+
+async fn device_description(Extension(device): Extension<Arc<DeviceInfo>>) -> Response {
+    xml_response(description::device_description(&device))
+}
+
+async fn content_directory_scpd() -> Response {
+    xml_response(description::CONTENT_DIRECTORY_SCPD)
+}
+
+async fn connection_manager_scpd() -> Response {
+    xml_response(description::CONNECTION_MANAGER_SCPD)
+}
+
+fn parse_request(headers: &HeaderMap, body: &str) -> Option<(String, HashMap<String, String>)> {
+    let (_, action) = headers
+        .get("soapaction")
+        .and_then(|value| value.to_str().ok())
+        .and_then(soap::parse_soap_action)?;
+    match soap::parse_arguments(body) {
+        Ok(arguments) => Some((action.to_string(), arguments)),
+        Err(e) => {
+            warn!("解析 DLNA 控制请求失败: {:#}", e);
+            None
+        }
+    }
+}
+
+async fn content_directory_control(
+    headers: HeaderMap,
+    Extension(db): Extension<Arc<DatabaseConnection>>,
+    Extension(device): Extension<Arc<DeviceInfo>>,
+    body: String,
+) -> Response {
+    let Some((action, arguments)) = parse_request(&headers, &body) else {
+        return soap::soap_response(CONTENT_DIRECTORY, "", Err(soap::INVALID_ACTION));
+    };
+    let result = match action.as_str() {
+        "Browse" => content_directory::browse(&db, &device.base_url, device.system_update_id, &arguments).await,
+        "GetSearchCapabilities" => Ok(vec![("SearchCaps", String::new())]),
+        "GetSortCapabilities" => Ok(vec![("SortCaps", String::new())]),
+        "GetSystemUpdateID" => Ok(vec![("Id", device.system_update_id.to_string())]),
+        _ => Err(soap::INVALID_ACTION),
+    };
+    soap::soap_response(CONTENT_DIRECTORY, &action, result)
+}
+
+async fn connection_manager_control(headers: HeaderMap, body: String) -> Response {
+    let Some((action, _)) = parse_request(&headers, &body) else {
+        return soap::soap_response(CONNECTION_MANAGER, "", Err(soap::INVALID_ACTION));
+    };
+    let result = match action.as_str() {
+        "GetProtocolInfo" => Ok(vec![
+            ("Source", SOURCE_PROTOCOL_INFO.to_string()),
+            ("Sink", String::new()),
+        ]),
+        "GetCurrentConnectionIDs" => Ok(vec![("ConnectionIDs", "0".to_string())]),
+        "GetCurrentConnectionInfo" => Ok(vec![
+            ("RcsID", "-1".to_string()),
+            ("AVTransportID", "-1".to_string()),
+            ("ProtocolInfo", String::new()),
+            ("PeerConnectionManager", String::new()),
+            ("PeerConnectionID", "-1".to_string()),
+            ("Direction", "Output".to_string()),
+            ("Status", "OK".to_string()),
+        ]),
+        _ => Err(soap::INVALID_ACTION),
+    };
+    soap::soap_response(CONNECTION_MANAGER, &action, result)
+}
+
+/// 目录内容只在重启服务时变化，不实际推送事件，但部分客户端订阅失败时会拒绝使用该服务器
+async fn event_subscription() -> Response {
+    let mut response = StatusCode::OK.into_response();
+    let headers = response.headers_mut();
+    if let Ok(sid) = HeaderValue::from_str(&format!("uuid:{}", uuid::Uuid::new_v4())) {
+        headers.insert("sid", sid);
+    }
+    headers.insert("timeout", HeaderValue::from_static("Second-1800"));
+    response
+}
+
+async fn media(
+    Path(file): Path<String>,
+    headers: HeaderMap,
+    Extension(db): Extension<Arc<DatabaseConnection>>,
+) -> Response {
+    let Some((page_id, _)) = parse_id_with_ext(&file) else {
+        return StatusCode::NOT_FOUND.into_response();
+    };
+    let path = match content_directory::find_browsable_page(&db, page_id).await {
+        Ok(page_model) => page_model
+            .as_ref()
+            .and_then(downloaded_page_path)
+            .filter(|path: &PathBuf| path.is_file()),
+        Err(e) => {
+            error!("查询 DLNA 媒体文件失败: {:#}", e);
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    };
+    let Some(path) = path else {
+        return StatusCode::NOT_FOUND.into_response();
+    };
+    match serve_file_with_range(&path, &headers).await {
+        Ok(mut response) => {
+            let headers = response.headers_mut();
+            headers.insert("transfermode.dlna.org", HeaderValue::from_static("Streaming"));
+            headers.insert(
+                "contentfeatures.dlna.org",
+                HeaderValue::from_static(content_directory::DLNA_FEATURES),
+            );
+            response
+        }
+        Err(e) => {
+            error!("DLNA 媒体传输失败: {:#}", e);
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+async fn bind(config: &DlnaConfig) -> Result<(Ipv4Addr, TcpListener)> {
+    let ip = resolve_interface(&config.interface)?;
+    let listener = TcpListener::bind((ip, config.port))
+        .await
+        .with_context(|| format!("DLNA 服务绑定 {}:{} 失败", ip, config.port))?;
+    Ok((ip, listener))
+}
+
+async fn serve(
+    db: Arc<DatabaseConnection>,
+    config: &DlnaConfig,
+    ip: Ipv4Addr,
+    listener: TcpListener,
+    token: CancellationToken,
+) -> Result<()> {
+    let device = Arc::new(DeviceInfo::new(config, ip));
+    let app = Router::new()
+        .route("/dlna/description.xml", get(device_description))
+        .route("/dlna/ContentDirectory.xml", get(content_directory_scpd))
+        .route("/dlna/ConnectionManager.xml", get(connection_manager_scpd))
+        .route("/dlna/control/ContentDirectory", post(content_directory_control))
+        .route("/dlna/control/ConnectionManager", post(connection_manager_control))
+        .route("/dlna/event/{service}", any(event_subscription))
+        .route("/dlna/media/{file}", get(media))
+        .layer(Extension(db))
+        .layer(Extension(device.clone()));
+
+    info!("DLNA 服务已启动，设备描述地址: {}", device.location());
+    let http = async {
+        tokio::select! {
+            res = axum::serve(listener, app) => res.context("DLNA HTTP 服务异常退出"),
+            _ = token.cancelled() => Ok(()),
+        }
+    };
+    tokio::try_join!(http, ssdp::run(ip, device.clone(), token.clone()))?;
+    Ok(())
+}
+
+/// 第 n 次连续失败后的重试间隔，从检查间隔开始翻倍
+fn retry_delay(failures: u32) -> Duration {
+    let secs = CONFIG_CHECK_INTERVAL_SECS << failures.saturating_sub(1).min(6);
+    Duration::from_secs(secs.min(MAX_RETRY_DELAY_SECS))
+}
+
+/// 记录一次失败，同一配置连续失败时累加次数，返回距离下次重试的间隔
+fn record_failure(failure: &mut Option<(DlnaConfig, u32, Instant)>, config: DlnaConfig) -> Duration {
+    let failures = match failure {
+        Some((failed, count, _)) if *failed == config => *count + 1,
+        _ => 1,
+    };
+    let delay = retry_delay(failures);
+    *failure = Some((config, failures, Instant::now() + delay));
+    delay
+}
+
+/// 按配置启停 DLNA 服务，配置变化后自动重启，运行失败只记录日志而不影响其它任务
+///
+/// 绑定成功后才记录正在运行的配置；网卡尚未就绪、端口被占用或服务异常退出时按退避间隔重试
+pub async fn dlna_server(db: Arc<DatabaseConnection>) {
+    let mut running: Option<(DlnaConfig, DropGuard, JoinHandle<()>)> = None;
+    // 最近一次失败的配置、连续失败次数和下次重试的时间
+    let mut failure: Option<(DlnaConfig, u32, Instant)> = None;
+    loop {
+        if running.as_ref().is_some_and(|(_, _, handle)| handle.is_finished()) {
+            if let Some((config, _, _)) = running.take() {
+                let delay = record_failure(&mut failure, config);
+                warn!("DLNA 服务已停止，{} 秒后重试", delay.as_secs());
+            }
+        }
+        let config = crate::config::with_config(|bundle| bundle.config.dlna.clone());
+        let desired = config.enabled.then_some(config);
+        if desired.as_ref() != running.as_ref().map(|(config, _, _)| config) {
+            if running.take().is_some() {
+                info!("DLNA 配置已变化，停止当前服务");
+            }
+            if let Some(config) = desired {
+                let waiting = failure
+                    .as_ref()
+                    .is_some_and(|(failed, _, retry_at)| *failed == config && Instant::now() < *retry_at);
+                if !waiting {
+                    match bind(&config).await {
+                        Ok((ip, listener)) => {
+                            let token = CancellationToken::new();
+                            let db = db.clone();
+                            let server_config = config.clone();
+                            let server_token = token.clone();
+                            let handle = tokio::spawn(async move {
+                                if let Err(e) = serve(db, &server_config, ip, listener, server_token).await {
+                                    error!("DLNA 服务运行失败: {:#}", e);
+                                }
+                            });
+                            running = Some((config, token.drop_guard(), handle));
+                        }
+                        Err(e) => {
+                            let delay = record_failure(&mut failure, config);
+                            error!("DLNA 服务启动失败，{} 秒后重试: {:#}", delay.as_secs(), e);
+                        }
+                    }
+                }
+            }
+        }
+        tokio::time::sleep(Duration::from_secs(CONFIG_CHECK_INTERVAL_SECS)).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_retry_delay() {
+        assert_eq!(retry_delay(1), Duration::from_secs(10));
+        assert_eq!(retry_delay(2), Duration::from_secs(20));
+        assert_eq!(retry_delay(5), Duration::from_secs(160));
+        assert_eq!(retry_delay(6), Duration::from_secs(300));
+        assert_eq!(retry_delay(100), Duration::from_secs(300));
+    }
+}
@@ -0,0 +1,219 @@
+//! UPnP 控制请求的 SOAP 解析与响应
+
+use std::collections::HashMap;
+
+use anyhow::Result;
+use axum::http::{header, HeaderValue, StatusCode};
+use axum::response::{IntoResponse, Response};
+use quick_xml::events::{BytesDecl, BytesText, Event};
+use quick_xml::reader::Reader;
+use quick_xml::writer::Writer;
+use tracing::error;
+
+/// UPnP 规范定义的错误码
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SoapFault {
+    pub code: u16,
+    pub description: &'static str,
+}
+
+pub const INVALID_ACTION: SoapFault = SoapFault {
+    code: 401,
+    description: "Invalid Action",
+};
+pub const INVALID_ARGS: SoapFault = SoapFault {
+    code: 402,
+    description: "Invalid Args",
+};
+pub const ACTION_FAILED: SoapFault = SoapFault {
+    code: 501,
+    description: "Action Failed",
+};
+pub const NO_SUCH_OBJECT: SoapFault = SoapFault {
+    code: 701,
+    description: "No such object",
+};
+
+/// 解析 SOAPACTION 头，例如 `"urn:schemas-upnp-org:service:ContentDirectory:1#Browse"`
+pub fn parse_soap_action(value: &str) -> Option<(&str, &str)> {
+    value.trim().trim_matches('"').split_once('#')
+}
+
+/// 读取动作元素下的全部参数，参数名不含命名空间前缀
+pub fn parse_arguments(body: &str) -> Result<HashMap<String, String>> {
+    let mut reader = Reader::from_str(body);
+    let mut arguments = HashMap::new();
+    // Envelope、Body、动作元素依次为第 1~3 层，参数位于第 4 层
+    let mut depth = 0;
+    let mut current: Option<String> = None;
+    loop {
+        match reader.read_event()? {
+            Event::Start(element) => {
+                depth += 1;
+                if depth == 4 {
+                    let name = String::from_utf8_lossy(element.local_name().as_ref()).into_owned();
+                    arguments.insert(name.clone(), String::new());
+                    current = Some(name);
+                }
+            }
+            Event::Empty(element) if depth == 3 => {
+                let name = String::from_utf8_lossy(element.local_name().as_ref()).into_owned();
+                arguments.insert(name, String::new());
+            }
+            Event::End(_) => {
+                if depth == 4 {
+                    current = None;
+                }
+                depth -= 1;
+            }
+            Event::Text(text) => {
+                if let Some(value) = current.as_ref().and_then(|name| arguments.get_mut(name)) {
+                    value.push_str(&text.unescape()?);
+                }
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+    Ok(arguments)
+}
+
+fn write_envelope(body: impl FnOnce(&mut Writer<Vec<u8>>) -> std::io::Result<()>) -> std::io::Result<String> {
+    let mut writer = Writer::new(Vec::new());
+    writer.write_event(Event::Decl(BytesDecl::new("1.0", Some("utf-8"), None)))?;
+    writer
+        .create_element("s:Envelope")
+        .with_attribute(("xmlns:s", "http://schemas.xmlsoap.org/soap/envelope/"))
+        .with_attribute(("s:encodingStyle", "http://schemas.xmlsoap.org/soap/encoding/"))
+        .write_inner_content(|writer| {
+            writer.create_element("s:Body").write_inner_content(body)?;
+            Ok(())
+        })?;
+    Ok(String::from_utf8_lossy(&writer.into_inner()).into_owned())
+}
+
+fn xml_response(status: StatusCode, body: String) -> Response {
+    let mut response = (status, body).into_response();
+    response.headers_mut().insert(
+        header::CONTENT_TYPE,
+        HeaderValue::from_static(r#"text/xml; charset="utf-8""#),
+    );
+    response
+}
+
+fn build_action_response(service_type: &str, action: &str, arguments: &[(&str, String)]) -> std::io::Result<String> {
+    write_envelope(|writer| {
+        writer
+            .create_element(format!("u:{}Response", action))
+            .with_attribute(("xmlns:u", service_type))
+            .write_inner_content(|writer| {
+                for (name, value) in arguments {
+                    writer.create_element(*name).write_text_content(BytesText::new(value))?;
+                }
+                Ok(())
+            })?;
+        Ok(())
+    })
+}
+
+fn build_fault(fault: SoapFault) -> std::io::Result<String> {
+    write_envelope(|writer| {
+        writer.create_element("s:Fault").write_inner_content(|writer| {
+            writer
+                .create_element("faultcode")
+                .write_text_content(BytesText::new("s:Client"))?;
+            writer
+                .create_element("faultstring")
+                .write_text_content(BytesText::new("UPnPError"))?;
+            writer.create_element("detail").write_inner_content(|writer| {
+                writer
+                    .create_element("UPnPError")
+                    .with_attribute(("xmlns", "urn:schemas-upnp-org:control-1-0"))
+                    .write_inner_content(|writer| {
+                        writer
+                            .create_element("errorCode")
+                            .write_text_content(BytesText::new(&fault.code.to_string()))?;
+                        writer
+                            .create_element("errorDescription")
+                            .write_text_content(BytesText::new(fault.description))?;
+                        Ok(())
+                    })?;
+                Ok(())
+            })?;
+            Ok(())
+        })?;
+        Ok(())
+    })
+}
+
+/// 动作执行结果转换为 SOAP 响应，失败时按规范返回 500 和 UPnPError
+pub fn soap_response(
+    service_type: &str,
+    action: &str,
+    result: std::result::Result<Vec<(&str, String)>, SoapFault>,
+) -> Response {
+    let (status, body) = match result {
+        Ok(arguments) => (StatusCode::OK, build_action_response(service_type, action, &arguments)),
+        Err(fault) => (StatusCode::INTERNAL_SERVER_ERROR, build_fault(fault)),
+    };
+    match body {
+        Ok(body) => xml_response(status, body),
+        Err(e) => {
+            error!("生成 SOAP 响应失败: {:#}", e);
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_soap_request() {
+        assert_eq!(
+            parse_soap_action(r#""urn:schemas-upnp-org:service:ContentDirectory:1#Browse""#),
+            Some(("urn:schemas-upnp-org:service:ContentDirectory:1", "Browse"))
+        );
+        assert_eq!(parse_soap_action("Browse"), None);
+
+        let body = r#"<?xml version="1.0" encoding="utf-8"?>
+<s:Envelope xmlns:s="http://schemas.xmlsoap.org/soap/envelope/" s:encodingStyle="http://schemas.xmlsoap.org/soap/encoding/">
+  <s:Body>
+    <u:Browse xmlns:u="urn:schemas-upnp-org:service:ContentDirectory:1">
+      <ObjectID>source/favorite/3</ObjectID>
+      <BrowseFlag>BrowseDirectChildren</BrowseFlag>
+      <Filter>*</Filter>
+      <StartingIndex>0</StartingIndex>
+      <RequestedCount>50</RequestedCount>
+      <SortCriteria/>
+    </u:Browse>
+  </s:Body>
+</s:Envelope>"#;
+        let arguments = parse_arguments(body).unwrap();
+        assert_eq!(arguments["ObjectID"], "source/favorite/3");
+        assert_eq!(arguments["BrowseFlag"], "BrowseDirectChildren");
+        assert_eq!(arguments["RequestedCount"], "50");
+        assert_eq!(arguments["SortCriteria"], "");
+        assert!(!arguments.contains_key("Browse"));
+    }
+
+    #[test]
+    fn test_build_action_response() {
+        let body = build_action_response(
+            "urn:schemas-upnp-org:service:ContentDirectory:1",
+            "Browse",
+            &[
+                ("Result", "<DIDL-Lite/>".to_string()),
+                ("NumberReturned", "0".to_string()),
+            ],
+        )
+        .unwrap();
+        assert!(body.contains(r#"<u:BrowseResponse xmlns:u="urn:schemas-upnp-org:service:ContentDirectory:1">"#));
+        // DIDL-Lite 作为文本嵌入，需要转义
+        assert!(body.contains("<Result>&lt;DIDL-Lite/&gt;</Result>"));
+
+        let fault = build_fault(NO_SUCH_OBJECT).unwrap();
+        assert!(fault.contains("<errorCode>701</errorCode>"));
+    }
+}
@@ -0,0 +1,190 @@
+//! SSDP 设备发现：响应 M-SEARCH 搜索并定期广播 NOTIFY
+
+use std::net::{Ipv4Addr, SocketAddr, SocketAddrV4};
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use socket2::{Domain, Protocol, Socket, Type};
+use tokio::net::UdpSocket;
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, warn};
+
+use super::{DeviceInfo, CONNECTION_MANAGER, CONTENT_DIRECTORY, DEVICE_TYPE};
+
+pub const SSDP_ADDR: Ipv4Addr = Ipv4Addr::new(239, 255, 255, 250);
+const SSDP_PORT: u16 = 1900;
+
+/// 宣告的有效期（秒），在过期前重新广播
+const MAX_AGE: u64 = 1800;
+const NOTIFY_INTERVAL_SECS: u64 = 600;
+
+/// 设备需要宣告的全部 (NT, USN) 组合
+fn notification_types(uuid: &str) -> Vec<(String, String)> {
+    let udn = format!("uuid:{}", uuid);
+    let mut types = vec![
+        ("upnp:rootdevice".to_string(), format!("{}::upnp:rootdevice", udn)),
+        (udn.clone(), udn.clone()),
+    ];
+    for urn in [DEVICE_TYPE, CONTENT_DIRECTORY, CONNECTION_MANAGER] {
+        types.push((urn.to_string(), format!("{}::{}", udn, urn)));
+    }
+    types
+}
+
+/// 解析 M-SEARCH 请求，返回搜索目标 ST
+fn parse_search(data: &[u8]) -> Option<String> {
+    let text = std::str::from_utf8(data).ok()?;
+    let mut lines = text.split("\r\n");
+    if !lines.next()?.trim().eq_ignore_ascii_case("M-SEARCH * HTTP/1.1") {
+        return None;
+    }
+    let mut search_target = None;
+    let mut discover = false;
+    for line in lines {
+        let Some((name, value)) = line.split_once(':') else {
+            continue;
+        };
+        let value = value.trim();
+        match name.trim().to_ascii_lowercase().as_str() {
+            "st" => search_target = Some(value.to_string()),
+            "man" => discover = value.trim_matches('"') == "ssdp:discover",
+            _ => {}
+        }
+    }
+    search_target.filter(|_| discover)
+}
+
+/// 与搜索目标匹配的 (ST, USN)，`ssdp:all` 匹配全部
+fn matching_targets<'a>(targets: &'a [(String, String)], search_target: &str) -> Vec<&'a (String, String)> {
+    targets
+        .iter()
+        .filter(|(nt, _)| search_target == "ssdp:all" || nt == search_target)
+        .collect()
+}
+
+fn server_header() -> String {
+    format!(
+        "{}/1.0 UPnP/1.0 bili-sync/{}",
+        std::env::consts::OS,
+        crate::config::version()
+    )
+}
+
+fn search_response(device: &DeviceInfo, search_target: &str, usn: &str) -> String {
+    format!(
+        "HTTP/1.1 200 OK\r\nCACHE-CONTROL: max-age={}\r\nDATE: {}\r\nEXT:\r\nLOCATION: {}\r\nSERVER: {}\r\nST: {}\r\nUSN: {}\r\nContent-Length: 0\r\n\r\n",
+        MAX_AGE,
+        chrono::Utc::now().format("%a, %d %b %Y %H:%M:%S GMT"),
+        device.location(),
+        server_header(),
+        search_target,
+        usn
+    )
+}
+
+fn notify_message(device: &DeviceInfo, nt: &str, usn: &str, alive: bool) -> String {
+    if alive {
+        format!(
+            "NOTIFY * HTTP/1.1\r\nHOST: {}:{}\r\nCACHE-CONTROL: max-age={}\r\nLOCATION: {}\r\nNT: {}\r\nNTS: ssdp:alive\r\nSERVER: {}\r\nUSN: {}\r\n\r\n",
+            SSDP_ADDR,
+            SSDP_PORT,
+            MAX_AGE,
+            device.location(),
+            nt,
+            server_header(),
+            usn
+        )
+    } else {
+        format!(
+            "NOTIFY * HTTP/1.1\r\nHOST: {}:{}\r\nNT: {}\r\nNTS: ssdp:byebye\r\nUSN: {}\r\n\r\n",
+            SSDP_ADDR, SSDP_PORT, nt, usn
+        )
+    }
+}
+
+/// 其它 DLNA 软件可能同时监听 1900 端口，需要开启地址复用
+fn bind_socket(interface: Ipv4Addr) -> Result<UdpSocket> {
+    let socket = Socket::new(Domain::IPV4, Type::DGRAM, Some(Protocol::UDP))?;
+    socket.set_reuse_address(true)?;
+    socket.set_nonblocking(true)?;
+    socket.bind(&SocketAddrV4::new(Ipv4Addr::UNSPECIFIED, SSDP_PORT).into())?;
+    socket.join_multicast_v4(&SSDP_ADDR, &interface)?;
+    socket.set_multicast_if_v4(&interface)?;
+    socket.set_multicast_ttl_v4(2)?;
+    Ok(UdpSocket::from_std(socket.into())?)
+}
+
+async fn send_notify(socket: &UdpSocket, device: &DeviceInfo, targets: &[(String, String)], alive: bool) {
+    let destination = SocketAddr::from((SSDP_ADDR, SSDP_PORT));
+    for (nt, usn) in targets {
+        let message = notify_message(device, nt, usn, alive);
+        if let Err(e) = socket.send_to(message.as_bytes(), destination).await {
+            warn!("发送 SSDP 通知失败: {:#}", e);
+            return;
+        }
+    }
+}
+
+pub async fn run(interface: Ipv4Addr, device: Arc<DeviceInfo>, token: CancellationToken) -> Result<()> {
+    let socket = bind_socket(interface).context("无法监听 SSDP 端口 1900")?;
+    let targets = notification_types(&device.uuid);
+    let mut interval = tokio::time::interval(Duration::from_secs(NOTIFY_INTERVAL_SECS));
+    let mut buffer = [0u8; 2048];
+    loop {
+        tokio::select! {
+            _ = token.cancelled() => {
+                send_notify(&socket, &device, &targets, false).await;
+                return Ok(());
+            }
+            _ = interval.tick() => send_notify(&socket, &device, &targets, true).await,
+            received = socket.recv_from(&mut buffer) => {
+                let (len, peer) = match received {
+                    Ok(received) => received,
+                    Err(e) => {
+                        warn!("接收 SSDP 消息失败: {:#}", e);
+                        continue;
+                    }
+                };
+                let Some(search_target) = parse_search(&buffer[..len]) else {
+                    continue;
+                };
+                for (nt, usn) in matching_targets(&targets, &search_target) {
+                    let message = search_response(&device, nt, usn);
+                    if let Err(e) = socket.send_to(message.as_bytes(), peer).await {
+                        debug!("回复 SSDP 搜索 {} 失败: {:#}", peer, e);
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_search_matching() {
+        let request = b"M-SEARCH * HTTP/1.1\r\nHOST: 239.255.255.250:1900\r\nMAN: \"ssdp:discover\"\r\nMX: 2\r\nST: urn:schemas-upnp-org:device:MediaServer:1\r\n\r\n";
+        assert_eq!(parse_search(request).as_deref(), Some(DEVICE_TYPE));
+        // 缺少 MAN 头或不是搜索请求时忽略
+        assert_eq!(parse_search(b"M-SEARCH * HTTP/1.1\r\nST: ssdp:all\r\n\r\n"), None);
+        assert_eq!(
+            parse_search(b"NOTIFY * HTTP/1.1\r\nMAN: \"ssdp:discover\"\r\nST: ssdp:all\r\n\r\n"),
+            None
+        );
+
+        let targets = notification_types("abc");
+        assert_eq!(matching_targets(&targets, "ssdp:all").len(), 5);
+        assert_eq!(
+            matching_targets(&targets, "upnp:rootdevice"),
+            vec![&("upnp:rootdevice".to_string(), "uuid:abc::upnp:rootdevice".to_string())]
+        );
+        assert_eq!(
+            matching_targets(&targets, CONTENT_DIRECTORY)[0].1,
+            format!("uuid:abc::{}", CONTENT_DIRECTORY)
+        );
+        assert!(matching_targets(&targets, "urn:schemas-upnp-org:device:MediaRenderer:1").is_empty());
+    }
+}
@@ -0,0 +1,194 @@
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use anyhow::{anyhow, Result};
+use bili_sync_entity::{page, video};
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use sea_orm::entity::prelude::*;
+use sea_orm::ActiveValue::{Set, Unchanged};
+use sea_orm::QuerySelect;
+use tokio::sync::Semaphore;
+use tracing::{error, info};
+
+use crate::adapter::video_source_of;
+use crate::bilibili::BiliClient;
+use crate::task::TASK_CONTROLLER;
+use crate::unified_downloader::UnifiedDownloader;
+use crate::utils::model::update_videos_model;
+use crate::utils::status::VideoStatus;
+use crate::workflow::download_video_pages;
+
+/// 正在下载中的视频，避免扫描轮次与「立即下载」同时处理同一个视频
+static ACTIVE_VIDEOS: Lazy<Mutex<HashSet<i32>>> = Lazy::new(|| Mutex::new(HashSet::new()));
+
+/// 视频的下载占用，离开作用域时自动释放
+pub struct VideoClaim(i32);
+
+impl Drop for VideoClaim {
+    fn drop(&mut self) {
+        ACTIVE_VIDEOS.lock().remove(&self.0);
+    }
+}
+
+/// 占用一个视频，已被其它任务占用时返回 None
+pub fn claim_video(video_id: i32) -> Option<VideoClaim> {
+    // 只在占用成功时构造 VideoClaim，否则它被丢弃时会释放别人的占用
+    if ACTIVE_VIDEOS.lock().insert(video_id) {
+        Some(VideoClaim(video_id))
+    } else {
+        None
+    }
+}
+
+fn is_video_active(video_id: i32) -> bool {
+    ACTIVE_VIDEOS.lock().contains(&video_id)
+}
+
+/// 按优先级从高到低排序，同优先级保持原有顺序
+pub fn sort_by_priority(videos: &mut [(video::Model, Vec<page::Model>)]) {
+    videos.sort_by_key(|(video, _)| std::cmp::Reverse(video.priority));
+}
+
+/// 「立即下载」的处理结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DownloadNowOutcome {
+    /// 已在后台开始下载
+    Started,
+    /// 已提升优先级，等待扫描轮次处理
+    Queued,
+    /// 视频已下载完成
+    AlreadyCompleted,
+}
+
+/// 将视频提升到所有视频之前，返回新的优先级
+async fn bump_to_front(video: &video::Model, connection: &DatabaseConnection) -> Result<i32> {
+    let others_max = video::Entity::find()
+        .filter(video::Column::Id.ne(video.id))
+        .select_only()
+        .column_as(video::Column::Priority.max(), "max_priority")
+        .into_tuple::<Option<i32>>()
+        .one(connection)
+        .await?
+        .flatten()
+        .unwrap_or(0);
+    if video.priority > others_max {
+        return Ok(video.priority);
+    }
+    let priority = others_max.saturating_add(1);
+    video::Entity::update(video::ActiveModel {
+        id: Unchanged(video.id),
+        priority: Set(priority),
+        ..Default::default()
+    })
+    .exec(connection)
+    .await?;
+    Ok(priority)
+}
+
+/// 提升视频优先级并尽可能立即开始下载，即使当前有扫描任务在进行
+pub async fn download_video_now(
+    connection: Arc<DatabaseConnection>,
+    video_id: i32,
+) -> Result<Option<(DownloadNowOutcome, i32)>> {
+    let Some(video_model) = video::Entity::find_by_id(video_id).one(connection.as_ref()).await? else {
+        return Ok(None);
+    };
+    // 已完成的视频不提升优先级，提升的优先级会在下载完成时清除
+    if VideoStatus::from(video_model.download_status).get_completed() {
+        return Ok(Some((DownloadNowOutcome::AlreadyCompleted, video_model.priority)));
+    }
+    let priority = bump_to_front(&video_model, &connection).await?;
+    // 尚未获取详情（没有分页信息）或任务已暂停时，只能等待扫描轮次按优先级处理
+    if video_model.single_page.is_none() || video_model.deleted != 0 || TASK_CONTROLLER.is_paused() {
+        if !TASK_CONTROLLER.is_paused() && !TASK_CONTROLLER.is_scanning() {
+            TASK_CONTROLLER.trigger_scan_now();
+        }
+        return Ok(Some((DownloadNowOutcome::Queued, priority)));
+    }
+    // 扫描轮次正在下载该视频
+    if is_video_active(video_id) {
+        return Ok(Some((DownloadNowOutcome::Queued, priority)));
+    }
+
+    let video_source = video_source_of(&video_model, &connection)
+        .await?
+        .ok_or_else(|| anyhow!("未找到视频「{}」所属的视频源", video_model.name))?;
+    let pages = page::Entity::find()
+        .filter(page::Column::VideoId.eq(video_id))
+        .all(connection.as_ref())
+        .await?;
+    let bili_client = BiliClient::new(String::new());
+    let downloader = match TASK_CONTROLLER.get_downloader().await {
+        Some(downloader) => downloader,
+        None => {
+            let downloader = Arc::new(UnifiedDownloader::new_smart(bili_client.client.clone()).await);
+            TASK_CONTROLLER.set_downloader(Some(downloader.clone())).await;
+            downloader
+        }
+    };
+    let token = TASK_CONTROLLER.get_cancellation_token().await;
+
+    // download_video_pages 内部会占用该视频，扫描轮次排到它时会跳过
+    tokio::spawn(async move {
+        let video_name = video_model.name.clone();
+        info!("开始立即下载视频「{}」", video_name);
+        // 独立的信号量，不与扫描轮次争抢并发名额
+        let semaphore = Semaphore::new(1);
+        let result = download_video_pages(
+            &bili_client,
+            &video_source,
+            video_model,
+            pages,
+            &connection,
+            &semaphore,
+            &downloader,
+            true,
+            token,
+        )
+        .await;
+        match result {
+            Ok(model) => {
+                if let Err(e) = update_videos_model(vec![model], &connection).await {
+                    error!("立即下载视频「{}」后更新数据库失败: {:#}", video_name, e);
+                } else {
+                    info!("立即下载视频「{}」处理完成", video_name);
+                }
+            }
+            Err(e) => error!("立即下载视频「{}」失败: {:#}", video_name, e),
+        }
+    });
+
+    Ok(Some((DownloadNowOutcome::Started, priority)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_claim_video_is_exclusive() {
+        let claim = claim_video(-1).unwrap();
+        assert!(claim_video(-1).is_none());
+        drop(claim);
+        assert!(claim_video(-1).is_some());
+    }
+
+    #[test]
+    fn test_sort_by_priority_is_stable() {
+        let video = |id, priority| {
+            (
+                video::Model {
+                    id,
+                    priority,
+                    ..Default::default()
+                },
+                vec![],
+            )
+        };
+        let mut videos = vec![video(1, 0), video(2, 5), video(3, 0), video(4, 5)];
+        sort_by_priority(&mut videos);
+        let ids = videos.iter().map(|(video, _)| video.id).collect::<Vec<_>>();
+        assert_eq!(ids, vec![2, 4, 1, 3]);
+    }
+}
@@ -1,10 +1,11 @@
+use std::net::SocketAddr;
 use std::sync::Arc;
 
 use anyhow::{Context, Result};
 use axum::extract::{Path, Request};
 use axum::http::{header, Uri};
 use axum::response::IntoResponse;
-use axum::routing::{delete, get, post, put};
+use axum::routing::{any, delete, get, post, put};
 use axum::{middleware, Extension, Router, ServiceExt};
 use reqwest::StatusCode;
 use rust_embed::Embed;
@@ -13,8 +14,10 @@ use utoipa::OpenApi;
 use utoipa_swagger_ui::{Config, SwaggerUi};
 
 use crate::api::auth;
+use crate::api::feed::{podcast_episode, podcast_feed};
 use crate::api::handler::{
     add_video_source,
+    adopt_video_source,
     ai_rename_history,
     batch_update_config_internal,
     check_initial_setup,
@@ -24,10 +27,13 @@ use crate::api::handler::{
     delete_video,
     delete_video_source,
     download_log_file,
+    download_video_now,
     generate_qr_code,
+    get_adopt_video_source_status,
     get_bangumi_seasons,
     get_bangumi_sources_for_merge,
     get_beta_image_update_status,
+    get_cdn_stats,
     get_config,
     get_config_history,
     get_config_migration_status,
@@ -42,6 +48,7 @@ use crate::api::handler::{
     get_notification_config,
     get_notification_status,
     get_queue_status,
+    get_reorganize_library_status,
     get_submission_videos,
     get_subscribed_collections,
     get_task_control_status,
@@ -49,9 +56,11 @@ use crate::api::handler::{
     get_user_favorites,
     get_user_favorites_by_uid,
     get_user_followings,
+    get_verify_library_status,
     get_video,
     get_video_bvid,
     get_video_play_info,
+    get_video_source_feed,
     get_video_source_keyword_filters,
     get_video_sources,
     get_videos,
@@ -62,13 +71,18 @@ use crate::api::handler::{
     refresh_scanning_endpoint,
     reload_config,
     reload_config_new_internal,
+    reorganize_library,
     reset_all_videos,
+    reset_cdn_stats,
     reset_specific_tasks,
     reset_video,
+    reset_video_source_feed,
     reset_video_source_path,
     resume_scanning_endpoint,
+    revert_reorganize_library,
     search_bilibili,
     setup_auth_token,
+    test_media_server_handler,
     test_notification_handler,
     test_risk_control_handler,
     migrate_config_schema,
@@ -85,10 +99,13 @@ use crate::api::handler::{
     validate_config,
     validate_favorite,
     validate_regex_pattern,
+    verify_library,
     ApiDoc,
 };
+use crate::api::hls::{hls_master_playlist, hls_variant_file};
 use crate::api::request::{BatchUpdateConfigRequest, UpdateConfigItemRequest};
 use crate::api::video_stream::stream_video;
+use crate::api::webdav::webdav;
 use crate::api::wrapper::ApiResponse;
 use crate::api::ws;
 use crate::bilibili::{get_captcha_info, serve_captcha_page, submit_captcha_result};
@@ -169,6 +186,8 @@ pub async fn http_server(_database_connection: Arc<DatabaseConnection>) -> Resul
             "/api/video-sources/{source_type}/{id}/reset-path",
             post(reset_video_source_path),
         )
+        .route("/api/video-sources/adopt/status", get(get_adopt_video_source_status))
+        .route("/api/video-sources/{source_type}/{id}/adopt", post(adopt_video_source))
         .route("/api/video-sources/{source_type}/{id}", delete(delete_video_source))
         .route(
             "/api/video-sources/submission/{id}/selected-videos",
@@ -178,6 +197,8 @@ pub async fn http_server(_database_connection: Arc<DatabaseConnection>) -> Resul
             "/api/video-sources/{source_type}/{id}/keyword-filters",
             put(update_video_source_keyword_filters).get(get_video_source_keyword_filters),
         )
+        .route("/api/video-sources/{source_type}/{id}/feed", get(get_video_source_feed))
+        .route("/api/video-sources/{source_type}/{id}/feed/reset", post(reset_video_source_feed))
         .route("/api/validate-regex", post(validate_regex_pattern))
         .route("/api/ai-rename/clear-cache", post(clear_ai_rename_cache))
         .route("/api/ai-rename/clear-cache/{source_type}/{id}", post(clear_ai_rename_cache_for_source))
@@ -186,9 +207,15 @@ pub async fn http_server(_database_connection: Arc<DatabaseConnection>) -> Resul
         .route("/api/videos/{id}", get(get_video))
         .route("/api/videos/{id}", delete(delete_video))
         .route("/api/videos/{id}/reset", post(reset_video))
+        .route("/api/videos/{id}/download-now", post(download_video_now))
         .route("/api/videos/{id}/update-status", post(update_video_status))
         .route("/api/videos/reset-all", post(reset_all_videos))
         .route("/api/videos/reset-specific-tasks", post(reset_specific_tasks))
+        .route("/api/videos/verify", post(verify_library))
+        .route("/api/videos/verify/status", get(get_verify_library_status))
+        .route("/api/library/reorganize", post(reorganize_library))
+        .route("/api/library/reorganize/revert", post(revert_reorganize_library))
+        .route("/api/library/reorganize/status", get(get_reorganize_library_status))
         .route("/api/dashboard", get(get_dashboard_data))
         .route("/api/reload-config", post(reload_config))
         .route("/api/config", get(get_config))
@@ -247,6 +274,8 @@ pub async fn http_server(_database_connection: Arc<DatabaseConnection>) -> Resul
         .route("/api/logs/files", get(get_log_files))
         .route("/api/logs/download", get(download_log_file))
         .route("/api/queue-status", get(get_queue_status))
+        .route("/api/cdn/stats", get(get_cdn_stats))
+        .route("/api/cdn/stats/reset", post(reset_cdn_stats))
         .route("/api/proxy/image", get(proxy_image))
         .route("/api/task-control/status", get(get_task_control_status))
         .route("/api/task-control/pause", post(pause_scanning_endpoint))
@@ -258,10 +287,25 @@ pub async fn http_server(_database_connection: Arc<DatabaseConnection>) -> Resul
         .route("/api/config/notification", get(get_notification_config))
         .route("/api/config/notification", post(update_notification_config))
         .route("/api/notification/status", get(get_notification_status))
+        // 媒体服务器刷新API
+        .route("/api/media-server/test", post(test_media_server_handler))
         // 测试API
         .route("/api/test/risk-control", post(test_risk_control_handler))
         // 视频流API
         .route("/api/videos/stream/{video_id}", get(stream_video))
+        // HLS 转码播放，主播放列表认证后签发播放密钥，档位和分片凭密钥访问
+        .route("/api/videos/stream/{video_id}/hls/master.m3u8", get(hls_master_playlist))
+        .route(
+            "/api/videos/stream/{video_id}/hls/{key}/{variant}/{file}",
+            get(hls_variant_file),
+        )
+        // 播客订阅，使用订阅自带的令牌认证
+        .route("/feeds/{source_type}/{file}", get(podcast_feed))
+        .route("/feeds/{source_type}/{id}/{file}", get(podcast_episode))
+        // 只读 WebDAV，自行校验 Basic 认证
+        .route("/webdav", any(webdav))
+        .route("/webdav/", any(webdav))
+        .route("/webdav/{*path}", any(webdav))
         // 新增在线播放API
         .route("/api/videos/{video_id}/play-info", get(get_video_play_info))
         .route("/api/videos/{video_id}/bvid", get(get_video_bvid))
@@ -288,6 +332,7 @@ pub async fn http_server(_database_connection: Arc<DatabaseConnection>) -> Resul
                 ),
         )
         .fallback_service(get(frontend_files));
+    crate::api::hls::spawn_idle_cleanup();
     // 使用动态配置而非静态CONFIG
     // 启动周期性数据库连接健康检查
     let health_check_connection = optimized_connection.clone();
@@ -312,7 +357,11 @@ pub async fn http_server(_database_connection: Arc<DatabaseConnection>) -> Resul
         .await
         .context("bind address failed")?;
     info!("开始运行管理页: http://{}", config.bind_address);
-    Ok(axum::serve(listener, ServiceExt::<Request>::into_make_service(app)).await?)
+    Ok(axum::serve(
+        listener,
+        ServiceExt::<Request>::into_make_service_with_connect_info::<SocketAddr>(app),
+    )
+    .await?)
 }
 
 async fn frontend_files(uri: Uri) -> impl IntoResponse {
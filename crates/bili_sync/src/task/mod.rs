@@ -1,6 +1,11 @@
+mod dlna;
+pub mod download_queue;
 mod http_server;
+pub mod postprocess;
+pub mod schedule;
 pub mod video_downloader;
 
+pub use dlna::dlna_server;
 pub use http_server::http_server;
 pub use video_downloader::video_downloader;
 
@@ -689,6 +694,22 @@ async fn delete_video_files_from_pages_task(
             }
         }
 
+        // 删除后处理保留原文件时另存的兼容副本
+        if let Some(copy_path) = &page.postprocess_copy_path {
+            let path = std::path::Path::new(copy_path);
+            if path.exists() {
+                match fs::remove_file(path).await {
+                    Ok(_) => {
+                        debug!("已删除后处理副本: {}", copy_path);
+                        deleted_count += 1;
+                    }
+                    Err(e) => {
+                        warn!("删除后处理副本失败: {} - {}", copy_path, e);
+                    }
+                }
+            }
+        }
+
         // 同时删除封面图片（如果存在且是本地文件）
         if let Some(image_path) = &page.image {
             // 跳过HTTP URL，只处理本地文件路径
@@ -789,6 +810,9 @@ async fn delete_video_files_from_pages_task(
                                 }
                             }
                         }
+
+                        // 删除进度条预览图（.trickplay 目录或 .bif 文件）
+                        deleted_count += crate::utils::trickplay::remove_trickplay(parent_dir, &file_stem_str).await;
                     }
                 }
             }
@@ -1617,6 +1641,8 @@ pub struct TaskController {
     pub is_scanning: AtomicBool,
     /// 是否刚刚恢复（用于立即开始新扫描）
     pub just_resumed: AtomicBool,
+    /// 是否因离开时间窗口而暂停（区别于用户手动暂停）
+    pub paused_by_schedule: AtomicBool,
     /// 全局取消令牌，用于取消所有下载任务
     pub cancellation_token: Arc<Mutex<CancellationToken>>,
     /// 下载器的引用，用于暂停时停止下载
@@ -1629,6 +1655,7 @@ impl TaskController {
             is_paused: AtomicBool::new(false),
             is_scanning: AtomicBool::new(false),
             just_resumed: AtomicBool::new(false),
+            paused_by_schedule: AtomicBool::new(false),
             cancellation_token: Arc::new(Mutex::new(CancellationToken::new())),
             downloader: Arc::new(Mutex::new(None)),
         }
@@ -1654,6 +1681,13 @@ impl TaskController {
         // 重置恢复标志
         self.just_resumed.store(false, Ordering::SeqCst);
 
+        self.stop_downloads().await;
+
+        info!("定时扫描任务已暂停，所有下载任务已取消");
+    }
+
+    /// 取消正在进行的下载任务并停止下载器
+    async fn stop_downloads(&self) {
         // 取消所有正在进行的下载任务
         if let Ok(token) = self.cancellation_token.try_lock() {
             token.cancel();
@@ -1669,13 +1703,39 @@ impl TaskController {
                 }
             }
         }
+    }
 
-        info!("定时扫描任务已暂停，所有下载任务已取消");
+    /// 只中断下载而不暂停扫描，下一轮扫描会重新创建下载器
+    pub async fn interrupt_downloads(&self) {
+        self.stop_downloads().await;
+        if let Ok(mut downloader_guard) = self.downloader.try_lock() {
+            *downloader_guard = None;
+        }
+    }
+
+    /// 因离开时间窗口而暂停，已被用户暂停时不做处理并返回 false
+    pub async fn pause_for_schedule(&self) -> bool {
+        if self.is_paused() {
+            return false;
+        }
+        self.paused_by_schedule.store(true, Ordering::SeqCst);
+        self.pause().await;
+        true
+    }
+
+    /// 恢复因时间窗口而暂停的任务，用户手动暂停的任务保持暂停并返回 false
+    pub fn resume_from_schedule(&self) -> bool {
+        if !self.paused_by_schedule.swap(false, Ordering::SeqCst) {
+            return false;
+        }
+        self.resume();
+        true
     }
 
     /// 恢复定时扫描任务
     pub fn resume(&self) {
         self.is_paused.store(false, Ordering::SeqCst);
+        self.paused_by_schedule.store(false, Ordering::SeqCst);
         // 设置恢复标志，表示应该立即开始新扫描
         self.just_resumed.store(true, Ordering::SeqCst);
         // 创建新的取消令牌，用于新的下载任务
@@ -1777,6 +1837,16 @@ pub fn resume_scanning() {
     TASK_CONTROLLER.resume();
 }
 
+/// 整理、导入等直接改动媒体库的后台任务开始前暂停定时扫描和下载，
+/// 返回是否由本次调用暂停，任务结束后只有返回 true 时才需要恢复
+pub async fn pause_scanning_for_job() -> bool {
+    if TASK_CONTROLLER.is_paused() {
+        return false;
+    }
+    TASK_CONTROLLER.pause().await;
+    true
+}
+
 /// 检查是否正在扫描的便捷函数
 pub fn is_scanning() -> bool {
     TASK_CONTROLLER.is_scanning()
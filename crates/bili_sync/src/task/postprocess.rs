@@ -0,0 +1,461 @@
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use anyhow::{bail, ensure, Context, Result};
+use bili_sync_entity::{page, video};
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use sea_orm::entity::prelude::*;
+use sea_orm::ActiveValue::{Set, Unchanged};
+use tokio::fs;
+use tokio::sync::Notify;
+use tracing::{error, info, warn};
+
+use crate::adapter::{video_source_of, VideoSource};
+use crate::config::PostProcessProfile;
+use crate::downloader::part_path;
+use crate::utils::ffmpeg::run_ffmpeg;
+
+/// 后处理任务状态，保存在 page.postprocess_status
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PostProcessStatus {
+    /// 没有后处理任务
+    None = 0,
+    /// 等待空闲的处理名额
+    Pending = 1,
+    Running = 2,
+    Succeeded = 3,
+    Failed = 4,
+    /// 视频不满足配置的处理条件，无需处理
+    Skipped = 5,
+}
+
+impl From<PostProcessStatus> for i32 {
+    fn from(status: PostProcessStatus) -> Self {
+        status as i32
+    }
+}
+
+/// 正在执行的后处理任务数。后处理使用独立的并发名额，避免转码占满资源时阻塞下载；
+/// 每个任务开始前按当前配置的 max_workers 检查，修改设置后无需重启即可生效
+static RUNNING_WORKERS: Lazy<Mutex<usize>> = Lazy::new(|| Mutex::new(0));
+static WORKER_RELEASED: Lazy<Notify> = Lazy::new(Notify::new);
+
+/// 调大 max_workers 时没有任务结束来唤醒排队的任务，定期重新检查
+const WORKER_RECHECK_INTERVAL: Duration = Duration::from_secs(5);
+
+/// 后处理名额，释放时唤醒排队的任务
+struct WorkerPermit;
+
+impl Drop for WorkerPermit {
+    fn drop(&mut self) {
+        *RUNNING_WORKERS.lock() -= 1;
+        WORKER_RELEASED.notify_waiters();
+    }
+}
+
+fn try_acquire_worker(max_workers: usize) -> Option<WorkerPermit> {
+    let mut running = RUNNING_WORKERS.lock();
+    if *running >= max_workers.max(1) {
+        return None;
+    }
+    *running += 1;
+    Some(WorkerPermit)
+}
+
+async fn acquire_worker() -> WorkerPermit {
+    loop {
+        // 先登记等待再检查，避免检查之后、等待之前释放的名额被错过
+        let released = WORKER_RELEASED.notified();
+        tokio::pin!(released);
+        released.as_mut().enable();
+        let max_workers = crate::config::with_config(|bundle| bundle.config.postprocess.max_workers);
+        if let Some(permit) = try_acquire_worker(max_workers) {
+            return permit;
+        }
+        let _ = tokio::time::timeout(WORKER_RECHECK_INTERVAL, released).await;
+    }
+}
+
+/// 已排队或正在处理的分页，避免同一文件被重复处理
+static QUEUED_PAGES: Lazy<Mutex<HashSet<i32>>> = Lazy::new(|| Mutex::new(HashSet::new()));
+
+/// 按名称查找后处理配置
+pub fn find_profile(name: &str) -> Option<PostProcessProfile> {
+    crate::config::with_config(|bundle| {
+        bundle
+            .config
+            .postprocess
+            .profiles
+            .iter()
+            .find(|profile| profile.name == name)
+            .cloned()
+    })
+}
+
+/// ffprobe 读取到的视频流信息
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct VideoProbe {
+    codec: String,
+    height: u32,
+}
+
+async fn probe_video(path: &Path) -> Result<VideoProbe> {
+    let output = tokio::process::Command::new("ffprobe")
+        .args([
+            "-v",
+            "error",
+            "-select_streams",
+            "v:0",
+            "-show_entries",
+            "stream=codec_name,height",
+            "-of",
+            "csv=p=0",
+        ])
+        .arg(path)
+        .output()
+        .await
+        .context("无法执行 ffprobe")?;
+    if !output.status.success() {
+        bail!(
+            "ffprobe 无法解析文件: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let (codec, height) = stdout
+        .trim()
+        .split_once(',')
+        .with_context(|| format!("文件中没有视频流: {}", stdout.trim()))?;
+    Ok(VideoProbe {
+        codec: codec.to_string(),
+        height: height.trim().parse().unwrap_or_default(),
+    })
+}
+
+/// 判断视频是否需要处理：未设置任何条件时总是处理，否则编码命中或高度超出任一条件即处理
+fn needs_processing(profile: &PostProcessProfile, probe: &VideoProbe) -> bool {
+    if profile.match_codecs.is_empty() && profile.max_height == 0 {
+        return true;
+    }
+    let codec_matched = profile
+        .match_codecs
+        .iter()
+        .any(|codec| codec.eq_ignore_ascii_case(&probe.codec));
+    let too_tall = profile.max_height > 0 && probe.height > profile.max_height;
+    codec_matched || too_tall
+}
+
+fn ffmpeg_args(profile: &PostProcessProfile, input: &Path, output: &Path) -> Vec<String> {
+    let mut args: Vec<String> = ["-hide_banner", "-y", "-i"].map(String::from).into();
+    args.push(input.to_string_lossy().into_owned());
+    // 只重新编码第一个视频流，内嵌封面（attached_pic）同样是视频流，需要保持原样复制
+    args.extend(["-map", "0", "-c", "copy", "-c:v:0"].map(String::from));
+    args.push(profile.video_codec.clone());
+    if let Some(crf) = profile.crf {
+        args.extend(["-crf".to_string(), crf.to_string()]);
+    }
+    if let Some(preset) = &profile.preset {
+        args.extend(["-preset".to_string(), preset.clone()]);
+    }
+    if profile.max_height > 0 {
+        // 只缩小不放大，宽度按比例取偶数
+        args.extend([
+            "-filter:v:0".to_string(),
+            format!("scale=-2:min({}\\,ih)", profile.max_height),
+        ]);
+    }
+    if let Some(audio_codec) = &profile.audio_codec {
+        args.extend(["-c:a".to_string(), audio_codec.clone()]);
+    }
+    args.extend(profile.extra_args.iter().cloned());
+    // 临时输出文件带 .part 后缀，ffmpeg 无法据此推断格式，按原文件的扩展名指定
+    let is_mkv = input.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("mkv"));
+    if is_mkv {
+        args.extend(["-f", "matroska"].map(String::from));
+    } else {
+        args.extend(["-movflags", "+faststart", "-f", "mp4"].map(String::from));
+    }
+    args.push(output.to_string_lossy().into_owned());
+    args
+}
+
+/// 兼容副本的路径：`标题.mp4` -> `标题 - compat.mp4`
+fn copy_path(path: &Path, suffix: &str) -> PathBuf {
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let ext = path.extension().map_or("mp4".into(), |ext| ext.to_string_lossy());
+    path.with_file_name(format!("{} - {}.{}", stem, suffix, ext))
+}
+
+/// 执行后处理，返回处理后的文件路径，与原文件相同时表示替换了原文件；`None` 表示无需处理
+async fn process_file(profile: &PostProcessProfile, path: &Path) -> Result<Option<PathBuf>> {
+    ensure!(path.exists(), "视频文件不存在: {}", path.display());
+    let probe = probe_video(path).await?;
+    if !needs_processing(profile, &probe) {
+        return Ok(None);
+    }
+
+    let target = if profile.keep_original {
+        copy_path(path, &profile.copy_suffix)
+    } else {
+        path.to_path_buf()
+    };
+    let tmp_output = part_path(&target);
+    if let Err(e) = run_ffmpeg(ffmpeg_args(profile, path, &tmp_output)).await {
+        let _ = fs::remove_file(&tmp_output).await;
+        return Err(e);
+    }
+    // 处理期间原文件可能已被重命名或删除，此时不再写回
+    if !path.exists() {
+        let _ = fs::remove_file(&tmp_output).await;
+        bail!("处理期间原文件已被移动: {}", path.display());
+    }
+    fs::rename(&tmp_output, &target).await?;
+    Ok(Some(target))
+}
+
+/// 删除之前留下的、与本次副本不同的兼容副本
+async fn remove_stale_copy(previous: Option<&str>, current: Option<&Path>) {
+    let Some(previous) = previous.map(Path::new).filter(|previous| Some(*previous) != current) else {
+        return;
+    };
+    match fs::remove_file(previous).await {
+        Ok(()) => info!("已删除之前的后处理副本: {}", previous.display()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+        Err(e) => warn!("删除之前的后处理副本失败: {}: {:#}", previous.display(), e),
+    }
+}
+
+/// 记录本次处理留下的兼容副本，并清理之前留下的副本
+async fn update_copy_path(
+    connection: &DatabaseConnection,
+    page_id: i32,
+    previous: Option<&str>,
+    current: Option<&Path>,
+) -> Result<()> {
+    remove_stale_copy(previous, current).await;
+    page::Entity::update(page::ActiveModel {
+        id: Unchanged(page_id),
+        postprocess_copy_path: Set(current.map(|path| path.to_string_lossy().into_owned())),
+        ..Default::default()
+    })
+    .exec(connection)
+    .await?;
+    Ok(())
+}
+
+async fn set_status(
+    connection: &DatabaseConnection,
+    page_id: i32,
+    status: PostProcessStatus,
+    error: Option<String>,
+) -> Result<()> {
+    page::Entity::update(page::ActiveModel {
+        id: Unchanged(page_id),
+        postprocess_status: Set(status.into()),
+        postprocess_error: Set(error),
+        ..Default::default()
+    })
+    .exec(connection)
+    .await?;
+    Ok(())
+}
+
+async fn run_job(connection: DatabaseConnection, page_id: i32, path: PathBuf, profile: PostProcessProfile) {
+    let _permit = acquire_worker().await;
+    // 排队期间文件可能已被重命名，以数据库中的最新路径为准
+    let (path, previous_copy) = match page::Entity::find_by_id(page_id).one(&connection).await {
+        Ok(Some(model)) => (
+            model.path.map(PathBuf::from).unwrap_or(path),
+            model.postprocess_copy_path,
+        ),
+        _ => (path, None),
+    };
+    if let Err(e) = set_status(&connection, page_id, PostProcessStatus::Running, None).await {
+        warn!("更新后处理状态失败: {:#}", e);
+    }
+    info!("开始后处理「{}」: {}", profile.name, path.display());
+
+    let result = process_file(&profile, &path).await;
+    // 处理成功或无需处理时，之前留下的兼容副本已不对应当前的配置和文件
+    if let Ok(output) = &result {
+        let copy = output.as_deref().filter(|output| *output != path);
+        if let Err(e) = update_copy_path(&connection, page_id, previous_copy.as_deref(), copy).await {
+            warn!("更新后处理副本记录失败: {:#}", e);
+        }
+    }
+    let (status, error) = match result {
+        Ok(None) => {
+            info!("视频无需后处理「{}」: {}", profile.name, path.display());
+            (PostProcessStatus::Skipped, None)
+        }
+        Ok(Some(output)) => {
+            // 原文件被替换后之前的校验记录已失效
+            if output == path {
+                let result = page::Entity::update(page::ActiveModel {
+                    id: Unchanged(page_id),
+                    file_size: Set(None),
+                    file_hash: Set(None),
+                    verified_at: Set(None),
+                    ..Default::default()
+                })
+                .exec(&connection)
+                .await;
+                if let Err(e) = result {
+                    warn!("清除校验记录失败: {:#}", e);
+                }
+            }
+            info!("后处理「{}」完成: {}", profile.name, path.display());
+            (PostProcessStatus::Succeeded, None)
+        }
+        Err(e) => {
+            error!("后处理「{}」失败: {}: {:#}", profile.name, path.display(), e);
+            (PostProcessStatus::Failed, Some(format!("{:#}", e)))
+        }
+    };
+    if let Err(e) = set_status(&connection, page_id, status, error).await {
+        warn!("更新后处理状态失败: {:#}", e);
+    }
+    QUEUED_PAGES.lock().remove(&page_id);
+}
+
+/// 将分页加入后处理队列，在独立的工作池中执行，不阻塞下载流程
+pub async fn enqueue(connection: &DatabaseConnection, page_id: i32, path: &Path, profile_name: &str) -> Result<()> {
+    let Some(profile) = find_profile(profile_name) else {
+        warn!("未找到名为「{}」的后处理配置，跳过: {}", profile_name, path.display());
+        return Ok(());
+    };
+    if !QUEUED_PAGES.lock().insert(page_id) {
+        return Ok(());
+    }
+    if let Err(e) = set_status(connection, page_id, PostProcessStatus::Pending, None).await {
+        QUEUED_PAGES.lock().remove(&page_id);
+        return Err(e);
+    }
+    tokio::spawn(run_job(connection.clone(), page_id, path.to_path_buf(), profile));
+    Ok(())
+}
+
+/// 启动时重新排队上次退出前未完成的后处理任务，返回排队的数量
+pub async fn resume_pending_jobs(connection: &DatabaseConnection) -> Result<usize> {
+    let pages = page::Entity::find()
+        .filter(page::Column::PostprocessStatus.is_in([
+            i32::from(PostProcessStatus::Pending),
+            i32::from(PostProcessStatus::Running),
+        ]))
+        .find_also_related(video::Entity)
+        .all(connection)
+        .await?;
+    let mut count = 0;
+    for (page_model, video_model) in pages {
+        let profile = match &video_model {
+            Some(video_model) => video_source_of(video_model, connection)
+                .await?
+                .map(|source| source.postprocess_profile().to_string())
+                .unwrap_or_default(),
+            None => String::new(),
+        };
+        match (page_model.path, profile.is_empty()) {
+            (Some(path), false) => {
+                enqueue(connection, page_model.id, Path::new(&path), &profile).await?;
+                count += 1;
+            }
+            // 视频源已取消后处理或文件信息缺失
+            _ => set_status(connection, page_model.id, PostProcessStatus::None, None).await?,
+        }
+    }
+    Ok(count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn profile() -> PostProcessProfile {
+        serde_json::from_str(r#"{"name": "compat", "match_codecs": ["av1", "hevc"], "crf": 22}"#).unwrap()
+    }
+
+    fn probe(codec: &str, height: u32) -> VideoProbe {
+        VideoProbe {
+            codec: codec.to_string(),
+            height,
+        }
+    }
+
+    #[test]
+    fn test_worker_limit_follows_setting() {
+        let first = try_acquire_worker(1).unwrap();
+        assert!(try_acquire_worker(1).is_none());
+        // 调大上限后立即可以获取新的名额
+        let second = try_acquire_worker(2).unwrap();
+        assert!(try_acquire_worker(2).is_none());
+        drop(first);
+        // 调小上限后，正在执行的任务数降到上限以下才放行
+        assert!(try_acquire_worker(1).is_none());
+        drop(second);
+        let third = try_acquire_worker(1).unwrap();
+        drop(third);
+        assert_eq!(*RUNNING_WORKERS.lock(), 0);
+    }
+
+    #[test]
+    fn test_needs_processing() {
+        let mut profile = profile();
+        assert!(needs_processing(&profile, &probe("av1", 1080)));
+        assert!(needs_processing(&profile, &probe("HEVC", 1080)));
+        assert!(!needs_processing(&profile, &probe("h264", 2160)));
+
+        profile.max_height = 1080;
+        assert!(needs_processing(&profile, &probe("h264", 2160)));
+        assert!(!needs_processing(&profile, &probe("h264", 1080)));
+
+        // 未设置任何条件时总是处理
+        profile.match_codecs.clear();
+        profile.max_height = 0;
+        assert!(needs_processing(&profile, &probe("h264", 720)));
+    }
+
+    #[test]
+    fn test_ffmpeg_args_and_copy_path() {
+        let mut profile = profile();
+        profile.max_height = 1080;
+        profile.preset = Some("veryfast".to_string());
+        let args = ffmpeg_args(&profile, Path::new("/v/a.mp4"), Path::new("/v/a.mp4.part"));
+        assert_eq!(
+            args.join(" "),
+            "-hide_banner -y -i /v/a.mp4 -map 0 -c copy -c:v:0 libx264 -crf 22 -preset veryfast \
+             -filter:v:0 scale=-2:min(1080\\,ih) -movflags +faststart -f mp4 /v/a.mp4.part"
+        );
+        // mkv 输出保持 Matroska 格式，字幕、弹幕和附件原样复制
+        let args = ffmpeg_args(&profile, Path::new("/v/a.mkv"), Path::new("/v/a.mkv.part"));
+        assert_eq!(&args[args.len() - 3..], ["-f", "matroska", "/v/a.mkv.part"]);
+        assert!(!args.contains(&"+faststart".to_string()));
+        assert_eq!(
+            copy_path(Path::new("/v/标题.mp4"), &profile.copy_suffix),
+            Path::new("/v/标题 - compat.mp4")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_remove_stale_copy() {
+        let dir = std::env::temp_dir().join(format!("bili-sync-postprocess-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let old_copy = dir.join("标题 - compat.mp4");
+        let new_copy = dir.join("标题 - h264.mp4");
+        std::fs::write(&old_copy, b"").unwrap();
+        std::fs::write(&new_copy, b"").unwrap();
+        let old = old_copy.to_string_lossy().into_owned();
+
+        // 本次写出的副本与之前相同时保留
+        remove_stale_copy(Some(&old), Some(&old_copy)).await;
+        assert!(old_copy.exists());
+        // 副本名称变化或不再保留副本时删除之前的副本
+        remove_stale_copy(Some(&old), Some(&new_copy)).await;
+        assert!(!old_copy.exists());
+        assert!(new_copy.exists());
+        remove_stale_copy(Some(&new_copy.to_string_lossy()), None).await;
+        assert!(!new_copy.exists());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
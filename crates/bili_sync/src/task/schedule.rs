@@ -0,0 +1,215 @@
+use chrono::{Datelike, Duration, NaiveDateTime, NaiveTime};
+use serde::Serialize;
+use tracing::{debug, info};
+use utoipa::ToSchema;
+
+use crate::config::{ScheduleConfig, TimeWindow};
+use crate::task::TASK_CONTROLLER;
+use crate::utils::time_format::{now_naive, STANDARD_TIME_FORMAT};
+
+/// 时间窗口检查间隔（秒）
+const CHECK_INTERVAL_SECS: u64 = 30;
+
+/// 计算下一个窗口时向后查找的天数，覆盖一整周的星期配置
+const LOOKAHEAD_DAYS: i64 = 8;
+
+fn parse_hm(value: &str) -> Option<NaiveTime> {
+    NaiveTime::parse_from_str(value.trim(), "%H:%M").ok()
+}
+
+fn weekday_matches(window: &TimeWindow, at: NaiveDateTime) -> bool {
+    window.weekdays.is_empty() || window.weekdays.contains(&(at.weekday().number_from_monday() as u8))
+}
+
+/// 判断时间点是否落在窗口内，格式错误的窗口视为不匹配
+fn window_contains(window: &TimeWindow, at: NaiveDateTime) -> bool {
+    let (Some(start), Some(end)) = (parse_hm(&window.start), parse_hm(&window.end)) else {
+        return false;
+    };
+    let time = at.time();
+    if start == end {
+        weekday_matches(window, at)
+    } else if start < end {
+        start <= time && time < end && weekday_matches(window, at)
+    } else {
+        // 跨越午夜：凌晨部分属于前一天开始的窗口
+        (time >= start && weekday_matches(window, at))
+            || (time < end && weekday_matches(window, at - Duration::days(1)))
+    }
+}
+
+fn any_contains(windows: &[TimeWindow], at: NaiveDateTime) -> bool {
+    windows.iter().any(|window| window_contains(window, at))
+}
+
+pub fn scan_allowed_at(config: &ScheduleConfig, at: NaiveDateTime) -> bool {
+    !config.enabled
+        || ((config.scan_windows.is_empty() || any_contains(&config.scan_windows, at))
+            && !any_contains(&config.quiet_windows, at))
+}
+
+pub fn download_allowed_at(config: &ScheduleConfig, at: NaiveDateTime) -> bool {
+    !config.enabled
+        || ((config.download_windows.is_empty() || any_contains(&config.download_windows, at))
+            && !any_contains(&config.quiet_windows, at))
+}
+
+/// 当前是否允许下载媒体文件
+pub fn download_allowed_now() -> bool {
+    let config = crate::config::with_config(|bundle| bundle.config.schedule.clone());
+    download_allowed_at(&config, now_naive())
+}
+
+/// 查找下一次允许的时间点，当前已允许时返回 `now`，一周内都不允许时返回 None
+///
+/// 允许状态只会在各窗口的起止时刻发生变化，因此只需检查这些边界
+pub fn next_allowed(
+    config: &ScheduleConfig,
+    now: NaiveDateTime,
+    allowed: fn(&ScheduleConfig, NaiveDateTime) -> bool,
+) -> Option<NaiveDateTime> {
+    if allowed(config, now) {
+        return Some(now);
+    }
+    let mut boundaries: Vec<NaiveDateTime> = config
+        .scan_windows
+        .iter()
+        .chain(&config.download_windows)
+        .chain(&config.quiet_windows)
+        .flat_map(|window| [parse_hm(&window.start), parse_hm(&window.end)])
+        .flatten()
+        .flat_map(|time| (0..=LOOKAHEAD_DAYS).map(move |offset| (now.date() + Duration::days(offset)).and_time(time)))
+        .filter(|boundary| *boundary > now)
+        .collect();
+    boundaries.sort();
+    boundaries.dedup();
+    boundaries.into_iter().find(|boundary| allowed(config, *boundary))
+}
+
+/// 首页展示的时间窗口状态
+#[derive(Serialize, ToSchema, Clone, Debug)]
+pub struct ScheduleStatus {
+    pub scan_allowed: bool,
+    pub download_allowed: bool,
+    /// 下一次允许扫描的时间，当前允许时为 None
+    pub next_scan_window: Option<String>,
+    /// 下一次允许下载的时间，当前允许时为 None
+    pub next_download_window: Option<String>,
+}
+
+/// 未启用时间窗口时返回 None
+pub fn schedule_status() -> Option<ScheduleStatus> {
+    let config = crate::config::with_config(|bundle| bundle.config.schedule.clone());
+    if !config.enabled {
+        return None;
+    }
+    let now = now_naive();
+    let format_next = |allowed: fn(&ScheduleConfig, NaiveDateTime) -> bool| {
+        next_allowed(&config, now, allowed)
+            .filter(|next| *next > now)
+            .map(|next| next.format(STANDARD_TIME_FORMAT).to_string())
+    };
+    Some(ScheduleStatus {
+        scan_allowed: scan_allowed_at(&config, now),
+        download_allowed: download_allowed_at(&config, now),
+        next_scan_window: format_next(scan_allowed_at),
+        next_download_window: format_next(download_allowed_at),
+    })
+}
+
+/// 周期检查时间窗口，在窗口开闭时暂停或恢复扫描与下载
+///
+/// 只在状态变化的时刻动作，用户在窗口外手动恢复任务不会被立即再次暂停
+pub async fn schedule_watcher() {
+    let mut last_scan_allowed = true;
+    let mut last_download_allowed = true;
+    loop {
+        let config = crate::config::with_config(|bundle| bundle.config.schedule.clone());
+        let now = now_naive();
+        let scan_allowed = scan_allowed_at(&config, now);
+        let download_allowed = download_allowed_at(&config, now);
+
+        if scan_allowed != last_scan_allowed {
+            if scan_allowed {
+                if TASK_CONTROLLER.resume_from_schedule() {
+                    info!("进入扫描时间窗口，恢复定时扫描任务");
+                }
+            } else if TASK_CONTROLLER.pause_for_schedule().await {
+                info!("离开扫描时间窗口，暂停定时扫描任务");
+            }
+        }
+
+        if download_allowed != last_download_allowed && scan_allowed {
+            if download_allowed {
+                // 窗口重新打开时立即开始新一轮，继续之前未完成的下载
+                if !TASK_CONTROLLER.is_paused() && !TASK_CONTROLLER.is_scanning() {
+                    info!("进入下载时间窗口，立即开始新一轮扫描");
+                    TASK_CONTROLLER.trigger_scan_now();
+                }
+            } else if TASK_CONTROLLER.is_scanning() {
+                info!("离开下载时间窗口，停止正在进行的下载");
+                TASK_CONTROLLER.interrupt_downloads().await;
+                // 本轮剩余的视频源会随取消令牌结束，随后立即以仅扫描方式重新开始
+                TASK_CONTROLLER.trigger_scan_now();
+            } else {
+                debug!("离开下载时间窗口");
+            }
+        }
+
+        last_scan_allowed = scan_allowed;
+        last_download_allowed = download_allowed;
+        tokio::time::sleep(tokio::time::Duration::from_secs(CHECK_INTERVAL_SECS)).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn window(start: &str, end: &str, weekdays: &[u8]) -> TimeWindow {
+        TimeWindow {
+            start: start.to_string(),
+            end: end.to_string(),
+            weekdays: weekdays.to_vec(),
+        }
+    }
+
+    fn at(value: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(value, STANDARD_TIME_FORMAT).unwrap()
+    }
+
+    #[test]
+    fn test_window_contains_across_midnight() {
+        // 2026-10-16 是周五
+        let friday_night = window("22:00", "02:00", &[5]);
+        assert!(window_contains(&friday_night, at("2026-10-16 23:00:00")));
+        assert!(window_contains(&friday_night, at("2026-10-17 01:30:00")));
+        assert!(!window_contains(&friday_night, at("2026-10-17 23:00:00")));
+        assert!(!window_contains(&friday_night, at("2026-10-16 01:30:00")));
+    }
+
+    #[test]
+    fn test_download_window_with_quiet_hours() {
+        let config = ScheduleConfig {
+            enabled: true,
+            scan_windows: vec![],
+            download_windows: vec![window("01:00", "07:00", &[])],
+            quiet_windows: vec![window("18:00", "23:00", &[1, 2, 3, 4, 5])],
+        };
+        assert!(scan_allowed_at(&config, at("2026-10-16 12:00:00")));
+        assert!(!download_allowed_at(&config, at("2026-10-16 12:00:00")));
+        assert!(download_allowed_at(&config, at("2026-10-17 03:00:00")));
+        // 工作日晚上静默
+        assert!(!scan_allowed_at(&config, at("2026-10-16 19:00:00")));
+        assert!(scan_allowed_at(&config, at("2026-10-17 19:00:00")));
+
+        assert_eq!(
+            next_allowed(&config, at("2026-10-16 12:00:00"), download_allowed_at),
+            Some(at("2026-10-17 01:00:00"))
+        );
+        assert_eq!(
+            next_allowed(&config, at("2026-10-16 19:00:00"), scan_allowed_at),
+            Some(at("2026-10-16 23:00:00"))
+        );
+    }
+}
@@ -147,6 +147,7 @@ async fn load_video_sources_from_db(
             args: Args::Collection { collection_item },
             path: PathBuf::from(collection.path),
             source_type: SourceType::Collection,
+            priority: collection.priority,
         });
     }
 
@@ -163,6 +164,7 @@ async fn load_video_sources_from_db(
             args: Args::Favorite { fid },
             path: PathBuf::from(favorite.path),
             source_type: SourceType::Favorite,
+            priority: favorite.priority,
         });
     }
 
@@ -242,6 +244,7 @@ async fn load_video_sources_from_db(
             args: Args::Submission { upper_id },
             path: PathBuf::from(submission.path),
             source_type: SourceType::Submission,
+            priority: submission.priority,
         });
     }
 
@@ -257,6 +260,7 @@ async fn load_video_sources_from_db(
             args: Args::WatchLater,
             path: PathBuf::from(watch_later.path),
             source_type: SourceType::WatchLater,
+            priority: watch_later.priority,
         });
     }
 
@@ -277,6 +281,7 @@ async fn load_video_sources_from_db(
             },
             path: PathBuf::from(bangumi.path),
             source_type: SourceType::Bangumi,
+            priority: bangumi.priority,
         });
     }
 
@@ -543,7 +548,9 @@ pub async fn video_downloader(connection: Arc<DatabaseConnection>) {
             }
 
             // 合并新旧源，新源在前
-            let ordered_sources = [new_sources, old_sources].concat();
+            let mut ordered_sources = [new_sources, old_sources].concat();
+            // 优先级高的源先扫描，同优先级保持原有顺序
+            ordered_sources.sort_by_key(|s| std::cmp::Reverse(s.priority));
 
             // 初始化扫描收集器来统计本轮扫描结果
             let mut scan_collector = ScanCollector::new();
@@ -786,8 +793,11 @@ pub async fn video_downloader(connection: Arc<DatabaseConnection>) {
 
             info!("本轮扫描完成 - 视频源数量: {}", ordered_sources.len());
 
-            // 生成扫描摘要并发送推送通知
+            // 生成扫描摘要，通知媒体服务器刷新并发送推送通知
             let scan_summary = scan_collector.generate_summary();
+            if let Err(e) = crate::utils::media_server::refresh_media_server().await {
+                warn!("通知媒体服务器刷新失败: {:#}", e);
+            }
             if let Err(e) = crate::utils::notification::send_scan_notification(scan_summary).await {
                 warn!("发送扫描完成推送失败: {}", e);
             }
@@ -0,0 +1,97 @@
+//! 多个模块的测试共用的夹具：临时目录下的视频源和重新处理已下载分页的流程
+
+use std::path::Path;
+
+use bili_sync_entity::watch_later::Model as WatchLater;
+use bili_sync_entity::{page, video};
+use tokio::sync::Semaphore;
+use tokio_util::sync::CancellationToken;
+
+use crate::adapter::VideoSourceEnum;
+use crate::bilibili::BiliClient;
+use crate::unified_downloader::UnifiedDownloader;
+use crate::utils::status::{PageStatus, STATUS_OK};
+use crate::workflow::download_page;
+
+/// 稍后再看视频源，保存目录位于临时目录下
+pub fn test_watch_later_source(audio_only: bool, output_container: &str) -> VideoSourceEnum {
+    VideoSourceEnum::from(WatchLater {
+        id: 1,
+        path: std::env::temp_dir()
+            .join("bili-sync-template")
+            .to_string_lossy()
+            .to_string(),
+        created_at: String::new(),
+        latest_row_at: String::new(),
+        enabled: true,
+        scan_deleted_videos: false,
+        keyword_filters: None,
+        keyword_filter_mode: None,
+        blacklist_keywords: None,
+        whitelist_keywords: None,
+        keyword_case_sensitive: false,
+        audio_only,
+        audio_only_m4a_only: false,
+        flat_folder: false,
+        download_danmaku: false,
+        download_subtitle: false,
+        download_speed_limit: 0,
+        priority: 0,
+        postprocess_profile: String::new(),
+        output_container: output_container.to_string(),
+        audio_format: "m4a".to_string(),
+        audio_normalize: false,
+        feed_token: None,
+        webdav_visible: false,
+        ai_rename: false,
+        ai_rename_video_prompt: String::new(),
+        ai_rename_audio_prompt: String::new(),
+        ai_rename_enable_multi_page: false,
+        ai_rename_enable_collection: false,
+        ai_rename_enable_bangumi: false,
+    })
+}
+
+/// 以给定状态重新处理路径指向 `media` 的已下载单P视频，返回写回数据库的分页
+///
+/// 视频子任务必须已完成，分页使用占位 CID：此时不会请求播放器接口或视频流，测试无需访问网络
+pub async fn rerun_downloaded_test_page(
+    source: &VideoSourceEnum,
+    media: &Path,
+    status: PageStatus,
+) -> page::ActiveModel {
+    assert_eq!(status.get(1), STATUS_OK, "测试分页的视频子任务必须已完成");
+    let video = video::Model {
+        id: 1,
+        bvid: "BV1nWcSeeEkV".to_string(),
+        name: "模板标题".to_string(),
+        single_page: Some(true),
+        category: 2,
+        ..Default::default()
+    };
+    let page = page::Model {
+        id: 1,
+        video_id: 1,
+        pid: 1,
+        cid: 0,
+        name: "模板标题".to_string(),
+        path: Some(media.to_string_lossy().to_string()),
+        download_status: status.into(),
+        ..Default::default()
+    };
+    let connection = sea_orm::Database::connect("sqlite::memory:").await.unwrap();
+    let downloader = UnifiedDownloader::new_native(crate::bilibili::Client::new());
+    download_page(
+        &BiliClient::new(String::new()),
+        source,
+        &video,
+        page,
+        &connection,
+        &Semaphore::new(1),
+        &downloader,
+        &std::env::temp_dir().join("bili-sync-template"),
+        CancellationToken::new(),
+    )
+    .await
+    .unwrap()
+}
@@ -5,6 +5,7 @@ use tracing::{info, warn};
 use crate::aria2_downloader::Aria2Downloader;
 use crate::bilibili::Client;
 use crate::downloader::Downloader;
+use crate::utils::bandwidth::Throttle;
 
 /// 统一下载器，可以在原生下载器和aria2下载器之间切换
 pub enum UnifiedDownloader {
@@ -36,8 +37,8 @@ impl UnifiedDownloader {
             return Self::new_native(client);
         }
 
-        // 如果用户关闭了 aria2，则直接使用原生多线程分片下载
-        if !parallel.use_aria2 {
+        // 如果用户关闭了 aria2，则直接使用原生多线程分片下载；配置了外部 aria2 时视为开启
+        if !parallel.use_aria2 && !config.external_aria2.enabled {
             info!("已关闭aria2，使用原生多线程下载");
             return Self::new_native(client);
         }
@@ -57,9 +58,14 @@ impl UnifiedDownloader {
 
     /// 下载文件，支持多个URL备选
     pub async fn fetch_with_fallback(&self, urls: &[&str], path: &Path) -> Result<()> {
+        self.fetch_with_throttle(urls, path, &Throttle::default()).await
+    }
+
+    /// 下载文件，除全局限速外还遵守调用方传入的视频源限速
+    pub async fn fetch_with_throttle(&self, urls: &[&str], path: &Path, throttle: &Throttle) -> Result<()> {
         match self {
-            Self::Native(downloader) => downloader.fetch_with_fallback(urls, path).await,
-            Self::Aria2(downloader) => downloader.fetch_with_aria2_fallback(urls, path).await,
+            Self::Native(downloader) => downloader.fetch_with_fallback(urls, path, throttle).await,
+            Self::Aria2(downloader) => downloader.fetch_with_aria2_fallback(urls, path, throttle).await,
         }
     }
 
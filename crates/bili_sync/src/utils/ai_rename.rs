@@ -1,6 +1,6 @@
 use std::collections::HashMap;
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::time::Duration;
 
 use anyhow::{anyhow, Result};
@@ -16,6 +16,7 @@ use tokio::sync::Mutex;
 use tracing::{debug, info, warn};
 
 use super::deepseek_web::DeepSeekSession;
+use super::trickplay::is_trickplay_of;
 use bili_sync_entity::ai_conversation_history;
 
 /// AI 重命名上下文（从 API 获取的视频信息）
@@ -442,6 +443,31 @@ struct ChatMessageResponse {
 /// - `new_ext`: 新的主文件扩展名（用于排除已重命名的主文件）
 pub fn rename_sidecars(old: &Path, new_stem: &str, new_ext: &str) -> Result<()> {
     let parent = old.parent().ok_or_else(|| anyhow!("Invalid path"))?;
+    move_sidecars(old, parent, new_stem, new_ext)?;
+    Ok(())
+}
+
+/// 将侧车文件移动到 `new_dir` 并按新的文件名基重命名，返回实际完成的 (原路径, 新路径) 列表
+pub fn move_sidecars(old: &Path, new_dir: &Path, new_stem: &str, new_ext: &str) -> Result<Vec<(PathBuf, PathBuf)>> {
+    let mut moved = Vec::new();
+    for (path, new_path) in sidecar_moves(old, new_dir, new_stem, new_ext)? {
+        let (filename, new_filename) = (path.display(), new_path.display());
+        // 执行重命名
+        if let Err(e) = fs::rename(&path, &new_path) {
+            warn!("重命名侧车文件失败 {} -> {}: {}", filename, new_filename, e);
+        } else {
+            info!("重命名侧车文件: {} -> {}", filename, new_filename);
+            moved.push((path, new_path));
+        }
+    }
+    Ok(moved)
+}
+
+/// 计算侧车文件的移动计划但不执行，返回 (原路径, 新路径) 列表。
+/// 除同名前缀的文件外，`{stem}.trickplay` 预览图目录也会跟随移动
+pub fn sidecar_moves(old: &Path, new_dir: &Path, new_stem: &str, new_ext: &str) -> Result<Vec<(PathBuf, PathBuf)>> {
+    let parent = old.parent().ok_or_else(|| anyhow!("Invalid path"))?;
+    let mut moves = Vec::new();
     let stem = old
         .file_stem()
         .and_then(|s| s.to_str())
@@ -454,17 +480,19 @@ pub fn rename_sidecars(old: &Path, new_stem: &str, new_ext: &str) -> Result<()>
     if let Ok(entries) = fs::read_dir(parent) {
         for entry in entries.flatten() {
             let path = entry.path();
-            if !path.is_file() {
-                continue;
-            }
 
             let filename = match path.file_name().and_then(|s| s.to_str()) {
                 Some(f) => f,
                 None => continue,
             };
 
+            let is_trickplay_dir = path.is_dir() && is_trickplay_of(filename, stem);
+            if !(path.is_file() || is_trickplay_dir) {
+                continue;
+            }
+
             // 跳过刚重命名的主文件
-            if filename == new_main_filename {
+            if parent == new_dir && filename == new_main_filename {
                 continue;
             }
 
@@ -492,7 +520,7 @@ pub fn rename_sidecars(old: &Path, new_stem: &str, new_ext: &str) -> Result<()>
 
             // 构建新文件名
             let new_filename = format!("{}{}", new_stem, suffix);
-            let new_path = parent.join(&new_filename);
+            let new_path = new_dir.join(&new_filename);
 
             // 如果新路径已存在则跳过
             if new_path.exists() {
@@ -500,16 +528,11 @@ pub fn rename_sidecars(old: &Path, new_stem: &str, new_ext: &str) -> Result<()>
                 continue;
             }
 
-            // 执行重命名
-            if let Err(e) = fs::rename(&path, &new_path) {
-                warn!("重命名侧车文件失败 {} -> {}: {}", filename, new_filename, e);
-            } else {
-                info!("重命名侧车文件: {} -> {}", filename, new_filename);
-            }
+            moves.push((path, new_path));
         }
     }
 
-    Ok(())
+    Ok(moves)
 }
 
 /// 更新NFO文件中的标题标签
@@ -1031,7 +1054,7 @@ pub async fn batch_rename_history_files(
                 .unwrap_or("mp4")
                 .to_string();
 
-            let is_audio = matches!(ext.as_str(), "m4a" | "mp3" | "flac" | "aac" | "ogg");
+            let is_audio = matches!(ext.as_str(), "m4a" | "mp3" | "flac" | "aac" | "ogg" | "opus");
 
             // 根据文件类型获取排序位置
             let current_sort_index = if is_audio {
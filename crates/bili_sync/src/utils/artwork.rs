@@ -0,0 +1,403 @@
+//! 媒体库图片布局
+//!
+//! B站只提供视频封面、合集封面、番剧的竖版/横版封面和 UP 主头像，这里按 Kodi/Jellyfin 约定的文件名
+//! 把它们放到剧集根目录：poster.jpg、fanart.jpg、landscape.jpg、seasonXX-poster.jpg 以及演员头像目录。
+//! 已存在的文件不会重复下载；同一地址的图片只下载一次，其余文件通过硬链接（跨设备时复制）复用。
+//! 演员头像以 upper_path 中的 UP 主头像为唯一来源，整个媒体库中每位 UP 主只下载一次。
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Context, Result};
+use bili_sync_entity::video;
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, warn};
+
+use crate::adapter::{VideoSource, VideoSourceEnum};
+use crate::bilibili::StaffInfo;
+use crate::config::ArtworkConfig;
+use crate::error::ExecutionStatus;
+use crate::unified_downloader::UnifiedDownloader;
+use crate::utils::bangumi_name_extractor::BangumiNameExtractor;
+use crate::utils::filenamify::filenamify;
+use crate::workflow::SeasonInfo;
+
+/// 剧集根目录可用的图片地址，缺失或无效的地址会被忽略
+#[derive(Debug, Default)]
+pub struct ArtworkSources {
+    /// 竖版封面
+    pub poster: Option<String>,
+    /// 背景图
+    pub fanart: Option<String>,
+    /// 横版封面
+    pub landscape: Option<String>,
+    /// 季度编号与对应的季度封面
+    pub season_poster: Option<(u32, String)>,
+}
+
+/// 需要放入演员目录的 UP 主
+#[derive(Debug)]
+pub struct ArtworkActor {
+    pub name: String,
+    pub face: String,
+}
+
+/// 单个待写入的图片文件
+#[derive(Debug, PartialEq)]
+pub struct ArtworkItem {
+    pub target: PathBuf,
+    pub url: String,
+    /// 全媒体库共享的缓存文件，先写入缓存再链接到目标位置
+    pub shared: Option<PathBuf>,
+}
+
+fn valid_url(url: Option<&str>) -> Option<&str> {
+    url.filter(|url| url.starts_with("http"))
+}
+
+/// UP 主头像在 upper_path 中的位置，与 UP 主头像下载使用相同的目录结构
+pub fn upper_face_path(upper_path: &Path, name: &str) -> Option<PathBuf> {
+    let name = filenamify(name);
+    let first_char = name.chars().next()?.to_string();
+    Some(upper_path.join(first_char).join(name).join("folder.jpg"))
+}
+
+/// Kodi 的演员头像文件名：空格替换为下划线
+fn actor_file_name(name: &str) -> String {
+    format!("{}.jpg", filenamify(name).replace(' ', "_"))
+}
+
+/// 生成需要写入的图片清单，`series_root` 为 false 时（例如单 P 视频所在目录）只放置演员头像
+pub fn plan_artwork(
+    config: &ArtworkConfig,
+    root: &Path,
+    series_root: bool,
+    sources: &ArtworkSources,
+    actors: &[ArtworkActor],
+    upper_path: &Path,
+) -> Vec<ArtworkItem> {
+    let mut items = Vec::new();
+    if !config.enabled {
+        return items;
+    }
+    if series_root {
+        let mut push = |enabled: bool, file_name: String, url: Option<&str>| {
+            if let Some(url) = valid_url(url).filter(|_| enabled) {
+                items.push(ArtworkItem {
+                    target: root.join(file_name),
+                    url: url.to_string(),
+                    shared: None,
+                });
+            }
+        };
+        push(config.poster, "poster.jpg".to_string(), sources.poster.as_deref());
+        push(config.fanart, "fanart.jpg".to_string(), sources.fanart.as_deref());
+        push(
+            config.landscape,
+            "landscape.jpg".to_string(),
+            sources.landscape.as_deref(),
+        );
+        if let Some((season_number, url)) = &sources.season_poster {
+            push(
+                config.season_poster,
+                format!("season{:02}-poster.jpg", season_number),
+                Some(url),
+            );
+        }
+    }
+    let actors_folder = config.actors_folder.trim();
+    if config.actor_thumbs && !actors_folder.is_empty() {
+        for actor in actors {
+            let Some(url) = valid_url(Some(&actor.face)) else {
+                continue;
+            };
+            let Some(shared) = upper_face_path(upper_path, &actor.name) else {
+                continue;
+            };
+            let target = root.join(actors_folder).join(actor_file_name(&actor.name));
+            if items.iter().any(|item| item.target == target) {
+                continue;
+            }
+            items.push(ArtworkItem {
+                target,
+                url: url.to_string(),
+                shared: Some(shared),
+            });
+        }
+    }
+    items
+}
+
+/// 优先硬链接，跨设备或文件系统不支持时退回复制
+async fn link_or_copy(source: &Path, target: &Path) -> Result<()> {
+    if let Some(parent) = target.parent() {
+        tokio::fs::create_dir_all(parent).await?;
+    }
+    if tokio::fs::hard_link(source, target).await.is_err() {
+        tokio::fs::copy(source, target)
+            .await
+            .with_context(|| format!("复制图片 {} 到 {} 失败", source.display(), target.display()))?;
+    }
+    Ok(())
+}
+
+async fn write_item(
+    item: &ArtworkItem,
+    written: &HashMap<String, PathBuf>,
+    downloader: &UnifiedDownloader,
+) -> Result<PathBuf> {
+    // 本轮已经写入过相同地址的图片，直接复用
+    if let Some(source) = written.get(&item.url) {
+        link_or_copy(source, &item.target).await?;
+        return Ok(source.clone());
+    }
+    let Some(shared) = &item.shared else {
+        downloader.fetch_with_fallback(&[&item.url], &item.target).await?;
+        return Ok(item.target.clone());
+    };
+    if !shared.exists() {
+        downloader.fetch_with_fallback(&[&item.url], shared).await?;
+    }
+    link_or_copy(shared, &item.target).await?;
+    Ok(shared.clone())
+}
+
+/// 写入图片清单，单个文件失败不影响其它文件
+pub async fn write_artwork(
+    items: Vec<ArtworkItem>,
+    downloader: &UnifiedDownloader,
+    token: CancellationToken,
+) -> Result<ExecutionStatus> {
+    let mut written: HashMap<String, PathBuf> = HashMap::new();
+    let (mut success_count, mut failed_count) = (0, 0);
+    for item in items {
+        if item.target.exists() {
+            continue;
+        }
+        let result = tokio::select! {
+            biased;
+            _ = token.cancelled() => return Ok(ExecutionStatus::Cancelled),
+            res = write_item(&item, &written, downloader) => res,
+        };
+        match result {
+            Ok(source) => {
+                debug!("已写入媒体库图片: {:?}", item.target);
+                written.insert(item.url, source);
+                success_count += 1;
+            }
+            Err(e) => {
+                warn!("写入媒体库图片 {:?} 失败: {:#}", item.target, e);
+                failed_count += 1;
+            }
+        }
+    }
+    if success_count > 0 {
+        Ok(ExecutionStatus::Succeeded)
+    } else if failed_count > 0 {
+        Ok(ExecutionStatus::Ignored(anyhow!("媒体库图片全部写入失败")))
+    } else {
+        Ok(ExecutionStatus::Skipped)
+    }
+}
+
+/// 补齐一个视频的媒体库图片所需的信息
+pub struct VideoArtwork<'a> {
+    pub video_source: &'a VideoSourceEnum,
+    pub video: &'a video::Model,
+    /// 番剧的季度信息，获取失败时为 None
+    pub season_info: Option<&'a SeasonInfo>,
+    /// 视频目录
+    pub base_path: &'a Path,
+    /// 番剧系列目录或启用 Season 结构时的视频根目录
+    pub series_path: Option<&'a Path>,
+    /// 是否使用季度子目录
+    pub has_season_folder: bool,
+}
+
+fn first_url(candidates: &[&Option<String>]) -> Option<String> {
+    candidates
+        .iter()
+        .find_map(|url| url.as_deref().filter(|url| !url.is_empty()))
+        .map(str::to_string)
+}
+
+fn video_artwork_sources(artwork: &VideoArtwork<'_>, is_bangumi: bool, use_season_structure: bool) -> ArtworkSources {
+    let video_cover = Some(artwork.video.cover.clone());
+    if let Some(season) = artwork.season_info.filter(|_| is_bangumi) {
+        let season_number = if use_season_structure {
+            BangumiNameExtractor::extract_series_name_and_season(&season.title, None).1
+        } else {
+            artwork.video.season_number.unwrap_or(1) as u32
+        };
+        return ArtworkSources {
+            poster: first_url(&[&season.series_cover, &season.cover]),
+            fanart: first_url(&[
+                &season.bkg_cover,
+                &season.horizontal_cover_169,
+                &season.horizontal_cover_1610,
+                &season.cover,
+            ]),
+            landscape: first_url(&[
+                &season.horizontal_cover_169,
+                &season.horizontal_cover_1610,
+                &season.new_ep_cover,
+                &video_cover,
+            ]),
+            season_poster: first_url(&[&season.cover])
+                .filter(|_| artwork.has_season_folder)
+                .map(|url| (season_number, url)),
+        };
+    }
+    // 普通视频封面为横版，合集优先使用合集封面
+    let collection_cover = match artwork.video_source {
+        VideoSourceEnum::Collection(collection_source) => collection_source.cover.clone(),
+        _ => None,
+    };
+    let cover = first_url(&[&collection_cover, &video_cover]);
+    ArtworkSources {
+        poster: cover.clone(),
+        fanart: first_url(&[&video_cover]),
+        landscape: cover.clone(),
+        season_poster: cover.filter(|_| artwork.has_season_folder).map(|url| (1, url)),
+    }
+}
+
+/// 把 UP 主和联合投稿成员作为演员
+fn video_actors(video: &video::Model) -> Vec<ArtworkActor> {
+    let mut actors = vec![ArtworkActor {
+        name: video.upper_name.clone(),
+        face: video.upper_face.clone(),
+    }];
+    if let Some(staff_list) = video
+        .staff_info
+        .as_ref()
+        .and_then(|staff_info| serde_json::from_value::<Vec<StaffInfo>>(staff_info.clone()).ok())
+    {
+        actors.extend(staff_list.into_iter().map(|staff| ArtworkActor {
+            name: staff.name,
+            face: staff.face,
+        }));
+    }
+    actors
+}
+
+/// 按配置补齐视频所在剧集根目录的媒体库图片（fanart、landscape、季度封面、演员头像），
+/// 未启用或平铺目录模式下跳过，已存在的文件直接跳过
+pub async fn write_video_artwork(
+    artwork: VideoArtwork<'_>,
+    downloader: &UnifiedDownloader,
+    token: CancellationToken,
+) -> Result<ExecutionStatus> {
+    let config = crate::config::reload_config();
+    if !config.artwork.enabled || artwork.video_source.flat_folder() {
+        return Ok(ExecutionStatus::Skipped);
+    }
+    let is_bangumi = matches!(artwork.video_source, VideoSourceEnum::BangumiSource(_));
+    // 剧集根目录：番剧系列目录、启用Season结构时的视频根目录或多P视频目录
+    let root = artwork.series_path.unwrap_or(artwork.base_path);
+    let is_series_root = is_bangumi || artwork.series_path.is_some() || artwork.video.single_page == Some(false);
+    let sources = video_artwork_sources(&artwork, is_bangumi, config.bangumi_use_season_structure);
+    // 番剧没有UP主信息
+    let actors = if is_bangumi {
+        Vec::new()
+    } else {
+        video_actors(artwork.video)
+    };
+    let items = plan_artwork(
+        &config.artwork,
+        root,
+        is_series_root,
+        &sources,
+        &actors,
+        &config.upper_path,
+    );
+    write_artwork(items, downloader, token).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn enabled_config() -> ArtworkConfig {
+        ArtworkConfig {
+            enabled: true,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_plan_artwork_layout() {
+        let sources = ArtworkSources {
+            poster: Some("https://i0.hdslb.com/poster.jpg".to_string()),
+            fanart: Some("https://i0.hdslb.com/bkg.jpg".to_string()),
+            landscape: Some(String::new()),
+            season_poster: Some((2, "https://i0.hdslb.com/season.jpg".to_string())),
+        };
+        let actors = vec![
+            ArtworkActor {
+                name: "某 UP 主".to_string(),
+                face: "https://i0.hdslb.com/face.jpg".to_string(),
+            },
+            ArtworkActor {
+                name: "无头像".to_string(),
+                face: String::new(),
+            },
+        ];
+        let root = Path::new("/library/番剧");
+        let upper_path = Path::new("/config/upper_face");
+
+        let items = plan_artwork(&enabled_config(), root, true, &sources, &actors, upper_path);
+        let targets = items.iter().map(|item| item.target.clone()).collect::<Vec<_>>();
+        // 空地址和无效头像被跳过
+        assert_eq!(
+            targets,
+            vec![
+                root.join("poster.jpg"),
+                root.join("fanart.jpg"),
+                root.join("season02-poster.jpg"),
+                root.join(".actors/某_UP_主.jpg"),
+            ]
+        );
+        // 演员头像共享 UP 主头像缓存
+        assert_eq!(
+            items[3].shared.as_deref(),
+            Some(Path::new("/config/upper_face/某/某 UP 主/folder.jpg"))
+        );
+
+        // 非剧集根目录只放演员头像
+        let items = plan_artwork(&enabled_config(), root, false, &sources, &actors, upper_path);
+        assert_eq!(items.len(), 1);
+
+        let config = ArtworkConfig {
+            fanart: false,
+            actors_folder: String::new(),
+            ..enabled_config()
+        };
+        let items = plan_artwork(&config, root, true, &sources, &actors, upper_path);
+        assert_eq!(items.len(), 2);
+        assert!(plan_artwork(&ArtworkConfig::default(), root, true, &sources, &actors, upper_path).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_link_or_copy_shares_file() {
+        let dir = std::env::temp_dir().join(format!("bili-sync-artwork-{}", std::process::id()));
+        let shared = dir.join("upper/folder.jpg");
+        std::fs::create_dir_all(shared.parent().unwrap()).unwrap();
+        std::fs::write(&shared, b"face").unwrap();
+
+        let first = dir.join("a/.actors/up.jpg");
+        let second = dir.join("b/.actors/up.jpg");
+        link_or_copy(&shared, &first).await.unwrap();
+        link_or_copy(&shared, &second).await.unwrap();
+        assert_eq!(std::fs::read(&first).unwrap(), b"face");
+        assert_eq!(std::fs::read(&second).unwrap(), b"face");
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::MetadataExt;
+            // 同一文件系统上使用硬链接，不占用额外空间
+            assert_eq!(std::fs::metadata(&shared).unwrap().nlink(), 3);
+        }
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
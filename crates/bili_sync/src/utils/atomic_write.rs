@@ -0,0 +1,82 @@
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use tokio::fs;
+use tokio::io::AsyncWriteExt;
+
+/// 在原文件名后追加后缀，例如 `a.mp4` + `.part` -> `a.mp4.part`
+///
+/// 不使用 `with_extension`，避免 `a.zh-CN.default.ass` 这类多段扩展名被截断
+pub fn append_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut name: OsString = path.file_name().map(|n| n.to_os_string()).unwrap_or_default();
+    name.push(suffix);
+    path.with_file_name(name)
+}
+
+/// 原子写入时使用的临时文件路径
+pub fn temp_path_for(path: &Path) -> PathBuf {
+    append_suffix(path, ".tmp")
+}
+
+/// 先写入同目录下的临时文件，刷盘后再重命名到目标路径
+///
+/// 同一文件系统内的 rename 是原子的，因此进程崩溃时目标路径要么是旧内容，要么是完整的新内容，
+/// 不会出现被下一次扫描误认为已完成的半截文件
+pub async fn write_atomic(path: &Path, contents: impl AsRef<[u8]>) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        if !parent.exists() {
+            fs::create_dir_all(parent).await?;
+        }
+    }
+    let tmp_path = temp_path_for(path);
+    let write_result = async {
+        let mut file = fs::File::create(&tmp_path).await?;
+        file.write_all(contents.as_ref()).await?;
+        file.sync_all().await?;
+        Ok::<_, std::io::Error>(())
+    }
+    .await;
+    if let Err(e) = write_result {
+        let _ = fs::remove_file(&tmp_path).await;
+        return Err(e).with_context(|| format!("写入临时文件失败: {}", tmp_path.display()));
+    }
+    commit_temp(&tmp_path, path).await
+}
+
+/// 将已写好的临时文件重命名到目标路径，失败时清理临时文件
+pub async fn commit_temp(tmp_path: &Path, path: &Path) -> Result<()> {
+    if let Err(e) = fs::rename(tmp_path, path).await {
+        let _ = fs::remove_file(tmp_path).await;
+        return Err(e).with_context(|| format!("重命名 {} -> {} 失败", tmp_path.display(), path.display()));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_append_suffix_keeps_all_extensions() {
+        assert_eq!(
+            append_suffix(Path::new("/a/b/S01E01.zh-CN.default.ass"), ".tmp"),
+            PathBuf::from("/a/b/S01E01.zh-CN.default.ass.tmp")
+        );
+        assert_eq!(
+            append_suffix(Path::new("video.mp4"), ".part"),
+            PathBuf::from("video.mp4.part")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_write_atomic_replaces_target() {
+        let dir = std::env::temp_dir().join(format!("bili-sync-atomic-{}", std::process::id()));
+        let target = dir.join("test.nfo");
+        write_atomic(&target, "first").await.unwrap();
+        write_atomic(&target, "second").await.unwrap();
+        assert_eq!(fs::read_to_string(&target).await.unwrap(), "second");
+        assert!(!temp_path_for(&target).exists());
+        let _ = fs::remove_dir_all(&dir).await;
+    }
+}
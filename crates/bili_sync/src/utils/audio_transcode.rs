@@ -0,0 +1,340 @@
+//! 仅音频模式的转码和响度标准化
+//!
+//! 下载得到的是 B 站的 DASH 音频流（AAC、杜比 E-AC-3 或 Hi-Res 无损 FLAC），按视频源设置转为其它格式，
+//! 并可使用 ffmpeg 的 loudnorm 滤镜按 EBU R128 两遍测量后统一响度。无损流不会被转为有损格式。
+
+use std::ffi::OsStr;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context, Result};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use tokio::fs;
+
+use crate::bilibili::AudioQuality;
+use crate::config::LoudnessConfig;
+use crate::utils::atomic_write::{commit_temp, temp_path_for};
+use crate::utils::ffmpeg::run_ffmpeg;
+use crate::utils::mp4_box::{read_top_box, scan_top_level};
+
+/// 仅音频模式的输出格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AudioFormat {
+    /// 保留下载的原始音频
+    M4a,
+    /// LAME V0 可变码率
+    Mp3,
+    Opus,
+    /// 仅对 Hi-Res 无损流生效
+    Flac,
+}
+
+impl AudioFormat {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "m4a" => Some(Self::M4a),
+            "mp3" => Some(Self::Mp3),
+            "opus" => Some(Self::Opus),
+            "flac" => Some(Self::Flac),
+            _ => None,
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::M4a => "m4a",
+            Self::Mp3 => "mp3",
+            Self::Opus => "opus",
+            Self::Flac => "flac",
+        }
+    }
+
+    /// 写入临时文件时无法从扩展名推断封装格式，需要显式指定
+    fn muxer(self) -> &'static str {
+        match self {
+            Self::M4a => "ipod",
+            Self::Mp3 => "mp3",
+            Self::Opus => "ogg",
+            Self::Flac => "flac",
+        }
+    }
+}
+
+/// 视频源的音频输出设置
+#[derive(Debug, Clone, Copy)]
+pub struct AudioOutput {
+    pub format: AudioFormat,
+    pub normalize: bool,
+}
+
+/// 根据第一条音轨的采样格式判断下载到的音质，只区分 Hi-Res 无损和杜比，普通 AAC 返回 None
+pub fn detect_quality(path: &Path) -> Result<Option<AudioQuality>> {
+    let mut reader = BufReader::new(File::open(path)?);
+    let tops = scan_top_level(&mut reader)?;
+    let moov_top = tops
+        .iter()
+        .find(|top| &top.kind == b"moov")
+        .context("文件中没有 moov")?;
+    let moov = read_top_box(&mut reader, moov_top)?;
+    for trak in moov.children_of(b"trak") {
+        let mdia = trak.child(b"mdia")?;
+        // hdlr：version/flags、pre_defined，之后是 handler_type
+        if mdia.child(b"hdlr")?.data()?.get(8..12) != Some(b"soun") {
+            continue;
+        }
+        let stbl = mdia.child(b"minf")?.child(b"stbl")?;
+        // stsd：version/flags、条目数，之后是第一个采样条目的大小和类型
+        let stsd = stbl.child(b"stsd")?.data()?;
+        let kind = stsd.get(12..16).context("stsd 数据不完整")?;
+        return Ok(match kind {
+            b"fLaC" => Some(AudioQuality::QualityHiRES),
+            b"ec-3" | b"ac-3" => Some(AudioQuality::QualityDolby),
+            _ => None,
+        });
+    }
+    bail!("文件中没有音轨")
+}
+
+/// 决定实际输出的格式，返回 None 表示无需处理
+///
+/// 无损流请求 mp3/opus 时改为 flac，避免不必要的有损转码；有损流请求 flac 时保留 m4a，转为无损只会增大体积
+fn target_format(requested: AudioFormat, quality: Option<AudioQuality>, normalize: bool) -> Option<AudioFormat> {
+    let lossless = quality == Some(AudioQuality::QualityHiRES);
+    let format = match requested {
+        AudioFormat::Mp3 | AudioFormat::Opus if lossless => AudioFormat::Flac,
+        AudioFormat::Flac if !lossless => AudioFormat::M4a,
+        other => other,
+    };
+    (format != AudioFormat::M4a || normalize).then_some(format)
+}
+
+fn codec_args(format: AudioFormat, lossless: bool, normalize: bool) -> &'static [&'static str] {
+    match format {
+        AudioFormat::Mp3 => &["-c:a", "libmp3lame", "-q:a", "0", "-ac", "2", "-id3v2_version", "3"],
+        AudioFormat::Opus => &["-c:a", "libopus", "-b:a", "192k"],
+        // 无损流只换封装时直接复制
+        AudioFormat::Flac if lossless && !normalize => &["-c:a", "copy"],
+        AudioFormat::Flac => &["-c:a", "flac"],
+        // m4a 只有响度标准化时才需要重新编码，无损流使用 ALAC 保持无损
+        AudioFormat::M4a if lossless => &["-c:a", "alac"],
+        AudioFormat::M4a => &["-c:a", "aac", "-b:a", "256k"],
+    }
+}
+
+fn ffmpeg_args(input: &Path, output: &Path, format: AudioFormat, codec: &[&str], filter: Option<&str>) -> Vec<String> {
+    let mut args: Vec<String> = ["-hide_banner", "-y", "-i"].map(String::from).into();
+    args.push(input.to_string_lossy().into_owned());
+    args.extend(["-map", "0:a:0"].map(String::from));
+    // 封面在 m4a 中作为附加图片流存在，ogg 封装不支持图片流
+    if format != AudioFormat::Opus {
+        args.extend(["-map", "0:v?", "-c:v", "copy", "-disposition:v", "attached_pic"].map(String::from));
+    }
+    args.extend(["-map_metadata", "0", "-map_chapters", "0"].map(String::from));
+    if let Some(filter) = filter {
+        args.extend(["-af".to_string(), filter.to_string()]);
+    }
+    args.extend(codec.iter().map(|arg| arg.to_string()));
+    args.extend(["-f".to_string(), format.muxer().to_string()]);
+    args.push(output.to_string_lossy().into_owned());
+    args
+}
+
+/// 第一遍测量得到的响度数据
+#[derive(Debug, PartialEq)]
+struct LoudnessMeasurement {
+    input_i: f64,
+    input_tp: f64,
+    input_lra: f64,
+    input_thresh: f64,
+    target_offset: f64,
+    sample_rate: Option<u32>,
+}
+
+/// ffmpeg 输出中音频流的采样率
+static SAMPLE_RATE_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"Audio: [^\n]*?, (\d+) Hz").expect("invalid regex"));
+
+/// 从 loudnorm 第一遍的输出中解析测量结果，静音等无法测量的情况返回 None
+fn parse_measurement(stderr: &str) -> Option<LoudnessMeasurement> {
+    let start = stderr.rfind('{')?;
+    let end = stderr[start..].find('}')? + start;
+    let json: serde_json::Value = serde_json::from_str(&stderr[start..=end]).ok()?;
+    let field = |name: &str| {
+        json[name]
+            .as_str()?
+            .trim()
+            .parse::<f64>()
+            .ok()
+            .filter(|value| value.is_finite())
+    };
+    // loudnorm 会把音频重采样到 192kHz，需要在输出前恢复原始采样率
+    let sample_rate = SAMPLE_RATE_RE.captures(stderr).and_then(|caps| caps[1].parse().ok());
+    Some(LoudnessMeasurement {
+        input_i: field("input_i")?,
+        input_tp: field("input_tp")?,
+        input_lra: field("input_lra")?,
+        input_thresh: field("input_thresh")?,
+        target_offset: field("target_offset")?,
+        sample_rate,
+    })
+}
+
+fn loudnorm_target(config: &LoudnessConfig) -> String {
+    format!(
+        "loudnorm=I={}:TP={}:LRA={}",
+        config.integrated, config.true_peak, config.range
+    )
+}
+
+fn normalize_filter(config: &LoudnessConfig, measured: &LoudnessMeasurement) -> String {
+    let mut filter = format!(
+        "{}:measured_I={}:measured_TP={}:measured_LRA={}:measured_thresh={}:offset={}:linear=true",
+        loudnorm_target(config),
+        measured.input_i,
+        measured.input_tp,
+        measured.input_lra,
+        measured.input_thresh,
+        measured.target_offset
+    );
+    if let Some(rate) = measured.sample_rate {
+        filter.push_str(&format!(",aresample={}", rate));
+    }
+    filter
+}
+
+/// 使用 loudnorm 测量音频响度（第一遍）
+async fn measure_loudness(input: &Path, config: &LoudnessConfig) -> Result<Option<LoudnessMeasurement>> {
+    let filter = format!("{}:print_format=json", loudnorm_target(config));
+    let stderr = run_ffmpeg([
+        OsStr::new("-hide_banner"),
+        OsStr::new("-nostats"),
+        OsStr::new("-i"),
+        input.as_os_str(),
+        OsStr::new("-map"),
+        OsStr::new("0:a:0"),
+        OsStr::new("-af"),
+        OsStr::new(&filter),
+        OsStr::new("-f"),
+        OsStr::new("null"),
+        OsStr::new("-"),
+    ])
+    .await?;
+    Ok(parse_measurement(&stderr))
+}
+
+/// 按视频源设置处理仅音频模式下载的文件，返回处理后的路径，无需处理时返回 None
+///
+/// 输出先写入临时文件，成功后替换；扩展名改变时删除原始文件
+pub async fn process_audio(input: &Path, output: &AudioOutput, loudness: &LoudnessConfig) -> Result<Option<PathBuf>> {
+    let probe_path = input.to_path_buf();
+    let quality = tokio::task::spawn_blocking(move || detect_quality(&probe_path)).await??;
+    let Some(format) = target_format(output.format, quality, output.normalize) else {
+        return Ok(None);
+    };
+    let lossless = quality == Some(AudioQuality::QualityHiRES);
+
+    let filter = if output.normalize {
+        match measure_loudness(input, loudness).await? {
+            Some(measured) => Some(normalize_filter(loudness, &measured)),
+            None => {
+                tracing::warn!("无法测量 {} 的响度（可能是静音），跳过响度标准化", input.display());
+                None
+            }
+        }
+    } else {
+        None
+    };
+    // 测量失败且格式不变时无需重新编码
+    if filter.is_none() && format == AudioFormat::M4a {
+        return Ok(None);
+    }
+
+    let target = input.with_extension(format.extension());
+    let tmp_output = temp_path_for(&target);
+    let codec = codec_args(format, lossless, filter.is_some());
+    if let Err(e) = run_ffmpeg(ffmpeg_args(input, &tmp_output, format, codec, filter.as_deref())).await {
+        let _ = fs::remove_file(&tmp_output).await;
+        return Err(e);
+    }
+    commit_temp(&tmp_output, &target).await?;
+    if target != input {
+        fs::remove_file(input)
+            .await
+            .with_context(|| format!("删除原始音频 {} 失败", input.display()))?;
+    }
+    Ok(Some(target))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_target_format() {
+        let hires = Some(AudioQuality::QualityHiRES);
+        // 无损流不转为有损格式
+        assert_eq!(target_format(AudioFormat::Mp3, hires, false), Some(AudioFormat::Flac));
+        assert_eq!(target_format(AudioFormat::Opus, hires, true), Some(AudioFormat::Flac));
+        // 有损流请求 flac 时保留 m4a
+        assert_eq!(target_format(AudioFormat::Flac, None, false), None);
+        assert_eq!(target_format(AudioFormat::Flac, None, true), Some(AudioFormat::M4a));
+        assert_eq!(target_format(AudioFormat::Mp3, None, false), Some(AudioFormat::Mp3));
+        assert_eq!(
+            target_format(AudioFormat::Opus, Some(AudioQuality::QualityDolby), false),
+            Some(AudioFormat::Opus)
+        );
+        assert_eq!(target_format(AudioFormat::M4a, hires, false), None);
+    }
+
+    #[test]
+    fn test_parse_measurement() {
+        let stderr = r#"Stream #0:0[0x1](und): Audio: flac (fLaC / 0x43614C66), 96000 Hz, stereo, s32 (24 bit)
+[Parsed_loudnorm_0 @ 0x55d5]
+{
+	"input_i" : "-9.82",
+	"input_tp" : "0.41",
+	"input_lra" : "5.60",
+	"input_thresh" : "-19.95",
+	"output_i" : "-15.95",
+	"output_tp" : "-1.50",
+	"output_lra" : "4.90",
+	"output_thresh" : "-26.05",
+	"normalization_type" : "dynamic",
+	"target_offset" : "-0.05"
+}"#;
+        let measured = parse_measurement(stderr).unwrap();
+        assert_eq!(measured.input_i, -9.82);
+        assert_eq!(measured.target_offset, -0.05);
+        assert_eq!(measured.sample_rate, Some(96000));
+        let filter = normalize_filter(&LoudnessConfig::default(), &measured);
+        assert!(filter.starts_with("loudnorm=I=-16:TP=-1.5:LRA=11:measured_I=-9.82:"));
+        assert!(filter.ends_with(":linear=true,aresample=96000"));
+        // 静音时 loudnorm 输出 -inf
+        assert!(parse_measurement(&stderr.replace("-9.82", "-inf")).is_none());
+    }
+
+    #[test]
+    fn test_ffmpeg_args() {
+        let args = ffmpeg_args(
+            Path::new("/a/b.m4a"),
+            Path::new("/a/b.mp3.tmp"),
+            AudioFormat::Mp3,
+            codec_args(AudioFormat::Mp3, false, false),
+            None,
+        )
+        .join(" ");
+        assert!(args.starts_with("-hide_banner -y -i /a/b.m4a -map 0:a:0 -map 0:v? -c:v copy"));
+        assert!(args.ends_with("-c:a libmp3lame -q:a 0 -ac 2 -id3v2_version 3 -f mp3 /a/b.mp3.tmp"));
+        let args = ffmpeg_args(
+            Path::new("/a/b.m4a"),
+            Path::new("/a/b.opus.tmp"),
+            AudioFormat::Opus,
+            codec_args(AudioFormat::Opus, false, true),
+            Some("loudnorm"),
+        )
+        .join(" ");
+        assert!(!args.contains("0:v?"));
+        assert!(args.contains("-af loudnorm -c:a libopus"));
+    }
+}
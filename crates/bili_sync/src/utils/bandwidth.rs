@@ -0,0 +1,191 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use futures::{Stream, StreamExt};
+use leaky_bucket::RateLimiter;
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use tokio::io::{AsyncWrite, AsyncWriteExt};
+
+/// 令牌桶的补充间隔，间隔越小速度曲线越平滑
+const REFILL_INTERVAL_MS: u64 = 100;
+
+/// 按字节计数的令牌桶，一个令牌对应一个字节
+pub struct SpeedLimiter {
+    kbps: u64,
+    /// 单次 acquire 的上限，等于桶容量
+    max_chunk: usize,
+    limiter: RateLimiter,
+    /// 正在进行的 aria2 下载任务数，aria2 无法共用令牌桶，这些任务平分限速值
+    external_tasks: AtomicUsize,
+}
+
+impl SpeedLimiter {
+    pub fn new(kbps: u64) -> Self {
+        let bytes_per_sec = (kbps.max(1) * 1024) as usize;
+        let refill = (bytes_per_sec * REFILL_INTERVAL_MS as usize / 1000).max(1);
+        Self {
+            kbps,
+            // 桶容量为一秒的流量，允许短时突发但不会长时间超速
+            max_chunk: bytes_per_sec,
+            limiter: RateLimiter::builder()
+                .max(bytes_per_sec)
+                .initial(refill)
+                .refill(refill)
+                .interval(Duration::from_millis(REFILL_INTERVAL_MS))
+                .build(),
+            external_tasks: AtomicUsize::new(0),
+        }
+    }
+
+    pub fn kbps(&self) -> u64 {
+        self.kbps
+    }
+
+    /// 消耗指定字节数的令牌，令牌不足时等待
+    pub async fn consume(&self, bytes: usize) {
+        let mut remaining = bytes;
+        while remaining > 0 {
+            let chunk = remaining.min(self.max_chunk);
+            self.limiter.acquire(chunk).await;
+            remaining -= chunk;
+        }
+    }
+}
+
+/// 各视频源独立的限速器，同一视频源的所有下载共用一个令牌桶
+static SOURCE_LIMITERS: Lazy<Mutex<HashMap<String, Arc<SpeedLimiter>>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// 获取视频源对应的限速器，限速值变化时重新创建，0 表示不限速
+pub fn source_limiter(source_key: &str, kbps: u64) -> Option<Arc<SpeedLimiter>> {
+    let mut limiters = SOURCE_LIMITERS.lock();
+    if kbps == 0 {
+        limiters.remove(source_key);
+        return None;
+    }
+    if let Some(limiter) = limiters.get(source_key) {
+        if limiter.kbps() == kbps {
+            return Some(limiter.clone());
+        }
+    }
+    let limiter = Arc::new(SpeedLimiter::new(kbps));
+    limiters.insert(source_key.to_string(), limiter.clone());
+    Some(limiter)
+}
+
+/// aria2 下载任务对视频源限速的占用，离开作用域时释放
+pub struct ExternalTask(Option<Arc<SpeedLimiter>>);
+
+impl Drop for ExternalTask {
+    fn drop(&mut self) {
+        if let Some(limiter) = &self.0 {
+            limiter.external_tasks.fetch_sub(1, Ordering::Relaxed);
+        }
+    }
+}
+
+/// 一次下载需要遵守的限速：全局限速（随配置热重载）与可选的视频源限速
+#[derive(Clone, Default)]
+pub struct Throttle {
+    source: Option<Arc<SpeedLimiter>>,
+}
+
+impl Throttle {
+    pub fn with_source(source: Option<Arc<SpeedLimiter>>) -> Self {
+        Self { source }
+    }
+
+    /// 登记一个由 aria2 执行的下载任务，同一视频源正在进行的 aria2 任务平分视频源限速
+    pub fn register_external_task(&self) -> ExternalTask {
+        if let Some(limiter) = &self.source {
+            limiter.external_tasks.fetch_add(1, Ordering::Relaxed);
+        }
+        ExternalTask(self.source.clone())
+    }
+
+    /// 每个 aria2 任务当前分到的视频源限速（KB/s），0 表示未设置；任务数变化后需要重新获取
+    pub fn external_task_kbps(&self) -> u64 {
+        self.source.as_ref().map_or(0, |limiter| {
+            let tasks = limiter.external_tasks.load(Ordering::Relaxed).max(1) as u64;
+            (limiter.kbps() / tasks).max(1)
+        })
+    }
+
+    pub async fn consume(&self, bytes: usize) {
+        // 每次都从配置包读取全局限速器，修改配置后正在进行的下载也会立即生效
+        let global = crate::config::with_config(|bundle| bundle.bandwidth_limiter.clone());
+        if let Some(global) = global {
+            global.consume(bytes).await;
+        }
+        if let Some(source) = &self.source {
+            source.consume(bytes).await;
+        }
+    }
+}
+
+/// 将响应流写入文件，同时按限速消耗令牌
+pub async fn copy_throttled<S, B, E, W>(mut stream: S, writer: &mut W, throttle: &Throttle) -> std::io::Result<u64>
+where
+    S: Stream<Item = Result<B, E>> + Unpin,
+    B: AsRef<[u8]>,
+    E: Into<Box<dyn std::error::Error + Send + Sync>>,
+    W: AsyncWrite + Unpin,
+{
+    let mut written = 0u64;
+    while let Some(chunk) = stream.next().await {
+        let chunk = chunk.map_err(std::io::Error::other)?;
+        let chunk = chunk.as_ref();
+        throttle.consume(chunk.len()).await;
+        writer.write_all(chunk).await?;
+        written += chunk.len() as u64;
+    }
+    Ok(written)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_source_limiter_reuse_and_reset() {
+        let first = source_limiter("test_source", 512).unwrap();
+        let second = source_limiter("test_source", 512).unwrap();
+        assert!(Arc::ptr_eq(&first, &second));
+
+        let changed = source_limiter("test_source", 1024).unwrap();
+        assert!(!Arc::ptr_eq(&first, &changed));
+        assert_eq!(changed.kbps(), 1024);
+
+        assert!(source_limiter("test_source", 0).is_none());
+        assert!(SOURCE_LIMITERS.lock().get("test_source").is_none());
+    }
+
+    #[test]
+    fn test_external_tasks_share_source_limit() {
+        let throttle = Throttle::with_source(Some(Arc::new(SpeedLimiter::new(900))));
+        let first = throttle.register_external_task();
+        assert_eq!(throttle.external_task_kbps(), 900);
+        let second = throttle.register_external_task();
+        let third = throttle.register_external_task();
+        assert_eq!(throttle.external_task_kbps(), 300);
+        drop(first);
+        drop(second);
+        assert_eq!(throttle.external_task_kbps(), 900);
+        drop(third);
+        assert_eq!(Throttle::default().external_task_kbps(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_copy_throttled_writes_all_bytes() {
+        let chunks: Vec<Result<Vec<u8>, std::io::Error>> = vec![Ok(vec![1u8; 3000]), Ok(vec![2u8; 2000])];
+        let throttle = Throttle::with_source(Some(Arc::new(SpeedLimiter::new(1024))));
+        let mut output = Vec::new();
+        let written = copy_throttled(futures::stream::iter(chunks), &mut output, &throttle)
+            .await
+            .unwrap();
+        assert_eq!(written, 5000);
+        assert_eq!(output.len(), 5000);
+    }
+}
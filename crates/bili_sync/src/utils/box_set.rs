@@ -0,0 +1,115 @@
+//! 媒体服务器合集
+//!
+//! 收藏夹和合集在 bili-sync 中本身就是一组视频，NFO 中写入 set 后媒体服务器会把它们归为一个合集。
+//! 配置了合集文件夹目录时，还会为每个收藏夹/合集生成 `<名称>/poster.jpg` 和合集 NFO，
+//! 对应 Kodi 的电影合集信息文件夹以及 Jellyfin/Emby 的合集元数据。
+
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+use tokio_util::sync::CancellationToken;
+use tracing::debug;
+
+use crate::adapter::VideoSourceEnum;
+use crate::config::{NFOConfig, NFOProfile};
+use crate::unified_downloader::UnifiedDownloader;
+use crate::utils::atomic_write::write_atomic;
+use crate::utils::filenamify::filenamify;
+use crate::utils::nfo::{CollectionSet, NFO};
+
+/// 视频源对应的合集，只有收藏夹和合集有天然的分组，未启用时返回 None
+pub fn collection_set(video_source: &VideoSourceEnum) -> Option<CollectionSet> {
+    if !crate::config::with_config(|bundle| bundle.config.nfo_config.collection_set) {
+        return None;
+    }
+    source_collection_set(video_source)
+}
+
+fn source_collection_set(video_source: &VideoSourceEnum) -> Option<CollectionSet> {
+    let (name, overview) = match video_source {
+        VideoSourceEnum::Favorite(favorite) => (&favorite.name, &favorite.description),
+        VideoSourceEnum::Collection(collection) => (&collection.name, &collection.description),
+        _ => return None,
+    };
+    Some(CollectionSet {
+        name: name.clone(),
+        overview: overview.clone().filter(|overview| !overview.trim().is_empty()),
+    })
+}
+
+fn source_cover(video_source: &VideoSourceEnum) -> Option<&str> {
+    match video_source {
+        VideoSourceEnum::Favorite(favorite) => favorite.cover.as_deref(),
+        VideoSourceEnum::Collection(collection) => collection.cover.as_deref(),
+        _ => None,
+    }
+    .filter(|cover| cover.starts_with("http"))
+}
+
+/// Kodi 读取 set.nfo，Jellyfin/Emby 的合集元数据文件为 collection.nfo
+fn box_set_nfo_name(config: &NFOConfig) -> &'static str {
+    match config.profile {
+        NFOProfile::Kodi => "set.nfo",
+        _ => "collection.nfo",
+    }
+}
+
+/// 合集文件夹使用与 NFO 中 set 名称一致的文件夹名，Kodi 按名称匹配合集图片
+fn box_set_dir(box_set_path: &Path, collection_set: &CollectionSet) -> PathBuf {
+    box_set_path.join(filenamify(&collection_set.name))
+}
+
+/// 生成合集文件夹：NFO 内容变化时重写，封面只在缺失时下载
+pub async fn write_box_set(
+    video_source: &VideoSourceEnum,
+    downloader: &UnifiedDownloader,
+    token: CancellationToken,
+) -> Result<()> {
+    let config = crate::config::with_config(|bundle| bundle.config.nfo_config.clone());
+    let box_set_path = config.box_set_path.trim();
+    if !config.enabled || box_set_path.is_empty() {
+        return Ok(());
+    }
+    let Some(collection_set) = collection_set(video_source) else {
+        return Ok(());
+    };
+    let dir = box_set_dir(Path::new(box_set_path), &collection_set);
+    let nfo_path = dir.join(box_set_nfo_name(&config));
+    let content = NFO::BoxSet(collection_set).generate_nfo().await?;
+    if tokio::fs::read_to_string(&nfo_path).await.ok().as_deref() != Some(content.as_str()) {
+        write_atomic(&nfo_path, content).await?;
+        debug!("已更新合集文件夹 NFO: {:?}", nfo_path);
+    }
+    let poster_path = dir.join("poster.jpg");
+    if let Some(cover) = source_cover(video_source).filter(|_| !poster_path.exists()) {
+        let urls = vec![cover];
+        tokio::select! {
+            biased;
+            _ = token.cancelled() => return Ok(()),
+            res = downloader.fetch_with_fallback(&urls, &poster_path) => res?,
+        }
+        debug!("已下载合集文件夹封面: {:?}", poster_path);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_box_set_layout() {
+        let collection_set = CollectionSet {
+            name: "收藏夹/稍后看".to_string(),
+            overview: None,
+        };
+        // 名称中的路径分隔符不能产生子目录
+        let dir = box_set_dir(Path::new("/library/collections"), &collection_set);
+        assert_eq!(dir.parent(), Some(Path::new("/library/collections")));
+
+        let mut config = NFOConfig::default();
+        assert_eq!(box_set_nfo_name(&config), "set.nfo");
+        config.profile = NFOProfile::Jellyfin;
+        assert_eq!(box_set_nfo_name(&config), "collection.nfo");
+    }
+}
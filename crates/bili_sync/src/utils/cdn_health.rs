@@ -0,0 +1,372 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+use chrono::NaiveDateTime;
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use serde::Serialize;
+use tracing::{info, warn};
+use utoipa::ToSchema;
+
+use crate::config::CdnHealthConfig;
+use crate::utils::time_format::{now_naive, STANDARD_TIME_FORMAT};
+
+/// 滑动平均的权重，越大越偏向最近的样本
+const EWMA_ALPHA: f64 = 0.3;
+
+/// 没有吞吐量数据时使用的中性值（MB/s），只用于未测速节点之间的比较
+const NEUTRAL_THROUGHPUT_MBPS: f64 = 1.0;
+
+/// 单个 CDN 节点的统计数据
+#[derive(Debug, Clone, Default)]
+struct HostStats {
+    successes: u64,
+    failures: u64,
+    consecutive_failures: u32,
+    /// 平均吞吐量（字节/秒）
+    throughput: Option<f64>,
+    /// 平均首字节时间（毫秒）
+    ttfb_ms: Option<f64>,
+    last_error: Option<String>,
+    blacklisted_until: Option<NaiveDateTime>,
+}
+
+fn ewma(previous: Option<f64>, sample: f64) -> f64 {
+    match previous {
+        Some(previous) => previous * (1.0 - EWMA_ALPHA) + sample * EWMA_ALPHA,
+        None => sample,
+    }
+}
+
+impl HostStats {
+    fn is_blacklisted(&self, now: NaiveDateTime) -> bool {
+        self.blacklisted_until.is_some_and(|until| until > now)
+    }
+
+    /// 排序分组：已测速的节点最优先，未测速的节点在其后作为备选，黑名单中的节点排到最后
+    ///
+    /// 未测速节点的评分来自中性吞吐量，不能与实测数据直接比较，否则会排在比中性值慢的已测速节点前面
+    fn tier(&self, now: NaiveDateTime) -> u8 {
+        if self.is_blacklisted(now) {
+            2
+        } else if self.throughput.is_none() {
+            1
+        } else {
+            0
+        }
+    }
+
+    /// 综合评分，越高越优先：平滑后的成功率 × 吞吐量(MB/s) ÷ (1 + 首字节秒数)
+    fn score(&self) -> f64 {
+        let success_rate = (self.successes as f64 + 1.0) / ((self.successes + self.failures) as f64 + 2.0);
+        let throughput_mbps = self
+            .throughput
+            .map_or(NEUTRAL_THROUGHPUT_MBPS, |bytes| bytes / 1024.0 / 1024.0);
+        let ttfb_secs = self.ttfb_ms.unwrap_or_default() / 1000.0;
+        success_rate * throughput_mbps / (1.0 + ttfb_secs)
+    }
+}
+
+#[derive(Default)]
+struct CdnHealth {
+    hosts: HashMap<String, HostStats>,
+}
+
+impl CdnHealth {
+    fn record_success(&mut self, host: &str, bytes: u64, elapsed: Duration) {
+        let stats = self.hosts.entry(host.to_string()).or_default();
+        stats.successes += 1;
+        stats.consecutive_failures = 0;
+        stats.blacklisted_until = None;
+        // 太小的文件测不出有意义的速度
+        if bytes >= 256 * 1024 && !elapsed.is_zero() {
+            stats.throughput = Some(ewma(stats.throughput, bytes as f64 / elapsed.as_secs_f64()));
+        }
+    }
+
+    fn record_first_byte(&mut self, host: &str, elapsed: Duration) {
+        let stats = self.hosts.entry(host.to_string()).or_default();
+        stats.ttfb_ms = Some(ewma(stats.ttfb_ms, elapsed.as_secs_f64() * 1000.0));
+    }
+
+    /// 记录一次失败，返回是否因此进入黑名单
+    fn record_failure(&mut self, host: &str, error: &str, config: &CdnHealthConfig, now: NaiveDateTime) -> bool {
+        let stats = self.hosts.entry(host.to_string()).or_default();
+        stats.failures += 1;
+        stats.consecutive_failures += 1;
+        stats.last_error = Some(error.to_string());
+        if stats.consecutive_failures >= config.blacklist_threshold.max(1) && !stats.is_blacklisted(now) {
+            stats.blacklisted_until = Some(now + chrono::Duration::minutes(config.blacklist_minutes as i64));
+            return true;
+        }
+        false
+    }
+
+    /// 按健康状况对链接做稳定排序：先按分组，同一分组内按评分从高到低
+    fn sort_urls(&self, urls: &mut [&str], now: NaiveDateTime) {
+        let neutral = HostStats::default();
+        let key = |url: &&str| {
+            let stats = host_of(url).and_then(|host| self.hosts.get(host)).unwrap_or(&neutral);
+            (stats.tier(now), -stats.score())
+        };
+        urls.sort_by(|a, b| {
+            let (a, b) = (key(a), key(b));
+            a.0.cmp(&b.0).then(a.1.total_cmp(&b.1))
+        });
+    }
+}
+
+static CDN_HEALTH: Lazy<Mutex<CdnHealth>> = Lazy::new(|| Mutex::new(CdnHealth::default()));
+
+fn config() -> CdnHealthConfig {
+    crate::config::with_config(|bundle| bundle.config.cdn_health.clone())
+}
+
+/// 提取链接中的主机名，统计按主机聚合
+pub fn host_of(url: &str) -> Option<&str> {
+    let rest = url.split_once("://").map_or(url, |(_, rest)| rest);
+    let authority = rest.split(['/', '?', '#']).next()?;
+    let host = authority.rsplit_once('@').map_or(authority, |(_, host)| host);
+    let host = host.split(':').next()?;
+    (!host.is_empty()).then_some(host)
+}
+
+/// 收到的数据量与服务端声明的大小不一致，通常是节点提前断开了连接
+#[derive(Debug)]
+pub struct IncompleteBody {
+    pub received: u64,
+    pub expected: u64,
+}
+
+impl std::fmt::Display for IncompleteBody {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "received {} bytes, expected {} bytes", self.received, self.expected)
+    }
+}
+
+impl std::error::Error for IncompleteBody {}
+
+/// 错误是否由网络或服务端引起（而不是本地磁盘等问题），只有这类错误计入节点的失败次数
+///
+/// 读取响应体时的错误被包装在 `io::Error` 中，而 `io::Error::source()` 会跳过被包装的错误本身，需要单独取出判断
+pub fn is_host_error(err: &anyhow::Error) -> bool {
+    err.chain().any(|cause| {
+        cause.is::<reqwest::Error>()
+            || cause.is::<IncompleteBody>()
+            || cause
+                .downcast_ref::<std::io::Error>()
+                .and_then(|e| e.get_ref())
+                .is_some_and(|inner| inner.is::<reqwest::Error>() || inner.is::<IncompleteBody>())
+    })
+}
+
+/// 记录一次成功的下载，`bytes` 为本次实际传输的字节数
+pub fn record_success(url: &str, bytes: u64, elapsed: Duration) {
+    if let Some(host) = host_of(url) {
+        CDN_HEALTH.lock().record_success(host, bytes, elapsed);
+    }
+}
+
+/// 记录从发出请求到收到响应头的耗时
+pub fn record_first_byte(url: &str, elapsed: Duration) {
+    if let Some(host) = host_of(url) {
+        CDN_HEALTH.lock().record_first_byte(host, elapsed);
+    }
+}
+
+/// 记录一次由节点导致的失败，连续失败过多时暂时拉黑该节点
+pub fn record_failure(url: &str, error: &str) {
+    let Some(host) = host_of(url) else {
+        return;
+    };
+    let config = config();
+    if CDN_HEALTH.lock().record_failure(host, error, &config, now_naive()) {
+        warn!(
+            "CDN节点 {} 连续失败 {} 次，暂停使用 {} 分钟: {}",
+            host, config.blacklist_threshold, config.blacklist_minutes, error
+        );
+    }
+}
+
+/// 按节点健康状况调整下载链接的顺序，未启用时保持原顺序
+pub fn sort_urls(urls: &mut [&str]) {
+    if urls.len() < 2 || !config().enabled {
+        return;
+    }
+    CDN_HEALTH.lock().sort_urls(urls, now_naive());
+}
+
+/// 清空统计数据和黑名单
+pub fn reset() {
+    CDN_HEALTH.lock().hosts.clear();
+    info!("CDN节点统计已清空");
+}
+
+/// 单个 CDN 节点的统计信息
+#[derive(Serialize, ToSchema, Clone, Debug)]
+pub struct CdnHostStats {
+    pub host: String,
+    pub successes: u64,
+    pub failures: u64,
+    /// 失败率（0~1）
+    pub error_rate: f64,
+    /// 平均吞吐量（KB/s）
+    pub throughput_kbps: Option<u64>,
+    /// 平均首字节时间（毫秒）
+    pub ttfb_ms: Option<u64>,
+    pub score: f64,
+    pub blacklisted: bool,
+    /// 黑名单解除时间
+    pub blacklisted_until: Option<String>,
+    pub last_error: Option<String>,
+}
+
+/// CDN 节点健康状况，与下载时的优先顺序一致
+#[derive(Serialize, ToSchema, Clone, Debug)]
+pub struct CdnHealthReport {
+    pub enabled: bool,
+    pub hosts: Vec<CdnHostStats>,
+}
+
+pub fn health_report() -> CdnHealthReport {
+    let now = now_naive();
+    let health = CDN_HEALTH.lock();
+    let mut hosts: Vec<(u8, CdnHostStats)> = health
+        .hosts
+        .iter()
+        .map(|(host, stats)| {
+            let blacklisted = stats.is_blacklisted(now);
+            let report = CdnHostStats {
+                host: host.clone(),
+                successes: stats.successes,
+                failures: stats.failures,
+                error_rate: stats.failures as f64 / (stats.successes + stats.failures).max(1) as f64,
+                throughput_kbps: stats.throughput.map(|bytes| (bytes / 1024.0) as u64),
+                ttfb_ms: stats.ttfb_ms.map(|ms| ms as u64),
+                score: stats.score(),
+                blacklisted,
+                blacklisted_until: stats
+                    .blacklisted_until
+                    .filter(|_| blacklisted)
+                    .map(|until| until.format(STANDARD_TIME_FORMAT).to_string()),
+                last_error: stats.last_error.clone(),
+            };
+            (stats.tier(now), report)
+        })
+        .collect();
+    drop(health);
+    // 与下载时的排序一致
+    hosts.sort_by(|(a_tier, a), (b_tier, b)| a_tier.cmp(b_tier).then(b.score.total_cmp(&a.score)));
+    CdnHealthReport {
+        enabled: config().enabled,
+        hosts: hosts.into_iter().map(|(_, report)| report).collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(value: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(value, STANDARD_TIME_FORMAT).unwrap()
+    }
+
+    #[test]
+    fn test_is_host_error() {
+        let incomplete = IncompleteBody {
+            received: 1,
+            expected: 2,
+        };
+        assert!(is_host_error(
+            &anyhow::Error::new(incomplete).context("Range分片下载不完整")
+        ));
+        let wrapped = std::io::Error::other(IncompleteBody {
+            received: 1,
+            expected: 2,
+        });
+        assert!(is_host_error(&anyhow::Error::new(wrapped)));
+        // 本地磁盘错误不计入节点失败
+        let disk = std::io::Error::new(std::io::ErrorKind::StorageFull, "磁盘已满");
+        assert!(!is_host_error(&anyhow::Error::new(disk)));
+    }
+
+    #[test]
+    fn test_host_of() {
+        assert_eq!(
+            host_of("https://upos-sz-mirrorcos.bilivideo.com/ugc/1.m4s?e=1"),
+            Some("upos-sz-mirrorcos.bilivideo.com")
+        );
+        assert_eq!(host_of("http://1.2.3.4:4480/v1/resource/1.m4s"), Some("1.2.3.4"));
+        assert_eq!(host_of("https://"), None);
+    }
+
+    #[test]
+    fn test_sort_urls_by_health() {
+        let config = CdnHealthConfig {
+            enabled: true,
+            blacklist_threshold: 2,
+            blacklist_minutes: 30,
+        };
+        let now = at("2026-10-17 12:00:00");
+        let mut health = CdnHealth::default();
+        // fast 速度快，slow 速度慢，bad 连续返回 403
+        health.record_success("fast.example", 64 * 1024 * 1024, Duration::from_secs(8));
+        health.record_success("slow.example", 8 * 1024 * 1024, Duration::from_secs(40));
+        assert!(!health.record_failure("bad.example", "403 Forbidden", &config, now));
+        assert!(health.record_failure("bad.example", "403 Forbidden", &config, now));
+
+        let mut urls = vec![
+            "https://bad.example/a.m4s",
+            "https://slow.example/a.m4s",
+            "https://unknown.example/a.m4s",
+            "https://fast.example/a.m4s",
+        ];
+        health.sort_urls(&mut urls, now);
+        assert_eq!(
+            urls,
+            vec![
+                "https://fast.example/a.m4s",
+                "https://slow.example/a.m4s",
+                "https://unknown.example/a.m4s",
+                "https://bad.example/a.m4s",
+            ]
+        );
+
+        // 黑名单到期后按评分重新参与排序，成功一次即解除
+        let later = at("2026-10-17 12:31:00");
+        assert!(!health.hosts["bad.example"].is_blacklisted(later));
+        health.record_success("bad.example", 0, Duration::from_secs(1));
+        assert_eq!(health.hosts["bad.example"].consecutive_failures, 0);
+    }
+
+    #[test]
+    fn test_unknown_hosts_after_measured() {
+        let config = CdnHealthConfig {
+            enabled: true,
+            blacklist_threshold: 3,
+            blacklist_minutes: 30,
+        };
+        let now = at("2026-10-17 12:00:00");
+        let mut health = CdnHealth::default();
+        // crawl 只有 0.1 MB/s，低于未测速节点的中性吞吐量，仍然排在未测速节点前面
+        health.record_success("crawl.example", 1024 * 1024, Duration::from_secs(10));
+        assert!(health.hosts["crawl.example"].score() < HostStats::default().score());
+        // flaky 失败过但没有进入黑名单，也没有测速数据，与未知节点同组并按成功率排在其后
+        assert!(!health.record_failure("flaky.example", "timeout", &config, now));
+
+        let mut urls = vec![
+            "https://flaky.example/a.m4s",
+            "https://unknown.example/a.m4s",
+            "https://crawl.example/a.m4s",
+        ];
+        health.sort_urls(&mut urls, now);
+        assert_eq!(
+            urls,
+            vec![
+                "https://crawl.example/a.m4s",
+                "https://unknown.example/a.m4s",
+                "https://flaky.example/a.m4s",
+            ]
+        );
+    }
+}
@@ -0,0 +1,149 @@
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Result};
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use sysinfo::{DiskRefreshKind, Disks};
+use tokio_util::sync::CancellationToken;
+use tracing::{info, warn};
+
+/// 当前处于空间不足状态的挂载点，按卷分别记录，只在该卷状态变化时记录日志和推送通知
+static LOW_SPACE_MOUNTS: Lazy<Mutex<HashSet<PathBuf>>> = Lazy::new(|| Mutex::new(HashSet::new()));
+
+const BYTES_PER_MB: u64 = 1024 * 1024;
+
+/// 在挂载点列表中找到包含该路径且最长的挂载点，返回该挂载点及其可用空间
+fn longest_mount_match<'a>(mounts: impl IntoIterator<Item = (&'a Path, u64)>, path: &Path) -> Option<(&'a Path, u64)> {
+    mounts
+        .into_iter()
+        .filter(|(mount_point, _)| path.starts_with(mount_point))
+        .max_by_key(|(mount_point, _)| mount_point.as_os_str().len())
+}
+
+/// 更新挂载点的空间不足状态，返回状态是否发生了变化
+fn set_low_space(mount_point: &Path, low: bool) -> bool {
+    let mut mounts = LOW_SPACE_MOUNTS.lock();
+    if low {
+        mounts.insert(mount_point.to_path_buf())
+    } else {
+        mounts.remove(mount_point)
+    }
+}
+
+/// 视频源目录可能尚未创建，向上找到第一个已存在的目录再解析真实路径
+fn resolve_existing(path: &Path) -> Option<PathBuf> {
+    path.ancestors()
+        .find(|ancestor| ancestor.exists())
+        .and_then(|ancestor| ancestor.canonicalize().ok())
+}
+
+/// 读取路径所在卷的挂载点和可用空间（字节），无法识别所在卷时返回 None
+pub async fn available_space(path: &Path) -> Option<(PathBuf, u64)> {
+    let path = path.to_path_buf();
+    // 网络存储的 statvfs 可能阻塞较长时间，放到阻塞线程中执行
+    tokio::task::spawn_blocking(move || {
+        let path = resolve_existing(&path)?;
+        let disks = Disks::new_with_refreshed_list_specifics(DiskRefreshKind::nothing().with_storage());
+        longest_mount_match(
+            disks
+                .list()
+                .iter()
+                .map(|disk| (disk.mount_point(), disk.available_space())),
+            &path,
+        )
+        .map(|(mount_point, available)| (mount_point.to_path_buf(), available))
+    })
+    .await
+    .ok()
+    .flatten()
+}
+
+/// 下载前检查视频源所在卷的剩余空间，不足时在此等待，直到空间释放或任务被取消
+pub async fn wait_for_free_space(path: &Path, token: &CancellationToken) -> Result<()> {
+    loop {
+        let config = crate::config::with_config(|bundle| bundle.config.disk_space_guard.clone());
+        if !config.enabled {
+            return Ok(());
+        }
+        let threshold = config.min_free_mb.saturating_mul(BYTES_PER_MB);
+        let (mount_point, available) = match available_space(path).await {
+            Some((mount_point, available)) if available < threshold => (mount_point, available),
+            Some((mount_point, _)) => {
+                if set_low_space(&mount_point, false) {
+                    info!(
+                        "磁盘剩余空间已恢复（{}），继续下载: {}",
+                        mount_point.display(),
+                        path.display()
+                    );
+                }
+                return Ok(());
+            }
+            None => return Ok(()),
+        };
+
+        if set_low_space(&mount_point, true) {
+            let message = format!(
+                "剩余空间 {} MB，低于设置的 {} MB，已暂停下载，空间释放后将自动继续",
+                available / BYTES_PER_MB,
+                config.min_free_mb
+            );
+            warn!("磁盘空间不足（{}）: {}", path.display(), message);
+            if let Err(e) = crate::utils::notification::send_error_notification(
+                "磁盘空间不足",
+                &message,
+                Some(&format!(
+                    "视频源路径: {}，挂载点: {}",
+                    path.display(),
+                    mount_point.display()
+                )),
+            )
+            .await
+            {
+                warn!("发送磁盘空间不足通知失败: {:#}", e);
+            }
+        }
+
+        tokio::select! {
+            biased;
+            _ = token.cancelled() => return Err(anyhow!("Download cancelled")),
+            _ = tokio::time::sleep(std::time::Duration::from_secs(config.check_interval_secs.max(1))) => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_longest_mount_match() {
+        let mounts = [
+            (Path::new("/"), 10),
+            (Path::new("/mnt/nas"), 20),
+            (Path::new("/mnt/nas/media"), 30),
+            (Path::new("/mnt/nas2"), 40),
+        ];
+        let available = |path: &str| longest_mount_match(mounts, Path::new(path)).map(|(_, available)| available);
+        assert_eq!(available("/mnt/nas/media/bili"), Some(30));
+        assert_eq!(available("/mnt/nas/other"), Some(20));
+        // 按路径组件匹配，/mnt/nas2 不应被 /mnt/nas 命中
+        assert_eq!(available("/mnt/nas2/bili"), Some(40));
+        assert_eq!(available("/home"), Some(10));
+        assert_eq!(longest_mount_match([], Path::new("/home")), None);
+    }
+
+    #[test]
+    fn test_low_space_tracked_per_mount() {
+        let nas = Path::new("/test-low-space/nas");
+        let local = Path::new("/test-low-space/local");
+        assert!(set_low_space(nas, true));
+        // 另一个卷的检查不应覆盖已记录的状态
+        assert!(!set_low_space(local, false));
+        assert!(!set_low_space(nas, true));
+        assert!(set_low_space(local, true));
+        assert!(set_low_space(nas, false));
+        assert!(!set_low_space(nas, false));
+        assert!(set_low_space(local, false));
+    }
+}
@@ -0,0 +1,44 @@
+//! 执行 ffmpeg 并整理错误信息
+
+use std::ffi::OsStr;
+
+use anyhow::{anyhow, Context, Result};
+
+/// ffmpeg 的错误信息在 stderr 的最后几行
+pub fn ffmpeg_error(stderr: &[u8]) -> anyhow::Error {
+    let stderr = String::from_utf8_lossy(stderr);
+    let mut tail = stderr.trim().lines().rev().take(5).collect::<Vec<_>>();
+    tail.reverse();
+    anyhow!("ffmpeg error: {}", tail.join("\n"))
+}
+
+/// 执行 ffmpeg，失败时返回 stderr 最后几行作为错误；成功时返回 stderr，loudnorm 等滤镜的测量结果输出在这里
+pub async fn run_ffmpeg<I, S>(args: I) -> Result<String>
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<OsStr>,
+{
+    let output = tokio::process::Command::new("ffmpeg")
+        .args(args)
+        .output()
+        .await
+        .context("无法执行 ffmpeg")?;
+    if !output.status.success() {
+        return Err(ffmpeg_error(&output.stderr));
+    }
+    Ok(String::from_utf8_lossy(&output.stderr).into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ffmpeg_error() {
+        let stderr = b"line 1\nline 2\nline 3\nline 4\nline 5\nline 6\nConversion failed!\n";
+        assert_eq!(
+            ffmpeg_error(stderr).to_string(),
+            "ffmpeg error: line 3\nline 4\nline 5\nline 6\nConversion failed!"
+        );
+    }
+}
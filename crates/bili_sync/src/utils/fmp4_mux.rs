@@ -0,0 +1,450 @@
+//! 内置的分片 MP4（fMP4）音视频合并
+//!
+//! B 站 DASH 流的视频和音频分别是只含一条轨道的 fMP4 文件（ftyp + moov + sidx + 若干 moof/mdat），
+//! 合并时只需要把两个 moov 中的轨道拼到一起，再按时间顺序交错写入各自的 moof/mdat，
+//! 不涉及任何重新编码，因此可以不依赖 ffmpeg 完成。无法处理的输入会返回错误，由调用方回退到 ffmpeg。
+
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Seek, Write};
+use std::path::Path;
+
+use anyhow::{anyhow, bail, ensure, Context, Result};
+
+use crate::utils::mp4_box::{
+    copy_range, leaf, read_top_box, read_top_box_raw, read_u32, read_versioned, scan_top_level, version_and_flags,
+    write_u32, write_versioned, Body, Mp4Box,
+};
+
+// tfhd / trun 中各可选字段的标志位
+const TFHD_BASE_DATA_OFFSET: u32 = 0x1;
+const TFHD_SAMPLE_DESCRIPTION_INDEX: u32 = 0x2;
+const TFHD_DEFAULT_SAMPLE_DURATION: u32 = 0x8;
+const TRUN_DATA_OFFSET: u32 = 0x1;
+const TRUN_FIRST_SAMPLE_FLAGS: u32 = 0x4;
+const TRUN_SAMPLE_DURATION: u32 = 0x100;
+const TRUN_SAMPLE_SIZE: u32 = 0x200;
+const TRUN_SAMPLE_FLAGS: u32 = 0x400;
+const TRUN_SAMPLE_CTO: u32 = 0x800;
+
+fn rescale(value: u64, from: u32, to: u32) -> u64 {
+    if from == to || from == 0 {
+        return value;
+    }
+    (value as u128 * to as u128 / from as u128).min(u64::MAX as u128) as u64
+}
+
+/// 一个 moof 及其后紧跟的 mdat
+struct Fragment {
+    moof: Mp4Box,
+    moof_size: u64,
+    /// 紧跟的 mdat 在输入文件中的位置（偏移、大小）
+    mdats: Vec<(u64, u64)>,
+    /// 解码起始时间（轨道时间刻度）
+    start: u64,
+    duration: u64,
+}
+
+/// 单轨道 fMP4 输入
+struct Track {
+    ftyp: Option<Vec<u8>>,
+    moov: Mp4Box,
+    track_id: u32,
+    /// 轨道（mdhd）的时间刻度
+    timescale: u32,
+    /// 影片（mvhd）的时间刻度
+    movie_timescale: u32,
+    fragments: Vec<Fragment>,
+}
+
+impl Track {
+    fn read<R: Read + Seek>(reader: &mut R) -> Result<Self> {
+        let mut ftyp = None;
+        let mut moov = None;
+        let mut fragments: Vec<Fragment> = Vec::new();
+        for top in scan_top_level(reader)? {
+            match &top.kind {
+                b"ftyp" => ftyp = Some(read_top_box_raw(reader, &top)?),
+                b"moov" => moov = Some(read_top_box(reader, &top)?),
+                b"moof" => fragments.push(Fragment {
+                    moof: read_top_box(reader, &top)?,
+                    moof_size: top.size,
+                    mdats: Vec::new(),
+                    start: 0,
+                    duration: 0,
+                }),
+                b"mdat" => match fragments.last_mut() {
+                    Some(fragment) => fragment.mdats.push((top.offset, top.size)),
+                    None => bail!("mdat 出现在 moof 之前，不是分片 MP4"),
+                },
+                // sidx、mfra 等索引在合并后失效，styp、free 等直接丢弃
+                _ => {}
+            }
+        }
+
+        let moov = moov.ok_or_else(|| anyhow!("缺少 moov"))?;
+        ensure!(!fragments.is_empty(), "没有 moof，不是分片 MP4");
+        let mvex = moov.child(b"mvex").context("不是分片 MP4")?;
+        let traks = moov.children_of(b"trak").collect::<Vec<_>>();
+        ensure!(traks.len() == 1, "需要单轨道输入，实际有 {} 条轨道", traks.len());
+        let tkhd = traks[0].child(b"tkhd")?.data()?;
+        let track_id = read_versioned(tkhd, 12, 20)? as u32;
+        let mdhd = traks[0].child(b"mdia")?.child(b"mdhd")?.data()?;
+        let timescale = read_versioned(mdhd, 12, 20)? as u32;
+        let movie_timescale = read_versioned(moov.child(b"mvhd")?.data()?, 12, 20)? as u32;
+        ensure!(timescale > 0 && movie_timescale > 0, "时间刻度无效");
+        let trex_default_duration = mvex
+            .children_of(b"trex")
+            .find(|trex| trex.data().and_then(|data| read_u32(data, 4)).ok() == Some(track_id))
+            .map(|trex| trex.data().and_then(|data| read_u32(data, 12)))
+            .transpose()?
+            .unwrap_or(0);
+
+        // 缺少 tfdt 时按前面分片的累计时长推算起始时间
+        let mut next_start = 0;
+        for fragment in &mut fragments {
+            ensure!(!fragment.mdats.is_empty(), "moof 之后缺少 mdat");
+            let (start, duration) = fragment_timing(&fragment.moof, track_id, trex_default_duration)?;
+            fragment.start = start.unwrap_or(next_start);
+            fragment.duration = duration;
+            next_start = fragment.start + duration;
+        }
+
+        Ok(Self {
+            ftyp,
+            moov,
+            track_id,
+            timescale,
+            movie_timescale,
+            fragments,
+        })
+    }
+
+    /// 轨道总时长（影片时间刻度）
+    fn movie_duration(&self, movie_timescale: u32) -> u64 {
+        let end = self
+            .fragments
+            .iter()
+            .map(|fragment| fragment.start + fragment.duration)
+            .max()
+            .unwrap_or(0);
+        rescale(end, self.timescale, movie_timescale)
+    }
+}
+
+/// 读取分片的解码起始时间（tfdt，可能缺失）和总时长
+fn fragment_timing(moof: &Mp4Box, track_id: u32, trex_default_duration: u32) -> Result<(Option<u64>, u64)> {
+    let trafs = moof.children_of(b"traf").collect::<Vec<_>>();
+    ensure!(trafs.len() == 1, "moof 中应只有一个 traf，实际有 {} 个", trafs.len());
+    let traf = trafs[0];
+
+    let tfhd = traf.child(b"tfhd")?.data()?;
+    let (_, flags) = version_and_flags(tfhd)?;
+    ensure!(read_u32(tfhd, 4)? == track_id, "traf 引用了未知的轨道");
+    // 绝对的数据偏移在交错写入后会失效
+    ensure!(flags & TFHD_BASE_DATA_OFFSET == 0, "不支持带 base-data-offset 的 tfhd");
+    let mut offset = 8;
+    if flags & TFHD_SAMPLE_DESCRIPTION_INDEX != 0 {
+        offset += 4;
+    }
+    let default_duration = if flags & TFHD_DEFAULT_SAMPLE_DURATION != 0 {
+        read_u32(tfhd, offset)?
+    } else {
+        trex_default_duration
+    };
+
+    let start = traf
+        .children_of(b"tfdt")
+        .next()
+        .map(|tfdt| read_versioned(tfdt.data()?, 4, 4))
+        .transpose()?;
+
+    let mut duration = 0u64;
+    for trun in traf.children_of(b"trun") {
+        let data = trun.data()?;
+        let (_, flags) = version_and_flags(data)?;
+        let sample_count = read_u32(data, 4)? as usize;
+        if flags & TRUN_SAMPLE_DURATION == 0 {
+            duration += sample_count as u64 * default_duration as u64;
+            continue;
+        }
+        let mut offset = 8;
+        if flags & TRUN_DATA_OFFSET != 0 {
+            offset += 4;
+        }
+        if flags & TRUN_FIRST_SAMPLE_FLAGS != 0 {
+            offset += 4;
+        }
+        let sample_size = [
+            TRUN_SAMPLE_DURATION,
+            TRUN_SAMPLE_SIZE,
+            TRUN_SAMPLE_FLAGS,
+            TRUN_SAMPLE_CTO,
+        ]
+        .iter()
+        .filter(|flag| flags & **flag != 0)
+        .count()
+            * 4;
+        for index in 0..sample_count {
+            duration += read_u32(data, offset + index * sample_size)? as u64;
+        }
+    }
+    Ok((start, duration))
+}
+
+/// 修改轨道编号，并把以影片时间刻度表示的时长换算到新的影片时间刻度
+fn patch_trak(trak: &mut Mp4Box, track_id: u32, from_timescale: u32, to_timescale: u32) -> Result<()> {
+    let tkhd = trak.child_mut(b"tkhd")?.data_mut()?;
+    write_versioned(tkhd, 12, 20, track_id as u64)?;
+    let duration = read_versioned(tkhd, 20, 28)?;
+    write_versioned(tkhd, 20, 28, rescale(duration, from_timescale, to_timescale))?;
+
+    if let Ok(edts) = trak.child_mut(b"edts") {
+        for elst in edts.children_mut().iter_mut().filter(|child| &child.kind == b"elst") {
+            let data = elst.data_mut()?;
+            let (version, _) = version_and_flags(data)?;
+            let entry_size = if version == 1 { 20 } else { 12 };
+            for index in 0..read_u32(data, 4)? as usize {
+                let offset = 8 + index * entry_size;
+                let duration = read_versioned(data, offset, offset)?;
+                write_versioned(data, offset, offset, rescale(duration, from_timescale, to_timescale))?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// 修改分片的序号和轨道编号，返回新的 moof 内容；长度必须不变，否则 trun 中的数据偏移会失效
+fn patch_moof(fragment: &Fragment, sequence_number: u32, track_id: u32) -> Result<Vec<u8>> {
+    let mut moof = fragment.moof.clone();
+    write_u32(moof.child_mut(b"mfhd")?.data_mut()?, 4, sequence_number)?;
+    let tfhd = moof.child_mut(b"traf")?.child_mut(b"tfhd")?.data_mut()?;
+    write_u32(tfhd, 4, track_id)?;
+    let bytes = moof.to_bytes();
+    ensure!(bytes.len() as u64 == fragment.moof_size, "moof 重新编码后长度发生变化");
+    Ok(bytes)
+}
+
+fn build_moov(video: &Track, audio: &Track, audio_track_id: u32) -> Result<Mp4Box> {
+    let movie_timescale = video.movie_timescale;
+    let duration = video
+        .movie_duration(movie_timescale)
+        .max(audio.movie_duration(movie_timescale));
+
+    let mut audio_trak = audio.moov.child(b"trak")?.clone();
+    patch_trak(&mut audio_trak, audio_track_id, audio.movie_timescale, movie_timescale)?;
+
+    let trex_of = |track: &Track| -> Result<Mp4Box> {
+        track
+            .moov
+            .child(b"mvex")?
+            .children_of(b"trex")
+            .find(|trex| trex.data().and_then(|data| read_u32(data, 4)).ok() == Some(track.track_id))
+            .cloned()
+            .ok_or_else(|| anyhow!("mvex 中缺少轨道 {} 的 trex", track.track_id))
+    };
+    let mut audio_trex = trex_of(audio)?;
+    write_u32(audio_trex.data_mut()?, 4, audio_track_id)?;
+    let mut mehd = vec![1, 0, 0, 0];
+    mehd.extend_from_slice(&duration.to_be_bytes());
+    let mvex = Mp4Box {
+        kind: *b"mvex",
+        body: Body::Container(vec![leaf(b"mehd", mehd), trex_of(video)?, audio_trex]),
+    };
+
+    let mut children = Vec::new();
+    for child in video.moov.children() {
+        match &child.kind {
+            b"mvhd" => {
+                let mut mvhd = child.clone();
+                let data = mvhd.data_mut()?;
+                write_versioned(data, 16, 24, duration)?;
+                // next_track_ID 是 mvhd 的最后一个字段
+                let next_track_id_offset = data.len().checked_sub(4).ok_or_else(|| anyhow!("mvhd 内容不完整"))?;
+                write_u32(data, next_track_id_offset, video.track_id.max(audio_track_id) + 1)?;
+                children.push(mvhd);
+            }
+            b"trak" => {
+                children.push(child.clone());
+                children.push(audio_trak.clone());
+            }
+            b"mvex" => children.push(mvex.clone()),
+            _ => children.push(child.clone()),
+        }
+    }
+    Ok(Mp4Box {
+        kind: *b"moov",
+        body: Body::Container(children),
+    })
+}
+
+/// 将单轨道的 fMP4 视频和音频合并为一个 fMP4 文件
+fn mux<R: Read + Seek, W: Write>(video_reader: &mut R, audio_reader: &mut R, writer: &mut W) -> Result<()> {
+    let video = Track::read(video_reader).context("解析视频流失败")?;
+    let audio = Track::read(audio_reader).context("解析音频流失败")?;
+    let audio_track_id = if audio.track_id == video.track_id {
+        video.track_id + 1
+    } else {
+        audio.track_id
+    };
+
+    let ftyp = video
+        .ftyp
+        .as_ref()
+        .or(audio.ftyp.as_ref())
+        .ok_or_else(|| anyhow!("缺少 ftyp"))?;
+    writer.write_all(ftyp)?;
+    writer.write_all(&build_moov(&video, &audio, audio_track_id)?.to_bytes())?;
+
+    // 按解码时间交错写入两条轨道的分片，播放器顺序读取时不需要来回跳转
+    let (mut video_index, mut audio_index) = (0, 0);
+    let mut sequence_number = 1;
+    while video_index < video.fragments.len() || audio_index < audio.fragments.len() {
+        let take_video = match (video.fragments.get(video_index), audio.fragments.get(audio_index)) {
+            (Some(v), Some(a)) => {
+                v.start as u128 * audio.timescale as u128 <= a.start as u128 * video.timescale as u128
+            }
+            (Some(_), None) => true,
+            _ => false,
+        };
+        let (fragment, track_id, reader) = if take_video {
+            video_index += 1;
+            (&video.fragments[video_index - 1], video.track_id, &mut *video_reader)
+        } else {
+            audio_index += 1;
+            (&audio.fragments[audio_index - 1], audio_track_id, &mut *audio_reader)
+        };
+        writer.write_all(&patch_moof(fragment, sequence_number, track_id)?)?;
+        for (offset, size) in &fragment.mdats {
+            copy_range(reader, writer, *offset, *size)?;
+        }
+        sequence_number += 1;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// 合并 DASH 的视频流和音频流，输入不是单轨道 fMP4 等无法处理的情况返回错误
+pub fn merge_dash_tracks(video_path: &Path, audio_path: &Path, output_path: &Path) -> Result<()> {
+    let mut video_reader = BufReader::new(File::open(video_path)?);
+    let mut audio_reader = BufReader::new(File::open(audio_path)?);
+    let file = File::create(output_path)?;
+    let mut writer = BufWriter::new(&file);
+    mux(&mut video_reader, &mut audio_reader, &mut writer)?;
+    drop(writer);
+    file.sync_all()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::utils::mp4_box::{parse_boxes, read_u64};
+
+    fn make_box(kind: &[u8; 4], payload: &[u8]) -> Vec<u8> {
+        leaf(kind, payload.to_vec()).to_bytes()
+    }
+
+    fn container(kind: &[u8; 4], children: &[Vec<u8>]) -> Vec<u8> {
+        make_box(kind, &children.concat())
+    }
+
+    fn full_box(kind: &[u8; 4], fields: &[u32]) -> Vec<u8> {
+        let payload = fields.iter().flat_map(|field| field.to_be_bytes()).collect::<Vec<_>>();
+        make_box(kind, &payload)
+    }
+
+    /// 构造轨道编号为 1 的 fMP4：每个分片包含 `samples` 个时长为 `sample_duration` 的样本，
+    /// mdat 内容为 `tag` 加分片序号，用于确认输出中的分片来源
+    fn fragmented(tag: u8, timescale: u32, sample_duration: u32, samples: u32, fragments: u32) -> Vec<u8> {
+        let track_id = 1;
+        // 版本、创建/修改时间、时间刻度、时长、速率、音量，之后是保留字段、矩阵和 next_track_ID
+        let mut mvhd_fields = vec![0, 0, 0, 1000, 0, 0x10000, 0x01000000];
+        mvhd_fields.extend([0; 17]);
+        mvhd_fields.push(2);
+        let mvhd = full_box(b"mvhd", &mvhd_fields);
+        let tkhd = full_box(b"tkhd", &[3, 0, 0, track_id, 0, 0]);
+        let mdhd = full_box(b"mdhd", &[0, 0, 0, timescale, 0, 0]);
+        let trak = container(b"trak", &[tkhd, container(b"mdia", &[mdhd])]);
+        let trex = full_box(b"trex", &[0, track_id, 1, sample_duration, 0, 0]);
+        let moov = container(b"moov", &[mvhd, trak, container(b"mvex", &[trex])]);
+
+        let mut file = [
+            make_box(b"ftyp", b"iso5\0\0\0\x01iso6mp41"),
+            moov,
+            full_box(b"sidx", &[0, 0]),
+        ]
+        .concat();
+        for index in 0..fragments {
+            let mfhd = full_box(b"mfhd", &[0, index + 1]);
+            let tfhd = full_box(b"tfhd", &[0x020000, track_id]);
+            let tfdt = full_box(b"tfdt", &[0, index * samples * sample_duration]);
+            let trun = full_box(b"trun", &[TRUN_DATA_OFFSET, samples, 0]);
+            let moof = container(b"moof", &[mfhd, container(b"traf", &[tfhd, tfdt, trun])]);
+            let mdat = make_box(b"mdat", &[tag + index as u8; 4]);
+            file.extend(moof);
+            file.extend(mdat);
+        }
+        file
+    }
+
+    #[test]
+    fn test_mux_interleaves_fragments() {
+        // 视频：时间刻度 1000，每个分片 2 秒；音频：时间刻度 48000，每个分片 1 秒
+        let mut video = Cursor::new(fragmented(0x10, 1000, 1000, 2, 2));
+        let mut audio = Cursor::new(fragmented(0x20, 48000, 1024, 47, 4));
+        let mut output = Vec::new();
+        mux(&mut video, &mut audio, &mut output).unwrap();
+
+        let boxes = parse_boxes(&output).unwrap();
+        let kinds = boxes.iter().map(|b| b.kind_str()).collect::<Vec<_>>();
+        // sidx 被丢弃，分片按解码时间交错
+        assert_eq!(kinds[..2], ["ftyp", "moov"]);
+        assert_eq!(kinds.len(), 2 + 6 * 2);
+
+        let moov = &boxes[1];
+        let track_ids = moov
+            .children_of(b"trak")
+            .map(|trak| read_u32(trak.child(b"tkhd").unwrap().data().unwrap(), 12).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(track_ids, vec![1, 2]);
+        let mvhd = moov.child(b"mvhd").unwrap().data().unwrap();
+        assert_eq!(read_u32(mvhd, mvhd.len() - 4).unwrap(), 3);
+        // 音频 4 × 47 × 1024 / 48000 ≈ 4.01 秒，比视频略长
+        assert_eq!(read_u32(mvhd, 16).unwrap(), 4010);
+        let mvex = moov.child(b"mvex").unwrap();
+        assert_eq!(read_u64(mvex.child(b"mehd").unwrap().data().unwrap(), 4).unwrap(), 4010);
+        assert_eq!(mvex.children_of(b"trex").count(), 2);
+
+        let fragments = boxes[2..]
+            .chunks(2)
+            .map(|pair| {
+                let moof = &pair[0];
+                let sequence = read_u32(moof.child(b"mfhd").unwrap().data().unwrap(), 4).unwrap();
+                let tfhd = moof.child(b"traf").unwrap().child(b"tfhd").unwrap().data().unwrap();
+                (sequence, read_u32(tfhd, 4).unwrap(), pair[1].data().unwrap()[0])
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            fragments,
+            vec![
+                (1, 1, 0x10),
+                (2, 2, 0x20),
+                (3, 2, 0x21),
+                (4, 1, 0x11),
+                (5, 2, 0x22),
+                (6, 2, 0x23)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_mux_rejects_unfragmented_input() {
+        let moov = container(b"moov", &[full_box(b"mvhd", &[0, 0, 0, 1000, 0])]);
+        let plain = [make_box(b"ftyp", b"isom\0\0\0\0"), moov, make_box(b"mdat", &[0; 16])].concat();
+        let mut video = Cursor::new(plain.clone());
+        let mut audio = Cursor::new(plain);
+        assert!(mux(&mut video, &mut audio, &mut Vec::new()).is_err());
+    }
+}
@@ -0,0 +1,649 @@
+//! 导入已有媒体库
+//!
+//! 从其它下载器迁移过来时，视频源目录下往往已经有大量视频。导入任务先按正常流程拉取视频源的视频列表与分页
+//! （不下载），再扫描视频源目录，根据文件名、所在目录名以及 NFO 中 uniqueid 里的 bvid、aid、cid 把文件对应到分页，
+//! 将匹配到的分页的视频内容标记为已完成，避免重新下载，缺少的侧车文件由后续下载流程补齐。
+//! 无法对应到任何视频的文件会在任务状态中列出。
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use anyhow::{bail, Context, Result};
+use bili_sync_entity::{collection, favorite, page, submission, video, video_source, watch_later};
+use once_cell::sync::Lazy;
+use parking_lot::RwLock;
+use regex::Regex;
+use sea_orm::entity::prelude::*;
+use sea_orm::ActiveValue::{Set, Unchanged};
+use sea_orm::TransactionTrait;
+use serde::Serialize;
+use tokio_util::sync::CancellationToken;
+use tracing::{error, info, warn};
+use utoipa::ToSchema;
+
+use crate::adapter::{video_source_from, Args, VideoSource};
+use crate::bilibili::{try_bvid_to_aid, BiliClient, CollectionItem, CollectionType};
+use crate::utils::status::{PageStatus, STATUS_OK};
+use crate::utils::time_format::now_standard_string;
+use crate::workflow::{fetch_video_details, refresh_video_source};
+
+/// 未匹配的文件
+#[derive(Serialize, ToSchema, Clone, Debug)]
+pub struct AdoptUnmatched {
+    pub path: String,
+    pub reason: String,
+}
+
+/// 导入任务的进度
+#[derive(Serialize, ToSchema, Clone, Default, Debug)]
+pub struct AdoptJobStatus {
+    pub running: bool,
+    pub source_type: String,
+    pub source_id: i32,
+    pub started_at: Option<String>,
+    pub finished_at: Option<String>,
+    /// 扫描到的媒体文件数量
+    pub scanned: usize,
+    /// 标记为已完成的分页数量
+    pub matched_pages: usize,
+    /// 所有分页均已导入的视频数量
+    pub matched_videos: usize,
+    /// 无法导入的文件数量
+    pub unmatched: usize,
+    /// 最近若干个无法导入的文件及原因
+    pub unmatched_files: Vec<AdoptUnmatched>,
+    /// 任务异常结束时的错误信息
+    pub error: Option<String>,
+}
+
+const MAX_RECORDED_UNMATCHED: usize = 500;
+
+const MEDIA_EXTENSIONS: &[&str] = &[
+    "mp4", "mkv", "m4a", "flv", "webm", "avi", "mov", "ts", "mp3", "flac", "aac", "opus",
+];
+
+static ADOPT_JOB: Lazy<RwLock<AdoptJobStatus>> = Lazy::new(|| RwLock::new(AdoptJobStatus::default()));
+
+static BVID_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"BV1[0-9A-Za-z]{9}").expect("invalid regex"));
+static AID_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?i)(?:^|[^a-z])av(\d{1,19})").expect("invalid regex"));
+static CID_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?i)(?:^|[^a-z])cid[ _=:-]?(\d{1,19})").expect("invalid regex"));
+static UNIQUEID_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"<uniqueid[^>]*?type="([^"]*)"[^>]*>\s*([^<\s]+)\s*</uniqueid>"#).expect("invalid regex")
+});
+static PAGE_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?i)(?:^|[^a-z])p(\d{1,4})(?:\D|$)|s\d{1,2}e(\d{1,4})").expect("invalid regex"));
+
+/// 从文件名、目录名或 NFO 中提取到的标识
+#[derive(Default, Debug, Clone, PartialEq)]
+struct FileIds {
+    bvids: Vec<String>,
+    aids: Vec<u64>,
+    cids: Vec<i64>,
+}
+
+impl FileIds {
+    fn is_empty(&self) -> bool {
+        self.bvids.is_empty() && self.aids.is_empty() && self.cids.is_empty()
+    }
+
+    fn extend(&mut self, other: &FileIds) {
+        self.bvids.extend(other.bvids.iter().cloned());
+        self.aids.extend(&other.aids);
+        self.cids.extend(&other.cids);
+    }
+}
+
+fn extract_ids(text: &str, ids: &mut FileIds) {
+    ids.bvids
+        .extend(BVID_RE.find_iter(text).map(|m| m.as_str().to_string()));
+    ids.aids
+        .extend(AID_RE.captures_iter(text).filter_map(|c| c[1].parse::<u64>().ok()));
+    ids.cids
+        .extend(CID_RE.captures_iter(text).filter_map(|c| c[1].parse::<i64>().ok()));
+}
+
+/// 读取 NFO 中的 uniqueid，只有类型中带 cid 的数字才作为 cid，其它纯数字的 bilibili 标识按 aid 处理
+fn extract_nfo_ids(content: &str, ids: &mut FileIds) {
+    for captures in UNIQUEID_RE.captures_iter(content) {
+        let (kind, value) = (captures[1].to_lowercase(), &captures[2]);
+        if value.starts_with("BV") {
+            ids.bvids.push(value.to_string());
+        } else if let Ok(number) = value.parse::<u64>() {
+            if kind.contains("cid") {
+                ids.cids.push(number as i64);
+            } else if kind.contains("aid")
+                || (kind.contains("bilibili") && !kind.contains("uid") && !kind.contains("season"))
+            {
+                ids.aids.push(number);
+            }
+        }
+    }
+    // 其它工具常把视频链接写在简介或 website 中
+    extract_ids(content, ids);
+}
+
+/// 文件名中的分 P 编号，例如 `P02`、`S01E02`
+fn page_hint(stem: &str) -> Option<i32> {
+    PAGE_RE
+        .captures(stem)
+        .and_then(|c| c.get(1).or_else(|| c.get(2)))
+        .and_then(|m| m.as_str().parse().ok())
+}
+
+/// 扫描到的媒体文件
+#[derive(Debug)]
+struct ScannedFile {
+    path: PathBuf,
+    ids: FileIds,
+    page: Option<i32>,
+}
+
+fn read_nfo_ids(path: &Path) -> FileIds {
+    let mut ids = FileIds::default();
+    if let Ok(content) = std::fs::read_to_string(path) {
+        extract_nfo_ids(&content, &mut ids);
+    }
+    ids
+}
+
+/// 递归扫描目录，文件自身（文件名与同名 NFO）没有标识时使用所在目录（目录名、tvshow.nfo、movie.nfo）的标识
+fn scan_dir(dir: &Path, inherited: &FileIds, files: &mut Vec<ScannedFile>) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        warn!("无法读取目录: {}", dir.display());
+        return;
+    };
+    let mut dir_ids = FileIds::default();
+    if let Some(name) = dir.file_name().and_then(|name| name.to_str()) {
+        extract_ids(name, &mut dir_ids);
+    }
+    for nfo in ["tvshow.nfo", "movie.nfo"] {
+        dir_ids.extend(&read_nfo_ids(&dir.join(nfo)));
+    }
+    if dir_ids.is_empty() {
+        dir_ids = inherited.clone();
+    }
+    let mut entries = entries.flatten().map(|entry| entry.path()).collect::<Vec<_>>();
+    entries.sort();
+    for path in entries {
+        let Some(name) = path.file_name().and_then(|name| name.to_str()) else {
+            continue;
+        };
+        // 跳过演员头像、预览图等隐藏目录
+        if name.starts_with('.') {
+            continue;
+        }
+        if path.is_dir() {
+            scan_dir(&path, &dir_ids, files);
+            continue;
+        }
+        let is_media = path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .is_some_and(|ext| MEDIA_EXTENSIONS.contains(&ext.to_lowercase().as_str()));
+        if !is_media {
+            continue;
+        }
+        let stem = path.file_stem().and_then(|stem| stem.to_str()).unwrap_or_default();
+        let mut ids = FileIds::default();
+        extract_ids(stem, &mut ids);
+        ids.extend(&read_nfo_ids(&path.with_extension("nfo")));
+        if ids.is_empty() {
+            ids = dir_ids.clone();
+        }
+        files.push(ScannedFile {
+            page: page_hint(stem),
+            path,
+            ids,
+        });
+    }
+}
+
+/// 视频源内视频与分页的查找表
+#[derive(Default)]
+struct LibraryIndex {
+    by_bvid: HashMap<String, i32>,
+    by_aid: HashMap<u64, i32>,
+    by_cid: HashMap<i64, i32>,
+    /// 视频 id -> [(pid, 分页 id)]
+    pages: HashMap<i32, Vec<(i32, i32)>>,
+}
+
+impl LibraryIndex {
+    fn new(videos: &[video::Model], pages: &[page::Model]) -> Self {
+        let mut index = Self::default();
+        for video_model in videos {
+            index.by_bvid.insert(video_model.bvid.clone(), video_model.id);
+            if let Some(aid) = try_bvid_to_aid(&video_model.bvid) {
+                index.by_aid.insert(aid, video_model.id);
+            }
+        }
+        for page_model in pages {
+            index.by_cid.insert(page_model.cid, page_model.id);
+            index
+                .pages
+                .entry(page_model.video_id)
+                .or_default()
+                .push((page_model.pid, page_model.id));
+        }
+        index
+    }
+
+    /// 按 cid、aid、bvid 的顺序匹配，返回分页 id
+    fn match_file(&self, file: &ScannedFile) -> std::result::Result<i32, &'static str> {
+        if let Some(page_id) = file.ids.cids.iter().find_map(|cid| self.by_cid.get(cid)) {
+            return Ok(*page_id);
+        }
+        let video_id = file
+            .ids
+            .aids
+            .iter()
+            .find_map(|aid| self.by_aid.get(aid))
+            .or_else(|| file.ids.bvids.iter().find_map(|bvid| self.by_bvid.get(bvid)));
+        let Some(video_id) = video_id else {
+            return Err(if file.ids.is_empty() {
+                "文件名和 NFO 中没有找到 bvid、aid 或 cid"
+            } else {
+                "视频源中没有对应的视频"
+            });
+        };
+        match self.pages.get(video_id).map(Vec::as_slice) {
+            None | Some([]) => Err("视频尚未获取到分页信息"),
+            Some([(_, page_id)]) => Ok(*page_id),
+            Some(pages) => file
+                .page
+                .and_then(|pid| pages.iter().find(|(page_pid, _)| *page_pid == pid))
+                .map(|(_, page_id)| *page_id)
+                .ok_or("多P视频无法从文件名确定分P"),
+        }
+    }
+}
+
+/// 视频所在目录：媒体文件的上级目录，位于 Season 文件夹中时取 Season 的上级目录
+fn video_dir(media: &Path) -> PathBuf {
+    let parent = media.parent().unwrap_or(media);
+    let is_season = parent
+        .file_name()
+        .and_then(|name| name.to_str())
+        .is_some_and(|name| name.starts_with("Season ") || name.starts_with("Specials"));
+    match parent.parent() {
+        Some(grandparent) if is_season => grandparent.to_path_buf(),
+        _ => parent.to_path_buf(),
+    }
+}
+
+/// 导入分页的下载状态：视频内容标记为完成，封面、NFO、弹幕、字幕、预览图只有在文件已存在时才标记为完成，
+/// 其余子任务保持未开始，由后续下载流程补齐
+fn adopted_page_status(media: &Path) -> PageStatus {
+    let stem = media.file_stem().and_then(|stem| stem.to_str()).unwrap_or_default();
+    let names = media
+        .parent()
+        .and_then(|parent| std::fs::read_dir(parent).ok())
+        .map(|entries| {
+            entries
+                .flatten()
+                .filter_map(|entry| entry.file_name().into_string().ok())
+                .filter_map(|name| name.strip_prefix(stem).map(str::to_string))
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+    let has = |matches: fn(&str) -> bool| {
+        if names.iter().any(|rest| matches(rest)) {
+            STATUS_OK
+        } else {
+            0
+        }
+    };
+    PageStatus::from([
+        has(|rest| rest == "-thumb.jpg" || rest == "-poster.jpg"),
+        STATUS_OK,
+        has(|rest| rest == ".nfo"),
+        has(|rest| rest.starts_with('.') && rest.ends_with(".ass")),
+        has(|rest| rest.starts_with('.') && rest.ends_with(".srt")),
+        has(|rest| rest == ".trickplay" || (rest.starts_with('-') && rest.ends_with(".bif"))),
+    ])
+}
+
+pub fn adopt_job_status() -> AdoptJobStatus {
+    ADOPT_JOB.read().clone()
+}
+
+/// 在后台启动导入任务，已有导入任务在运行时返回 false
+pub fn start_adopt_job(db: Arc<DatabaseConnection>, source_type: String, source_id: i32) -> bool {
+    {
+        let mut status = ADOPT_JOB.write();
+        if status.running {
+            return false;
+        }
+        *status = AdoptJobStatus {
+            running: true,
+            source_type: source_type.clone(),
+            source_id,
+            started_at: Some(now_standard_string()),
+            ..Default::default()
+        };
+    }
+    tokio::spawn(async move {
+        // 导入期间暂停定时扫描，避免扫描同时写入同一批视频和分页
+        let paused = crate::task::pause_scanning_for_job().await;
+        let result = run_adopt_job(&db, &source_type, source_id).await;
+        if paused {
+            crate::task::resume_scanning();
+        }
+        let mut status = ADOPT_JOB.write();
+        if let Err(e) = result {
+            error!("导入已有媒体库失败: {:#}", e);
+            status.error = Some(format!("{:#}", e));
+        }
+        status.running = false;
+        status.finished_at = Some(now_standard_string());
+        info!(
+            "导入已有媒体库完成：扫描 {} 个文件，导入 {} 个分页（{} 个视频），{} 个文件未匹配",
+            status.scanned, status.matched_pages, status.matched_videos, status.unmatched
+        );
+    });
+    true
+}
+
+/// 根据视频源类型与 id 构造扫描参数，与定时扫描加载视频源的方式一致
+async fn source_args(db: &DatabaseConnection, source_type: &str, id: i32) -> Result<(Args, PathBuf)> {
+    let result = match source_type {
+        "collection" => {
+            let model = collection::Entity::find_by_id(id)
+                .one(db)
+                .await?
+                .context("未找到指定的合集")?;
+            let collection_type = if model.r#type == 1 {
+                CollectionType::Series
+            } else {
+                CollectionType::Season
+            };
+            let collection_item = CollectionItem {
+                mid: model.m_id.to_string(),
+                sid: model.s_id.to_string(),
+                collection_type,
+            };
+            (Args::Collection { collection_item }, model.path)
+        }
+        "favorite" => {
+            let model = favorite::Entity::find_by_id(id)
+                .one(db)
+                .await?
+                .context("未找到指定的收藏夹")?;
+            let fid = model.f_id.to_string();
+            (Args::Favorite { fid }, model.path)
+        }
+        "submission" => {
+            let model = submission::Entity::find_by_id(id)
+                .one(db)
+                .await?
+                .context("未找到指定的UP主投稿")?;
+            let upper_id = model.upper_id.to_string();
+            (Args::Submission { upper_id }, model.path)
+        }
+        "watch_later" => {
+            let model = watch_later::Entity::find_by_id(id)
+                .one(db)
+                .await?
+                .context("未找到指定的稍后观看")?;
+            (Args::WatchLater, model.path)
+        }
+        "bangumi" => {
+            let model = video_source::Entity::find_by_id(id)
+                .one(db)
+                .await?
+                .context("未找到指定的番剧")?;
+            let args = Args::Bangumi {
+                season_id: model.season_id,
+                media_id: model.media_id,
+                ep_id: model.ep_id,
+            };
+            (args, model.path)
+        }
+        _ => bail!("不支持的视频源类型: {}", source_type),
+    };
+    Ok((result.0, PathBuf::from(result.1)))
+}
+
+fn record_unmatched(path: &Path, reason: &str) {
+    let mut status = ADOPT_JOB.write();
+    status.unmatched += 1;
+    if status.unmatched_files.len() < MAX_RECORDED_UNMATCHED {
+        status.unmatched_files.push(AdoptUnmatched {
+            path: path.to_string_lossy().to_string(),
+            reason: reason.to_string(),
+        });
+    }
+}
+
+async fn run_adopt_job(db: &DatabaseConnection, source_type: &str, source_id: i32) -> Result<()> {
+    let (args, path) = source_args(db, source_type, source_id).await?;
+    if !path.is_dir() {
+        bail!("视频源目录不存在: {}", path.display());
+    }
+
+    // 拉取视频列表与分页信息，与扫描流程相同但不进入下载阶段
+    let bili_client = BiliClient::new(String::new());
+    let token = CancellationToken::new();
+    let (video_source, video_streams) = video_source_from(&args, &path, &bili_client, db, Some(token.clone())).await?;
+    refresh_video_source(&video_source, video_streams, db, token.clone(), &bili_client).await?;
+    fetch_video_details(&bili_client, &video_source, db, token.clone()).await?;
+
+    let videos = video::Entity::find()
+        .filter(video_source.filter_expr())
+        .filter(video::Column::Deleted.eq(0))
+        .all(db)
+        .await?;
+    let pages = page::Entity::find()
+        .filter(page::Column::VideoId.is_in(videos.iter().map(|v| v.id)))
+        .all(db)
+        .await?;
+    let index = LibraryIndex::new(&videos, &pages);
+
+    let root = path.clone();
+    let files = tokio::task::spawn_blocking(move || {
+        let mut files = Vec::new();
+        scan_dir(&root, &FileIds::default(), &mut files);
+        files
+    })
+    .await?;
+    ADOPT_JOB.write().scanned = files.len();
+    info!("开始导入已有媒体库: {}，共 {} 个媒体文件", path.display(), files.len());
+
+    let pages_by_id = pages.iter().map(|p| (p.id, p)).collect::<HashMap<_, _>>();
+    let mut adopted: HashMap<i32, PathBuf> = HashMap::new();
+    for file in &files {
+        match index.match_file(file) {
+            Ok(page_id) if adopted.contains_key(&page_id) => record_unmatched(&file.path, "与其它文件对应到同一个分页"),
+            Ok(page_id) if PageStatus::from(pages_by_id[&page_id].download_status).get_completed() => {
+                record_unmatched(&file.path, "对应的分页已下载完成")
+            }
+            Ok(page_id) => {
+                adopted.insert(page_id, file.path.clone());
+            }
+            Err(reason) => record_unmatched(&file.path, reason),
+        }
+    }
+
+    // 视频的所有分页都已导入（或此前已下载完成）时记录视频目录，后续补齐视频级文件时写入已有目录而不是新建目录；
+    // 视频自身的子任务保持原状，由下载流程补齐缺少的封面和 NFO
+    let txn = db.begin().await?;
+    let mut matched_videos = 0;
+    for video_model in &videos {
+        let Some(video_pages) = index.pages.get(&video_model.id) else {
+            continue;
+        };
+        let mut video_path = None;
+        let mut all_done = true;
+        for (_, page_id) in video_pages {
+            match adopted.get(page_id) {
+                Some(file) => {
+                    page::Entity::update(page::ActiveModel {
+                        id: Unchanged(*page_id),
+                        path: Set(Some(file.to_string_lossy().to_string())),
+                        download_status: Set(adopted_page_status(file).into()),
+                        ..Default::default()
+                    })
+                    .exec(&txn)
+                    .await?;
+                    video_path.get_or_insert_with(|| video_dir(file));
+                }
+                None => all_done &= PageStatus::from(pages_by_id[page_id].download_status).get_completed(),
+            }
+        }
+        if let (Some(video_path), true) = (video_path, all_done) {
+            video::Entity::update(video::ActiveModel {
+                id: Unchanged(video_model.id),
+                path: Set(video_path.to_string_lossy().to_string()),
+                ..Default::default()
+            })
+            .exec(&txn)
+            .await?;
+            matched_videos += 1;
+        }
+    }
+    txn.commit().await?;
+
+    let mut status = ADOPT_JOB.write();
+    status.matched_pages = adopted.len();
+    status.matched_videos = matched_videos;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extract_ids() {
+        let mut ids = FileIds::default();
+        extract_ids("[BV1nWcSeeEkV] 标题 av170001 cid_279786 P2", &mut ids);
+        assert_eq!(ids.bvids, vec!["BV1nWcSeeEkV"]);
+        assert_eq!(ids.aids, vec![170001]);
+        assert_eq!(ids.cids, vec![279786]);
+        // 单词中间的 av 不应被识别
+        let mut ids = FileIds::default();
+        extract_ids("java12345", &mut ids);
+        assert!(ids.is_empty());
+
+        let nfo = r#"<episodedetails>
+  <uniqueid type="bilibili" default="true">BV1nWcSeeEkV</uniqueid>
+  <uniqueid type="bilibili_aid">114514</uniqueid>
+  <uniqueid type="bilibili_uid">1</uniqueid>
+</episodedetails>"#;
+        let mut ids = FileIds::default();
+        extract_nfo_ids(nfo, &mut ids);
+        assert!(ids.bvids.contains(&"BV1nWcSeeEkV".to_string()));
+        assert_eq!(ids.aids, vec![114514]);
+        assert!(ids.cids.is_empty());
+        // 类型未注明 cid 的数字不能当作 cid，否则会与其它视频分页的 cid 撞上
+        let nfo = r#"<uniqueid type="bilibili">170001</uniqueid><uniqueid type="bilibili_cid">279786</uniqueid>"#;
+        let mut ids = FileIds::default();
+        extract_nfo_ids(nfo, &mut ids);
+        assert_eq!(ids.aids, vec![170001]);
+        assert_eq!(ids.cids, vec![279786]);
+
+        assert_eq!(page_hint("标题 - P03"), Some(3));
+        assert_eq!(page_hint("S01E12 - 标题"), Some(12));
+        assert_eq!(page_hint("标题"), None);
+    }
+
+    #[test]
+    fn test_match_file() {
+        let videos = vec![
+            video::Model {
+                id: 1,
+                bvid: "BV1nWcSeeEkV".to_string(),
+                ..Default::default()
+            },
+            video::Model {
+                id: 2,
+                bvid: "BV1bSJez1Et8".to_string(),
+                ..Default::default()
+            },
+        ];
+        let page = |id, video_id, cid, pid| page::Model {
+            id,
+            video_id,
+            cid,
+            pid,
+            ..Default::default()
+        };
+        let pages = vec![page(10, 1, 100, 1), page(20, 2, 200, 1), page(21, 2, 201, 2)];
+        let index = LibraryIndex::new(&videos, &pages);
+        let file = |bvid: &str, cids: Vec<i64>, page: Option<i32>| ScannedFile {
+            path: PathBuf::new(),
+            ids: FileIds {
+                bvids: vec![bvid.to_string()],
+                aids: Vec::new(),
+                cids,
+            },
+            page,
+        };
+
+        assert_eq!(index.match_file(&file("BV1nWcSeeEkV", vec![], None)), Ok(10));
+        // cid 优先于 bvid
+        assert_eq!(index.match_file(&file("BV1nWcSeeEkV", vec![201], None)), Ok(21));
+        assert_eq!(index.match_file(&file("BV1bSJez1Et8", vec![], Some(2))), Ok(21));
+        assert!(index.match_file(&file("BV1bSJez1Et8", vec![], None)).is_err());
+        assert!(index.match_file(&file("BV1xx411c7mD", vec![], None)).is_err());
+
+        let aid = try_bvid_to_aid("BV1nWcSeeEkV").unwrap();
+        let by_aid = ScannedFile {
+            path: PathBuf::new(),
+            ids: FileIds {
+                aids: vec![aid],
+                ..Default::default()
+            },
+            page: None,
+        };
+        assert_eq!(index.match_file(&by_aid), Ok(10));
+
+        assert_eq!(
+            video_dir(Path::new("/library/多P视频/Season 01/S01E01.mp4")),
+            PathBuf::from("/library/多P视频")
+        );
+        assert_eq!(
+            video_dir(Path::new("/library/单P视频/单P视频.mp4")),
+            PathBuf::from("/library/单P视频")
+        );
+    }
+
+    #[test]
+    fn test_adopted_page_status() {
+        let dir = std::env::temp_dir().join(format!("bili-sync-adopt-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        for name in ["video.mp4", "video.nfo", "video.zh-CN.default.ass", "other-thumb.jpg"] {
+            std::fs::write(dir.join(name), b"").unwrap();
+        }
+        let status = adopted_page_status(&dir.join("video.mp4"));
+        // 只有视频内容和已存在的侧车文件被标记为完成，其它子任务留给下载流程
+        assert_eq!(<[u32; 6]>::from(status), [0, STATUS_OK, STATUS_OK, STATUS_OK, 0, 0]);
+        assert!(!status.get_completed());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_download_adopted_page_keeps_path() {
+        let dir = std::env::temp_dir().join(format!("bili-sync-adopt-rerun-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        for name in [
+            "已有视频.mp4",
+            "已有视频-thumb.jpg",
+            "已有视频.zh-CN.default.ass",
+            "已有视频.srt",
+        ] {
+            std::fs::write(dir.join(name), b"").unwrap();
+        }
+        let media = dir.join("已有视频.mp4");
+        let source = crate::test_utils::test_watch_later_source(false, "mp4");
+        // 接管后只缺 NFO，其它子任务已完成，视频不会重新下载
+        let updated = crate::test_utils::rerun_downloaded_test_page(&source, &media, adopted_page_status(&media)).await;
+
+        // 路径保持为接管的文件，缺少的 NFO 写在它旁边而不是模板目录
+        assert_eq!(updated.path.unwrap(), Some(media.to_string_lossy().to_string()));
+        assert!(dir.join("已有视频.nfo").exists());
+        assert!(PageStatus::from(updated.download_status.unwrap()).get_completed());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,512 @@
+//! 扫描结束后通知 Jellyfin/Emby/Plex 刷新新增视频所在的目录
+//!
+//! 媒体服务器默认按计划任务扫描媒体库，新下载的视频可能要数小时后才出现。
+//! 这里只刷新本轮新增视频的目录，避免对整个媒体库做全量扫描。
+//!
+//! 需要刷新的目录没有取自 `ScanCollector`：它只记录扫描中新发现的视频，视频真正下载完成可能在之后的轮次，
+//! 「立即下载」的视频也不经过扫描，因此改为在分页下载完成时记录目录，每轮扫描结束后统一刷新。
+
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use anyhow::{bail, Context, Result};
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use tracing::{debug, info, warn};
+
+use crate::config::MediaServerConfig;
+
+/// 单次刷新的目录数量上限，超过时改为刷新整个媒体库
+const MAX_PATH_UPDATES: usize = 100;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MediaServerKind {
+    Jellyfin,
+    Emby,
+    Plex,
+}
+
+impl MediaServerKind {
+    pub fn parse(kind: &str) -> Result<Self> {
+        match kind.trim().to_ascii_lowercase().as_str() {
+            "jellyfin" => Ok(Self::Jellyfin),
+            "emby" => Ok(Self::Emby),
+            "plex" => Ok(Self::Plex),
+            other => bail!("未知的媒体服务器类型: {}", other),
+        }
+    }
+
+    fn display_name(self) -> &'static str {
+        match self {
+            Self::Jellyfin => "Jellyfin",
+            Self::Emby => "Emby",
+            Self::Plex => "Plex",
+        }
+    }
+}
+
+// Jellyfin/Emby 路径更新请求结构
+#[derive(Serialize)]
+#[serde(rename_all = "PascalCase")]
+struct MediaUpdateRequest {
+    updates: Vec<MediaUpdate>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "PascalCase")]
+struct MediaUpdate {
+    path: String,
+    update_type: &'static str,
+}
+
+// Jellyfin/Emby 服务器信息
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct SystemInfo {
+    #[serde(default)]
+    server_name: String,
+    #[serde(default)]
+    version: String,
+}
+
+// Plex 媒体库列表响应结构
+#[derive(Deserialize)]
+struct PlexSectionsResponse {
+    #[serde(rename = "MediaContainer")]
+    media_container: PlexMediaContainer,
+}
+
+#[derive(Deserialize)]
+struct PlexMediaContainer {
+    #[serde(rename = "Directory", default)]
+    directories: Vec<PlexSection>,
+}
+
+#[derive(Deserialize)]
+struct PlexSection {
+    key: String,
+    #[serde(default)]
+    title: String,
+    #[serde(rename = "Location", default)]
+    locations: Vec<PlexLocation>,
+}
+
+#[derive(Deserialize)]
+struct PlexLocation {
+    path: String,
+}
+
+impl PlexSection {
+    /// 目录属于该媒体库的任一位置时返回 true，按路径分段比较，避免 /media/tv 匹配到 /media/tv2
+    fn contains(&self, remote_path: &str) -> bool {
+        self.locations.iter().any(|location| {
+            let root = location.path.trim_end_matches(['/', '\\']);
+            remote_path == root
+                || remote_path
+                    .strip_prefix(root)
+                    .is_some_and(|rest| rest.starts_with(['/', '\\']))
+        })
+    }
+}
+
+pub struct MediaServerClient {
+    client: Client,
+    config: MediaServerConfig,
+    kind: MediaServerKind,
+}
+
+impl MediaServerClient {
+    pub fn new(config: MediaServerConfig) -> Result<Self> {
+        let kind = MediaServerKind::parse(&config.kind)?;
+        if config.url.trim().is_empty() {
+            bail!("未配置媒体服务器地址");
+        }
+        if config.api_key.trim().is_empty() {
+            bail!("未配置媒体服务器 API 密钥");
+        }
+        let client = Client::builder()
+            .timeout(Duration::from_secs(config.timeout.max(1)))
+            .build()
+            .context("创建媒体服务器 HTTP 客户端失败")?;
+        Ok(Self { client, config, kind })
+    }
+
+    fn endpoint(&self, path: &str) -> String {
+        format!("{}{}", self.config.url.trim().trim_end_matches('/'), path)
+    }
+
+    fn get(&self, path: &str) -> reqwest::RequestBuilder {
+        self.authorize(self.client.get(self.endpoint(path)))
+    }
+
+    fn post(&self, path: &str) -> reqwest::RequestBuilder {
+        self.authorize(self.client.post(self.endpoint(path)))
+    }
+
+    fn authorize(&self, request: reqwest::RequestBuilder) -> reqwest::RequestBuilder {
+        let api_key = self.config.api_key.trim();
+        match self.kind {
+            MediaServerKind::Jellyfin | MediaServerKind::Emby => request.header("X-Emby-Token", api_key),
+            MediaServerKind::Plex => request
+                .header("X-Plex-Token", api_key)
+                .header(reqwest::header::ACCEPT, "application/json"),
+        }
+    }
+
+    /// 本程序中的目录转换为媒体服务器中的路径
+    fn remote_path(&self, path: &Path) -> String {
+        let local_prefix = self.config.local_path_prefix.trim();
+        let rest = match path.strip_prefix(local_prefix) {
+            Ok(rest) if !local_prefix.is_empty() => rest,
+            _ => return path.to_string_lossy().into_owned(),
+        };
+        let remote_prefix = self.config.remote_path_prefix.trim();
+        // 媒体服务器运行在 Windows 上时沿用其反斜杠分隔符
+        let separator = if remote_prefix.contains('\\') { '\\' } else { '/' };
+        let mut translated = remote_prefix.trim_end_matches(['/', '\\']).to_string();
+        for component in rest.components() {
+            translated.push(separator);
+            translated.push_str(&component.as_os_str().to_string_lossy());
+        }
+        if translated.is_empty() {
+            translated.push(separator);
+        }
+        translated
+    }
+
+    /// 测试连接和密钥是否可用，成功时返回服务器描述
+    pub async fn test_connection(&self) -> Result<String> {
+        match self.kind {
+            MediaServerKind::Jellyfin | MediaServerKind::Emby => {
+                let info: SystemInfo = self
+                    .get("/System/Info")
+                    .send()
+                    .await?
+                    .error_for_status()?
+                    .json()
+                    .await
+                    .context("解析服务器信息失败")?;
+                Ok(format!(
+                    "{} {} ({})",
+                    self.kind.display_name(),
+                    info.version,
+                    info.server_name
+                ))
+            }
+            MediaServerKind::Plex => {
+                let sections = self.plex_sections().await?;
+                Ok(format!("Plex，共 {} 个媒体库", sections.len()))
+            }
+        }
+    }
+
+    async fn plex_sections(&self) -> Result<Vec<PlexSection>> {
+        let response: PlexSectionsResponse = self
+            .get("/library/sections")
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await
+            .context("解析 Plex 媒体库列表失败")?;
+        Ok(response.media_container.directories)
+    }
+
+    /// 刷新指定的本地目录，返回刷新失败、值得下一轮重试的目录
+    ///
+    /// 整个请求失败时返回错误，此时所有目录都应重试；无法对应到媒体库的目录记录日志后直接丢弃。
+    pub async fn refresh_paths(&self, paths: &[PathBuf]) -> Result<Vec<PathBuf>> {
+        if paths.is_empty() {
+            return Ok(Vec::new());
+        }
+        match self.kind {
+            MediaServerKind::Jellyfin | MediaServerKind::Emby => {
+                let remote_paths = paths.iter().map(|path| self.remote_path(path)).collect();
+                self.refresh_emby_paths(remote_paths).await?;
+                Ok(Vec::new())
+            }
+            MediaServerKind::Plex => self.refresh_plex_paths(paths).await,
+        }
+    }
+
+    async fn refresh_emby_paths(&self, remote_paths: Vec<String>) -> Result<()> {
+        if remote_paths.len() > MAX_PATH_UPDATES {
+            info!(
+                "需要刷新的目录过多 ({} 个)，改为刷新 {} 整个媒体库",
+                remote_paths.len(),
+                self.kind.display_name()
+            );
+            self.post("/Library/Refresh").send().await?.error_for_status()?;
+            return Ok(());
+        }
+        let request = MediaUpdateRequest {
+            updates: remote_paths
+                .into_iter()
+                .map(|path| MediaUpdate {
+                    path,
+                    update_type: "Created",
+                })
+                .collect(),
+        };
+        self.post("/Library/Media/Updated")
+            .json(&request)
+            .send()
+            .await?
+            .error_for_status()?;
+        Ok(())
+    }
+
+    async fn refresh_plex_paths(&self, paths: &[PathBuf]) -> Result<Vec<PathBuf>> {
+        let sections = self.plex_sections().await?;
+        let mut retry = Vec::new();
+        for path in paths {
+            let remote_path = self.remote_path(path);
+            let Some(section) = sections.iter().find(|section| section.contains(&remote_path)) else {
+                // 重试也无法成功，丢弃该目录，避免每轮都重复告警
+                warn!(
+                    "Plex 中没有包含目录 {} 的媒体库，已跳过，请检查路径映射配置",
+                    remote_path
+                );
+                continue;
+            };
+            debug!("通知 Plex 媒体库 {} 刷新目录: {}", section.title, remote_path);
+            let result = self
+                .get(&format!("/library/sections/{}/refresh", section.key))
+                .query(&[("path", &remote_path)])
+                .send()
+                .await
+                .and_then(|response| response.error_for_status());
+            if let Err(e) = result {
+                warn!("通知 Plex 刷新目录 {} 失败，将在下一轮重试: {}", remote_path, e);
+                retry.push(path.clone());
+            }
+        }
+        Ok(retry)
+    }
+}
+
+/// 上次通知媒体服务器后完成下载的视频目录，包括扫描轮次之外「立即下载」的视频
+static DOWNLOADED_FOLDERS: Lazy<Mutex<BTreeSet<PathBuf>>> = Lazy::new(|| Mutex::new(BTreeSet::new()));
+
+/// 记录分页下载完成的视频目录，在本轮扫描结束时通知媒体服务器刷新
+pub fn record_downloaded_folder(folder: &Path) {
+    DOWNLOADED_FOLDERS.lock().insert(folder.to_path_buf());
+}
+
+/// 按配置通知媒体服务器刷新上次通知后完成下载的目录，失败的目录留到下一轮重试
+pub async fn refresh_media_server() -> Result<()> {
+    refresh_downloaded_folders(crate::config::reload_config().media_server).await
+}
+
+async fn refresh_downloaded_folders(config: MediaServerConfig) -> Result<()> {
+    if !config.enabled {
+        DOWNLOADED_FOLDERS.lock().clear();
+        return Ok(());
+    }
+    // 先创建客户端再取出目录，配置有误时目录留在队列中，修正配置后的下一轮继续刷新
+    let client = MediaServerClient::new(config)?;
+    let folders: Vec<PathBuf> = std::mem::take(&mut *DOWNLOADED_FOLDERS.lock()).into_iter().collect();
+    if folders.is_empty() {
+        debug!("本轮没有新下载的视频，跳过媒体服务器刷新");
+        return Ok(());
+    }
+    let retry = match client.refresh_paths(&folders).await {
+        Ok(retry) => retry,
+        Err(e) => {
+            DOWNLOADED_FOLDERS.lock().extend(folders);
+            return Err(e);
+        }
+    };
+    info!(
+        "已通知 {} 刷新 {} 个目录",
+        client.kind.display_name(),
+        folders.len() - retry.len()
+    );
+    if !retry.is_empty() {
+        warn!("{} 个目录刷新失败，将在下一轮重试", retry.len());
+        DOWNLOADED_FOLDERS.lock().extend(retry);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use axum::extract::State;
+    use axum::http::{HeaderMap, Method, Uri};
+    use axum::routing::get;
+    use axum::{Json, Router};
+
+    use super::*;
+
+    /// 记录收到的请求：方法、路径与查询参数、令牌、请求体
+    type Recorded = Arc<Mutex<Vec<(Method, String, String, String)>>>;
+
+    async fn record(State(recorded): State<Recorded>, method: Method, uri: Uri, headers: HeaderMap, body: String) {
+        let token = headers
+            .get("x-emby-token")
+            .or_else(|| headers.get("x-plex-token"))
+            .and_then(|value| value.to_str().ok())
+            .unwrap_or_default()
+            .to_string();
+        recorded.lock().unwrap().push((method, uri.to_string(), token, body));
+    }
+
+    async fn spawn_mock(router: Router<Recorded>) -> (String, Recorded) {
+        let recorded = Recorded::default();
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let app = router.fallback(record).with_state(recorded.clone());
+        tokio::spawn(async move { axum::serve(listener, app).await });
+        (url, recorded)
+    }
+
+    fn config(kind: &str, url: String) -> MediaServerConfig {
+        MediaServerConfig {
+            enabled: true,
+            kind: kind.to_string(),
+            url,
+            api_key: "secret".to_string(),
+            local_path_prefix: "/downloads".to_string(),
+            remote_path_prefix: "/media/bilibili".to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_remote_path() {
+        let client = MediaServerClient::new(config("jellyfin", "http://127.0.0.1".to_string())).unwrap();
+        assert_eq!(
+            client.remote_path(Path::new("/downloads/收藏夹/视频")),
+            "/media/bilibili/收藏夹/视频"
+        );
+        // 不在本地前缀下的目录保持原样
+        assert_eq!(client.remote_path(Path::new("/other/视频")), "/other/视频");
+
+        let mut windows = config("emby", "http://127.0.0.1".to_string());
+        windows.remote_path_prefix = "D:\\Media\\".to_string();
+        let client = MediaServerClient::new(windows).unwrap();
+        assert_eq!(client.remote_path(Path::new("/downloads/a/b")), "D:\\Media\\a\\b");
+
+        assert!(MediaServerClient::new(config("kodi", "http://127.0.0.1".to_string())).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_jellyfin_refresh() {
+        let router = Router::new().route(
+            "/System/Info",
+            get(|| async { Json(serde_json::json!({"ServerName": "home", "Version": "10.9.11"})) }),
+        );
+        let (url, recorded) = spawn_mock(router).await;
+        let client = MediaServerClient::new(config("jellyfin", format!("{}/", url))).unwrap();
+
+        assert_eq!(client.test_connection().await.unwrap(), "Jellyfin 10.9.11 (home)");
+        let retry = client
+            .refresh_paths(&[PathBuf::from("/downloads/收藏夹/视频A")])
+            .await
+            .unwrap();
+        assert!(retry.is_empty());
+
+        let recorded = recorded.lock().unwrap();
+        assert_eq!(recorded.len(), 1);
+        let (method, uri, token, body) = &recorded[0];
+        assert_eq!(method, Method::POST);
+        assert_eq!(uri, "/Library/Media/Updated");
+        assert_eq!(token, "secret");
+        let body: serde_json::Value = serde_json::from_str(body).unwrap();
+        assert_eq!(
+            body,
+            serde_json::json!({"Updates": [{"Path": "/media/bilibili/收藏夹/视频A", "UpdateType": "Created"}]})
+        );
+    }
+
+    #[tokio::test]
+    async fn test_plex_refresh() {
+        // 刷新请求由 fallback 记录
+        let router = Router::new().route(
+            "/library/sections",
+            get(|| async {
+                Json(serde_json::json!({"MediaContainer": {"Directory": [
+                    {"key": "1", "title": "电影", "Location": [{"id": 1, "path": "/media/movies"}]},
+                    {"key": "2", "title": "B站", "Location": [{"id": 2, "path": "/media/bilibili"}]}
+                ]}}))
+            }),
+        );
+        let (url, recorded) = spawn_mock(router).await;
+        let client = MediaServerClient::new(config("plex", url)).unwrap();
+
+        assert_eq!(client.test_connection().await.unwrap(), "Plex，共 2 个媒体库");
+        let retry = client
+            .refresh_paths(&[PathBuf::from("/downloads/UP主/视频 B")])
+            .await
+            .unwrap();
+        assert!(retry.is_empty());
+        {
+            let recorded = recorded.lock().unwrap();
+            assert_eq!(recorded.len(), 1);
+            let (method, uri, token, _) = &recorded[0];
+            assert_eq!(method, Method::GET);
+            assert_eq!(
+                uri,
+                "/library/sections/2/refresh?path=%2Fmedia%2Fbilibili%2FUP%E4%B8%BB%2F%E8%A7%86%E9%A2%91+B"
+            );
+            assert_eq!(token, "secret");
+        }
+
+        // 不属于任何媒体库的目录直接丢弃，不再重试
+        let retry = client.refresh_paths(&[PathBuf::from("/elsewhere/视频")]).await.unwrap();
+        assert!(retry.is_empty());
+        assert_eq!(recorded.lock().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_plex_refresh_retries_failed_paths() {
+        let router = Router::new()
+            .route(
+                "/library/sections",
+                get(|| async {
+                    Json(serde_json::json!({"MediaContainer": {"Directory": [
+                        {"key": "1", "title": "正常", "Location": [{"id": 1, "path": "/media/bilibili/ok"}]},
+                        {"key": "2", "title": "故障", "Location": [{"id": 2, "path": "/media/bilibili/broken"}]}
+                    ]}}))
+                }),
+            )
+            .route(
+                "/library/sections/2/refresh",
+                get(|| async { axum::http::StatusCode::INTERNAL_SERVER_ERROR }),
+            );
+        let (url, _) = spawn_mock(router).await;
+        let client = MediaServerClient::new(config("plex", url)).unwrap();
+
+        let retry = client
+            .refresh_paths(&[
+                PathBuf::from("/downloads/ok/视频"),
+                PathBuf::from("/downloads/broken/视频"),
+                PathBuf::from("/elsewhere/视频"),
+            ])
+            .await
+            .unwrap();
+        // 只有刷新请求失败的目录需要重试，已刷新和无法映射的目录都不再保留
+        assert_eq!(retry, vec![PathBuf::from("/downloads/broken/视频")]);
+    }
+
+    #[tokio::test]
+    async fn test_invalid_config_keeps_folders() {
+        let folder = PathBuf::from("/downloads/配置错误/视频");
+        record_downloaded_folder(&folder);
+        // 未知的服务器类型和缺少 api_key 时都无法创建客户端，已记录的目录保留到下一轮
+        assert!(
+            refresh_downloaded_folders(config("kodi", "http://127.0.0.1".to_string()))
+                .await
+                .is_err()
+        );
+        let mut missing_key = config("jellyfin", "http://127.0.0.1".to_string());
+        missing_key.api_key.clear();
+        assert!(refresh_downloaded_folders(missing_key).await.is_err());
+        assert!(DOWNLOADED_FOLDERS.lock().contains(&folder));
+    }
+}
@@ -0,0 +1,331 @@
+use std::io::Read;
+use std::path::Path;
+use std::sync::Arc;
+
+use anyhow::{bail, Context, Result};
+use bili_sync_entity::{page, video};
+use once_cell::sync::Lazy;
+use parking_lot::RwLock;
+use sea_orm::entity::prelude::*;
+use sea_orm::ActiveValue::{Set, Unchanged};
+use sea_orm::{QuerySelect, TransactionTrait};
+use serde::Serialize;
+use tracing::{error, info, warn};
+use utoipa::ToSchema;
+
+use crate::config::MediaVerifyConfig;
+use crate::utils::status::{PageStatus, VideoStatus, STATUS_OK};
+use crate::utils::time_format::now_standard_string;
+
+/// 一次成功校验得到的文件信息
+#[derive(Debug, Clone)]
+pub struct VerifiedFile {
+    pub size: u64,
+    pub hash: Option<String>,
+}
+
+/// 使用 ffprobe 读取容器时长（秒）
+///
+/// ffprobe 不可用时返回 `Ok(None)`，由调用方退化为仅检查文件大小
+pub async fn probe_duration(path: &Path) -> Result<Option<f64>> {
+    let output = tokio::process::Command::new("ffprobe")
+        .args(["-v", "error", "-show_entries", "format=duration", "-of", "csv=p=0"])
+        .arg(path)
+        .output()
+        .await;
+    let output = match output {
+        Ok(output) => output,
+        Err(e) => {
+            warn!("ffprobe不可用，跳过时长校验: {:#}", e);
+            return Ok(None);
+        }
+    };
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        bail!("ffprobe 无法解析文件: {}", stderr.trim());
+    }
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    match stdout.trim().parse::<f64>() {
+        Ok(duration) => Ok(Some(duration)),
+        Err(_) => bail!("文件缺少有效的时长信息: {}", stdout.trim()),
+    }
+}
+
+/// 判断实际时长是否在允许的误差范围内，期望时长未知（为 0）时直接视为通过
+pub fn duration_within_tolerance(expected: u32, actual: f64, config: &MediaVerifyConfig) -> bool {
+    if expected == 0 {
+        return true;
+    }
+    let expected = expected as f64;
+    let tolerance =
+        (config.duration_tolerance_secs as f64).max(expected * config.duration_tolerance_percent as f64 / 100.0);
+    (expected - actual).abs() <= tolerance
+}
+
+/// 计算文件的 md5，在阻塞线程中执行以免占用异步运行时
+pub async fn hash_file(path: &Path) -> Result<String> {
+    let path = path.to_path_buf();
+    tokio::task::spawn_blocking(move || {
+        let mut file = std::fs::File::open(&path).with_context(|| format!("打开文件失败: {}", path.display()))?;
+        let mut context = md5::Context::new();
+        let mut buffer = vec![0u8; 1024 * 1024];
+        loop {
+            let read = file.read(&mut buffer)?;
+            if read == 0 {
+                break;
+            }
+            context.consume(&buffer[..read]);
+        }
+        Ok(format!("{:x}", context.compute()))
+    })
+    .await?
+}
+
+/// 校验下载完成的媒体文件：非空、ffprobe 可解析、时长与分页信息一致，可选计算哈希
+pub async fn verify_media_file(
+    path: &Path,
+    expected_duration: u32,
+    config: &MediaVerifyConfig,
+) -> Result<VerifiedFile> {
+    let metadata = tokio::fs::metadata(path)
+        .await
+        .with_context(|| format!("无法读取文件元数据: {}", path.display()))?;
+    let size = metadata.len();
+    if size == 0 {
+        bail!("文件为空: {}", path.display());
+    }
+    if let Some(actual) = probe_duration(path).await? {
+        if !duration_within_tolerance(expected_duration, actual, config) {
+            bail!(
+                "文件时长不符（期望 {}s，实际 {:.1}s），可能下载不完整: {}",
+                expected_duration,
+                actual,
+                path.display()
+            );
+        }
+    }
+    let hash = if config.compute_hash {
+        Some(hash_file(path).await?)
+    } else {
+        None
+    };
+    Ok(VerifiedFile { size, hash })
+}
+
+/// 全库校验任务的进度
+#[derive(Serialize, ToSchema, Clone, Default, Debug)]
+pub struct VerifyJobStatus {
+    pub running: bool,
+    pub started_at: Option<String>,
+    pub finished_at: Option<String>,
+    pub total: usize,
+    pub checked: usize,
+    pub passed: usize,
+    pub failed: usize,
+    /// 最近若干个校验失败的文件及原因
+    pub failures: Vec<VerifyFailure>,
+}
+
+#[derive(Serialize, ToSchema, Clone, Debug)]
+pub struct VerifyFailure {
+    pub page_id: i32,
+    pub video_id: i32,
+    pub path: String,
+    pub reason: String,
+}
+
+const MAX_RECORDED_FAILURES: usize = 100;
+
+static VERIFY_JOB: Lazy<RwLock<VerifyJobStatus>> = Lazy::new(|| RwLock::new(VerifyJobStatus::default()));
+
+pub fn verify_job_status() -> VerifyJobStatus {
+    VERIFY_JOB.read().clone()
+}
+
+/// 在后台启动全库校验任务，已有任务在运行时返回 false
+pub fn start_verify_job(db: Arc<DatabaseConnection>, rehash: bool) -> bool {
+    {
+        let mut status = VERIFY_JOB.write();
+        if status.running {
+            return false;
+        }
+        *status = VerifyJobStatus {
+            running: true,
+            started_at: Some(now_standard_string()),
+            ..Default::default()
+        };
+    }
+    tokio::spawn(async move {
+        if let Err(e) = run_verify_job(&db, rehash).await {
+            error!("全库文件校验任务异常结束: {:#}", e);
+        }
+        let mut status = VERIFY_JOB.write();
+        status.running = false;
+        status.finished_at = Some(now_standard_string());
+        info!(
+            "全库文件校验完成：共 {} 个，通过 {} 个，失败 {} 个",
+            status.total, status.passed, status.failed
+        );
+    });
+    true
+}
+
+type VerifyCandidate = (i32, i32, Option<String>, u32, u32, Option<i64>, Option<String>);
+
+async fn run_verify_job(db: &DatabaseConnection, rehash: bool) -> Result<()> {
+    let config = crate::config::reload_config().media_verify;
+    let candidates = page::Entity::find()
+        .inner_join(video::Entity)
+        .filter(video::Column::Deleted.eq(0))
+        .filter(page::Column::Path.is_not_null())
+        .select_only()
+        .columns([
+            page::Column::Id,
+            page::Column::VideoId,
+            page::Column::Path,
+            page::Column::Duration,
+            page::Column::DownloadStatus,
+            page::Column::FileSize,
+            page::Column::FileHash,
+        ])
+        .into_tuple::<VerifyCandidate>()
+        .all(db)
+        .await?
+        .into_iter()
+        .filter(|(_, _, _, _, download_status, _, _)| PageStatus::from(*download_status).get(1) == STATUS_OK)
+        .collect::<Vec<_>>();
+    VERIFY_JOB.write().total = candidates.len();
+    info!("开始全库文件校验，共 {} 个分页", candidates.len());
+
+    for (page_id, video_id, path, duration, download_status, file_size, file_hash) in candidates {
+        let Some(path) = path else { continue };
+        let result = verify_existing_file(Path::new(&path), duration, file_size, file_hash, rehash, &config).await;
+        match result {
+            Ok(verified) => {
+                page::Entity::update(page::ActiveModel {
+                    id: Unchanged(page_id),
+                    file_size: Set(Some(verified.size as i64)),
+                    file_hash: Set(verified.hash),
+                    verified_at: Set(Some(now_standard_string())),
+                    ..Default::default()
+                })
+                .exec(db)
+                .await?;
+                let mut status = VERIFY_JOB.write();
+                status.checked += 1;
+                status.passed += 1;
+            }
+            Err(e) => {
+                warn!("分页文件校验失败，将重新下载: {} ({:#})", path, e);
+                reset_page_video_task(db, page_id, video_id, download_status).await?;
+                let mut status = VERIFY_JOB.write();
+                status.checked += 1;
+                status.failed += 1;
+                if status.failures.len() < MAX_RECORDED_FAILURES {
+                    status.failures.push(VerifyFailure {
+                        page_id,
+                        video_id,
+                        path,
+                        reason: format!("{:#}", e),
+                    });
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+async fn verify_existing_file(
+    path: &Path,
+    duration: u32,
+    file_size: Option<i64>,
+    file_hash: Option<String>,
+    rehash: bool,
+    config: &MediaVerifyConfig,
+) -> Result<VerifiedFile> {
+    if !path.exists() {
+        bail!("文件不存在");
+    }
+    if let Some(expected) = file_size {
+        let actual = tokio::fs::metadata(path).await?.len();
+        if actual as i64 != expected {
+            bail!("文件大小与记录不符（记录 {}，实际 {}）", expected, actual);
+        }
+    }
+    // 已记录哈希且未要求重新计算时，只做快速检查，避免每次都完整读取整个媒体库
+    let need_hash = config.compute_hash && (rehash || file_hash.is_none());
+    let check_config = MediaVerifyConfig {
+        compute_hash: need_hash,
+        ..config.clone()
+    };
+    let mut verified = verify_media_file(path, duration, &check_config).await?;
+    match (&file_hash, &verified.hash) {
+        (Some(expected), Some(actual)) if expected != actual => {
+            bail!("文件哈希与记录不符（记录 {}，实际 {}）", expected, actual);
+        }
+        (Some(_), None) => verified.hash = file_hash,
+        _ => {}
+    }
+    Ok(verified)
+}
+
+/// 将分页的视频子任务与视频的分P下载子任务置零，交由常规下载流程重新下载
+async fn reset_page_video_task(
+    db: &DatabaseConnection,
+    page_id: i32,
+    video_id: i32,
+    download_status: u32,
+) -> Result<()> {
+    let mut page_status = PageStatus::from(download_status);
+    page_status.set(1, 0);
+    let txn = db.begin().await?;
+    page::Entity::update(page::ActiveModel {
+        id: Unchanged(page_id),
+        download_status: Set(page_status.into()),
+        file_size: Set(None),
+        file_hash: Set(None),
+        verified_at: Set(None),
+        ..Default::default()
+    })
+    .exec(&txn)
+    .await?;
+    if let Some(video_model) = video::Entity::find_by_id(video_id).one(&txn).await? {
+        let mut video_status = VideoStatus::from(video_model.download_status);
+        video_status.set(4, 0);
+        video::Entity::update(video::ActiveModel {
+            id: Unchanged(video_id),
+            download_status: Set(video_status.into()),
+            ..Default::default()
+        })
+        .exec(&txn)
+        .await?;
+    }
+    txn.commit().await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_duration_within_tolerance() {
+        let config = MediaVerifyConfig::default();
+        // 期望时长未知时不做判断
+        assert!(duration_within_tolerance(0, 12.0, &config));
+        // 短视频使用秒数下限
+        assert!(duration_within_tolerance(60, 56.0, &config));
+        assert!(!duration_within_tolerance(60, 50.0, &config));
+        // 长视频按比例放宽：3600s * 2% = 72s
+        assert!(duration_within_tolerance(3600, 3540.0, &config));
+        assert!(!duration_within_tolerance(3600, 1800.0, &config));
+    }
+
+    #[tokio::test]
+    async fn test_hash_file() {
+        let path = std::env::temp_dir().join(format!("bili-sync-verify-{}.bin", std::process::id()));
+        tokio::fs::write(&path, b"hello").await.unwrap();
+        assert_eq!(hash_file(&path).await.unwrap(), "5d41402abc4b2a76b9719d911017c592");
+        let _ = tokio::fs::remove_file(&path).await;
+    }
+}
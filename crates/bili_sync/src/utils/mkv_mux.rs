@@ -0,0 +1,148 @@
+//! 将视频、各语言字幕、弹幕和封面封装为单个 mkv 文件
+//!
+//! 字幕和弹幕作为软字幕轨道，封面作为附件，移动文件时不会再和外挂文件分离。
+
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+use tokio::fs;
+
+use crate::utils::atomic_write::{commit_temp, temp_path_for};
+use crate::utils::ffmpeg::run_ffmpeg;
+
+/// 封装 mkv 所需的输入文件
+#[derive(Debug, Default)]
+pub struct MkvInputs {
+    pub video: PathBuf,
+    /// 字幕语言（`SubTitleInfo::lan`）及对应的 srt 文件
+    pub subtitles: Vec<(String, PathBuf)>,
+    /// 弹幕 ass 文件，封装为非默认轨道
+    pub danmaku: Option<PathBuf>,
+    /// 封面图片，封装为附件
+    pub cover: Option<PathBuf>,
+}
+
+/// 将 B 站字幕的语言代码（如 zh-CN、ai-zh、en-US）转换为 mkv 使用的 ISO 639-2 代码
+pub fn subtitle_language(lan: &str) -> &'static str {
+    let lan = lan.strip_prefix("ai-").unwrap_or(lan);
+    let primary = lan.split(['-', '_']).next().unwrap_or_default().to_ascii_lowercase();
+    match primary.as_str() {
+        "zh" => "chi",
+        "en" => "eng",
+        "ja" => "jpn",
+        "ko" => "kor",
+        "es" => "spa",
+        "fr" => "fre",
+        "de" => "ger",
+        "ru" => "rus",
+        "pt" => "por",
+        "it" => "ita",
+        "ar" => "ara",
+        "id" => "ind",
+        "th" => "tha",
+        "vi" => "vie",
+        _ => "und",
+    }
+}
+
+fn ffmpeg_args(inputs: &MkvInputs, output: &Path) -> Vec<String> {
+    let mut args: Vec<String> = ["-hide_banner", "-y", "-i"].map(String::from).into();
+    args.push(inputs.video.to_string_lossy().into_owned());
+    for (_, path) in &inputs.subtitles {
+        args.extend(["-i".to_string(), path.to_string_lossy().into_owned()]);
+    }
+    if let Some(danmaku) = &inputs.danmaku {
+        args.extend(["-i".to_string(), danmaku.to_string_lossy().into_owned()]);
+    }
+    // 0:V 不包含 mp4 中作为视频流存在的内嵌封面，封面改为附件写入
+    args.extend(["-map", "0:V", "-map", "0:a?"].map(String::from));
+    let subtitle_inputs = inputs.subtitles.len() + usize::from(inputs.danmaku.is_some());
+    for index in 1..=subtitle_inputs {
+        args.extend(["-map".to_string(), format!("{}:s", index)]);
+    }
+    args.extend(["-c", "copy"].map(String::from));
+
+    for (index, (lan, _)) in inputs.subtitles.iter().enumerate() {
+        args.extend([
+            format!("-metadata:s:s:{}", index),
+            format!("language={}", subtitle_language(lan)),
+            format!("-metadata:s:s:{}", index),
+            format!("title={}", lan),
+            format!("-disposition:s:{}", index),
+            // 只有第一条字幕作为默认轨道
+            if index == 0 { "default" } else { "0" }.to_string(),
+        ]);
+    }
+    if inputs.danmaku.is_some() {
+        let index = inputs.subtitles.len();
+        args.extend([
+            format!("-metadata:s:s:{}", index),
+            "language=chi".to_string(),
+            format!("-metadata:s:s:{}", index),
+            "title=弹幕".to_string(),
+            format!("-disposition:s:{}", index),
+            "0".to_string(),
+        ]);
+    }
+    if let Some(cover) = &inputs.cover {
+        let (mimetype, filename) = match cover.extension().and_then(|ext| ext.to_str()) {
+            Some(ext) if ext.eq_ignore_ascii_case("png") => ("image/png", "cover.png"),
+            _ => ("image/jpeg", "cover.jpg"),
+        };
+        args.extend([
+            "-attach".to_string(),
+            cover.to_string_lossy().into_owned(),
+            "-metadata:s:t".to_string(),
+            format!("mimetype={}", mimetype),
+            "-metadata:s:t".to_string(),
+            format!("filename={}", filename),
+        ]);
+    }
+    args.extend(["-f", "matroska"].map(String::from));
+    args.push(output.to_string_lossy().into_owned());
+    args
+}
+
+/// 使用 ffmpeg 封装 mkv，先写入临时文件，成功后再替换目标文件
+pub async fn mux_mkv(inputs: &MkvInputs, output: &Path) -> Result<()> {
+    let tmp_output = temp_path_for(output);
+    if let Err(e) = run_ffmpeg(ffmpeg_args(inputs, &tmp_output)).await {
+        let _ = fs::remove_file(&tmp_output).await;
+        return Err(e);
+    }
+    commit_temp(&tmp_output, output).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_subtitle_language() {
+        assert_eq!(subtitle_language("zh-CN"), "chi");
+        assert_eq!(subtitle_language("ai-zh"), "chi");
+        assert_eq!(subtitle_language("en-US"), "eng");
+        assert_eq!(subtitle_language("ja"), "jpn");
+        assert_eq!(subtitle_language("xx"), "und");
+    }
+
+    #[test]
+    fn test_ffmpeg_args() {
+        let inputs = MkvInputs {
+            video: PathBuf::from("/v/a.mp4"),
+            subtitles: vec![
+                ("zh-CN".to_string(), PathBuf::from("/v/a.zh-CN.srt")),
+                ("en-US".to_string(), PathBuf::from("/v/a.en-US.srt")),
+            ],
+            danmaku: Some(PathBuf::from("/v/a.zh-CN.default.ass")),
+            cover: Some(PathBuf::from("/v/a-thumb.jpg")),
+        };
+        let args = ffmpeg_args(&inputs, Path::new("/v/a.mkv.tmp")).join(" ");
+        assert!(args.contains("-i /v/a.mp4 -i /v/a.zh-CN.srt -i /v/a.en-US.srt -i /v/a.zh-CN.default.ass"));
+        assert!(args.contains("-map 0:V -map 0:a? -map 1:s -map 2:s -map 3:s -c copy"));
+        assert!(args.contains("-metadata:s:s:1 language=eng -metadata:s:s:1 title=en-US -disposition:s:1 0"));
+        assert!(args.contains("-metadata:s:s:2 title=弹幕 -disposition:s:2 0"));
+        assert!(args.contains("-attach /v/a-thumb.jpg -metadata:s:t mimetype=image/jpeg"));
+        assert!(args.ends_with("-f matroska /v/a.mkv.tmp"));
+    }
+}
@@ -1,16 +1,32 @@
 pub mod ai_rename;
+pub mod artwork;
+pub mod atomic_write;
+pub mod audio_transcode;
+pub mod bandwidth;
 pub mod bangumi_cache;
 pub mod bangumi_name_extractor;
+pub mod box_set;
+pub mod cdn_health;
 pub mod convert;
 pub mod deepseek_pow;
 pub mod deepseek_web;
+pub mod disk_space;
+pub mod ffmpeg;
 pub mod file_logger;
 pub mod filenamify;
+pub mod fmp4_mux;
 pub mod format_arg;
 pub mod keyword_filter;
+pub mod library_adopt;
+pub mod media_server;
+pub mod media_verify;
+pub mod mkv_mux;
 pub mod model;
+pub mod mp4_box;
+pub mod mp4_tags;
 pub mod nfo;
 pub mod notification;
+pub mod reorganize;
 pub mod scan_collector;
 pub mod scan_id_tracker;
 pub mod signal;
@@ -18,6 +34,7 @@ pub mod status;
 pub mod submission_checkpoint;
 pub mod task_notifier;
 pub mod time_format;
+pub mod trickplay;
 
 use std::fmt;
 use tracing::{Event, Subscriber};
@@ -1,7 +1,7 @@
 use anyhow::{Context, Result};
 use bili_sync_entity::*;
 use sea_orm::entity::prelude::*;
-use sea_orm::sea_query::{OnConflict, SimpleExpr};
+use sea_orm::sea_query::{Expr, OnConflict, SimpleExpr};
 use sea_orm::DatabaseTransaction;
 use std::collections::HashSet;
 use tracing::{debug, info};
@@ -918,6 +918,10 @@ pub async fn create_pages(
 
 /// 更新视频 model 的下载状态
 pub async fn update_videos_model(videos: Vec<video::ActiveModel>, connection: &DatabaseConnection) -> Result<()> {
+    let ids: Vec<i32> = videos
+        .iter()
+        .filter_map(|video| video.id.try_as_ref().copied())
+        .collect();
     video::Entity::insert_many(videos)
         .on_conflict(
             OnConflict::column(video::Column::Id)
@@ -927,6 +931,15 @@ pub async fn update_videos_model(videos: Vec<video::ActiveModel>, connection: &D
         .exec(connection)
         .await?;
 
+    // 「立即下载」提升的优先级只在下载完成前有效，完成后恢复默认，避免重置后仍排在队列最前
+    video::Entity::update_many()
+        .col_expr(video::Column::Priority, Expr::value(0))
+        .filter(video::Column::Id.is_in(ids))
+        .filter(video::Column::DownloadStatus.gte(STATUS_COMPLETED))
+        .filter(video::Column::Priority.ne(0))
+        .exec(connection)
+        .await?;
+
     Ok(())
 }
 
@@ -934,7 +947,13 @@ pub async fn update_videos_model(videos: Vec<video::ActiveModel>, connection: &D
 pub async fn update_pages_model(pages: Vec<page::ActiveModel>, connection: &DatabaseConnection) -> Result<()> {
     let query = page::Entity::insert_many(pages).on_conflict(
         OnConflict::column(page::Column::Id)
-            .update_columns([page::Column::DownloadStatus, page::Column::Path])
+            .update_columns([
+                page::Column::DownloadStatus,
+                page::Column::Path,
+                page::Column::FileSize,
+                page::Column::FileHash,
+                page::Column::VerifiedAt,
+            ])
             .to_owned(),
     );
     query.exec(connection).await?;
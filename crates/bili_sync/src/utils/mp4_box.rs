@@ -0,0 +1,251 @@
+//! MP4（ISO BMFF）box 的读写，供内置音视频合并和元数据写入共用
+
+use std::io::{self, Read, Seek, SeekFrom, Write};
+
+use anyhow::{anyhow, bail, ensure, Context, Result};
+
+/// moov、moof 等元数据 box 的大小上限，超出时视为不支持的输入，避免把整个文件读进内存
+pub const MAX_METADATA_BOX_SIZE: u64 = 64 * 1024 * 1024;
+
+/// 需要展开子 box 的容器类型，其余 box 按原始内容保留
+const CONTAINER_KINDS: [&[u8; 4]; 10] = [
+    b"moov", b"trak", b"mdia", b"minf", b"stbl", b"edts", b"udta", b"mvex", b"moof", b"traf",
+];
+
+#[derive(Debug, Clone)]
+pub enum Body {
+    Leaf(Vec<u8>),
+    Container(Vec<Mp4Box>),
+}
+
+#[derive(Debug, Clone)]
+pub struct Mp4Box {
+    pub kind: [u8; 4],
+    pub body: Body,
+}
+
+impl Mp4Box {
+    pub fn kind_str(&self) -> String {
+        String::from_utf8_lossy(&self.kind).into_owned()
+    }
+
+    pub fn data(&self) -> Result<&[u8]> {
+        match &self.body {
+            Body::Leaf(data) => Ok(data),
+            Body::Container(_) => bail!("{} 不是数据 box", self.kind_str()),
+        }
+    }
+
+    pub fn data_mut(&mut self) -> Result<&mut Vec<u8>> {
+        let kind = self.kind_str();
+        match &mut self.body {
+            Body::Leaf(data) => Ok(data),
+            Body::Container(_) => bail!("{} 不是数据 box", kind),
+        }
+    }
+
+    pub fn children(&self) -> &[Mp4Box] {
+        match &self.body {
+            Body::Container(children) => children,
+            Body::Leaf(_) => &[],
+        }
+    }
+
+    pub fn children_mut(&mut self) -> &mut [Mp4Box] {
+        match &mut self.body {
+            Body::Container(children) => children,
+            Body::Leaf(_) => &mut [],
+        }
+    }
+
+    pub fn child(&self, kind: &[u8; 4]) -> Result<&Mp4Box> {
+        self.children()
+            .iter()
+            .find(|child| &child.kind == kind)
+            .ok_or_else(|| anyhow!("{} 中缺少 {}", self.kind_str(), String::from_utf8_lossy(kind)))
+    }
+
+    pub fn child_mut(&mut self, kind: &[u8; 4]) -> Result<&mut Mp4Box> {
+        let parent = self.kind_str();
+        self.children_mut()
+            .iter_mut()
+            .find(|child| &child.kind == kind)
+            .ok_or_else(|| anyhow!("{} 中缺少 {}", parent, String::from_utf8_lossy(kind)))
+    }
+
+    pub fn children_of<'a>(&'a self, kind: &'a [u8; 4]) -> impl Iterator<Item = &'a Mp4Box> {
+        self.children().iter().filter(move |child| &child.kind == kind)
+    }
+
+    pub fn write_to(&self, out: &mut Vec<u8>) {
+        let mut payload = Vec::new();
+        match &self.body {
+            Body::Leaf(data) => payload.extend_from_slice(data),
+            Body::Container(children) => children.iter().for_each(|child| child.write_to(&mut payload)),
+        }
+        let size = payload.len() as u64 + 8;
+        if size > u32::MAX as u64 {
+            out.extend_from_slice(&1u32.to_be_bytes());
+            out.extend_from_slice(&self.kind);
+            out.extend_from_slice(&(size + 8).to_be_bytes());
+        } else {
+            out.extend_from_slice(&(size as u32).to_be_bytes());
+            out.extend_from_slice(&self.kind);
+        }
+        out.extend_from_slice(&payload);
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        self.write_to(&mut out);
+        out
+    }
+}
+
+pub fn leaf(kind: &[u8; 4], data: Vec<u8>) -> Mp4Box {
+    Mp4Box {
+        kind: *kind,
+        body: Body::Leaf(data),
+    }
+}
+
+pub fn parse_boxes(mut data: &[u8]) -> Result<Vec<Mp4Box>> {
+    let mut boxes = Vec::new();
+    while !data.is_empty() {
+        ensure!(data.len() >= 8, "box 头部不完整");
+        let size = read_u32(data, 0)? as u64;
+        let kind: [u8; 4] = data[4..8].try_into()?;
+        let (header_len, size) = match size {
+            0 => (8, data.len() as u64),
+            1 => (16, read_u64(data, 8)?),
+            size => (8, size),
+        };
+        ensure!(
+            size >= header_len && size <= data.len() as u64,
+            "box {} 的大小 {} 无效",
+            String::from_utf8_lossy(&kind),
+            size
+        );
+        let payload = &data[header_len as usize..size as usize];
+        let body = if CONTAINER_KINDS.contains(&&kind) {
+            Body::Container(parse_boxes(payload)?)
+        } else {
+            Body::Leaf(payload.to_vec())
+        };
+        boxes.push(Mp4Box { kind, body });
+        data = &data[size as usize..];
+    }
+    Ok(boxes)
+}
+
+pub fn read_u32(data: &[u8], offset: usize) -> Result<u32> {
+    let bytes = data.get(offset..offset + 4).ok_or_else(|| anyhow!("box 内容不完整"))?;
+    Ok(u32::from_be_bytes(bytes.try_into()?))
+}
+
+pub fn read_u64(data: &[u8], offset: usize) -> Result<u64> {
+    let bytes = data.get(offset..offset + 8).ok_or_else(|| anyhow!("box 内容不完整"))?;
+    Ok(u64::from_be_bytes(bytes.try_into()?))
+}
+
+pub fn write_u32(data: &mut [u8], offset: usize, value: u32) -> Result<()> {
+    let bytes = data
+        .get_mut(offset..offset + 4)
+        .ok_or_else(|| anyhow!("box 内容不完整"))?;
+    bytes.copy_from_slice(&value.to_be_bytes());
+    Ok(())
+}
+
+pub fn write_u64(data: &mut [u8], offset: usize, value: u64) -> Result<()> {
+    let bytes = data
+        .get_mut(offset..offset + 8)
+        .ok_or_else(|| anyhow!("box 内容不完整"))?;
+    bytes.copy_from_slice(&value.to_be_bytes());
+    Ok(())
+}
+
+/// FullBox 的版本号和标志位
+pub fn version_and_flags(data: &[u8]) -> Result<(u8, u32)> {
+    let value = read_u32(data, 0)?;
+    Ok(((value >> 24) as u8, value & 0x00ff_ffff))
+}
+
+/// 读取或写入按版本区分为 32/64 位的字段，`offset_v0` / `offset_v1` 为两种版本下的偏移
+pub fn read_versioned(data: &[u8], offset_v0: usize, offset_v1: usize) -> Result<u64> {
+    match version_and_flags(data)?.0 {
+        1 => read_u64(data, offset_v1),
+        _ => read_u32(data, offset_v0).map(u64::from),
+    }
+}
+
+pub fn write_versioned(data: &mut [u8], offset_v0: usize, offset_v1: usize, value: u64) -> Result<()> {
+    match version_and_flags(data)?.0 {
+        1 => write_u64(data, offset_v1, value),
+        _ => write_u32(data, offset_v0, value.min(u32::MAX as u64) as u32),
+    }
+}
+
+/// 顶层 box 在文件中的位置
+pub struct TopBox {
+    pub kind: [u8; 4],
+    pub offset: u64,
+    pub size: u64,
+}
+
+pub fn scan_top_level<R: Read + Seek>(reader: &mut R) -> Result<Vec<TopBox>> {
+    let file_len = reader.seek(SeekFrom::End(0))?;
+    let mut boxes = Vec::new();
+    let mut offset = 0;
+    while offset < file_len {
+        reader.seek(SeekFrom::Start(offset))?;
+        let mut header = [0u8; 8];
+        reader.read_exact(&mut header).context("box 头部不完整")?;
+        let kind: [u8; 4] = header[4..8].try_into()?;
+        let size = match read_u32(&header, 0)? {
+            // 延伸到文件末尾的 box 无法在其后继续追加内容
+            0 => bail!("不支持大小为 0 的 {} box", String::from_utf8_lossy(&kind)),
+            1 => {
+                let mut large = [0u8; 8];
+                reader.read_exact(&mut large)?;
+                u64::from_be_bytes(large)
+            }
+            size => size as u64,
+        };
+        ensure!(
+            size >= 8 && offset + size <= file_len,
+            "box {} 的大小 {} 超出文件范围，文件可能不完整",
+            String::from_utf8_lossy(&kind),
+            size
+        );
+        boxes.push(TopBox { kind, offset, size });
+        offset += size;
+    }
+    Ok(boxes)
+}
+
+pub fn read_top_box_raw<R: Read + Seek>(reader: &mut R, top: &TopBox) -> Result<Vec<u8>> {
+    ensure!(
+        top.size <= MAX_METADATA_BOX_SIZE,
+        "{} box 过大（{} 字节）",
+        String::from_utf8_lossy(&top.kind),
+        top.size
+    );
+    let mut data = vec![0u8; top.size as usize];
+    reader.seek(SeekFrom::Start(top.offset))?;
+    reader.read_exact(&mut data)?;
+    Ok(data)
+}
+
+pub fn read_top_box<R: Read + Seek>(reader: &mut R, top: &TopBox) -> Result<Mp4Box> {
+    parse_boxes(&read_top_box_raw(reader, top)?)?
+        .pop()
+        .ok_or_else(|| anyhow!("无法解析 {} box", String::from_utf8_lossy(&top.kind)))
+}
+
+/// 将输入文件中的一段原样复制到输出
+pub fn copy_range<R: Read + Seek, W: Write>(reader: &mut R, writer: &mut W, offset: u64, size: u64) -> Result<()> {
+    reader.seek(SeekFrom::Start(offset))?;
+    let copied = io::copy(&mut reader.take(size), writer)?;
+    ensure!(copied == size, "读取文件内容不完整");
+    Ok(())
+}
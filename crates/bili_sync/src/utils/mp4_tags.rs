@@ -0,0 +1,392 @@
+//! 向下载完成的 mp4/m4a 文件写入元数据标签、封面和章节
+//!
+//! 使用 iTunes 风格的 `moov/udta/meta/ilst`，大多数播放器和媒体库都能识别。写入时只重建 moov，
+//! 媒体数据原样复制；moov 位于 mdat 之前时，会同步修正 stco/co64 中的块偏移。
+
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Seek, Write};
+use std::path::Path;
+
+use anyhow::{anyhow, bail, ensure, Context, Result};
+
+use crate::utils::atomic_write::{commit_temp, temp_path_for};
+use crate::utils::mp4_box::{
+    copy_range, leaf, parse_boxes, read_top_box, read_u32, read_u64, scan_top_level, version_and_flags, write_u32,
+    write_u64, Body, Mp4Box,
+};
+
+const TFHD_BASE_DATA_OFFSET: u32 = 0x1;
+
+// data box 中的数据类型
+const DATA_TYPE_IMPLICIT: u32 = 0;
+const DATA_TYPE_UTF8: u32 = 1;
+const DATA_TYPE_JPEG: u32 = 13;
+const DATA_TYPE_PNG: u32 = 14;
+
+/// ilst 中由本模块写入的条目，写入前会先移除文件中已有的同名条目
+const MANAGED_ITEMS: [&[u8; 4]; 9] = [
+    b"\xa9nam", b"\xa9ART", b"\xa9alb", b"\xa9day", b"desc", b"ldes", b"\xa9gen", b"trkn", b"covr",
+];
+
+/// 需要写入文件的元数据
+#[derive(Debug, Clone, Default)]
+pub struct MediaTags {
+    pub title: String,
+    /// 作者（UP 主）
+    pub artist: Option<String>,
+    /// 多 P 视频的分页以视频标题作为专辑
+    pub album: Option<String>,
+    pub date: Option<String>,
+    pub description: Option<String>,
+    pub genres: Vec<String>,
+    /// 分页序号和总分页数
+    pub track: Option<(u16, u16)>,
+    /// 封面图片的原始内容，仅支持 jpeg 和 png
+    pub cover: Option<Vec<u8>>,
+    /// 章节的起始时间（毫秒）和标题
+    pub chapters: Vec<(u64, String)>,
+}
+
+/// 根据文件头判断封面的图片格式
+fn cover_data_type(image: &[u8]) -> Option<u32> {
+    if image.starts_with(&[0xff, 0xd8, 0xff]) {
+        Some(DATA_TYPE_JPEG)
+    } else if image.starts_with(b"\x89PNG\r\n\x1a\n") {
+        Some(DATA_TYPE_PNG)
+    } else {
+        None
+    }
+}
+
+fn item(kind: &[u8; 4], data_type: u32, value: &[u8]) -> Mp4Box {
+    let mut data = Vec::with_capacity(value.len() + 8);
+    data.extend_from_slice(&data_type.to_be_bytes());
+    // locale
+    data.extend_from_slice(&0u32.to_be_bytes());
+    data.extend_from_slice(value);
+    leaf(kind, leaf(b"data", data).to_bytes())
+}
+
+fn text_item(kind: &[u8; 4], value: &str) -> Option<Mp4Box> {
+    let value = value.trim();
+    (!value.is_empty()).then(|| item(kind, DATA_TYPE_UTF8, value.as_bytes()))
+}
+
+impl MediaTags {
+    fn items(&self) -> Vec<Mp4Box> {
+        let mut items = Vec::new();
+        items.extend(text_item(b"\xa9nam", &self.title));
+        items.extend(self.artist.as_deref().and_then(|v| text_item(b"\xa9ART", v)));
+        items.extend(self.album.as_deref().and_then(|v| text_item(b"\xa9alb", v)));
+        items.extend(self.date.as_deref().and_then(|v| text_item(b"\xa9day", v)));
+        if let Some(description) = self.description.as_deref() {
+            items.extend(text_item(b"desc", description));
+            items.extend(text_item(b"ldes", description));
+        }
+        items.extend(text_item(b"\xa9gen", &self.genres.join("; ")));
+        if let Some((number, total)) = self.track {
+            let mut value = Vec::with_capacity(8);
+            value.extend_from_slice(&0u16.to_be_bytes());
+            value.extend_from_slice(&number.to_be_bytes());
+            value.extend_from_slice(&total.to_be_bytes());
+            value.extend_from_slice(&0u16.to_be_bytes());
+            items.push(item(b"trkn", DATA_TYPE_IMPLICIT, &value));
+        }
+        if let Some(cover) = self.cover.as_deref() {
+            if let Some(data_type) = cover_data_type(cover) {
+                items.push(item(b"covr", data_type, cover));
+            }
+        }
+        items
+    }
+}
+
+/// 截断到不超过 `max_len` 字节的字符边界
+fn truncate_utf8(value: &str, max_len: usize) -> &str {
+    let mut end = value.len().min(max_len);
+    while !value.is_char_boundary(end) {
+        end -= 1;
+    }
+    &value[..end]
+}
+
+/// Nero 格式的章节列表（udta/chpl），ffmpeg 及基于它的媒体库都能读取
+fn build_chpl(chapters: &[(u64, String)]) -> Mp4Box {
+    let chapters = &chapters[..chapters.len().min(u8::MAX as usize)];
+    let mut data = Vec::new();
+    // version 1 及其后的保留字段
+    data.extend_from_slice(&0x0100_0000u32.to_be_bytes());
+    data.extend_from_slice(&0u32.to_be_bytes());
+    data.push(chapters.len() as u8);
+    for (start_ms, title) in chapters {
+        // 时间单位为 100 纳秒
+        data.extend_from_slice(&start_ms.saturating_mul(10_000).to_be_bytes());
+        let title = truncate_utf8(title, u8::MAX as usize);
+        data.push(title.len() as u8);
+        data.extend_from_slice(title.as_bytes());
+    }
+    leaf(b"chpl", data)
+}
+
+fn replace_or_push(children: &mut Vec<Mp4Box>, new_box: Mp4Box) {
+    match children.iter_mut().find(|child| child.kind == new_box.kind) {
+        Some(existing) => *existing = new_box,
+        None => children.push(new_box),
+    }
+}
+
+/// 读取已有 meta 中 ilst 的条目，无法识别的 meta 视为不存在
+fn existing_items(meta: &Mp4Box) -> Vec<Mp4Box> {
+    let Ok(data) = meta.data() else {
+        return Vec::new();
+    };
+    let Some(children) = data.get(4..).and_then(|payload| parse_boxes(payload).ok()) else {
+        return Vec::new();
+    };
+    children
+        .into_iter()
+        .find(|child| &child.kind == b"ilst")
+        .and_then(|ilst| parse_boxes(ilst.data().ok()?).ok())
+        .unwrap_or_default()
+}
+
+fn build_meta(items: Vec<Mp4Box>) -> Mp4Box {
+    let mut hdlr = Vec::new();
+    // version/flags、pre_defined
+    hdlr.extend_from_slice(&[0u8; 8]);
+    hdlr.extend_from_slice(b"mdir");
+    hdlr.extend_from_slice(b"appl");
+    hdlr.extend_from_slice(&[0u8; 8]);
+    // 空的名称
+    hdlr.push(0);
+    let mut ilst = Vec::new();
+    items.iter().for_each(|item| item.write_to(&mut ilst));
+
+    let mut meta = vec![0u8; 4];
+    leaf(b"hdlr", hdlr).write_to(&mut meta);
+    leaf(b"ilst", ilst).write_to(&mut meta);
+    leaf(b"meta", meta)
+}
+
+/// 将标签写入 moov，保留 ilst 中不由本模块管理的条目（例如编码器信息）
+fn apply_tags(moov: &mut Mp4Box, tags: &MediaTags) -> Result<()> {
+    let Body::Container(children) = &mut moov.body else {
+        bail!("moov 不是容器 box");
+    };
+    if !children.iter().any(|child| &child.kind == b"udta") {
+        children.push(Mp4Box {
+            kind: *b"udta",
+            body: Body::Container(Vec::new()),
+        });
+    }
+    let udta = moov.child_mut(b"udta")?;
+    let Body::Container(udta_children) = &mut udta.body else {
+        bail!("udta 不是容器 box");
+    };
+    let new_items = tags.items();
+    if !new_items.is_empty() {
+        let mut items = udta_children
+            .iter()
+            .find(|child| &child.kind == b"meta")
+            .map(existing_items)
+            .unwrap_or_default();
+        items.retain(|item| !MANAGED_ITEMS.contains(&&item.kind));
+        items.extend(new_items);
+        replace_or_push(udta_children, build_meta(items));
+    }
+    if !tags.chapters.is_empty() {
+        replace_or_push(udta_children, build_chpl(&tags.chapters));
+    }
+    Ok(())
+}
+
+/// 将所有轨道的块偏移平移 `delta` 字节
+fn shift_chunk_offsets(moov: &mut Mp4Box, delta: i64) -> Result<()> {
+    for trak in moov.children_mut().iter_mut().filter(|child| &child.kind == b"trak") {
+        let stbl = trak.child_mut(b"mdia")?.child_mut(b"minf")?.child_mut(b"stbl")?;
+        for table in stbl.children_mut() {
+            let wide = match &table.kind {
+                b"stco" => false,
+                b"co64" => true,
+                _ => continue,
+            };
+            let data = table.data_mut()?;
+            let count = read_u32(data, 4)? as usize;
+            for index in 0..count {
+                if wide {
+                    let offset = 8 + index * 8;
+                    let value = read_u64(data, offset)?
+                        .checked_add_signed(delta)
+                        .ok_or_else(|| anyhow!("co64 块偏移溢出"))?;
+                    write_u64(data, offset, value)?;
+                } else {
+                    let offset = 8 + index * 4;
+                    let value = (read_u32(data, offset)? as u64)
+                        .checked_add_signed(delta)
+                        .and_then(|value| u32::try_from(value).ok())
+                        .ok_or_else(|| anyhow!("stco 块偏移超出 32 位范围"))?;
+                    write_u32(data, offset, value)?;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// 分片的 tfhd 使用绝对偏移时，移动 moov 会使其失效
+fn ensure_relative_fragment(moof: &Mp4Box) -> Result<()> {
+    for traf in moof.children_of(b"traf") {
+        let (_, flags) = version_and_flags(traf.child(b"tfhd")?.data()?)?;
+        ensure!(flags & TFHD_BASE_DATA_OFFSET == 0, "不支持使用绝对偏移的分片");
+    }
+    Ok(())
+}
+
+fn rewrite<R: Read + Seek, W: Write>(reader: &mut R, writer: &mut W, tags: &MediaTags) -> Result<()> {
+    let tops = scan_top_level(reader)?;
+    let mut moov_tops = tops.iter().filter(|top| &top.kind == b"moov");
+    let moov_top = moov_tops.next().ok_or_else(|| anyhow!("文件中没有 moov"))?;
+    ensure!(moov_tops.next().is_none(), "文件中有多个 moov");
+
+    let mut moov = read_top_box(reader, moov_top)?;
+    apply_tags(&mut moov, tags)?;
+    let delta = moov.to_bytes().len() as i64 - moov_top.size as i64;
+    if delta != 0 {
+        let after_moov = || tops.iter().filter(|top| top.offset > moov_top.offset);
+        for top in after_moov().filter(|top| &top.kind == b"moof") {
+            ensure_relative_fragment(&read_top_box(reader, top)?)?;
+        }
+        if after_moov().any(|top| &top.kind == b"mdat") {
+            shift_chunk_offsets(&mut moov, delta)?;
+        }
+    }
+
+    let last_offset = tops.last().map(|top| top.offset);
+    for top in &tops {
+        match &top.kind {
+            b"moov" => writer.write_all(&moov.to_bytes())?,
+            // 位于末尾的 mfra 记录了各分片的绝对位置，移动后已失效，播放时并不需要
+            b"mfra" if Some(top.offset) == last_offset && delta != 0 => {}
+            _ => copy_range(reader, writer, top.offset, top.size)?,
+        }
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// 将标签写入 `input`，结果保存到 `output`
+pub fn write_tags(input: &Path, output: &Path, tags: &MediaTags) -> Result<()> {
+    let mut reader = BufReader::new(File::open(input)?);
+    let file = File::create(output)?;
+    let mut writer = BufWriter::new(&file);
+    rewrite(&mut reader, &mut writer, tags)?;
+    drop(writer);
+    file.sync_all()?;
+    Ok(())
+}
+
+/// 向已下载完成的 mp4/m4a 文件写入标签，先写入临时文件再替换原文件
+pub async fn embed_tags(path: &Path, tags: MediaTags) -> Result<()> {
+    let tmp_path = temp_path_for(path);
+    let (input, output) = (path.to_path_buf(), tmp_path.clone());
+    let result = tokio::task::spawn_blocking(move || write_tags(&input, &output, &tags))
+        .await
+        .context("写入元数据的任务异常退出")?;
+    if let Err(e) = result {
+        let _ = tokio::fs::remove_file(&tmp_path).await;
+        return Err(e);
+    }
+    commit_temp(&tmp_path, path).await
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+
+    fn container(kind: &[u8; 4], children: &[Vec<u8>]) -> Vec<u8> {
+        leaf(kind, children.concat()).to_bytes()
+    }
+
+    fn stco(offsets: &[u32]) -> Vec<u8> {
+        let mut data = vec![0u8; 4];
+        data.extend_from_slice(&(offsets.len() as u32).to_be_bytes());
+        offsets
+            .iter()
+            .for_each(|offset| data.extend_from_slice(&offset.to_be_bytes()));
+        leaf(b"stco", data).to_bytes()
+    }
+
+    /// ftyp + moov + mdat，stco 指向 mdat 的内容
+    fn faststart_file(payload: &[u8]) -> Vec<u8> {
+        let ftyp = leaf(b"ftyp", b"isom\0\0\0\0".to_vec()).to_bytes();
+        let build_moov = |chunk_offset: u32| {
+            let stbl = container(b"stbl", &[stco(&[chunk_offset])]);
+            let trak = container(b"trak", &[container(b"mdia", &[container(b"minf", &[stbl])])]);
+            container(b"moov", &[trak])
+        };
+        let moov_len = build_moov(0).len() as u32;
+        let chunk_offset = ftyp.len() as u32 + moov_len + 8;
+        [
+            ftyp,
+            build_moov(chunk_offset),
+            leaf(b"mdat", payload.to_vec()).to_bytes(),
+        ]
+        .concat()
+    }
+
+    fn ilst_items(moov: &Mp4Box) -> Vec<Mp4Box> {
+        existing_items(moov.child(b"udta").unwrap().child(b"meta").unwrap())
+    }
+
+    #[test]
+    fn test_embed_tags_shifts_chunk_offsets() {
+        let input = faststart_file(b"media-payload");
+        let tags = MediaTags {
+            title: "标题".to_string(),
+            artist: Some("UP主".to_string()),
+            date: Some("2024-01-02".to_string()),
+            genres: vec!["游戏".to_string(), "单机".to_string()],
+            track: Some((2, 3)),
+            cover: Some(vec![0xff, 0xd8, 0xff, 0xe0, 1, 2, 3]),
+            chapters: vec![(0, "开场".to_string()), (95_000, "正片".to_string())],
+            ..Default::default()
+        };
+        let mut output = Vec::new();
+        rewrite(&mut Cursor::new(&input), &mut output, &tags).unwrap();
+
+        let boxes = parse_boxes(&output).unwrap();
+        let moov = boxes.iter().find(|b| &b.kind == b"moov").unwrap();
+        let stco = moov
+            .child(b"trak")
+            .unwrap()
+            .child(b"mdia")
+            .unwrap()
+            .child(b"minf")
+            .unwrap();
+        let stco = stco.child(b"stbl").unwrap().child(b"stco").unwrap();
+        let chunk_offset = read_u32(stco.data().unwrap(), 8).unwrap() as usize;
+        assert_eq!(&output[chunk_offset..], b"media-payload");
+
+        let items = ilst_items(moov);
+        let kinds = items.iter().map(|item| &item.kind).collect::<Vec<_>>();
+        assert_eq!(
+            kinds,
+            vec![b"\xa9nam", b"\xa9ART", b"\xa9day", b"\xa9gen", b"trkn", b"covr"]
+        );
+        let genre = parse_boxes(items[3].data().unwrap()).unwrap();
+        assert_eq!(&genre[0].data().unwrap()[8..], "游戏; 单机".as_bytes());
+        let cover = parse_boxes(items[5].data().unwrap()).unwrap();
+        assert_eq!(read_u32(cover[0].data().unwrap(), 0).unwrap(), DATA_TYPE_JPEG);
+
+        let chpl = moov.child(b"udta").unwrap().child(b"chpl").unwrap().data().unwrap();
+        assert_eq!(chpl[8], 2);
+        assert_eq!(read_u64(chpl, 9 + 8 + 1 + "开场".len()).unwrap(), 950_000_000);
+
+        // 再次写入时替换已有条目，不会重复
+        let mut again = Vec::new();
+        rewrite(&mut Cursor::new(&output), &mut again, &tags).unwrap();
+        assert_eq!(again, output);
+    }
+}
@@ -7,7 +7,8 @@ use quick_xml::writer::Writer;
 use quick_xml::Error;
 use tokio::io::{AsyncWriteExt, BufWriter};
 
-use crate::config::{EmptyUpperStrategy, NFOConfig, NFOTimeType};
+use crate::bilibili::{try_bvid_to_aid, Chapter};
+use crate::config::{EmptyUpperStrategy, NFOConfig, NFOProfile, NFOTimeType};
 
 #[allow(clippy::upper_case_acronyms)]
 pub enum NFO<'a> {
@@ -16,6 +17,7 @@ pub enum NFO<'a> {
     Upper(Upper),
     Episode(Episode<'a>),
     Season(Season<'a>),
+    BoxSet(CollectionSet),
 }
 
 pub struct Movie<'a> {
@@ -27,6 +29,7 @@ pub struct Movie<'a> {
     pub upper_name: &'a str,
     pub aired: NaiveDateTime,
     pub premiered: NaiveDateTime,
+    pub pubtime: NaiveDateTime, // 实际发布时间，Jellyfin 等按它填写首映日期
     pub tags: Option<Vec<String>>,
     pub user_rating: Option<f32>,
     pub mpaa: Option<&'a str>,
@@ -46,6 +49,8 @@ pub struct Movie<'a> {
     pub cover_url: &'a str,                        // 封面图片URL
     pub fanart_url: Option<&'a str>,               // 背景图片URL
     pub upper_face_url: Option<&'a str>,           // UP主头像URL（用于演员thumb）
+    pub chapters: &'a [Chapter],                   // 分段章节
+    pub collection_set: Option<CollectionSet>,     // 所属收藏夹/合集
 }
 
 pub struct TVShow<'a> {
@@ -57,6 +62,7 @@ pub struct TVShow<'a> {
     pub upper_name: &'a str,
     pub aired: NaiveDateTime,
     pub premiered: NaiveDateTime,
+    pub pubtime: NaiveDateTime, // 实际发布时间，Jellyfin 等按它填写首映日期
     pub tags: Option<Vec<String>>,
     pub user_rating: Option<f32>,
     pub mpaa: Option<&'a str>,
@@ -79,6 +85,14 @@ pub struct TVShow<'a> {
     pub upper_face_url: Option<&'a str>,           // UP主头像URL（用于演员thumb）
     pub season_id: Option<String>,                 // 番剧季度ID（从API获取）
     pub media_id: Option<i64>,                     // 媒体ID（从API获取）
+    pub collection_set: Option<CollectionSet>,     // 所属收藏夹/合集
+}
+
+/// 视频源对应的媒体服务器合集（Kodi 的 set，Jellyfin/Emby 的 BoxSet）
+#[derive(Clone, Debug, PartialEq)]
+pub struct CollectionSet {
+    pub name: String,
+    pub overview: Option<String>,
 }
 
 pub struct Upper {
@@ -99,14 +113,16 @@ pub struct Episode<'a> {
     pub user_rating: Option<f32>,
     pub director: Option<&'a str>,
     pub credits: Option<&'a str>,
-    pub bvid: &'a str,               // B站视频ID
-    pub category: i32,               // 视频分类（用于番剧检测）
-    pub mpaa: Option<&'a str>,       // 年龄分级
-    pub country: Option<&'a str>,    // 国家
-    pub studio: Option<&'a str>,     // 制作工作室
-    pub genres: Option<Vec<String>>, // 类型标签
-    pub thumb_url: Option<&'a str>,  // 缩略图URL
-    pub fanart_url: Option<&'a str>, // 背景图URL
+    pub bvid: &'a str,                         // B站视频ID
+    pub category: i32,                         // 视频分类（用于番剧检测）
+    pub mpaa: Option<&'a str>,                 // 年龄分级
+    pub country: Option<&'a str>,              // 国家
+    pub studio: Option<&'a str>,               // 制作工作室
+    pub genres: Option<Vec<String>>,           // 类型标签
+    pub thumb_url: Option<&'a str>,            // 缩略图URL
+    pub fanart_url: Option<&'a str>,           // 背景图URL
+    pub chapters: &'a [Chapter],               // 分段章节
+    pub collection_set: Option<CollectionSet>, // 所属收藏夹/合集
 }
 
 pub struct Season<'a> {
@@ -119,6 +135,7 @@ pub struct Season<'a> {
     pub upper_name: &'a str,
     pub aired: NaiveDateTime,
     pub premiered: NaiveDateTime,
+    pub pubtime: NaiveDateTime, // 实际发布时间，Jellyfin 等按它填写首映日期
     pub tags: Option<Vec<String>>,
     pub user_rating: Option<f32>,
     pub mpaa: Option<&'a str>,
@@ -142,9 +159,36 @@ pub struct Season<'a> {
     pub media_id: Option<i64>,                     // 媒体ID
 }
 
+impl<'a> NFO<'a> {
+    /// 普通单P视频的 NFO，按输出方案写为电影或剧集中的一集
+    pub fn single_page(
+        video: &'a video::Model,
+        page: &'a page::Model,
+        chapters: &'a [Chapter],
+        collection_set: Option<CollectionSet>,
+        profile: NFOProfile,
+    ) -> Self {
+        if profile.single_page_as_movie() {
+            let mut movie = Movie::from_video_with_pages(video, std::slice::from_ref(page));
+            movie.chapters = chapters;
+            movie.collection_set = collection_set;
+            NFO::Movie(movie)
+        } else {
+            let mut episode = Episode::from_video_and_page(video, page);
+            episode.chapters = chapters;
+            episode.collection_set = collection_set;
+            NFO::Episode(episode)
+        }
+    }
+}
+
 impl NFO<'_> {
     pub async fn generate_nfo(self) -> Result<String> {
         let config = crate::config::reload_config();
+        self.generate_nfo_with_config(&config.nfo_config).await
+    }
+
+    async fn generate_nfo_with_config(self, config: &NFOConfig) -> Result<String> {
         let mut buffer = r#"<?xml version="1.0" encoding="utf-8" standalone="yes"?>
 "#
         .as_bytes()
@@ -153,19 +197,22 @@ impl NFO<'_> {
         let writer = Writer::new_with_indent(&mut tokio_buffer, b' ', 4);
         match self {
             NFO::Movie(movie) => {
-                Self::write_movie_nfo(writer, movie, &config.nfo_config).await?;
+                Self::write_movie_nfo(writer, movie, config).await?;
             }
             NFO::TVShow(tvshow) => {
-                Self::write_tvshow_nfo(writer, tvshow, &config.nfo_config).await?;
+                Self::write_tvshow_nfo(writer, tvshow, config).await?;
             }
             NFO::Upper(upper) => {
                 Self::write_upper_nfo(writer, upper).await?;
             }
             NFO::Episode(episode) => {
-                Self::write_episode_nfo(writer, episode, &config.nfo_config).await?;
+                Self::write_episode_nfo(writer, episode, config).await?;
             }
             NFO::Season(season) => {
-                Self::write_season_nfo(writer, season, &config.nfo_config).await?;
+                Self::write_season_nfo(writer, season, config).await?;
+            }
+            NFO::BoxSet(collection_set) => {
+                Self::write_box_set_nfo(writer, collection_set, config).await?;
             }
         }
         tokio_buffer.flush().await?;
@@ -246,6 +293,7 @@ impl NFO<'_> {
                         })
                         .await?;
                 }
+                Self::write_collection_set(writer, movie.collection_set.as_ref(), movie.set.is_some()).await?;
 
                 // 评分信息
                 if let Some(rating) = movie.user_rating {
@@ -258,7 +306,7 @@ impl NFO<'_> {
                 // 剧情简介
                 writer
                     .create_element("plot")
-                    .write_cdata_content_async(BytesCData::new(Self::format_plot(movie.bvid, movie.intro)))
+                    .write_cdata_content_async(BytesCData::new(Self::format_plot(config, movie.bvid, movie.intro)))
                     .await?;
                 writer.create_element("outline").write_empty_async().await?;
 
@@ -271,12 +319,7 @@ impl NFO<'_> {
                 }
 
                 // 唯一标识符
-                writer
-                    .create_element("uniqueid")
-                    .with_attribute(("type", "bilibili"))
-                    .with_attribute(("default", "true"))
-                    .write_text_content_async(BytesText::new(movie.bvid))
-                    .await?;
+                Self::write_unique_ids(writer, config, movie.bvid, movie.bvid, None, None).await?;
 
                 // 类型标签
                 if let Some(tags) = movie.tags {
@@ -329,18 +372,7 @@ impl NFO<'_> {
                 }
 
                 // 时间信息
-                writer
-                    .create_element("year")
-                    .write_text_content_async(BytesText::new(&movie.aired.format("%Y").to_string()))
-                    .await?;
-                writer
-                    .create_element("premiered")
-                    .write_text_content_async(BytesText::new(&movie.premiered.format("%Y-%m-%d").to_string()))
-                    .await?;
-                writer
-                    .create_element("aired")
-                    .write_text_content_async(BytesText::new(&movie.aired.format("%Y-%m-%d").to_string()))
-                    .await?;
+                Self::write_dates(writer, config, movie.aired, movie.premiered, movie.pubtime).await?;
 
                 // 制作信息
                 if let Some(studio) = movie.studio {
@@ -387,7 +419,7 @@ impl NFO<'_> {
                             let mid_value = *mid;
                             let name_clone = name.clone();
                             let title_clone = title.clone();
-                            let face_clone = face.clone();
+                            let thumb = face.as_deref().and_then(|face| Self::actor_thumb(config, name, face));
                             writer
                                 .create_element("actor")
                                 .write_inner_content_async::<_, _, Error>(|writer| async move {
@@ -399,14 +431,12 @@ impl NFO<'_> {
                                         .create_element("role")
                                         .write_text_content_async(BytesText::new(&title_clone))
                                         .await?;
-                                    // 头像URL
-                                    if let Some(ref thumb) = face_clone {
-                                        if !thumb.is_empty() {
-                                            writer
-                                                .create_element("thumb")
-                                                .write_text_content_async(BytesText::new(thumb))
-                                                .await?;
-                                        }
+                                    // 头像
+                                    if let Some(ref thumb) = thumb {
+                                        writer
+                                            .create_element("thumb")
+                                            .write_text_content_async(BytesText::new(thumb))
+                                            .await?;
                                     }
                                     // 稳定标识：B站空间链接（便于脚本按UID映射）
                                     if mid_value > 0 {
@@ -431,6 +461,9 @@ impl NFO<'_> {
                         let actor_info = Self::get_actor_info(movie.upper_id, movie.upper_name, config);
                         if let Some((actor_name, role_name)) = actor_info {
                             let upper_id = movie.upper_id;
+                            let thumb = movie
+                                .upper_face_url
+                                .and_then(|face| Self::actor_thumb(config, movie.upper_name, face));
                             writer
                                 .create_element("actor")
                                 .write_inner_content_async::<_, _, Error>(|writer| async move {
@@ -443,13 +476,11 @@ impl NFO<'_> {
                                         .write_text_content_async(BytesText::new(&role_name))
                                         .await?;
                                     // 头像（如果有）
-                                    if let Some(thumb) = movie.upper_face_url {
-                                        if !thumb.is_empty() {
-                                            writer
-                                                .create_element("thumb")
-                                                .write_text_content_async(BytesText::new(thumb))
-                                                .await?;
-                                        }
+                                    if let Some(ref thumb) = thumb {
+                                        writer
+                                            .create_element("thumb")
+                                            .write_text_content_async(BytesText::new(thumb))
+                                            .await?;
                                     }
                                     // 稳定标识：B站空间链接（便于脚本按UID映射）
                                     if upper_id > 0 {
@@ -480,6 +511,8 @@ impl NFO<'_> {
                         .await?;
                 }
 
+                Self::write_chapters(writer, movie.chapters).await?;
+
                 // B站特有信息作为自定义标签
                 if config.include_bilibili_info {
                     if let Some(view_count) = movie.view_count {
@@ -632,11 +665,12 @@ impl NFO<'_> {
                         })
                         .await?;
                 }
+                Self::write_collection_set(writer, tvshow.collection_set.as_ref(), tvshow.set.is_some()).await?;
 
                 // 剧情简介
                 writer
                     .create_element("plot")
-                    .write_cdata_content_async(BytesCData::new(Self::format_plot(tvshow.bvid, tvshow.intro)))
+                    .write_cdata_content_async(BytesCData::new(Self::format_plot(config, tvshow.bvid, tvshow.intro)))
                     .await?;
                 writer.create_element("outline").write_empty_async().await?;
 
@@ -656,31 +690,16 @@ impl NFO<'_> {
                         .await?;
                 }
 
-                // 唯一标识符
-                writer
-                    .create_element("uniqueid")
-                    .with_attribute(("type", "bilibili"))
-                    .with_attribute(("default", "true"))
-                    .write_text_content_async(BytesText::new(tvshow.bvid))
-                    .await?;
-
-                // 添加番剧季度ID作为额外的uniqueid
-                if let Some(ref season_id) = tvshow.season_id {
-                    writer
-                        .create_element("uniqueid")
-                        .with_attribute(("type", "bilibili_season"))
-                        .write_text_content_async(BytesText::new(season_id))
-                        .await?;
-                }
-
-                // 添加媒体ID作为额外的uniqueid
-                if let Some(media_id) = tvshow.media_id {
-                    writer
-                        .create_element("uniqueid")
-                        .with_attribute(("type", "bilibili_media"))
-                        .write_text_content_async(BytesText::new(&media_id.to_string()))
-                        .await?;
-                }
+                // 唯一标识符（含番剧季度ID和媒体ID）
+                Self::write_unique_ids(
+                    writer,
+                    config,
+                    tvshow.bvid,
+                    tvshow.bvid,
+                    tvshow.season_id.as_deref(),
+                    tvshow.media_id,
+                )
+                .await?;
 
                 // 类型标签
                 if let Some(tags) = tvshow.tags {
@@ -734,18 +753,7 @@ impl NFO<'_> {
                 }
 
                 // 时间信息
-                writer
-                    .create_element("year")
-                    .write_text_content_async(BytesText::new(&tvshow.aired.format("%Y").to_string()))
-                    .await?;
-                writer
-                    .create_element("premiered")
-                    .write_text_content_async(BytesText::new(&tvshow.premiered.format("%Y-%m-%d").to_string()))
-                    .await?;
-                writer
-                    .create_element("aired")
-                    .write_text_content_async(BytesText::new(&tvshow.aired.format("%Y-%m-%d").to_string()))
-                    .await?;
+                Self::write_dates(writer, config, tvshow.aired, tvshow.premiered, tvshow.pubtime).await?;
 
                 // 制作信息
                 if let Some(studio) = tvshow.studio {
@@ -792,7 +800,7 @@ impl NFO<'_> {
                             let mid_value = *mid;
                             let name_clone = name.clone();
                             let title_clone = title.clone();
-                            let face_clone = face.clone();
+                            let thumb = face.as_deref().and_then(|face| Self::actor_thumb(config, name, face));
                             writer
                                 .create_element("actor")
                                 .write_inner_content_async::<_, _, Error>(|writer| async move {
@@ -804,14 +812,12 @@ impl NFO<'_> {
                                         .create_element("role")
                                         .write_text_content_async(BytesText::new(&title_clone))
                                         .await?;
-                                    // 头像URL
-                                    if let Some(ref thumb) = face_clone {
-                                        if !thumb.is_empty() {
-                                            writer
-                                                .create_element("thumb")
-                                                .write_text_content_async(BytesText::new(thumb))
-                                                .await?;
-                                        }
+                                    // 头像
+                                    if let Some(ref thumb) = thumb {
+                                        writer
+                                            .create_element("thumb")
+                                            .write_text_content_async(BytesText::new(thumb))
+                                            .await?;
                                     }
                                     // 稳定标识：B站空间链接（便于脚本按UID映射）
                                     if mid_value > 0 {
@@ -836,6 +842,9 @@ impl NFO<'_> {
                         let actor_info = Self::get_actor_info(tvshow.upper_id, tvshow.upper_name, config);
                         if let Some((actor_name, role_name)) = actor_info {
                             let upper_id = tvshow.upper_id;
+                            let thumb = tvshow
+                                .upper_face_url
+                                .and_then(|face| Self::actor_thumb(config, tvshow.upper_name, face));
                             writer
                                 .create_element("actor")
                                 .write_inner_content_async::<_, _, Error>(|writer| async move {
@@ -848,13 +857,11 @@ impl NFO<'_> {
                                         .write_text_content_async(BytesText::new(&role_name))
                                         .await?;
                                     // 头像（如果有）
-                                    if let Some(thumb) = tvshow.upper_face_url {
-                                        if !thumb.is_empty() {
-                                            writer
-                                                .create_element("thumb")
-                                                .write_text_content_async(BytesText::new(thumb))
-                                                .await?;
-                                        }
+                                    if let Some(ref thumb) = thumb {
+                                        writer
+                                            .create_element("thumb")
+                                            .write_text_content_async(BytesText::new(thumb))
+                                            .await?;
                                     }
                                     // 稳定标识：B站空间链接（便于脚本按UID映射）
                                     if upper_id > 0 {
@@ -925,6 +932,40 @@ impl NFO<'_> {
         Ok(())
     }
 
+    /// 合集文件夹的 NFO：Kodi 的 set.nfo 使用 overview，Jellyfin/Emby 的 collection.nfo 使用 plot
+    async fn write_box_set_nfo(
+        mut writer: Writer<&mut BufWriter<&mut Vec<u8>>>,
+        collection_set: CollectionSet,
+        config: &NFOConfig,
+    ) -> Result<()> {
+        let (root, overview_element) = match config.profile {
+            NFOProfile::Kodi => ("set", "overview"),
+            _ => ("collection", "plot"),
+        };
+        writer
+            .create_element(root)
+            .write_inner_content_async::<_, _, Error>(|writer| async move {
+                writer
+                    .create_element("title")
+                    .write_text_content_async(BytesText::new(&collection_set.name))
+                    .await?;
+                match &collection_set.overview {
+                    Some(overview) => {
+                        writer
+                            .create_element(overview_element)
+                            .write_cdata_content_async(BytesCData::new(overview))
+                            .await?;
+                    }
+                    None => {
+                        writer.create_element(overview_element).write_empty_async().await?;
+                    }
+                }
+                Ok(writer)
+            })
+            .await?;
+        Ok(())
+    }
+
     async fn write_upper_nfo(mut writer: Writer<&mut BufWriter<&mut Vec<u8>>>, upper: Upper) -> Result<()> {
         writer
             .create_element("person")
@@ -1016,12 +1057,7 @@ impl NFO<'_> {
                     } else {
                         &episode.pid
                     };
-                writer
-                    .create_element("uniqueid")
-                    .with_attribute(("type", "bilibili"))
-                    .with_attribute(("default", "true"))
-                    .write_text_content_async(BytesText::new(unique_id))
-                    .await?;
+                Self::write_unique_ids(writer, config, unique_id, episode.bvid, None, None).await?;
 
                 // 类型标签
                 if let Some(ref genres) = episode.genres {
@@ -1097,6 +1133,9 @@ impl NFO<'_> {
                         .await?;
                 }
 
+                Self::write_chapters(writer, episode.chapters).await?;
+                Self::write_collection_set(writer, episode.collection_set.as_ref(), false).await?;
+
                 // 评分信息
                 if let Some(rating) = episode.user_rating {
                     writer
@@ -1228,12 +1267,16 @@ impl NFO<'_> {
                 // 剧情简介 - 为Season添加季度特定的前缀
                 let season_plot = if Self::is_bangumi_video(season.category) {
                     if let Some(season_title) = Self::extract_season_title_from_full_name(season.name) {
-                        format!("【{}】{}", season_title, Self::format_plot(season.bvid, season.intro))
+                        format!(
+                            "【{}】{}",
+                            season_title,
+                            Self::format_plot(config, season.bvid, season.intro)
+                        )
                     } else {
-                        Self::format_plot(season.bvid, season.intro)
+                        Self::format_plot(config, season.bvid, season.intro)
                     }
                 } else {
-                    Self::format_plot(season.bvid, season.intro)
+                    Self::format_plot(config, season.bvid, season.intro)
                 };
                 writer
                     .create_element("plot")
@@ -1257,31 +1300,16 @@ impl NFO<'_> {
                         .await?;
                 }
 
-                // 唯一标识符
-                writer
-                    .create_element("uniqueid")
-                    .with_attribute(("type", "bilibili"))
-                    .with_attribute(("default", "true"))
-                    .write_text_content_async(BytesText::new(season.bvid))
-                    .await?;
-
-                // 添加番剧季度ID作为额外的uniqueid
-                if let Some(ref season_id) = season.season_id {
-                    writer
-                        .create_element("uniqueid")
-                        .with_attribute(("type", "bilibili_season"))
-                        .write_text_content_async(BytesText::new(season_id))
-                        .await?;
-                }
-
-                // 添加媒体ID作为额外的uniqueid
-                if let Some(media_id) = season.media_id {
-                    writer
-                        .create_element("uniqueid")
-                        .with_attribute(("type", "bilibili_media"))
-                        .write_text_content_async(BytesText::new(&media_id.to_string()))
-                        .await?;
-                }
+                // 唯一标识符（含番剧季度ID和媒体ID）
+                Self::write_unique_ids(
+                    writer,
+                    config,
+                    season.bvid,
+                    season.bvid,
+                    season.season_id.as_deref(),
+                    season.media_id,
+                )
+                .await?;
 
                 // 类型标签
                 if let Some(tags) = season.tags {
@@ -1328,18 +1356,7 @@ impl NFO<'_> {
                 }
 
                 // 时间信息
-                writer
-                    .create_element("year")
-                    .write_text_content_async(BytesText::new(&season.aired.format("%Y").to_string()))
-                    .await?;
-                writer
-                    .create_element("premiered")
-                    .write_text_content_async(BytesText::new(&season.premiered.format("%Y-%m-%d").to_string()))
-                    .await?;
-                writer
-                    .create_element("aired")
-                    .write_text_content_async(BytesText::new(&season.aired.format("%Y-%m-%d").to_string()))
-                    .await?;
+                Self::write_dates(writer, config, season.aired, season.premiered, season.pubtime).await?;
 
                 // 制作信息
                 if let Some(studio) = season.studio {
@@ -1386,7 +1403,7 @@ impl NFO<'_> {
                             let mid_value = *mid;
                             let name_clone = name.clone();
                             let title_clone = title.clone();
-                            let face_clone = face.clone();
+                            let thumb = face.as_deref().and_then(|face| Self::actor_thumb(config, name, face));
                             writer
                                 .create_element("actor")
                                 .write_inner_content_async::<_, _, Error>(|writer| async move {
@@ -1398,14 +1415,12 @@ impl NFO<'_> {
                                         .create_element("role")
                                         .write_text_content_async(BytesText::new(&title_clone))
                                         .await?;
-                                    // 头像URL
-                                    if let Some(ref thumb) = face_clone {
-                                        if !thumb.is_empty() {
-                                            writer
-                                                .create_element("thumb")
-                                                .write_text_content_async(BytesText::new(thumb))
-                                                .await?;
-                                        }
+                                    // 头像
+                                    if let Some(ref thumb) = thumb {
+                                        writer
+                                            .create_element("thumb")
+                                            .write_text_content_async(BytesText::new(thumb))
+                                            .await?;
                                     }
                                     // 稳定标识：B站空间链接（便于脚本按UID映射）
                                     if mid_value > 0 {
@@ -1430,6 +1445,9 @@ impl NFO<'_> {
                         let actor_info = Self::get_actor_info(season.upper_id, season.upper_name, config);
                         if let Some((actor_name, role_name)) = actor_info {
                             let upper_id = season.upper_id;
+                            let thumb = season
+                                .upper_face_url
+                                .and_then(|face| Self::actor_thumb(config, season.upper_name, face));
                             writer
                                 .create_element("actor")
                                 .write_inner_content_async::<_, _, Error>(|writer| async move {
@@ -1442,13 +1460,11 @@ impl NFO<'_> {
                                         .write_text_content_async(BytesText::new(&role_name))
                                         .await?;
                                     // 头像（如果有）
-                                    if let Some(thumb) = season.upper_face_url {
-                                        if !thumb.is_empty() {
-                                            writer
-                                                .create_element("thumb")
-                                                .write_text_content_async(BytesText::new(thumb))
-                                                .await?;
-                                        }
+                                    if let Some(ref thumb) = thumb {
+                                        writer
+                                            .create_element("thumb")
+                                            .write_text_content_async(BytesText::new(thumb))
+                                            .await?;
                                     }
                                     // 稳定标识：B站空间链接（便于脚本按UID映射）
                                     if upper_id > 0 {
@@ -1520,11 +1536,15 @@ impl NFO<'_> {
     }
 
     #[inline]
-    fn format_plot(bvid: &str, intro: &str) -> String {
-        format!(
-            r#"原始视频：<a href="https://www.bilibili.com/video/{}/">{}</a><br/><br/>{}"#,
-            bvid, bvid, intro,
-        )
+    fn format_plot(config: &NFOConfig, bvid: &str, intro: &str) -> String {
+        match config.profile {
+            // Plex 会原样显示 HTML 标签
+            NFOProfile::Plex => format!("原始视频：https://www.bilibili.com/video/{}/\n\n{}", bvid, intro),
+            _ => format!(
+                r#"原始视频：<a href="https://www.bilibili.com/video/{}/">{}</a><br/><br/>{}"#,
+                bvid, bvid, intro,
+            ),
+        }
     }
 
     /// 检测是否为番剧视频（基于 category 字段）
@@ -1607,6 +1627,187 @@ impl NFO<'_> {
         1
     }
 
+    /// 写入分段章节，沿用 Jellyfin 章节信息的字段（名称 + 起始位置），起止时间以毫秒为单位；
+    /// 视频文件内同时嵌入了章节，媒体库扫描时两者一致
+    async fn write_chapters<W: tokio::io::AsyncWrite + Unpin>(
+        writer: &mut Writer<W>,
+        chapters: &[Chapter],
+    ) -> std::result::Result<(), Error> {
+        if chapters.is_empty() {
+            return Ok(());
+        }
+        writer
+            .create_element("chapters")
+            .write_inner_content_async::<_, _, Error>(|writer| async move {
+                for chapter in chapters {
+                    writer
+                        .create_element("chapter")
+                        .write_inner_content_async::<_, _, Error>(|writer| async move {
+                            writer
+                                .create_element("name")
+                                .write_text_content_async(BytesText::new(&chapter.content))
+                                .await?;
+                            writer
+                                .create_element("startpositionms")
+                                .write_text_content_async(BytesText::new(&(u64::from(chapter.from) * 1000).to_string()))
+                                .await?;
+                            writer
+                                .create_element("endpositionms")
+                                .write_text_content_async(BytesText::new(&(u64::from(chapter.to) * 1000).to_string()))
+                                .await?;
+                            Ok(writer)
+                        })
+                        .await?;
+                }
+                Ok(writer)
+            })
+            .await?;
+        Ok(())
+    }
+
+    /// 写入所属收藏夹/合集：Kodi 和 Jellyfin/Emby 按 set 归组，Plex 可按同名 tag 建立智能合集；
+    /// 番剧已用 set 表示系列时只追加 tag，避免同一条目出现两个 set
+    async fn write_collection_set<W: tokio::io::AsyncWrite + Unpin>(
+        writer: &mut Writer<W>,
+        collection_set: Option<&CollectionSet>,
+        has_set: bool,
+    ) -> std::result::Result<(), Error> {
+        let Some(collection_set) = collection_set else {
+            return Ok(());
+        };
+        if !has_set {
+            writer
+                .create_element("set")
+                .write_inner_content_async::<_, _, Error>(|writer| async move {
+                    writer
+                        .create_element("name")
+                        .write_text_content_async(BytesText::new(&collection_set.name))
+                        .await?;
+                    if let Some(overview) = &collection_set.overview {
+                        writer
+                            .create_element("overview")
+                            .write_text_content_async(BytesText::new(overview))
+                            .await?;
+                    }
+                    Ok(writer)
+                })
+                .await?;
+        }
+        writer
+            .create_element("tag")
+            .write_text_content_async(BytesText::new(&collection_set.name))
+            .await?;
+        Ok(())
+    }
+
+    /// 演员头像：Jellyfin/Emby 引用 upper_path 中按人物元数据目录结构缓存的头像，
+    /// 与服务器 People 目录中的图片保持一致；Kodi 和 Plex 直接使用头像链接
+    fn actor_thumb(config: &NFOConfig, name: &str, face_url: &str) -> Option<String> {
+        if face_url.is_empty() {
+            return None;
+        }
+        match config.profile {
+            NFOProfile::Jellyfin | NFOProfile::Emby => {
+                let name = crate::utils::filenamify::filenamify(name);
+                let first_char = name.chars().next()?.to_string();
+                let path = crate::config::with_config(|bundle| bundle.config.upper_path.clone())
+                    .join(first_char)
+                    .join(&name)
+                    .join("folder.jpg");
+                Some(path.to_string_lossy().into_owned())
+            }
+            NFOProfile::Kodi | NFOProfile::Plex => Some(face_url.to_string()),
+        }
+    }
+
+    /// 写入唯一标识，bvid 之外附带 aid 及番剧的季度ID、媒体ID，便于媒体服务器重新扫描时稳定匹配
+    async fn write_unique_ids<W: tokio::io::AsyncWrite + Unpin>(
+        writer: &mut Writer<W>,
+        config: &NFOConfig,
+        default_id: &str,
+        bvid: &str,
+        season_id: Option<&str>,
+        media_id: Option<i64>,
+    ) -> std::result::Result<(), Error> {
+        if config.profile == NFOProfile::Plex {
+            // XBMCnfo 导入器只读取 id 元素
+            writer
+                .create_element("id")
+                .write_text_content_async(BytesText::new(default_id))
+                .await?;
+        }
+        writer
+            .create_element("uniqueid")
+            .with_attribute(("type", "bilibili"))
+            .with_attribute(("default", "true"))
+            .write_text_content_async(BytesText::new(default_id))
+            .await?;
+        if let Some(aid) = try_bvid_to_aid(bvid) {
+            writer
+                .create_element("uniqueid")
+                .with_attribute(("type", "bilibili_aid"))
+                .write_text_content_async(BytesText::new(&aid.to_string()))
+                .await?;
+        }
+        if let Some(season_id) = season_id {
+            writer
+                .create_element("uniqueid")
+                .with_attribute(("type", "bilibili_season"))
+                .write_text_content_async(BytesText::new(season_id))
+                .await?;
+        }
+        if let Some(media_id) = media_id {
+            writer
+                .create_element("uniqueid")
+                .with_attribute(("type", "bilibili_media"))
+                .write_text_content_async(BytesText::new(&media_id.to_string()))
+                .await?;
+        }
+        Ok(())
+    }
+
+    /// 写入年份与日期
+    ///
+    /// Jellyfin/Emby 把 aired 和 premiered 都当作首映日期，后写入的会覆盖前者，
+    /// 因此首映日期只使用发布时间，配置的时间类型（默认收藏时间）写入 dateadded 用于「最近添加」排序；
+    /// Plex 不读取 dateadded，只写发布时间。
+    async fn write_dates<W: tokio::io::AsyncWrite + Unpin>(
+        writer: &mut Writer<W>,
+        config: &NFOConfig,
+        aired: NaiveDateTime,
+        premiered: NaiveDateTime,
+        pubtime: NaiveDateTime,
+    ) -> std::result::Result<(), Error> {
+        let (year, premiered) = match config.profile {
+            NFOProfile::Kodi => (aired, premiered),
+            NFOProfile::Jellyfin | NFOProfile::Emby | NFOProfile::Plex => (pubtime, pubtime),
+        };
+        writer
+            .create_element("year")
+            .write_text_content_async(BytesText::new(&year.format("%Y").to_string()))
+            .await?;
+        writer
+            .create_element("premiered")
+            .write_text_content_async(BytesText::new(&premiered.format("%Y-%m-%d").to_string()))
+            .await?;
+        match config.profile {
+            NFOProfile::Kodi => {
+                writer
+                    .create_element("aired")
+                    .write_text_content_async(BytesText::new(&aired.format("%Y-%m-%d").to_string()))
+                    .await?;
+            }
+            NFOProfile::Jellyfin | NFOProfile::Emby => {
+                writer
+                    .create_element("dateadded")
+                    .write_text_content_async(BytesText::new(&aired.format("%Y-%m-%d %H:%M:%S").to_string()))
+                    .await?;
+            }
+            NFOProfile::Plex => {}
+        }
+        Ok(())
+    }
+
     /// 从share_copy或标题中提取副标题信息
     fn extract_subtitle_from_share_copy(share_copy: &str) -> Option<String> {
         // 匹配 "《番剧名称》副标题" 格式，提取副标题
@@ -1785,6 +1986,7 @@ impl<'a> From<&'a video::Model> for Movie<'a> {
             upper_name: &video.upper_name,
             aired: aired_time,
             premiered: aired_time,
+            pubtime: video.pubtime,
             tags: video
                 .tags
                 .as_ref()
@@ -1811,6 +2013,8 @@ impl<'a> From<&'a video::Model> for Movie<'a> {
             } else {
                 None
             },
+            chapters: &[],
+            collection_set: None,
         }
     }
 }
@@ -1880,6 +2084,7 @@ impl<'a> From<&'a video::Model> for TVShow<'a> {
             upper_name: &video.upper_name,
             aired: aired_time,
             premiered: aired_time,
+            pubtime: video.pubtime,
             tags: video
                 .tags
                 .as_ref()
@@ -1917,6 +2122,7 @@ impl<'a> From<&'a video::Model> for TVShow<'a> {
             },
             season_id: None, // 普通视频没有season_id
             media_id: None,  // 普通视频没有media_id
+            collection_set: None,
         }
     }
 }
@@ -2043,6 +2249,11 @@ impl<'a> TVShow<'a> {
             upper_name: &video.upper_name,
             aired: aired_time,
             premiered: aired_time,
+            pubtime: season_info
+                .publish_time
+                .as_deref()
+                .and_then(parse_time_string)
+                .unwrap_or(video.pubtime),
             tags: genres,
             user_rating: season_info.rating,
             mpaa: None, // 可以从API的"分级"字段获取，但目前API中没有
@@ -2079,6 +2290,7 @@ impl<'a> TVShow<'a> {
             // 使用season_id和media_id作为额外的uniqueid（通过扩展字段传递）
             season_id: Some(season_info.season_id.clone()),
             media_id: season_info.media_id,
+            collection_set: None,
         }
     }
 }
@@ -2115,6 +2327,8 @@ impl<'a> From<&'a page::Model> for Episode<'a> {
             genres: None,                              // 无类型标签
             thumb_url: None,                           // 暂不设置本地路径
             fanart_url: None,                          // 暂不设置本地路径
+            chapters: &[],                             // 章节需要单独获取
+            collection_set: None,
         }
     }
 }
@@ -2157,6 +2371,8 @@ impl<'a> Episode<'a> {
                 .and_then(|tags| serde_json::from_value(tags.clone()).ok()), // 从视频标签提取类型
             thumb_url: None,                                          // 暂不设置本地路径
             fanart_url: None,                                         // 暂不设置本地路径
+            chapters: &[],                                            // 章节需要单独获取
+            collection_set: None,
         }
     }
 }
@@ -2227,6 +2443,7 @@ impl<'a> From<&'a video::Model> for Season<'a> {
             upper_name: &video.upper_name,
             aired: aired_time,
             premiered: aired_time,
+            pubtime: video.pubtime,
             tags: video
                 .tags
                 .as_ref()
@@ -2314,6 +2531,11 @@ impl<'a> Season<'a> {
             upper_name: &video.upper_name,
             aired: aired_time,
             premiered: aired_time,
+            pubtime: season_info
+                .publish_time
+                .as_deref()
+                .and_then(parse_time_string)
+                .unwrap_or(video.pubtime),
             tags: genres,
             user_rating: season_info.rating,
             mpaa: None, // 可以从API的"分级"字段获取，但目前API中没有
@@ -2724,6 +2946,89 @@ mod tests {
         println!("增强NFO功能测试通过");
     }
 
+    #[tokio::test]
+    async fn test_episode_chapters() {
+        let video = video::Model {
+            name: "测试视频".to_string(),
+            single_page: Some(false),
+            ..Default::default()
+        };
+        let page = page::Model {
+            pid: 1,
+            name: "P1".to_string(),
+            ..Default::default()
+        };
+        let chapters = vec![
+            Chapter {
+                from: 0,
+                to: 95,
+                content: "开场".to_string(),
+            },
+            Chapter {
+                from: 95,
+                to: 600,
+                content: "正片 & 花絮".to_string(),
+            },
+        ];
+        let mut episode = Episode::from_video_and_page(&video, &page);
+        episode.chapters = &chapters;
+        let nfo = NFO::Episode(episode).generate_nfo().await.unwrap();
+        assert_eq!(nfo.matches("<chapter>").count(), 2);
+        assert!(nfo.contains("<name>正片 &amp; 花絮</name>"));
+        assert!(nfo.contains("<startpositionms>95000</startpositionms>"));
+        assert!(nfo.contains("<endpositionms>600000</endpositionms>"));
+    }
+
+    #[tokio::test]
+    async fn test_collection_set() {
+        let video = video::Model {
+            name: "测试视频".to_string(),
+            bvid: "BV1Tr421n746".to_string(),
+            single_page: Some(true),
+            ..Default::default()
+        };
+        let collection_set = CollectionSet {
+            name: "我的收藏".to_string(),
+            overview: Some("收藏夹简介".to_string()),
+        };
+        let mut movie: Movie = (&video).into();
+        movie.collection_set = Some(collection_set.clone());
+        let nfo = NFO::Movie(movie)
+            .generate_nfo_with_config(&NFOConfig::default())
+            .await
+            .unwrap();
+        assert!(
+            nfo.contains("<set>\n        <name>我的收藏</name>\n        <overview>收藏夹简介</overview>\n    </set>")
+        );
+        assert!(nfo.contains("<tag>我的收藏</tag>"));
+
+        // 番剧已用 set 表示系列时只写入 tag
+        let mut movie: Movie = (&video).into();
+        movie.set = Some("系列".to_string());
+        movie.collection_set = Some(collection_set.clone());
+        let nfo = NFO::Movie(movie)
+            .generate_nfo_with_config(&NFOConfig::default())
+            .await
+            .unwrap();
+        assert_eq!(nfo.matches("<set>").count(), 1);
+        assert!(nfo.contains("<tag>我的收藏</tag>"));
+
+        let kodi = NFO::BoxSet(collection_set.clone())
+            .generate_nfo_with_config(&NFOConfig::default())
+            .await
+            .unwrap();
+        assert!(kodi.contains("<set>\n    <title>我的收藏</title>\n    <overview><![CDATA[收藏夹简介]]></overview>"));
+        let config = NFOConfig {
+            profile: NFOProfile::Jellyfin,
+            ..Default::default()
+        };
+        let jellyfin = NFO::BoxSet(collection_set)
+            .generate_nfo_with_config(&config)
+            .await
+            .unwrap();
+        assert!(jellyfin.contains("<collection>\n    <title>我的收藏</title>\n    <plot><![CDATA[收藏夹简介]]></plot>"));
+    }
+
     #[tokio::test]
     async fn test_subtitle_extraction() {
         // 测试副标题提取功能
@@ -3185,4 +3490,80 @@ mod tests {
 
         println!("NFO演员信息（UID和角色）测试通过");
     }
+
+    /// 与 `src/utils/nfo_golden/<方案>/` 下的期望输出逐字比较，
+    /// 调整输出后可设置 `UPDATE_NFO_GOLDEN=1` 重新生成期望文件
+    #[tokio::test]
+    async fn test_nfo_profile_golden() {
+        let video = video::Model {
+            intro: "一段简介".to_string(),
+            name: "测试视频".to_string(),
+            upper_id: 42,
+            upper_name: "测试UP主".to_string(),
+            upper_face: "https://i0.hdslb.com/bfs/face/upper.jpg".to_string(),
+            cover: "https://i0.hdslb.com/bfs/archive/cover.jpg".to_string(),
+            favtime: chrono::NaiveDateTime::new(
+                chrono::NaiveDate::from_ymd_opt(2024, 5, 6).unwrap(),
+                chrono::NaiveTime::from_hms_opt(7, 8, 9).unwrap(),
+            ),
+            pubtime: chrono::NaiveDateTime::new(
+                chrono::NaiveDate::from_ymd_opt(2023, 1, 2).unwrap(),
+                chrono::NaiveTime::from_hms_opt(3, 4, 5).unwrap(),
+            ),
+            bvid: "BV1Tr421n746".to_string(),
+            tags: Some(serde_json::json!(["科技", "教程"])),
+            single_page: Some(true),
+            ..Default::default()
+        };
+        let page = page::Model {
+            name: "第一P".to_string(),
+            pid: 1,
+            duration: 600,
+            ..Default::default()
+        };
+        let golden_dir = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("src/utils/nfo_golden");
+        let update = std::env::var_os("UPDATE_NFO_GOLDEN").is_some();
+        let upper_path = crate::config::with_config(|bundle| bundle.config.upper_path.to_string_lossy().into_owned());
+
+        for (profile, name) in [
+            (NFOProfile::Kodi, "kodi"),
+            (NFOProfile::Jellyfin, "jellyfin"),
+            (NFOProfile::Emby, "emby"),
+            (NFOProfile::Plex, "plex"),
+        ] {
+            let config = NFOConfig {
+                profile,
+                ..Default::default()
+            };
+            // 收藏时间与发布时间不同，才能体现各方案的日期差异
+            let mut movie: Movie = (&video).into();
+            movie.aired = video.favtime;
+            movie.premiered = video.favtime;
+            let mut tvshow: TVShow = (&video).into();
+            tvshow.aired = video.favtime;
+            tvshow.premiered = video.favtime;
+            let outputs = [
+                ("movie.nfo", NFO::Movie(movie)),
+                ("tvshow.nfo", NFO::TVShow(tvshow)),
+                ("episode.nfo", NFO::Episode(Episode::from_video_and_page(&video, &page))),
+                ("single_page.nfo", NFO::single_page(&video, &page, &[], None, profile)),
+            ];
+            for (file, nfo) in outputs {
+                // 本地缓存的演员头像路径随配置目录变化，替换为占位符后再比较
+                let generated = nfo
+                    .generate_nfo_with_config(&config)
+                    .await
+                    .unwrap()
+                    .replace(upper_path.as_str(), "{upper_path}");
+                let path = golden_dir.join(name).join(file);
+                if update {
+                    std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+                    std::fs::write(&path, &generated).unwrap();
+                    continue;
+                }
+                let expected = std::fs::read_to_string(&path).unwrap();
+                assert_eq!(generated, expected, "{} 与期望输出不一致", path.display());
+            }
+        }
+    }
 }
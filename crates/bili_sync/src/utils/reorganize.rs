@@ -0,0 +1,891 @@
+//! 媒体库重新整理
+//!
+//! 修改 video_name、page_name、multi_page_name、folder_structure、bangumi_folder_name 等路径模板后，
+//! 只有新下载的视频使用新布局。这里按当前模板重新计算每个视频的目录和分页文件名，把媒体文件连同侧车文件
+//! 一起移动过去，在同一个事务中更新 video.path 和 page.path，并把每一步移动写入可回滚的移动日志。
+
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use anyhow::{bail, Context, Result};
+use bili_sync_entity::{page, video};
+use once_cell::sync::Lazy;
+use parking_lot::RwLock;
+use sea_orm::entity::prelude::*;
+use sea_orm::ActiveValue::{Set, Unchanged};
+use sea_orm::TransactionTrait;
+use serde::{Deserialize, Serialize};
+use tokio_util::sync::CancellationToken;
+use tracing::{error, info, warn};
+use utoipa::ToSchema;
+
+use crate::adapter::{video_source_of, VideoSourceEnum};
+use crate::bilibili::BiliClient;
+use crate::config::CONFIG_DIR;
+use crate::utils::ai_rename::{move_sidecars, sidecar_moves};
+use crate::utils::time_format::now_standard_string;
+use crate::workflow::{render_page_base_name, resolve_video_folders};
+
+/// 一次文件或目录移动
+#[derive(Serialize, Deserialize, ToSchema, Clone, Debug, PartialEq)]
+pub struct ReorganizeMove {
+    pub from: String,
+    pub to: String,
+}
+
+#[derive(Serialize, ToSchema, Clone, Debug)]
+pub struct ReorganizeFailure {
+    pub video_id: i32,
+    pub path: String,
+    pub reason: String,
+}
+
+/// 媒体库整理任务的进度
+#[derive(Serialize, ToSchema, Clone, Default, Debug)]
+pub struct ReorganizeJobStatus {
+    pub running: bool,
+    /// 预演模式只计算移动计划，不改动文件和数据库
+    pub dry_run: bool,
+    /// 本次任务是否为回滚
+    pub revert: bool,
+    pub started_at: Option<String>,
+    pub finished_at: Option<String>,
+    /// 检查的视频数量
+    pub total: usize,
+    /// 路径需要变化的视频数量
+    pub changed: usize,
+    /// 已移动（预演时为计划移动）的文件和目录数量
+    pub moved: usize,
+    pub failed: usize,
+    /// 最近若干条移动记录
+    pub moves: Vec<ReorganizeMove>,
+    /// 最近若干个无法处理的视频及原因
+    pub failures: Vec<ReorganizeFailure>,
+    /// 本次整理写入（或回滚使用）的移动日志文件名
+    pub log_file: Option<String>,
+}
+
+const MAX_RECORDED_MOVES: usize = 500;
+const MAX_RECORDED_FAILURES: usize = 100;
+
+static REORGANIZE_JOB: Lazy<RwLock<ReorganizeJobStatus>> = Lazy::new(|| RwLock::new(ReorganizeJobStatus::default()));
+
+/// 数据库中一条路径记录的变化
+#[derive(Serialize, Deserialize, Clone, Debug)]
+struct PathChange {
+    id: i32,
+    from: String,
+    to: String,
+}
+
+/// 可回滚的移动日志，moves 按执行顺序记录，回滚时倒序执行
+#[derive(Serialize, Deserialize, Default, Debug)]
+struct MoveLog {
+    created_at: String,
+    moves: Vec<ReorganizeMove>,
+    videos: Vec<PathChange>,
+    pages: Vec<PathChange>,
+}
+
+/// 单个分页的整理计划
+#[derive(Debug, Clone)]
+struct PagePlan {
+    page_id: i32,
+    /// 当前媒体文件路径
+    path: PathBuf,
+    /// 目标目录与文件名基
+    dir: PathBuf,
+    stem: String,
+}
+
+impl PagePlan {
+    fn target(&self) -> PathBuf {
+        match self.path.extension().and_then(|ext| ext.to_str()) {
+            Some(ext) => self.dir.join(format!("{}.{}", self.stem, ext)),
+            None => self.dir.join(&self.stem),
+        }
+    }
+}
+
+/// 单个视频的整理计划
+#[derive(Debug, Clone)]
+struct VideoPlan {
+    video_id: i32,
+    old_root: PathBuf,
+    new_root: PathBuf,
+    /// 番剧的各集共用番剧文件夹，不参与目录冲突检查
+    shared_root: bool,
+    pages: Vec<PagePlan>,
+}
+
+pub fn reorganize_job_status() -> ReorganizeJobStatus {
+    REORGANIZE_JOB.read().clone()
+}
+
+fn reorganize_log_dir() -> PathBuf {
+    CONFIG_DIR.join("reorganize")
+}
+
+/// 标记任务开始，已有任务在运行时返回 false
+fn begin_job(dry_run: bool, revert: bool) -> bool {
+    let mut status = REORGANIZE_JOB.write();
+    if status.running {
+        return false;
+    }
+    *status = ReorganizeJobStatus {
+        running: true,
+        dry_run,
+        revert,
+        started_at: Some(now_standard_string()),
+        ..Default::default()
+    };
+    true
+}
+
+fn finish_job() {
+    let mut status = REORGANIZE_JOB.write();
+    status.running = false;
+    status.finished_at = Some(now_standard_string());
+    info!(
+        "媒体库整理{}完成：检查 {} 个视频，{} 个需要变化，移动 {} 项，失败 {} 个",
+        if status.dry_run { "预演" } else { "" },
+        status.total,
+        status.changed,
+        status.moved,
+        status.failed
+    );
+}
+
+fn record_moves(moves: &[ReorganizeMove]) {
+    let mut status = REORGANIZE_JOB.write();
+    status.moved += moves.len();
+    let room = MAX_RECORDED_MOVES.saturating_sub(status.moves.len());
+    status.moves.extend(moves.iter().take(room).cloned());
+}
+
+fn record_failure(video_id: i32, path: &Path, reason: String) {
+    let mut status = REORGANIZE_JOB.write();
+    status.failed += 1;
+    if status.failures.len() < MAX_RECORDED_FAILURES {
+        status.failures.push(ReorganizeFailure {
+            video_id,
+            path: path.to_string_lossy().to_string(),
+            reason,
+        });
+    }
+}
+
+/// 在后台启动媒体库整理任务，已有整理或回滚任务在运行时返回 false
+pub fn start_reorganize_job(db: Arc<DatabaseConnection>, dry_run: bool) -> bool {
+    if !begin_job(dry_run, false) {
+        return false;
+    }
+    tokio::spawn(async move {
+        // 预演不改动文件和数据库，无需暂停扫描
+        let paused = !dry_run && crate::task::pause_scanning_for_job().await;
+        if let Err(e) = run_reorganize_job(&db, dry_run).await {
+            error!("媒体库整理任务异常结束: {:#}", e);
+        }
+        if paused {
+            crate::task::resume_scanning();
+        }
+        finish_job();
+    });
+    true
+}
+
+/// 在后台按移动日志回滚一次整理，未指定日志时使用最近一次未回滚的日志
+pub fn start_revert_job(db: Arc<DatabaseConnection>, log_file: Option<String>) -> bool {
+    if !begin_job(false, true) {
+        return false;
+    }
+    tokio::spawn(async move {
+        let paused = crate::task::pause_scanning_for_job().await;
+        if let Err(e) = run_revert_job(&db, log_file).await {
+            error!("媒体库整理回滚异常结束: {:#}", e);
+        }
+        if paused {
+            crate::task::resume_scanning();
+        }
+        finish_job();
+    });
+    true
+}
+
+/// 与下载流程保存 video.path 的规则一致：番剧保存番剧文件夹，使用 Season 结构时保存 Season 的上级目录
+fn video_root(
+    is_bangumi: bool,
+    base_path: &Path,
+    season_folder: Option<&str>,
+    bangumi_folder_path: Option<&Path>,
+) -> PathBuf {
+    if is_bangumi {
+        bangumi_folder_path.unwrap_or(base_path).to_path_buf()
+    } else if season_folder.is_some() {
+        base_path.parent().unwrap_or(base_path).to_path_buf()
+    } else {
+        base_path.to_path_buf()
+    }
+}
+
+async fn plan_video(
+    bili_client: &BiliClient,
+    video_source: &VideoSourceEnum,
+    video_model: &video::Model,
+    pages: &[page::Model],
+    db: &DatabaseConnection,
+) -> Result<VideoPlan> {
+    let token = CancellationToken::new();
+    let (base_path, season_folder, bangumi_folder_path) =
+        resolve_video_folders(bili_client, video_source, video_model, token.clone()).await?;
+    let is_bangumi = matches!(video_source, VideoSourceEnum::BangumiSource(_));
+    let new_root = video_root(
+        is_bangumi,
+        &base_path,
+        season_folder.as_deref(),
+        bangumi_folder_path.as_deref(),
+    );
+    let mut page_plans = Vec::new();
+    for page_model in pages {
+        let Some(path) = page_model.path.as_deref().map(PathBuf::from) else {
+            continue;
+        };
+        // AI 重命名过的分页保留现有文件名，只跟随目录移动
+        let stem = if page_model.ai_renamed.unwrap_or(0) != 0 {
+            path.file_stem()
+                .and_then(|stem| stem.to_str())
+                .context("无法解析分页文件名")?
+                .to_string()
+        } else {
+            render_page_base_name(bili_client, video_source, video_model, page_model, db, token.clone()).await?
+        };
+        page_plans.push(PagePlan {
+            page_id: page_model.id,
+            path,
+            dir: base_path.clone(),
+            stem,
+        });
+    }
+    Ok(VideoPlan {
+        video_id: video_model.id,
+        old_root: PathBuf::from(&video_model.path),
+        new_root,
+        shared_root: is_bangumi,
+        pages: page_plans,
+    })
+}
+
+/// 视频记录中标识所属视频源的字段：合集、收藏夹、投稿、稍后再看、番剧
+type SourceKey = (Option<i32>, Option<i32>, Option<i32>, Option<i32>, Option<i32>);
+
+/// 按当前模板为所有已下载视频生成整理计划，返回计划和数据库中所有视频的 (id, 目录)
+async fn plan_library(db: &DatabaseConnection) -> Result<(Vec<VideoPlan>, Vec<(i32, PathBuf)>)> {
+    let videos = video::Entity::find()
+        .filter(video::Column::Deleted.eq(0))
+        .filter(video::Column::Path.ne(""))
+        .all(db)
+        .await?;
+    REORGANIZE_JOB.write().total = videos.len();
+    info!("开始计算媒体库整理计划，共 {} 个视频", videos.len());
+
+    let bili_client = BiliClient::new(String::new());
+    let mut sources: HashMap<SourceKey, VideoSourceEnum> = HashMap::new();
+    let mut plans: Vec<VideoPlan> = Vec::new();
+    let video_roots: Vec<(i32, PathBuf)> = videos
+        .iter()
+        .map(|video_model| (video_model.id, PathBuf::from(&video_model.path)))
+        .collect();
+    for video_model in videos {
+        let key = (
+            video_model.collection_id,
+            video_model.favorite_id,
+            video_model.submission_id,
+            video_model.watch_later_id,
+            video_model.source_id,
+        );
+        let result = async {
+            let video_source = match sources.entry(key) {
+                Entry::Occupied(entry) => entry.into_mut(),
+                Entry::Vacant(entry) => entry.insert(
+                    video_source_of(&video_model, db)
+                        .await?
+                        .context("找不到视频所属的视频源")?,
+                ),
+            };
+            let pages = page::Entity::find()
+                .filter(page::Column::VideoId.eq(video_model.id))
+                .all(db)
+                .await?;
+            plan_video(&bili_client, video_source, &video_model, &pages, db).await
+        }
+        .await;
+        match result {
+            Ok(plan) => {
+                let changed = plan.old_root != plan.new_root || plan.pages.iter().any(|p| p.path != p.target());
+                if changed {
+                    REORGANIZE_JOB.write().changed += 1;
+                    plans.push(plan);
+                }
+            }
+            Err(e) => {
+                warn!("无法计算视频「{}」的新路径，跳过: {:#}", video_model.name, e);
+                record_failure(video_model.id, Path::new(&video_model.path), format!("{:#}", e));
+            }
+        }
+    }
+    let collisions = find_collisions(&plans, &video_roots);
+    for (video_id, path, reason) in &collisions {
+        warn!("视频整理目标冲突，跳过: {} ({})", path.display(), reason);
+        record_failure(*video_id, path, reason.clone());
+    }
+    plans.retain(|plan| !collisions.iter().any(|(video_id, _, _)| *video_id == plan.video_id));
+    Ok((plans, video_roots))
+}
+
+/// 检查整理计划之间的冲突：多个分页的目标文件相同，或多个目录的视频将整理到同一个目录、
+/// 目标目录已被其它不参与整理的视频占用。冲突的视频全部跳过，返回 (视频 id, 路径, 原因)
+fn find_collisions(plans: &[VideoPlan], video_roots: &[(i32, PathBuf)]) -> Vec<(i32, PathBuf, String)> {
+    let mut collisions = Vec::new();
+    let mut targets: HashMap<PathBuf, i32> = HashMap::new();
+    for plan in plans {
+        for page_plan in &plan.pages {
+            let target = page_plan.target();
+            match targets.entry(target) {
+                Entry::Occupied(entry) => {
+                    let reason = "多个分页的目标文件相同".to_string();
+                    collisions.push((*entry.get(), entry.key().clone(), reason.clone()));
+                    collisions.push((plan.video_id, entry.key().clone(), reason));
+                }
+                Entry::Vacant(entry) => {
+                    entry.insert(plan.video_id);
+                }
+            }
+        }
+    }
+    let planned = plans.iter().map(|plan| plan.video_id).collect::<Vec<_>>();
+    for plan in plans
+        .iter()
+        .filter(|plan| !plan.shared_root && plan.new_root != plan.old_root)
+    {
+        let from_other_root = plans.iter().any(|other| {
+            other.video_id != plan.video_id && other.new_root == plan.new_root && other.old_root != plan.old_root
+        });
+        let occupied = video_roots
+            .iter()
+            .any(|(id, root)| *root == plan.new_root && !planned.contains(id));
+        if from_other_root || occupied {
+            collisions.push((
+                plan.video_id,
+                plan.new_root.clone(),
+                "目标目录已被其它视频使用".to_string(),
+            ));
+        }
+    }
+    collisions.sort_by_key(|(video_id, _, _)| *video_id);
+    collisions.dedup_by_key(|(video_id, _, _)| *video_id);
+    collisions
+}
+
+/// 执行结果：移动日志以及无法移动的视频
+struct Execution {
+    log: MoveLog,
+    failures: Vec<(i32, PathBuf, String)>,
+}
+
+/// 移动单个分页的媒体文件及其侧车文件，返回新的媒体文件路径
+///
+/// 预演时不改动文件，`current` 仍为原始位置，只检查源文件与目标冲突并列出将要移动的侧车文件
+fn move_page(current: &Path, plan: &PagePlan, dry_run: bool, moves: &mut Vec<ReorganizeMove>) -> Result<PathBuf> {
+    let target = plan.target();
+    if current == target {
+        return Ok(target);
+    }
+    if !current.exists() {
+        bail!("文件不存在: {}", current.display());
+    }
+    if target.exists() {
+        bail!("目标文件已存在: {}", target.display());
+    }
+    let push = |moves: &mut Vec<ReorganizeMove>, from: &Path, to: &Path| {
+        moves.push(ReorganizeMove {
+            from: from.to_string_lossy().to_string(),
+            to: to.to_string_lossy().to_string(),
+        })
+    };
+    let ext = target.extension().and_then(|ext| ext.to_str()).unwrap_or_default();
+    if dry_run {
+        push(moves, current, &target);
+        for (from, to) in sidecar_moves(current, &plan.dir, &plan.stem, ext)? {
+            push(moves, &from, &to);
+        }
+        return Ok(target);
+    }
+    std::fs::create_dir_all(&plan.dir).with_context(|| format!("创建目录失败: {}", plan.dir.display()))?;
+    std::fs::rename(current, &target)
+        .with_context(|| format!("移动文件失败: {} -> {}", current.display(), target.display()))?;
+    push(moves, current, &target);
+    for (from, to) in move_sidecars(current, &plan.dir, &plan.stem, ext)? {
+        push(moves, &from, &to);
+    }
+    Ok(target)
+}
+
+/// 执行整理计划：同一目录下的视频全部迁往同一个新目录时整体重命名目录（连同视频级的海报、NFO 一起移动），
+/// 否则逐个移动分页文件。`video_roots` 为数据库中所有视频的目录，目录内还有其它视频（包括无需整理或无法计划的视频）时
+/// 不会整体重命名
+fn execute_plans(plans: &[VideoPlan], video_roots: &[(i32, PathBuf)], dry_run: bool) -> Execution {
+    let mut execution = Execution {
+        log: MoveLog {
+            created_at: now_standard_string(),
+            ..Default::default()
+        },
+        failures: Vec::new(),
+    };
+    let mut groups: Vec<(&Path, Vec<&VideoPlan>)> = Vec::new();
+    for plan in plans {
+        match groups.iter_mut().find(|(root, _)| *root == plan.old_root) {
+            Some((_, group)) => group.push(plan),
+            None => groups.push((&plan.old_root, vec![plan])),
+        }
+    }
+    for (old_root, group) in groups {
+        let new_root = &group[0].new_root;
+        let rename_dir = new_root != old_root
+            && group.iter().all(|plan| &plan.new_root == new_root)
+            && old_root.is_dir()
+            && !new_root.exists()
+            && !new_root.starts_with(old_root)
+            && !video_roots
+                .iter()
+                .any(|(id, root)| root.starts_with(old_root) && !group.iter().any(|plan| plan.video_id == *id));
+        let dir_renamed = rename_dir
+            && (dry_run
+                || new_root
+                    .parent()
+                    .map_or(Ok(()), std::fs::create_dir_all)
+                    .and_then(|_| std::fs::rename(old_root, new_root))
+                    .map_err(|e| {
+                        warn!(
+                            "重命名目录失败，改为逐个移动文件: {:?} -> {:?}, err={}",
+                            old_root, new_root, e
+                        )
+                    })
+                    .is_ok());
+        if dir_renamed {
+            execution.log.moves.push(ReorganizeMove {
+                from: old_root.to_string_lossy().to_string(),
+                to: new_root.to_string_lossy().to_string(),
+            });
+        }
+
+        for plan in group {
+            let mut moves = Vec::new();
+            let mut page_changes = Vec::new();
+            let mut failed = false;
+            for page_plan in &plan.pages {
+                // 目录已整体重命名时，文件的当前位置随之变化
+                let current = match page_plan.path.strip_prefix(old_root) {
+                    Ok(relative) if dir_renamed && !dry_run => new_root.join(relative),
+                    _ => page_plan.path.clone(),
+                };
+                match move_page(&current, page_plan, dry_run, &mut moves) {
+                    Ok(new_path) => {
+                        if new_path != page_plan.path {
+                            page_changes.push(PathChange {
+                                id: page_plan.page_id,
+                                from: page_plan.path.to_string_lossy().to_string(),
+                                to: new_path.to_string_lossy().to_string(),
+                            });
+                        }
+                    }
+                    Err(e) => {
+                        failed = true;
+                        execution
+                            .failures
+                            .push((plan.video_id, page_plan.path.clone(), format!("{:#}", e)));
+                        // 文件移动失败但目录已重命名时，分页路径仍需跟随目录更新
+                        if current != page_plan.path {
+                            page_changes.push(PathChange {
+                                id: page_plan.page_id,
+                                from: page_plan.path.to_string_lossy().to_string(),
+                                to: current.to_string_lossy().to_string(),
+                            });
+                        }
+                    }
+                }
+            }
+            if !dry_run && !dir_renamed {
+                // 逐个移动后清理已经空了的旧目录，目录非空时 remove_dir 会失败，直接忽略
+                for page_plan in &plan.pages {
+                    if let Some(parent) = page_plan.path.parent().filter(|parent| *parent != page_plan.dir) {
+                        let _ = std::fs::remove_dir(parent);
+                    }
+                }
+                if old_root != plan.new_root {
+                    let _ = std::fs::remove_dir(old_root);
+                }
+            }
+            // 部分分页无法移动时，视频仍以旧目录为准
+            if plan.new_root != old_root && (dir_renamed || !failed) {
+                execution.log.videos.push(PathChange {
+                    id: plan.video_id,
+                    from: old_root.to_string_lossy().to_string(),
+                    to: plan.new_root.to_string_lossy().to_string(),
+                });
+            }
+            execution.log.moves.extend(moves);
+            execution.log.pages.extend(page_changes);
+        }
+    }
+    execution
+}
+
+/// 倒序撤销移动，返回无法撤销的移动及原因
+fn revert_moves(moves: &[ReorganizeMove]) -> Vec<(ReorganizeMove, String)> {
+    let mut failures: Vec<(ReorganizeMove, String)> = Vec::new();
+    for m in moves.iter().rev() {
+        let (from, to) = (Path::new(&m.from), Path::new(&m.to));
+        // 目录内还有未能撤销的移动时保留整个目录，避免这些文件被带到既不是原位置也不是新位置的地方
+        let result = if failures.iter().any(|(failed, _)| Path::new(&failed.to).starts_with(to)) {
+            Err(anyhow::anyhow!("目录中有未能撤销的移动"))
+        } else if !to.exists() {
+            Err(anyhow::anyhow!("整理后的文件已不存在"))
+        } else if from.exists() {
+            Err(anyhow::anyhow!("原位置已被占用"))
+        } else {
+            from.parent()
+                .map_or(Ok(()), std::fs::create_dir_all)
+                .and_then(|_| std::fs::rename(to, from))
+                .map_err(anyhow::Error::from)
+        };
+        if let Err(e) = result {
+            warn!("撤销移动失败: {} -> {} ({:#})", m.to, m.from, e);
+            failures.push((m.clone(), format!("{:#}", e)));
+        }
+    }
+    failures
+}
+
+/// 在一个事务中写入路径变化，`forward` 为 false 时写回原路径
+async fn apply_path_changes(db: &DatabaseConnection, log: &MoveLog, forward: bool) -> Result<()> {
+    let pick = |change: &PathChange| {
+        if forward {
+            change.to.clone()
+        } else {
+            change.from.clone()
+        }
+    };
+    let txn = db.begin().await?;
+    for change in &log.videos {
+        video::Entity::update(video::ActiveModel {
+            id: Unchanged(change.id),
+            path: Set(pick(change)),
+            ..Default::default()
+        })
+        .exec(&txn)
+        .await?;
+    }
+    for change in &log.pages {
+        page::Entity::update(page::ActiveModel {
+            id: Unchanged(change.id),
+            path: Set(Some(pick(change))),
+            ..Default::default()
+        })
+        .exec(&txn)
+        .await?;
+    }
+    txn.commit().await?;
+    Ok(())
+}
+
+async fn run_reorganize_job(db: &DatabaseConnection, dry_run: bool) -> Result<()> {
+    let (plans, video_roots) = plan_library(db).await?;
+    let execution = tokio::task::spawn_blocking(move || execute_plans(&plans, &video_roots, dry_run)).await?;
+    for (video_id, path, reason) in &execution.failures {
+        record_failure(*video_id, path, reason.clone());
+    }
+    record_moves(&execution.log.moves);
+    if dry_run || execution.log.moves.is_empty() {
+        return Ok(());
+    }
+
+    // 先写日志再更新数据库，进程中途退出时仍可根据日志回滚文件
+    let log_dir = reorganize_log_dir();
+    tokio::fs::create_dir_all(&log_dir).await?;
+    let log_name = format!("{}.json", chrono::Local::now().format("%Y%m%d-%H%M%S"));
+    let log_path = log_dir.join(&log_name);
+    tokio::fs::write(&log_path, serde_json::to_vec_pretty(&execution.log)?)
+        .await
+        .with_context(|| format!("写入移动日志失败: {}", log_path.display()))?;
+    REORGANIZE_JOB.write().log_file = Some(log_name);
+
+    if let Err(e) = apply_path_changes(db, &execution.log, true).await {
+        error!("更新数据库路径失败，撤销本次文件移动: {:#}", e);
+        let moves = execution.log.moves.clone();
+        let failures = tokio::task::spawn_blocking(move || revert_moves(&moves)).await?;
+        if failures.is_empty() {
+            let _ = tokio::fs::remove_file(&log_path).await;
+            REORGANIZE_JOB.write().log_file = None;
+        } else {
+            // 数据库仍是原路径，日志中只需保留没能撤销的移动
+            let (_, remainder) = split_reverted(execution.log, &failures);
+            tokio::fs::write(&log_path, serde_json::to_vec_pretty(&remainder)?).await?;
+        }
+        return Err(e);
+    }
+    info!("媒体库整理已写入移动日志: {}", log_path.display());
+    Ok(())
+}
+
+/// 找到要回滚的日志，只接受日志目录下的文件名
+fn resolve_log_file(log_file: Option<String>) -> Result<PathBuf> {
+    let log_dir = reorganize_log_dir();
+    let name = match log_file {
+        Some(name) => {
+            if name.contains(['/', '\\']) || name.starts_with('.') {
+                bail!("无效的移动日志文件名: {}", name);
+            }
+            name
+        }
+        None => std::fs::read_dir(&log_dir)
+            .with_context(|| format!("读取移动日志目录失败: {}", log_dir.display()))?
+            .flatten()
+            .filter_map(|entry| entry.file_name().into_string().ok())
+            .filter(|name| name.ends_with(".json") && !name.ends_with(".reverted.json"))
+            .max()
+            .context("没有可回滚的移动日志")?,
+    };
+    Ok(log_dir.join(name))
+}
+
+async fn run_revert_job(db: &DatabaseConnection, log_file: Option<String>) -> Result<()> {
+    let log_path = resolve_log_file(log_file)?;
+    let content = tokio::fs::read(&log_path)
+        .await
+        .with_context(|| format!("读取移动日志失败: {}", log_path.display()))?;
+    let log: MoveLog = serde_json::from_slice(&content)?;
+    REORGANIZE_JOB.write().log_file = log_path.file_name().map(|name| name.to_string_lossy().to_string());
+    info!(
+        "开始回滚媒体库整理: {}，共 {} 项移动",
+        log_path.display(),
+        log.moves.len()
+    );
+
+    let moves = log.moves.clone();
+    let failures = tokio::task::spawn_blocking(move || revert_moves(&moves)).await?;
+    {
+        let mut status = REORGANIZE_JOB.write();
+        status.total = log.videos.len();
+        status.changed = log.videos.len();
+    }
+    let undone = log
+        .moves
+        .iter()
+        .filter(|m| !failures.iter().any(|(failed, _)| failed == *m))
+        .map(|m| ReorganizeMove {
+            from: m.to.clone(),
+            to: m.from.clone(),
+        })
+        .collect::<Vec<_>>();
+    record_moves(&undone);
+    for (m, reason) in &failures {
+        record_failure(0, Path::new(&m.to), reason.clone());
+    }
+    let (reverted, remainder) = split_reverted(log, &failures);
+    apply_path_changes(db, &reverted, false).await?;
+    if remainder.moves.is_empty() {
+        let reverted_path = log_path.with_extension("reverted.json");
+        tokio::fs::rename(&log_path, &reverted_path).await?;
+    } else {
+        // 只保留未能撤销的部分，处理好冲突后可以使用同一个日志再次回滚
+        tokio::fs::write(&log_path, serde_json::to_vec_pretty(&remainder)?)
+            .await
+            .with_context(|| format!("写入移动日志失败: {}", log_path.display()))?;
+        warn!(
+            "有 {} 项移动未能撤销，已在移动日志中保留: {}",
+            remainder.moves.len(),
+            log_path.display()
+        );
+    }
+    Ok(())
+}
+
+/// 按撤销结果拆分移动日志：返回已撤销、可以写回数据库的部分，以及需要保留在日志中的未撤销部分。
+/// 路径记录的新位置就是某个未撤销的移动，或位于未撤销的目录移动之中时视为未撤销
+fn split_reverted(log: MoveLog, failures: &[(ReorganizeMove, String)]) -> (MoveLog, MoveLog) {
+    let is_failed = |change: &PathChange| failures.iter().any(|(m, _)| Path::new(&change.to).starts_with(&m.to));
+    let (failed_videos, videos) = log.videos.into_iter().partition(is_failed);
+    let (failed_pages, pages) = log.pages.into_iter().partition(is_failed);
+    let reverted = MoveLog {
+        created_at: log.created_at.clone(),
+        moves: Vec::new(),
+        videos,
+        pages,
+    };
+    let remainder = MoveLog {
+        created_at: log.created_at,
+        moves: log
+            .moves
+            .into_iter()
+            .filter(|m| failures.iter().any(|(failed, _)| failed == m))
+            .collect(),
+        videos: failed_videos,
+        pages: failed_pages,
+    };
+    (reverted, remainder)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_video_root() {
+        let base = Path::new("/library/多P视频/Season 01");
+        assert_eq!(
+            video_root(false, base, Some("Season 01"), Some(Path::new("/library/多P视频"))),
+            PathBuf::from("/library/多P视频")
+        );
+        assert_eq!(video_root(false, base, None, None), base.to_path_buf());
+        assert_eq!(
+            video_root(true, base, Some("Season 01"), Some(Path::new("/library/番剧"))),
+            PathBuf::from("/library/番剧")
+        );
+        assert_eq!(video_root(true, base, None, None), base.to_path_buf());
+    }
+
+    #[test]
+    fn test_find_collisions() {
+        let plan = |video_id, old_root: &str, new_root: &str, stem: &str| VideoPlan {
+            video_id,
+            old_root: PathBuf::from(old_root),
+            new_root: PathBuf::from(new_root),
+            shared_root: false,
+            pages: vec![PagePlan {
+                page_id: video_id,
+                path: Path::new(old_root).join("a.mp4"),
+                dir: PathBuf::from(new_root),
+                stem: stem.to_string(),
+            }],
+        };
+        let roots = vec![
+            (1, PathBuf::from("/a")),
+            (2, PathBuf::from("/b")),
+            (3, PathBuf::from("/c")),
+        ];
+        assert!(find_collisions(&[plan(1, "/a", "/x", "x"), plan(2, "/b", "/y", "y")], &roots).is_empty());
+        // 两个目录的视频整理到同一个目录
+        let collisions = find_collisions(&[plan(1, "/a", "/x", "1"), plan(2, "/b", "/x", "2")], &roots);
+        assert_eq!(collisions.iter().map(|c| c.0).collect::<Vec<_>>(), vec![1, 2]);
+        // 目标目录是其它不参与整理的视频的目录
+        let collisions = find_collisions(&[plan(1, "/a", "/c", "1")], &roots);
+        assert_eq!(collisions.iter().map(|c| c.0).collect::<Vec<_>>(), vec![1]);
+        // 番剧各集目标目录相同但文件名相同时仍然冲突
+        let mut first = plan(1, "/a", "/x", "同名");
+        let mut second = plan(2, "/a", "/x", "同名");
+        first.shared_root = true;
+        second.shared_root = true;
+        let collisions = find_collisions(&[first, second], &roots);
+        assert_eq!(collisions.len(), 2);
+    }
+
+    #[test]
+    fn test_execute_and_revert_plans() {
+        let dir = std::env::temp_dir().join(format!("bili-sync-reorganize-{}", std::process::id()));
+        let old_root = dir.join("旧目录");
+        std::fs::create_dir_all(&old_root).unwrap();
+        for name in [
+            "old.mp4",
+            "old.nfo",
+            "old-thumb.jpg",
+            "old.zh-CN.default.ass",
+            "tvshow.nfo",
+        ] {
+            std::fs::write(old_root.join(name), name).unwrap();
+        }
+        std::fs::create_dir_all(old_root.join("old.trickplay/320 - 10x10")).unwrap();
+        std::fs::write(old_root.join("old.trickplay/320 - 10x10/0.jpg"), "0.jpg").unwrap();
+        let new_root = dir.join("新目录");
+        let plans = vec![VideoPlan {
+            video_id: 1,
+            old_root: old_root.clone(),
+            new_root: new_root.clone(),
+            shared_root: false,
+            pages: vec![PagePlan {
+                page_id: 2,
+                path: old_root.join("old.mp4"),
+                dir: new_root.clone(),
+                stem: "new".to_string(),
+            }],
+        }];
+
+        let video_roots = vec![(1, old_root.clone())];
+        let dir_move = ReorganizeMove {
+            from: old_root.to_string_lossy().to_string(),
+            to: new_root.to_string_lossy().to_string(),
+        };
+        // 预演不改动任何文件
+        let execution = execute_plans(&plans, &video_roots, true);
+        // 目录重命名、媒体文件以及 NFO、封面、弹幕、预览图四个侧车文件
+        assert_eq!(execution.log.moves.len(), 6);
+        assert!(execution.log.moves.contains(&dir_move));
+        assert!(execution
+            .log
+            .moves
+            .iter()
+            .any(|m| m.to == new_root.join("new.nfo").to_string_lossy()));
+        assert!(old_root.join("old.mp4").exists());
+        // 目录中还有其它视频时不能整体重命名目录
+        let shared_roots = vec![(1, old_root.clone()), (3, old_root.clone())];
+        let execution = execute_plans(&plans, &shared_roots, true);
+        assert!(!execution.log.moves.contains(&dir_move));
+
+        let execution = execute_plans(&plans, &video_roots, false);
+        assert!(execution.failures.is_empty());
+        // 目录整体重命名，视频级文件随之移动，分页媒体与侧车文件改用新文件名
+        for name in [
+            "new.mp4",
+            "new.nfo",
+            "new-thumb.jpg",
+            "new.zh-CN.default.ass",
+            "tvshow.nfo",
+            "new.trickplay/320 - 10x10/0.jpg",
+        ] {
+            assert!(new_root.join(name).exists(), "{} 未移动", name);
+        }
+        assert!(!old_root.exists());
+        assert_eq!(execution.log.videos.len(), 1);
+        assert_eq!(execution.log.pages[0].to, new_root.join("new.mp4").to_string_lossy());
+
+        // 未能撤销的移动及相关路径记录保留在日志中，其余记录写回数据库
+        let blocked = execution.log.moves[1].clone();
+        let failures = vec![(blocked.clone(), String::new())];
+        let log = MoveLog {
+            created_at: String::new(),
+            moves: execution.log.moves.clone(),
+            videos: execution.log.videos.clone(),
+            pages: execution.log.pages.clone(),
+        };
+        let (reverted, remainder) = split_reverted(log, &failures);
+        assert_eq!(remainder.moves, vec![blocked]);
+        assert_eq!(remainder.pages.len(), 1);
+        assert!(reverted.pages.is_empty());
+        assert_eq!(reverted.videos.len(), 1);
+
+        assert!(revert_moves(&execution.log.moves).is_empty());
+        for name in [
+            "old.mp4",
+            "old.nfo",
+            "old-thumb.jpg",
+            "old.zh-CN.default.ass",
+            "tvshow.nfo",
+            "old.trickplay/320 - 10x10/0.jpg",
+        ] {
+            assert!(old_root.join(name).exists(), "{} 未还原", name);
+        }
+        assert!(!new_root.exists());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
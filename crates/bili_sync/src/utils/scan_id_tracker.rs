@@ -98,6 +98,8 @@ pub struct VideoSourceWithId {
     pub args: crate::adapter::Args,
     pub path: std::path::PathBuf,
     pub source_type: SourceType,
+    /// 下载优先级，数值越大越先扫描
+    pub priority: i32,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -109,13 +111,25 @@ pub enum SourceType {
     Bangumi,
 }
 
+/// 源的扫描顺序：优先级高的在前，同优先级按 ID 从小到大
+fn scan_order(priority: i32, id: i32) -> (std::cmp::Reverse<i32>, i32) {
+    (std::cmp::Reverse(priority), id)
+}
+
 /// 将视频源按新旧分组，并支持断点续传
+///
+/// 断点按扫描顺序而非 ID 判断：排在上次处理的源之后的旧源才需要继续扫描，
+/// 上次处理的源已被删除时按优先级 0 估计它的位置
 pub fn group_sources_by_new_old(
     sources: Vec<VideoSourceWithId>,
     last_scanned_ids: &LastScannedIds,
 ) -> (Vec<VideoSourceWithId>, Vec<VideoSourceWithId>) {
     let mut new_sources = Vec::new();
     let mut old_sources = Vec::new();
+    let priorities: HashMap<(SourceType, i32), i32> = sources
+        .iter()
+        .map(|source| ((source.source_type, source.id), source.priority))
+        .collect();
 
     for source in sources {
         let (max_id, last_processed_id) = match source.source_type {
@@ -148,8 +162,13 @@ pub fn group_sources_by_new_old(
                 _ => false,
             };
 
+            let after_last_processed = last_processed_id.is_none_or(|last_id| {
+                let last_priority = priorities.get(&(source.source_type, last_id)).copied().unwrap_or(0);
+                scan_order(source.priority, source.id) > scan_order(last_priority, last_id)
+            });
+
             // 如果有断点或者还未处理，则添加到旧源列表
-            if has_checkpoint || last_processed_id.is_none() || source.id > last_processed_id.unwrap() {
+            if has_checkpoint || after_last_processed {
                 if has_checkpoint {
                     debug!(
                         "检测到断点恢复源 (ID: {}, 类型: {:?})，包含在扫描列表中",
@@ -164,8 +183,8 @@ pub fn group_sources_by_new_old(
         }
     }
 
-    // 对旧源按ID排序，确保从小到大处理
-    old_sources.sort_by_key(|s| s.id);
+    // 对旧源按扫描顺序排序，与断点判断保持一致
+    old_sources.sort_by_key(|s| scan_order(s.priority, s.id));
 
     (new_sources, old_sources)
 }
@@ -256,3 +275,32 @@ impl LastScannedIds {
         self.last_processed_bangumi = None;
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn favorite(id: i32, priority: i32) -> VideoSourceWithId {
+        VideoSourceWithId {
+            id,
+            args: crate::adapter::Args::WatchLater,
+            path: std::path::PathBuf::new(),
+            source_type: SourceType::Favorite,
+            priority,
+        }
+    }
+
+    #[test]
+    fn test_group_sources_resumes_in_priority_order() {
+        let last_scanned_ids = LastScannedIds {
+            favorite: Some(4),
+            last_processed_favorite: Some(3),
+            ..Default::default()
+        };
+        // 扫描顺序为 3、1、2、4，上次处理到 3 时只剩 1、2、4
+        let sources = vec![favorite(1, 0), favorite(2, 0), favorite(3, 5), favorite(4, -1)];
+        let (new_sources, old_sources) = group_sources_by_new_old(sources, &last_scanned_ids);
+        assert!(new_sources.is_empty());
+        assert_eq!(old_sources.iter().map(|s| s.id).collect::<Vec<_>>(), vec![1, 2, 4]);
+    }
+}
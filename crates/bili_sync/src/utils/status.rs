@@ -188,8 +188,8 @@ impl<const N: usize> From<[u32; N]> for Status<N> {
 /// 包含五个子任务，从前到后依次是：视频封面、视频信息、Up 主头像、Up 主信息、分 P 下载
 pub type VideoStatus = Status<5>;
 
-/// 包含五个子任务，从前到后分别是：视频封面、视频内容、视频信息、视频弹幕、视频字幕
-pub type PageStatus = Status<5>;
+/// 包含六个子任务，从前到后分别是：视频封面、视频内容、视频信息、视频弹幕、视频字幕、进度条预览图
+pub type PageStatus = Status<6>;
 
 #[cfg(test)]
 mod test {
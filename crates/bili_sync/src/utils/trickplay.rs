@@ -0,0 +1,315 @@
+//! 生成媒体服务器进度条拖动时显示的预览图（trickplay）
+//!
+//! 缩略图来自 B 站的视频快照雪碧图或本地视频截图，先整理为按固定间隔排列的单帧图片，
+//! 再拼接为 Jellyfin 的拼图目录或打包为 BIF 文件。
+
+use std::ffi::OsStr;
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, ensure, Context, Result};
+use tokio::fs;
+
+use crate::bilibili::VideoShot;
+use crate::config::{TrickplayConfig, TrickplayLayout};
+use crate::utils::atomic_write::{append_suffix, write_atomic};
+use crate::utils::ffmpeg::run_ffmpeg;
+
+/// 缩略图来源
+pub enum FrameSource<'a> {
+    /// B 站视频快照及按顺序下载好的雪碧图内容
+    Sprites { shot: &'a VideoShot, sheets: Vec<Vec<u8>> },
+    /// 本地视频文件
+    Video(&'a Path),
+}
+
+const BIF_MAGIC: [u8; 8] = [0x89, b'B', b'I', b'F', 0x0d, 0x0a, 0x1a, 0x0a];
+const BIF_HEADER_SIZE: usize = 64;
+
+/// 预览图的输出位置：Jellyfin 为目录，BIF 为单个文件
+pub fn output_path(config: &TrickplayConfig, video_path: &Path) -> PathBuf {
+    let stem = video_path.file_stem().unwrap_or_default().to_string_lossy();
+    match config.layout {
+        TrickplayLayout::Jellyfin => video_path.with_file_name(format!("{}.trickplay", stem)),
+        TrickplayLayout::Bif => video_path.with_file_name(format!("{}-{}-{}.bif", stem, config.width, config.interval)),
+    }
+}
+
+/// 是否为 `stem` 对应的预览图，即 `{stem}.trickplay` 目录或 `{stem}-{宽度}-{间隔}.bif`
+pub(crate) fn is_trickplay_of(file_name: &str, stem: &str) -> bool {
+    if file_name.strip_prefix(stem) == Some(".trickplay") {
+        return true;
+    }
+    let Some(rest) = file_name
+        .strip_prefix(stem)
+        .and_then(|rest| rest.strip_prefix('-'))
+        .and_then(|rest| rest.strip_suffix(".bif"))
+    else {
+        return false;
+    };
+    let mut parts = rest.split('-');
+    matches!(
+        (parts.next(), parts.next(), parts.next()),
+        (Some(width), Some(interval), None)
+            if !width.is_empty() && !interval.is_empty()
+                && width.bytes().chain(interval.bytes()).all(|b| b.is_ascii_digit())
+    )
+}
+
+/// 删除目录下 `stem` 对应的所有预览图，返回删除的数量
+pub async fn remove_trickplay(parent: &Path, stem: &str) -> usize {
+    let Ok(mut entries) = fs::read_dir(parent).await else {
+        return 0;
+    };
+    let mut removed = 0;
+    while let Ok(Some(entry)) = entries.next_entry().await {
+        if !is_trickplay_of(&entry.file_name().to_string_lossy(), stem) {
+            continue;
+        }
+        let path = entry.path();
+        let result = if path.is_dir() {
+            fs::remove_dir_all(&path).await
+        } else {
+            fs::remove_file(&path).await
+        };
+        match result {
+            Ok(_) => removed += 1,
+            Err(e) => tracing::warn!("删除预览图 {} 失败: {}", path.display(), e),
+        }
+    }
+    removed
+}
+
+/// 覆盖整个时长所需的预览图数量
+fn frame_count(duration: u32, interval: u32) -> u32 {
+    duration.div_ceil(interval).max(1)
+}
+
+/// 按固定间隔为每个时间点选择不晚于该时间的最后一张快照，返回快照序号
+///
+/// `available` 为雪碧图中实际包含的缩略图数量，快照时间多于缩略图时忽略多出的部分
+fn select_thumbnails(index: &[u32], available: usize, interval: u32, duration: u32) -> Vec<usize> {
+    let index = &index[..index.len().min(available)];
+    if index.is_empty() {
+        return Vec::new();
+    }
+    (0..frame_count(duration, interval))
+        .map(|k| index.partition_point(|&time| time <= k * interval).saturating_sub(1))
+        .collect()
+}
+
+/// 按 Roku BIF 格式打包预览图，`interval_ms` 为相邻两张图片的间隔
+fn build_bif(frames: &[Vec<u8>], interval_ms: u32) -> Vec<u8> {
+    let table_size = (frames.len() + 1) * 8;
+    let total = BIF_HEADER_SIZE + table_size + frames.iter().map(Vec::len).sum::<usize>();
+    let mut data = Vec::with_capacity(total);
+    data.extend_from_slice(&BIF_MAGIC);
+    // 版本号
+    data.extend_from_slice(&0u32.to_le_bytes());
+    data.extend_from_slice(&(frames.len() as u32).to_le_bytes());
+    data.extend_from_slice(&interval_ms.to_le_bytes());
+    data.resize(BIF_HEADER_SIZE, 0);
+
+    let mut offset = (BIF_HEADER_SIZE + table_size) as u32;
+    for (index, frame) in frames.iter().enumerate() {
+        data.extend_from_slice(&(index as u32).to_le_bytes());
+        data.extend_from_slice(&offset.to_le_bytes());
+        offset += frame.len() as u32;
+    }
+    // 索引表以 0xffffffff 和文件末尾的偏移结束
+    data.extend_from_slice(&u32::MAX.to_le_bytes());
+    data.extend_from_slice(&offset.to_le_bytes());
+    for frame in frames {
+        data.extend_from_slice(frame);
+    }
+    data
+}
+
+/// 以安静模式执行 ffmpeg，只在出错时输出日志
+async fn run_ffmpeg_quiet<I, S>(args: I) -> Result<()>
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<OsStr>,
+{
+    let mut all_args: Vec<std::ffi::OsString> = ["-hide_banner", "-loglevel", "error", "-y"].map(Into::into).into();
+    all_args.extend(args.into_iter().map(|arg| arg.as_ref().to_os_string()));
+    run_ffmpeg(all_args).await?;
+    Ok(())
+}
+
+/// 在 `work_dir/frames` 中生成按间隔排列、从 00000.jpg 开始编号的单帧图片
+async fn prepare_frames(
+    config: &TrickplayConfig,
+    source: FrameSource<'_>,
+    duration: u32,
+    work_dir: &Path,
+) -> Result<Vec<PathBuf>> {
+    let frames_dir = work_dir.join("frames");
+    fs::create_dir_all(&frames_dir).await?;
+    match source {
+        FrameSource::Video(video_path) => {
+            run_ffmpeg_quiet([
+                OsStr::new("-i"),
+                video_path.as_os_str(),
+                OsStr::new("-vf"),
+                OsStr::new(&format!("fps=1/{},scale={}:-2", config.interval, config.width)),
+                OsStr::new("-q:v"),
+                OsStr::new("5"),
+                OsStr::new("-start_number"),
+                OsStr::new("0"),
+                frames_dir.join("%05d.jpg").as_os_str(),
+            ])
+            .await?;
+        }
+        FrameSource::Sprites { shot, sheets } => {
+            let tiles_dir = work_dir.join("tiles");
+            fs::create_dir_all(&tiles_dir).await?;
+            let per_sheet = (shot.img_x_len * shot.img_y_len) as usize;
+            for (number, sheet) in sheets.iter().enumerate() {
+                let sheet_path = work_dir.join(format!("sheet{}.jpg", number));
+                fs::write(&sheet_path, sheet).await?;
+                // 将雪碧图拆成单张缩略图，并缩放到目标宽度
+                run_ffmpeg_quiet([
+                    OsStr::new("-i"),
+                    sheet_path.as_os_str(),
+                    OsStr::new("-vf"),
+                    OsStr::new(&format!(
+                        "untile={}x{},scale={}:-2",
+                        shot.img_x_len, shot.img_y_len, config.width
+                    )),
+                    OsStr::new("-fps_mode"),
+                    OsStr::new("passthrough"),
+                    OsStr::new("-q:v"),
+                    OsStr::new("5"),
+                    OsStr::new("-start_number"),
+                    OsStr::new("0"),
+                    tiles_dir.join(format!("{}_%03d.jpg", number)).as_os_str(),
+                ])
+                .await
+                .with_context(|| format!("拆分第 {} 张雪碧图失败", number + 1))?;
+            }
+            let selected = select_thumbnails(&shot.index, sheets.len() * per_sheet, config.interval, duration);
+            for (position, thumbnail) in selected.into_iter().enumerate() {
+                let tile = tiles_dir.join(format!("{}_{:03}.jpg", thumbnail / per_sheet, thumbnail % per_sheet));
+                fs::copy(&tile, frames_dir.join(format!("{:05}.jpg", position)))
+                    .await
+                    .with_context(|| format!("缩略图 {} 不存在", tile.display()))?;
+            }
+        }
+    }
+
+    let mut frames = Vec::new();
+    let mut entries = fs::read_dir(&frames_dir).await?;
+    while let Some(entry) = entries.next_entry().await? {
+        frames.push(entry.path());
+    }
+    frames.sort();
+    if frames.is_empty() {
+        bail!("没有生成任何预览图");
+    }
+    Ok(frames)
+}
+
+/// 拼接为 Jellyfin 的 `{宽度} - {列}x{行}/{序号}.jpg` 目录后替换旧的预览图目录
+async fn write_jellyfin(config: &TrickplayConfig, work_dir: &Path, output: &Path) -> Result<()> {
+    let staging = work_dir.join("out");
+    let tiles_dir = staging.join(format!(
+        "{} - {}x{}",
+        config.width, config.tile_columns, config.tile_rows
+    ));
+    fs::create_dir_all(&tiles_dir).await?;
+    run_ffmpeg_quiet([
+        OsStr::new("-framerate"),
+        OsStr::new("1"),
+        OsStr::new("-i"),
+        work_dir.join("frames").join("%05d.jpg").as_os_str(),
+        OsStr::new("-vf"),
+        OsStr::new(&format!("tile={}x{}", config.tile_columns, config.tile_rows)),
+        OsStr::new("-fps_mode"),
+        OsStr::new("passthrough"),
+        OsStr::new("-q:v"),
+        OsStr::new("5"),
+        OsStr::new("-start_number"),
+        OsStr::new("0"),
+        tiles_dir.join("%d.jpg").as_os_str(),
+    ])
+    .await
+    .context("拼接预览图失败")?;
+    if output.exists() {
+        fs::remove_dir_all(output).await?;
+    }
+    fs::rename(&staging, output)
+        .await
+        .with_context(|| format!("移动预览图目录到 {} 失败", output.display()))
+}
+
+/// 为视频生成预览图并写入视频所在目录，返回输出路径
+///
+/// 所有中间文件都在临时目录中生成，失败时不会破坏已有的预览图
+pub async fn generate_trickplay(
+    config: &TrickplayConfig,
+    source: FrameSource<'_>,
+    duration: u32,
+    video_path: &Path,
+) -> Result<PathBuf> {
+    ensure!(
+        config.interval > 0 && config.width > 0 && config.tile_columns > 0 && config.tile_rows > 0,
+        "预览图的间隔、宽度和拼图行列数必须大于 0"
+    );
+    let output = output_path(config, video_path);
+    let work_dir = append_suffix(&output, ".work");
+    let _ = fs::remove_dir_all(&work_dir).await;
+    fs::create_dir_all(&work_dir).await?;
+    let result = async {
+        let frames = prepare_frames(config, source, duration, &work_dir).await?;
+        match config.layout {
+            TrickplayLayout::Jellyfin => write_jellyfin(config, &work_dir, &output).await,
+            TrickplayLayout::Bif => {
+                let mut images = Vec::with_capacity(frames.len());
+                for frame in &frames {
+                    images.push(fs::read(frame).await?);
+                }
+                write_atomic(&output, build_bif(&images, config.interval * 1000)).await
+            }
+        }
+    }
+    .await;
+    let _ = fs::remove_dir_all(&work_dir).await;
+    result.map(|_| output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_select_thumbnails() {
+        // 快照间隔不均匀时，取不晚于目标时间的最后一张
+        let index = [0, 6, 12, 25, 31];
+        assert_eq!(select_thumbnails(&index, 5, 10, 45), vec![0, 1, 2, 3, 4]);
+        // 雪碧图中的缩略图少于快照时间时不越界
+        assert_eq!(select_thumbnails(&index, 2, 10, 40), vec![0, 1, 1, 1]);
+        assert!(select_thumbnails(&[], 5, 10, 40).is_empty());
+    }
+
+    #[test]
+    fn test_build_bif() {
+        let bif = build_bif(&[vec![1, 2, 3], vec![4, 5]], 10_000);
+        assert_eq!(&bif[..8], &BIF_MAGIC);
+        assert_eq!(u32::from_le_bytes(bif[12..16].try_into().unwrap()), 2);
+        assert_eq!(u32::from_le_bytes(bif[16..20].try_into().unwrap()), 10_000);
+        // 索引表：(0, 88) (1, 91) (0xffffffff, 93)
+        let table = |i: usize| u32::from_le_bytes(bif[64 + i * 4..68 + i * 4].try_into().unwrap());
+        assert_eq!((table(0), table(1)), (0, 88));
+        assert_eq!((table(2), table(3)), (1, 91));
+        assert_eq!((table(4), table(5)), (u32::MAX, 93));
+        assert_eq!(&bif[88..], &[1, 2, 3, 4, 5]);
+    }
+
+    #[test]
+    fn test_is_trickplay_of() {
+        assert!(is_trickplay_of("S01E01.trickplay", "S01E01"));
+        assert!(is_trickplay_of("S01E01-320-10.bif", "S01E01"));
+        assert!(!is_trickplay_of("S01E01-part2-320-10.bif", "S01E01"));
+        assert!(!is_trickplay_of("S01E01-thumb.jpg", "S01E01"));
+    }
+}
@@ -7,6 +7,7 @@ use anyhow::{anyhow, bail, Context, Result};
 use bili_sync_entity::*;
 use futures::stream::FuturesUnordered;
 use futures::{Stream, StreamExt, TryStreamExt};
+use html_escape::decode_html_entities;
 use sea_orm::entity::prelude::*;
 use sea_orm::ActiveValue::Set;
 use sea_orm::{DatabaseBackend, Statement, TransactionTrait};
@@ -24,21 +25,32 @@ lazy_static::lazy_static! {
 
 use crate::adapter::{video_source_from, Args, VideoSource, VideoSourceEnum};
 use crate::bilibili::{
-    BestStream, BiliClient, BiliError, Dimension, PageInfo, Stream as VideoStream, Video, VideoInfo,
+    BestStream, BiliClient, BiliError, Chapter, Dimension, PageInfo, PlayerInfo, Stream as VideoStream, Video,
+    VideoInfo,
 };
-use crate::config::ARGS;
+use crate::config::{TrickplaySource, ARGS};
 use crate::error::{DownloadAbortError, ExecutionStatus, ProcessPageError};
+use crate::task::download_queue::{claim_video, sort_by_priority};
 use crate::task::{DeleteVideoTask, VIDEO_DELETE_TASK_QUEUE};
 use crate::unified_downloader::UnifiedDownloader;
+use crate::utils::artwork::{write_video_artwork, VideoArtwork};
+use crate::utils::atomic_write::write_atomic;
+use crate::utils::audio_transcode::{process_audio, AudioFormat, AudioOutput};
+use crate::utils::bandwidth::{source_limiter, Throttle};
+use crate::utils::disk_space::wait_for_free_space;
 use crate::utils::format_arg::{page_format_args, video_format_args};
+use crate::utils::media_verify::{verify_media_file, VerifiedFile};
+use crate::utils::mkv_mux::{mux_mkv, MkvInputs};
 use crate::utils::model::{
     create_pages, create_videos, filter_unfilled_videos, filter_unhandled_video_pages,
     get_failed_videos_in_current_cycle, update_pages_model, update_videos_model,
 };
-use crate::utils::nfo::NFO;
+use crate::utils::mp4_tags::{embed_tags, MediaTags};
+use crate::utils::nfo::{CollectionSet, Movie, TVShow, NFO};
 use crate::utils::notification::NewVideoInfo;
 use crate::utils::scan_collector::create_new_video_info;
 use crate::utils::status::{PageStatus, VideoStatus, STATUS_OK};
+use crate::utils::trickplay::{generate_trickplay, FrameSource};
 
 fn is_bili_request_failed_404(err: &anyhow::Error) -> bool {
     err.chain().any(|cause| {
@@ -212,6 +224,8 @@ pub async fn process_video_source(
 
     if ARGS.scan_only {
         warn!("已开启仅扫描模式，跳过视频下载..");
+    } else if !crate::task::schedule::download_allowed_now() {
+        info!("当前不在下载时间窗口内，跳过视频下载");
     } else {
         // 从数据库中查找所有未下载的视频与分页，下载并处理
         if let Err(e) =
@@ -901,7 +915,7 @@ pub async fn fetch_video_details(
                                     );
 
                                     let ok_video_status: u32 = VideoStatus::from([STATUS_OK; 5]).into();
-                                    let ok_page_status: u32 = PageStatus::from([STATUS_OK; 5]).into();
+                                    let ok_page_status: u32 = PageStatus::from([STATUS_OK; 6]).into();
 
                                     video::Entity::update(video::ActiveModel {
                                         id: Unchanged(video_id),
@@ -1200,9 +1214,15 @@ pub async fn download_unprocessed_videos(
         return Ok(());
     }
     video_source.log_download_video_start();
+    // 收藏夹/合集对应的媒体服务器合集文件夹
+    if let Err(e) = crate::utils::box_set::write_box_set(video_source, downloader, token.clone()).await {
+        warn!("生成合集文件夹失败: {:#}", e);
+    }
     let current_config = crate::config::reload_config();
     let semaphore = Semaphore::new(current_config.concurrent_limit.video);
-    let unhandled_videos_pages = filter_unhandled_video_pages(video_source.filter_expr(), connection).await?;
+    let mut unhandled_videos_pages = filter_unhandled_video_pages(video_source.filter_expr(), connection).await?;
+    // 按优先级排队，信号量按先来先得分配名额，优先级高的视频会先开始下载
+    sort_by_priority(&mut unhandled_videos_pages);
 
     // 只有当有未处理视频时才显示日志
     if !unhandled_videos_pages.is_empty() {
@@ -1511,7 +1531,7 @@ pub async fn batch_ai_rename_for_source(video_source: &VideoSourceEnum, connecti
                 continue;
             }
 
-            let is_audio = matches!(file_ext.as_str(), "m4a" | "mp3" | "flac" | "aac" | "ogg");
+            let is_audio = matches!(file_ext.as_str(), "m4a" | "mp3" | "flac" | "aac" | "ogg" | "opus");
 
             // 构建 AI 重命名上下文
             let ctx = AiRenameContext {
@@ -1880,7 +1900,8 @@ pub async fn retry_failed_videos_once(
         info!("任务已暂停/取消，跳过失败视频重试阶段");
         return Ok(());
     }
-    let failed_videos_pages = get_failed_videos_in_current_cycle(video_source.filter_expr(), connection).await?;
+    let mut failed_videos_pages = get_failed_videos_in_current_cycle(video_source.filter_expr(), connection).await?;
+    sort_by_priority(&mut failed_videos_pages);
 
     if failed_videos_pages.is_empty() {
         debug!("当前循环中没有失败的视频需要重试");
@@ -2038,6 +2059,17 @@ pub async fn download_video_pages(
         _ = token.cancelled() => return Err(anyhow!("Download cancelled")),
         permit = semaphore.acquire() => permit.context("acquire semaphore failed")?,
     };
+    // 与「立即下载」互斥，同一视频同时只由一个任务处理
+    let Some(_claim) = claim_video(video_model.id) else {
+        info!("视频「{}」正在立即下载中，本轮跳过", &video_model.name);
+        return Ok(video_model.into());
+    };
+    // 排队期间可能已被「立即下载」处理过，状态变化时以数据库为准，避免重复下载
+    if let Some(latest) = video::Entity::find_by_id(video_model.id).one(connection).await? {
+        if latest.download_status != video_model.download_status {
+            return Ok(latest.into());
+        }
+    }
     let mut status = VideoStatus::from(video_model.download_status);
     let separate_status = status.should_run();
     // “重置后恢复”判断：如果数据库里已存在 page.path，通常代表曾经下载过；此时遇到 B站-404 应恢复为未重置
@@ -2181,246 +2213,8 @@ pub async fn download_video_pages(
     let flat_folder = video_source.flat_folder();
 
     // 获取番剧源和季度信息
-    let (base_path, season_folder, bangumi_folder_path) = if is_bangumi {
-        let bangumi_source = match video_source {
-            VideoSourceEnum::BangumiSource(source) => source,
-            _ => unreachable!(),
-        };
-
-        // 为番剧创建独立的文件夹：配置路径 -> 番剧文件夹 -> Season文件夹
-        let bangumi_root_path = bangumi_source.path();
-
-        // 平铺目录模式：直接使用视频源根目录，不创建番剧文件夹/Season结构
-        if flat_folder {
-            debug!("平铺目录模式：番剧所有文件直接放在视频源根目录，跳过Season目录结构");
-            (
-                bangumi_root_path.to_path_buf(),
-                None,
-                Some(bangumi_root_path.to_path_buf()),
-            )
-        } else {
-            // 创建临时的page模型来获取格式化参数（只创建一次，避免重复）
-            let temp_page = bili_sync_entity::page::Model {
-                id: 0,
-                video_id: video_model.id,
-                cid: 0,
-                pid: 1,
-                name: "temp".to_string(),
-                width: None,
-                height: None,
-                duration: 0,
-                path: None,
-                image: None,
-                download_status: 0,
-                created_at: now_standard_string(),
-                ai_renamed: None,
-            };
-
-            // 获取真实的番剧标题（从缓存或API）
-            let api_title = if let Some(ref season_id) = video_model.season_id {
-                get_cached_season_title(bili_client, season_id, token.clone()).await
-            } else {
-                None
-            };
-
-            // 使用番剧格式化参数，优先使用API提供的真实标题
-            let format_args =
-                crate::utils::format_arg::bangumi_page_format_args(&video_model, &temp_page, api_title.as_deref());
-
-            // 检查是否有有效的series_title，如果没有则跳过番剧处理
-            let series_title = format_args["series_title"].as_str().unwrap_or("");
-            if series_title.is_empty() {
-                return Err(anyhow::anyhow!(
-                    "番剧 {} (BVID: {}) 缺少API标题数据，无法创建番剧文件夹",
-                    video_model.name,
-                    video_model.bvid
-                ));
-            }
-
-            // 生成番剧文件夹名称
-            let bangumi_folder_name =
-                crate::config::with_config(|bundle| bundle.render_bangumi_folder_template(&format_args))
-                    .map_err(|e| anyhow::anyhow!("渲染番剧文件夹模板失败: {}", e))?;
-
-            // 番剧文件夹路径
-            let bangumi_folder_path = bangumi_root_path.join(&bangumi_folder_name);
-
-            // 延迟创建番剧文件夹，只在实际需要时创建
-
-            // 检查是否启用番剧Season结构
-            let use_bangumi_season_structure =
-                crate::config::with_config(|bundle| bundle.config.bangumi_use_season_structure);
-
-            if use_bangumi_season_structure {
-                // 启用番剧Season结构：创建统一的系列根目录，在其下创建Season子目录
-
-                // 提取基础系列名称和季度信息
-                let series_title = api_title.as_deref().unwrap_or(&video_model.name);
-                let season_title = format_args.get("season_title").and_then(|v| v.as_str());
-
-                let (base_series_name_raw, season_number) =
-                    crate::utils::bangumi_name_extractor::BangumiNameExtractor::extract_series_name_and_season(
-                        series_title,
-                        season_title,
-                    );
-
-                // 系列根目录路径，直接使用提取后的基础系列名称（不应用标准化）
-                // 这样确保同一系列的不同季度使用相同的根目录
-                let series_root_path = bangumi_root_path.join(&base_series_name_raw);
-
-                // 生成标准的Season文件夹名称，根据实际季度编号生成
-                let season_folder_name =
-                    crate::utils::bangumi_name_extractor::BangumiNameExtractor::generate_season_folder_name(
-                        season_number,
-                    );
-                let season_path = series_root_path.join(&season_folder_name);
-
-                (season_path, Some(season_folder_name), Some(series_root_path))
-            } else {
-                // 原有逻辑：根据配置决定是否创建季度子目录
-                let should_create_season_folder = bangumi_source.download_all_seasons
-                    || (bangumi_source
-                        .selected_seasons
-                        .as_ref()
-                        .map(|s| !s.is_empty())
-                        .unwrap_or(false))
-                    || video_model.season_id.is_some(); // 单季度番剧：如果有season_id就创建目录
-
-                if should_create_season_folder && video_model.season_id.is_some() {
-                    // 使用配置的folder_structure模板生成季度文件夹名称（复用已有的format_args）
-                    let season_folder_name =
-                        crate::config::with_config(|bundle| bundle.render_folder_structure_template(&format_args))
-                            .map_err(|e| anyhow::anyhow!("渲染季度文件夹模板失败: {}", e))?;
-
-                    (
-                        bangumi_folder_path.join(&season_folder_name),
-                        Some(season_folder_name),
-                        Some(bangumi_folder_path),
-                    )
-                } else {
-                    // 不启用下载所有季度且没有选中特定季度时，直接使用番剧文件夹路径
-                    (bangumi_folder_path.clone(), None, Some(bangumi_folder_path))
-                }
-            }
-        }
-    } else {
-        // 非番剧使用原来的逻辑，但对合集进行特殊处理
-        // 【重要】：始终从视频源的原始路径开始计算，避免使用已保存的视频路径
-        let video_source_base_path = video_source.path();
-
-        debug!("=== 路径计算开始 ===");
-        debug!("视频源基础路径: {:?}", video_source_base_path);
-        debug!("视频BVID: {}", final_video_model.bvid);
-        debug!(
-            "视频UP主: {} ({})",
-            final_video_model.upper_name, final_video_model.upper_id
-        );
-        debug!("数据库中保存的路径: {:?}", final_video_model.path);
-        debug!("注意：将忽略数据库中的路径，从视频源基础路径重新计算");
-
-        if flat_folder {
-            debug!("平铺目录模式：所有文件直接放在视频源根目录");
-        }
-
-        let path = if flat_folder {
-            // 平铺目录模式：直接使用视频源根目录，不创建子文件夹
-            video_source_base_path.to_path_buf()
-        } else if let VideoSourceEnum::Collection(collection_source) = video_source {
-            // 合集的特殊处理
-            let config = crate::config::reload_config();
-            match config.collection_folder_mode.as_ref() {
-                "unified" => {
-                    // 统一模式：所有视频放在以合集名称命名的同一个文件夹下
-                    let safe_collection_name = crate::utils::filenamify::filenamify(&collection_source.name);
-                    debug!(
-                        "合集统一模式 - 原名称: '{}', 安全化后: '{}'",
-                        collection_source.name, safe_collection_name
-                    );
-                    video_source_base_path.join(&safe_collection_name)
-                }
-                _ => {
-                    // 分离模式（默认）：每个视频有自己的文件夹
-                    let base_folder_name = crate::config::with_config(|bundle| {
-                        bundle.render_video_template(&video_format_args(&final_video_model))
-                    })
-                    .map_err(|e| anyhow::anyhow!("模板渲染失败: {}", e))?;
-
-                    debug!("合集分离模式 - 渲染的文件夹名: '{}'", base_folder_name);
-                    debug!("合集分离模式 - 基础路径: {:?}", video_source_base_path);
-
-                    // **智能判断：根据模板内容决定是否需要去重**
-                    let video_template =
-                        crate::config::with_config(|bundle| bundle.config.video_name.as_ref().to_string());
-                    let needs_deduplication = video_template.contains("title")
-                        || (video_template.contains("name") && !video_template.contains("upper_name"));
-
-                    if needs_deduplication {
-                        // 智能去重：检查文件夹名是否已存在，如果存在则追加唯一标识符
-                        let unique_folder_name = generate_unique_folder_name(
-                            video_source_base_path,
-                            &base_folder_name,
-                            &video_model,
-                            &video_model.pubtime.format("%Y-%m-%d").to_string(),
-                        );
-                        video_source_base_path.join(&unique_folder_name)
-                    } else {
-                        // 不使用去重，允许多个视频共享同一文件夹
-                        video_source_base_path.join(&base_folder_name)
-                    }
-                }
-            }
-        } else {
-            // 其他类型的视频源使用原来的逻辑
-            let base_folder_name = crate::config::with_config(|bundle| {
-                bundle.render_video_template(&video_format_args(&final_video_model))
-            })
-            .map_err(|e| anyhow::anyhow!("模板渲染失败: {}", e))?;
-
-            debug!("普通视频源 - 渲染的文件夹名: '{}'", base_folder_name);
-            debug!("普通视频源 - 基础路径: {:?}", video_source_base_path);
-
-            // **智能判断：根据模板内容决定是否需要去重**
-            let video_template = crate::config::with_config(|bundle| bundle.config.video_name.as_ref().to_string());
-            let needs_deduplication = video_template.contains("title")
-                || (video_template.contains("name") && !video_template.contains("upper_name"));
-
-            if needs_deduplication {
-                // 智能去重：检查文件夹名是否已存在，如果存在则追加唯一标识符
-                let unique_folder_name = generate_unique_folder_name(
-                    video_source_base_path,
-                    &base_folder_name,
-                    &final_video_model,
-                    &final_video_model.pubtime.format("%Y-%m-%d").to_string(),
-                );
-                debug!("使用去重文件夹名: '{}'", unique_folder_name);
-                let final_path = video_source_base_path.join(&unique_folder_name);
-                debug!("最终计算路径: {:?}", final_path);
-                final_path
-            } else {
-                // 不使用去重，允许多个视频共享同一文件夹
-                debug!("不使用去重，直接使用基础文件夹名: '{}'", base_folder_name);
-                let final_path = video_source_base_path.join(&base_folder_name);
-                debug!("最终计算路径: {:?}", final_path);
-                final_path
-            }
-        };
-
-        // 检查是否为多P视频且启用了Season结构
-        let config = crate::config::reload_config();
-        let is_single_page = final_video_model.single_page.unwrap_or(true);
-
-        if !flat_folder
-            && ((!is_single_page && config.multi_page_use_season_structure)
-                || (is_collection && config.collection_use_season_structure))
-        {
-            // 为多P视频或合集创建Season文件夹结构
-            let season_folder_name = "Season 01".to_string();
-            let season_path = path.join(&season_folder_name);
-            (season_path, Some(season_folder_name), Some(path))
-        } else {
-            (path, None, None)
-        }
-    };
+    let (base_path, season_folder, bangumi_folder_path) =
+        resolve_video_folders(bili_client, video_source, &final_video_model, token.clone()).await?;
 
     // 延迟创建季度文件夹，只在实际需要写入文件时创建
 
@@ -2504,6 +2298,9 @@ pub async fn download_video_pages(
     if disable_tvshow_assets {
         debug!("平铺目录模式：已启用跳过TVShow/Season元数据下载");
     }
+    // 单P视频按剧集写入时（Plex），视频文件夹同样需要 tvshow.nfo；平铺目录下多个视频共用文件夹，不生成
+    let single_page_as_show =
+        is_single_page && !flat_folder && !current_config.nfo_config.profile.single_page_as_movie();
 
     // 为番剧判断是否下载元数据（依赖数据库状态，不检查文件存在性）
     // 只有第一个集（should_download_upper=true）才负责下载Series级别图片
@@ -2602,8 +2399,8 @@ pub async fn download_video_pages(
                 false
             }
         } else {
-            // 普通视频：为多P视频生成nfo
-            separate_status[2] && !is_single_page && !disable_tvshow_assets
+            // 普通视频：为多P视频或按剧集写入的单P视频生成nfo
+            separate_status[2] && (!is_single_page || single_page_as_show) && !disable_tvshow_assets
         };
 
         if should_generate_nfo && is_collection {
@@ -2623,6 +2420,7 @@ pub async fn download_video_pages(
                     &video_model,
                     Some(&collection_source.name),
                     collection_cover.as_deref(),
+                    crate::utils::box_set::collection_set(video_source),
                     if let Some(ref bangumi_path) = bangumi_folder_path {
                         // 多P视频或合集使用Season结构时，tvshow.nfo放在视频根目录
                         let config = crate::config::reload_config();
@@ -2663,6 +2461,7 @@ pub async fn download_video_pages(
             generate_video_nfo(
                 should_generate_nfo,
                 &video_model,
+                crate::utils::box_set::collection_set(video_source),
                 if let Some(ref bangumi_path) = bangumi_folder_path {
                     if is_bangumi {
                         // 番剧tvshow.nfo放在番剧文件夹根目录，使用固定文件名
@@ -2675,6 +2474,8 @@ pub async fn download_video_pages(
                             && season_folder.is_some()
                         {
                             bangumi_path.join("tvshow.nfo")
+                        } else if single_page_as_show {
+                            base_path.join("tvshow.nfo")
                         } else {
                             // 不使用Season结构时，保持原有逻辑
                             base_path.join(format!("{}.nfo", video_base_name))
@@ -2692,6 +2493,9 @@ pub async fn download_video_pages(
                             .parent()
                             .map(|parent| parent.join("tvshow.nfo"))
                             .unwrap_or_else(|| base_path.join("tvshow.nfo"))
+                    } else if single_page_as_show {
+                        // 单P视频的剧集nfo与单集nfo同在视频文件夹，使用固定文件名避免同名
+                        base_path.join("tvshow.nfo")
                     } else {
                         // 普通视频nfo放在视频文件夹
                         base_path.join(format!("{}.nfo", video_base_name))
@@ -3209,7 +3013,7 @@ pub async fn download_video_pages(
         }
 
         // 立即修复数据库分页状态，避免前端仍显示“分页未完成”
-        let ok_page_status: u32 = PageStatus::from([STATUS_OK; 5]).into();
+        let ok_page_status: u32 = PageStatus::from([STATUS_OK; 6]).into();
         let txn = connection.begin().await?;
         video::Entity::update(video::ActiveModel {
             id: Unchanged(final_video_model.id),
@@ -3235,13 +3039,29 @@ pub async fn download_video_pages(
         Ok(ExecutionStatus::Skipped)
     };
 
-    // 额外的结果单独处理（季度NFO、季度图片、根目录Emby兼容封面、staff头像）
+    // 按配置补齐媒体库图片（fanart、landscape、季度封面、演员头像），已存在的文件直接跳过
+    let artwork_result = write_video_artwork(
+        VideoArtwork {
+            video_source,
+            video: &final_video_model,
+            season_info: season_info.as_ref(),
+            base_path: &base_path,
+            series_path: bangumi_folder_path.as_deref(),
+            has_season_folder: season_folder.is_some(),
+        },
+        downloader,
+        token.clone(),
+    )
+    .await;
+
+    // 额外的结果单独处理（季度NFO、季度图片、根目录Emby兼容封面、staff头像、媒体库图片）
     let extra_results = [
         Ok(season_nfo_result.unwrap_or(ExecutionStatus::Skipped)),
         Ok(season_images_result.unwrap_or(ExecutionStatus::Skipped)),
         res_2,              // 番剧/多P/合集根目录 poster.jpg 的结果（Emby兼容）
         res_folder,         // 番剧/多P/合集根目录 folder.jpg 的结果（Emby优先识别）
         staff_faces_result, // staff成员头像下载结果
+        artwork_result,     // 媒体库图片布局的结果
     ]
     .into_iter()
     .map(Into::into)
@@ -3255,7 +3075,6 @@ pub async fn download_video_pages(
 
     all_results
         .iter()
-        .take(9)
         .zip([
             "封面",
             "详情",
@@ -3266,6 +3085,8 @@ pub async fn download_video_pages(
             "季度图片",
             "根目录poster",
             "根目录folder",
+            "合作UP主头像",
+            "媒体库图片",
         ])
         .for_each(|(res, task_name)| match res {
             ExecutionStatus::Skipped => debug!("处理视频「{}」{}已成功过，跳过", &video_model.name, task_name),
@@ -3573,7 +3394,7 @@ pub async fn dispatch_download_page(args: DownloadPageArgs<'_>, token: Cancellat
                 // 这样会导致即使分页中有失败到 MAX_RETRY 的情况，视频层的分 P 下载状态也会被认为是 Succeeded，不够准确
                 // 新版本实现会将此处取值为所有子任务状态的最小值，这样只有所有分页的子任务全部成功时才会认为视频层的分 P 下载状态是 Succeeded
                 let page_download_status = model.download_status.try_as_ref().expect("download_status must be set");
-                let separate_status: [u32; 5] = PageStatus::from(*page_download_status).into();
+                let separate_status: [u32; 6] = PageStatus::from(*page_download_status).into();
                 for status in separate_status {
                     target_status = target_status.min(status);
                 }
@@ -3694,56 +3515,277 @@ pub async fn dispatch_download_page(args: DownloadPageArgs<'_>, token: Cancellat
     Ok(ExecutionStatus::Succeeded)
 }
 
-/// 下载某个分页，未发生风控且正常运行时返回 Ok(Page::ActiveModel)，其中 status 字段存储了新的下载状态，发生风控时返回 DownloadAbortError
-#[allow(clippy::too_many_arguments)]
-pub async fn download_page(
+/// 按当前模板计算视频的存放目录，返回 (分页所在目录, 季度文件夹名, 剧集根目录)
+pub(crate) async fn resolve_video_folders(
     bili_client: &BiliClient,
     video_source: &VideoSourceEnum,
-    video_model: &video::Model,
-    mut page_model: page::Model,
-    connection: &DatabaseConnection,
-    semaphore: &Semaphore,
-    downloader: &UnifiedDownloader,
-    base_path: &Path,
+    final_video_model: &video::Model,
     token: CancellationToken,
-) -> Result<page::ActiveModel> {
-    let _permit = tokio::select! {
-        biased;
-        _ = token.cancelled() => return Err(anyhow!("Download cancelled")),
-        permit = semaphore.acquire() => permit.context("acquire semaphore failed")?,
-    };
-    let mut status = PageStatus::from(page_model.download_status);
-    let mut separate_status = status.should_run();
-    let is_single_page = video_model.single_page.context("single_page is null")?;
+) -> Result<(PathBuf, Option<String>, Option<PathBuf>)> {
+    let video_model = final_video_model;
+    let is_bangumi = matches!(video_source, VideoSourceEnum::BangumiSource(_));
+    let is_collection = matches!(video_source, VideoSourceEnum::Collection(_));
+    let flat_folder = video_source.flat_folder();
+    let (base_path, season_folder, bangumi_folder_path) = if is_bangumi {
+        let bangumi_source = match video_source {
+            VideoSourceEnum::BangumiSource(source) => source,
+            _ => unreachable!(),
+        };
 
-    // 根据视频源设置调整弹幕和字幕下载开关
-    // separate_status[3] = 弹幕, separate_status[4] = 字幕
-    if !video_source.download_danmaku() {
-        separate_status[3] = false;
-    }
-    if !video_source.download_subtitle() {
-        separate_status[4] = false;
-    }
+        // 为番剧创建独立的文件夹：配置路径 -> 番剧文件夹 -> Season文件夹
+        let bangumi_root_path = bangumi_source.path();
 
-    // 获取是否仅下载音频的设置
-    let audio_only = video_source.audio_only();
+        // 平铺目录模式：直接使用视频源根目录，不创建番剧文件夹/Season结构
+        if flat_folder {
+            debug!("平铺目录模式：番剧所有文件直接放在视频源根目录，跳过Season目录结构");
+            (
+                bangumi_root_path.to_path_buf(),
+                None,
+                Some(bangumi_root_path.to_path_buf()),
+            )
+        } else {
+            // 创建临时的page模型来获取格式化参数（只创建一次，避免重复）
+            let temp_page = bili_sync_entity::page::Model {
+                id: 0,
+                video_id: video_model.id,
+                cid: 0,
+                pid: 1,
+                name: "temp".to_string(),
+                width: None,
+                height: None,
+                duration: 0,
+                path: None,
+                image: None,
+                download_status: 0,
+                created_at: now_standard_string(),
+                ai_renamed: None,
+                file_size: None,
+                file_hash: None,
+                verified_at: None,
+                postprocess_status: 0,
+                postprocess_error: None,
+                postprocess_copy_path: None,
+            };
 
-    // 仅音频模式下，如果启用了audio_only_m4a_only，跳过所有sidecar文件
-    // separate_status[0] = 封面, [2] = NFO, [3] = 弹幕, [4] = 字幕
-    if audio_only && video_source.audio_only_m4a_only() {
-        separate_status[0] = false; // 跳过封面
-        separate_status[2] = false; // 跳过NFO
-        separate_status[3] = false; // 跳过弹幕
-        separate_status[4] = false; // 跳过字幕
-    }
+            // 获取真实的番剧标题（从缓存或API）
+            let api_title = if let Some(ref season_id) = video_model.season_id {
+                get_cached_season_title(bili_client, season_id, token.clone()).await
+            } else {
+                None
+            };
 
-    // 检查是否为番剧
-    let is_bangumi = match video_model.source_type {
-        Some(1) => true, // source_type = 1 表示为番剧
-        _ => false,
+            // 使用番剧格式化参数，优先使用API提供的真实标题
+            let format_args =
+                crate::utils::format_arg::bangumi_page_format_args(video_model, &temp_page, api_title.as_deref());
+
+            // 检查是否有有效的series_title，如果没有则跳过番剧处理
+            let series_title = format_args["series_title"].as_str().unwrap_or("");
+            if series_title.is_empty() {
+                return Err(anyhow::anyhow!(
+                    "番剧 {} (BVID: {}) 缺少API标题数据，无法创建番剧文件夹",
+                    video_model.name,
+                    video_model.bvid
+                ));
+            }
+
+            // 生成番剧文件夹名称
+            let bangumi_folder_name =
+                crate::config::with_config(|bundle| bundle.render_bangumi_folder_template(&format_args))
+                    .map_err(|e| anyhow::anyhow!("渲染番剧文件夹模板失败: {}", e))?;
+
+            // 番剧文件夹路径
+            let bangumi_folder_path = bangumi_root_path.join(&bangumi_folder_name);
+
+            // 延迟创建番剧文件夹，只在实际需要时创建
+
+            // 检查是否启用番剧Season结构
+            let use_bangumi_season_structure =
+                crate::config::with_config(|bundle| bundle.config.bangumi_use_season_structure);
+
+            if use_bangumi_season_structure {
+                // 启用番剧Season结构：创建统一的系列根目录，在其下创建Season子目录
+
+                // 提取基础系列名称和季度信息
+                let series_title = api_title.as_deref().unwrap_or(&video_model.name);
+                let season_title = format_args.get("season_title").and_then(|v| v.as_str());
+
+                let (base_series_name_raw, season_number) =
+                    crate::utils::bangumi_name_extractor::BangumiNameExtractor::extract_series_name_and_season(
+                        series_title,
+                        season_title,
+                    );
+
+                // 系列根目录路径，直接使用提取后的基础系列名称（不应用标准化）
+                // 这样确保同一系列的不同季度使用相同的根目录
+                let series_root_path = bangumi_root_path.join(&base_series_name_raw);
+
+                // 生成标准的Season文件夹名称，根据实际季度编号生成
+                let season_folder_name =
+                    crate::utils::bangumi_name_extractor::BangumiNameExtractor::generate_season_folder_name(
+                        season_number,
+                    );
+                let season_path = series_root_path.join(&season_folder_name);
+
+                (season_path, Some(season_folder_name), Some(series_root_path))
+            } else {
+                // 原有逻辑：根据配置决定是否创建季度子目录
+                let should_create_season_folder = bangumi_source.download_all_seasons
+                    || (bangumi_source
+                        .selected_seasons
+                        .as_ref()
+                        .map(|s| !s.is_empty())
+                        .unwrap_or(false))
+                    || video_model.season_id.is_some(); // 单季度番剧：如果有season_id就创建目录
+
+                if should_create_season_folder && video_model.season_id.is_some() {
+                    // 使用配置的folder_structure模板生成季度文件夹名称（复用已有的format_args）
+                    let season_folder_name =
+                        crate::config::with_config(|bundle| bundle.render_folder_structure_template(&format_args))
+                            .map_err(|e| anyhow::anyhow!("渲染季度文件夹模板失败: {}", e))?;
+
+                    (
+                        bangumi_folder_path.join(&season_folder_name),
+                        Some(season_folder_name),
+                        Some(bangumi_folder_path),
+                    )
+                } else {
+                    // 不启用下载所有季度且没有选中特定季度时，直接使用番剧文件夹路径
+                    (bangumi_folder_path.clone(), None, Some(bangumi_folder_path))
+                }
+            }
+        }
+    } else {
+        // 非番剧使用原来的逻辑，但对合集进行特殊处理
+        // 【重要】：始终从视频源的原始路径开始计算，避免使用已保存的视频路径
+        let video_source_base_path = video_source.path();
+
+        debug!("=== 路径计算开始 ===");
+        debug!("视频源基础路径: {:?}", video_source_base_path);
+        debug!("视频BVID: {}", final_video_model.bvid);
+        debug!(
+            "视频UP主: {} ({})",
+            final_video_model.upper_name, final_video_model.upper_id
+        );
+        debug!("数据库中保存的路径: {:?}", final_video_model.path);
+        debug!("注意：将忽略数据库中的路径，从视频源基础路径重新计算");
+
+        if flat_folder {
+            debug!("平铺目录模式：所有文件直接放在视频源根目录");
+        }
+
+        let path = if flat_folder {
+            // 平铺目录模式：直接使用视频源根目录，不创建子文件夹
+            video_source_base_path.to_path_buf()
+        } else if let VideoSourceEnum::Collection(collection_source) = video_source {
+            // 合集的特殊处理
+            let config = crate::config::reload_config();
+            match config.collection_folder_mode.as_ref() {
+                "unified" => {
+                    // 统一模式：所有视频放在以合集名称命名的同一个文件夹下
+                    let safe_collection_name = crate::utils::filenamify::filenamify(&collection_source.name);
+                    debug!(
+                        "合集统一模式 - 原名称: '{}', 安全化后: '{}'",
+                        collection_source.name, safe_collection_name
+                    );
+                    video_source_base_path.join(&safe_collection_name)
+                }
+                _ => {
+                    // 分离模式（默认）：每个视频有自己的文件夹
+                    let base_folder_name = crate::config::with_config(|bundle| {
+                        bundle.render_video_template(&video_format_args(final_video_model))
+                    })
+                    .map_err(|e| anyhow::anyhow!("模板渲染失败: {}", e))?;
+
+                    debug!("合集分离模式 - 渲染的文件夹名: '{}'", base_folder_name);
+                    debug!("合集分离模式 - 基础路径: {:?}", video_source_base_path);
+
+                    // **智能判断：根据模板内容决定是否需要去重**
+                    let video_template =
+                        crate::config::with_config(|bundle| bundle.config.video_name.as_ref().to_string());
+                    let needs_deduplication = video_template.contains("title")
+                        || (video_template.contains("name") && !video_template.contains("upper_name"));
+
+                    if needs_deduplication {
+                        // 智能去重：检查文件夹名是否已存在，如果存在则追加唯一标识符
+                        let unique_folder_name = generate_unique_folder_name(
+                            video_source_base_path,
+                            &base_folder_name,
+                            video_model,
+                            &video_model.pubtime.format("%Y-%m-%d").to_string(),
+                        );
+                        video_source_base_path.join(&unique_folder_name)
+                    } else {
+                        // 不使用去重，允许多个视频共享同一文件夹
+                        video_source_base_path.join(&base_folder_name)
+                    }
+                }
+            }
+        } else {
+            // 其他类型的视频源使用原来的逻辑
+            let base_folder_name = crate::config::with_config(|bundle| {
+                bundle.render_video_template(&video_format_args(final_video_model))
+            })
+            .map_err(|e| anyhow::anyhow!("模板渲染失败: {}", e))?;
+
+            debug!("普通视频源 - 渲染的文件夹名: '{}'", base_folder_name);
+            debug!("普通视频源 - 基础路径: {:?}", video_source_base_path);
+
+            // **智能判断：根据模板内容决定是否需要去重**
+            let video_template = crate::config::with_config(|bundle| bundle.config.video_name.as_ref().to_string());
+            let needs_deduplication = video_template.contains("title")
+                || (video_template.contains("name") && !video_template.contains("upper_name"));
+
+            if needs_deduplication {
+                // 智能去重：检查文件夹名是否已存在，如果存在则追加唯一标识符
+                let unique_folder_name = generate_unique_folder_name(
+                    video_source_base_path,
+                    &base_folder_name,
+                    final_video_model,
+                    &final_video_model.pubtime.format("%Y-%m-%d").to_string(),
+                );
+                debug!("使用去重文件夹名: '{}'", unique_folder_name);
+                let final_path = video_source_base_path.join(&unique_folder_name);
+                debug!("最终计算路径: {:?}", final_path);
+                final_path
+            } else {
+                // 不使用去重，允许多个视频共享同一文件夹
+                debug!("不使用去重，直接使用基础文件夹名: '{}'", base_folder_name);
+                let final_path = video_source_base_path.join(&base_folder_name);
+                debug!("最终计算路径: {:?}", final_path);
+                final_path
+            }
+        };
+
+        // 检查是否为多P视频且启用了Season结构
+        let config = crate::config::reload_config();
+        let is_single_page = final_video_model.single_page.unwrap_or(true);
+
+        if !flat_folder
+            && ((!is_single_page && config.multi_page_use_season_structure)
+                || (is_collection && config.collection_use_season_structure))
+        {
+            // 为多P视频或合集创建Season文件夹结构
+            let season_folder_name = "Season 01".to_string();
+            let season_path = path.join(&season_folder_name);
+            (season_path, Some(season_folder_name), Some(path))
+        } else {
+            (path, None, None)
+        }
     };
+    Ok((base_path, season_folder, bangumi_folder_path))
+}
 
-    // 根据视频源类型选择不同的模板渲染方式
+/// 按当前模板渲染分页文件名（不含扩展名），下载与媒体库重新整理共用同一套规则
+pub(crate) async fn render_page_base_name(
+    bili_client: &BiliClient,
+    video_source: &VideoSourceEnum,
+    video_model: &video::Model,
+    page_model: &page::Model,
+    connection: &DatabaseConnection,
+    token: CancellationToken,
+) -> Result<String> {
+    let is_bangumi = matches!(video_model.source_type, Some(1));
+    let is_single_page = video_model.single_page.context("single_page is null")?;
     let base_name = if let VideoSourceEnum::Collection(collection_source) = video_source {
         // 合集视频的特殊处理
         let config = crate::config::reload_config();
@@ -3765,7 +3807,7 @@ pub async fn download_page(
                 Err(_) => {
                     // 如果获取序号失败，使用默认命名
                     crate::config::with_config(|bundle| {
-                        bundle.render_page_template(&page_format_args(video_model, &page_model))
+                        bundle.render_page_template(&page_format_args(video_model, page_model))
                     })
                     .map_err(|e| anyhow::anyhow!("模板渲染失败: {}", e))?
                 }
@@ -3775,7 +3817,7 @@ pub async fn download_page(
             let is_single_page = video_model.single_page.unwrap_or(true);
             if !is_single_page {
                 // 多P视频：使用multi_page_name模板
-                let page_args = page_format_args(video_model, &page_model);
+                let page_args = page_format_args(video_model, page_model);
                 match crate::config::with_config(|bundle| bundle.render_multi_page_template(&page_args)) {
                     Ok(rendered) => rendered,
                     Err(_) => {
@@ -3788,7 +3830,7 @@ pub async fn download_page(
             } else {
                 // 单P视频：使用page_name模板
                 crate::config::with_config(|bundle| {
-                    bundle.render_page_template(&page_format_args(video_model, &page_model))
+                    bundle.render_page_template(&page_format_args(video_model, page_model))
                 })
                 .map_err(|e| anyhow::anyhow!("模板渲染失败: {}", e))?
             }
@@ -3804,18 +3846,16 @@ pub async fn download_page(
             };
 
             bangumi_source
-                .render_page_name(video_model, &page_model, connection, api_title.as_deref())
+                .render_page_name(video_model, page_model, connection, api_title.as_deref())
                 .await?
         } else {
             // 如果类型不匹配，使用最新配置手动渲染
-            crate::config::with_config(|bundle| {
-                bundle.render_page_template(&page_format_args(video_model, &page_model))
-            })
-            .map_err(|e| anyhow::anyhow!("模板渲染失败: {}", e))?
+            crate::config::with_config(|bundle| bundle.render_page_template(&page_format_args(video_model, page_model)))
+                .map_err(|e| anyhow::anyhow!("模板渲染失败: {}", e))?
         }
     } else if !is_single_page {
         // 对于多P视频（非番剧），使用最新配置中的multi_page_name模板
-        let page_args = page_format_args(video_model, &page_model);
+        let page_args = page_format_args(video_model, page_model);
         match crate::config::with_config(|bundle| bundle.render_multi_page_template(&page_args)) {
             Ok(rendered) => rendered,
             Err(_) => {
@@ -3827,9 +3867,94 @@ pub async fn download_page(
         }
     } else {
         // 单P视频使用最新配置的page_name模板
-        crate::config::with_config(|bundle| bundle.render_page_template(&page_format_args(video_model, &page_model)))
+        crate::config::with_config(|bundle| bundle.render_page_template(&page_format_args(video_model, page_model)))
             .map_err(|e| anyhow::anyhow!("模板渲染失败: {}", e))?
     };
+    Ok(base_name)
+}
+
+/// 下载某个分页，未发生风控且正常运行时返回 Ok(Page::ActiveModel)，其中 status 字段存储了新的下载状态，发生风控时返回 DownloadAbortError
+#[allow(clippy::too_many_arguments)]
+pub async fn download_page(
+    bili_client: &BiliClient,
+    video_source: &VideoSourceEnum,
+    video_model: &video::Model,
+    mut page_model: page::Model,
+    connection: &DatabaseConnection,
+    semaphore: &Semaphore,
+    downloader: &UnifiedDownloader,
+    base_path: &Path,
+    token: CancellationToken,
+) -> Result<page::ActiveModel> {
+    // 磁盘空间不足时在此等待，避免写满磁盘后留下大量合并失败的半成品文件
+    wait_for_free_space(video_source.path(), &token).await?;
+    let _permit = tokio::select! {
+        biased;
+        _ = token.cancelled() => return Err(anyhow!("Download cancelled")),
+        permit = semaphore.acquire() => permit.context("acquire semaphore failed")?,
+    };
+    let mut status = PageStatus::from(page_model.download_status);
+    let mut separate_status = status.should_run();
+    let is_single_page = video_model.single_page.context("single_page is null")?;
+
+    // 根据视频源设置调整弹幕和字幕下载开关
+    // separate_status[3] = 弹幕, separate_status[4] = 字幕
+    if !video_source.download_danmaku() {
+        separate_status[3] = false;
+    }
+    if !video_source.download_subtitle() {
+        separate_status[4] = false;
+    }
+
+    // 获取是否仅下载音频的设置
+    let audio_only = video_source.audio_only();
+
+    // 仅音频模式下，如果启用了audio_only_m4a_only，跳过所有sidecar文件
+    // separate_status[0] = 封面, [2] = NFO, [3] = 弹幕, [4] = 字幕
+    if audio_only && video_source.audio_only_m4a_only() {
+        separate_status[0] = false; // 跳过封面
+        separate_status[2] = false; // 跳过NFO
+        separate_status[3] = false; // 跳过弹幕
+        separate_status[4] = false; // 跳过字幕
+    }
+
+    // separate_status[5] = 进度条预览图，仅音频没有画面，未启用时同样视为完成；之后开启时会重置缺少预览图的分页
+    if audio_only || !crate::config::reload_config().trickplay.enabled {
+        separate_status[5] = false;
+    }
+
+    // 检查是否为番剧
+    let is_bangumi = match video_model.source_type {
+        Some(1) => true, // source_type = 1 表示为番剧
+        _ => false,
+    };
+
+    // 视频无需重新下载时沿用数据库记录的文件路径：接管的已有文件、封装的 mkv 和转码后的音频都不在模板路径上，
+    // 附属文件同样写在该文件旁边，避免把记录的路径改回不存在的模板路径
+    let existing_video_path = page_model
+        .path
+        .as_deref()
+        .filter(|_| !separate_status[1])
+        .map(PathBuf::from);
+    let (base_path, base_name) = match existing_video_path
+        .as_deref()
+        .and_then(|path| Some((path.parent()?, path.file_stem()?.to_str()?)))
+    {
+        Some((dir, stem)) => (dir.to_path_buf(), stem.to_string()),
+        // 根据视频源类型选择不同的模板渲染方式
+        None => (
+            base_path.to_path_buf(),
+            render_page_base_name(
+                bili_client,
+                video_source,
+                video_model,
+                &page_model,
+                connection,
+                token.clone(),
+            )
+            .await?,
+        ),
+    };
 
     // 根据audio_only设置选择文件扩展名
     let media_ext = if audio_only { "m4a" } else { "mp4" };
@@ -3865,6 +3990,7 @@ pub async fn download_page(
             base_path.join(format!("{}.srt", &base_name)),
         )
     };
+    let video_path = existing_video_path.unwrap_or(video_path);
     let dimension = match (page_model.width, page_model.height) {
         (Some(width), Some(height)) => Some(Dimension {
             width,
@@ -3931,6 +4057,35 @@ pub async fn download_page(
         dimension,
         ..Default::default()
     };
+    // 同一视频源的所有分页共用一个限速器
+    let throttle = Throttle::with_source(source_limiter(
+        &video_source.source_key(),
+        video_source.download_speed_limit(),
+    ));
+    // 字幕和分段章节来自同一个播放器接口，每个分页只请求一次；CID 仍无效时无法请求
+    let player_info = if page_model.cid > 0 && (separate_status[1] || separate_status[2] || separate_status[4]) {
+        Some(
+            Video::new(bili_client, video_model.bvid.clone())
+                .get_player_info(&page_info)
+                .await,
+        )
+    } else {
+        None
+    };
+    // 分段章节同时写入视频文件和 NFO，属于可选信息，获取失败时不影响其它任务
+    let chapters = match &player_info {
+        Some(Ok(player_info)) => player_info.chapters(),
+        Some(Err(e)) if separate_status[1] || separate_status[2] => {
+            warn!(
+                "视频「{}」第 {} 页获取分段章节失败: {:#}",
+                &video_model.name, page_model.pid, e
+            );
+            Vec::new()
+        }
+        _ => Vec::new(),
+    };
+    // 封面在写入媒体标签时复用
+    let page_poster_path = poster_path.clone();
     // 使用 tokio::join! 替代装箱的 Future，零分配并行执行
     let (res_1, res_2, res_3, res_4, res_5) = tokio::join!(
         fetch_page_poster(
@@ -3950,9 +4105,18 @@ pub async fn download_page(
             &page_info,
             &video_path,
             audio_only,
+            &throttle,
             token.clone(),
         ),
-        generate_page_nfo(separate_status[2], video_model, &page_model, nfo_path, connection),
+        generate_page_nfo(
+            separate_status[2],
+            video_model,
+            &page_model,
+            &chapters,
+            crate::utils::box_set::collection_set(video_source),
+            nfo_path,
+            connection
+        ),
         fetch_page_danmaku(
             separate_status[3],
             bili_client,
@@ -3965,13 +4129,61 @@ pub async fn download_page(
             separate_status[4],
             bili_client,
             video_model,
-            &page_info,
+            player_info.as_ref(),
             &subtitle_path,
             token.clone(),
         )
     );
 
-    let results = [res_1, res_2, res_3, res_4, res_5]
+    // 视频刚下载完成时立即校验，不完整的文件按失败处理，交由重试流程重新下载
+    let mut video_refreshed = false;
+    let mut verified_file = None;
+    let res_2 = match res_2 {
+        Ok(ExecutionStatus::Succeeded) => {
+            video_refreshed = true;
+            // 先写入标签再校验，保证记录的文件大小和哈希与最终文件一致
+            embed_page_tags(
+                bili_client,
+                video_model,
+                &page_model,
+                &chapters,
+                &video_path,
+                &page_poster_path,
+                connection,
+                token.clone(),
+            )
+            .await;
+            let verify_config = crate::config::reload_config().media_verify;
+            if verify_config.enabled {
+                match verify_media_file(&video_path, page_model.duration, &verify_config).await {
+                    Ok(verified) => {
+                        verified_file = Some(verified);
+                        Ok(ExecutionStatus::Succeeded)
+                    }
+                    Err(e) => {
+                        let _ = tokio::fs::remove_file(&video_path).await;
+                        Err(e.context("下载后文件校验失败"))
+                    }
+                }
+            } else {
+                Ok(ExecutionStatus::Succeeded)
+            }
+        }
+        other => other,
+    };
+
+    // 本地生成预览图需要完整的视频文件，因此在其它子任务完成后执行
+    let res_6 = generate_page_trickplay(
+        separate_status[5],
+        bili_client,
+        video_model,
+        &page_info,
+        &video_path,
+        token.clone(),
+    )
+    .await;
+
+    let results = [res_1, res_2, res_3, res_4, res_5, res_6]
         .into_iter()
         .map(Into::into)
         .collect::<Vec<_>>();
@@ -3981,7 +4193,7 @@ pub async fn download_page(
 
     results
         .iter()
-        .zip(["封面", "视频", "详情", "弹幕", "字幕"])
+        .zip(["封面", "视频", "详情", "弹幕", "字幕", "预览图"])
         .for_each(|(res, task_name)| match res {
             ExecutionStatus::Skipped => debug!(
                 "处理视频「{}」第 {} 页{}已成功过，跳过",
@@ -4146,14 +4358,298 @@ pub async fn download_page(
 
     // AI 重命名已移至视频源下载完成后批量执行（batch_ai_rename_for_source）
     // 此处仅保存原始文件路径，批量重命名时会更新
-    let final_video_path = video_path.clone();
+    let mut final_video_path = video_path.clone();
+
+    // 仅音频模式按视频源设置转码和标准化响度，失败时保留原始 m4a
+    if video_refreshed && status.get(1) == STATUS_OK && audio_only {
+        let output = AudioOutput {
+            format: AudioFormat::parse(video_source.audio_format()).unwrap_or(AudioFormat::M4a),
+            normalize: video_source.audio_normalize(),
+        };
+        let loudness = crate::config::reload_config().loudness;
+        match process_audio(&video_path, &output, &loudness).await {
+            Ok(Some(audio_path)) => {
+                if verified_file.is_some() {
+                    verified_file = verify_final_file(&audio_path, video_model, &page_model, &mut status).await;
+                }
+                final_video_path = audio_path;
+            }
+            Ok(None) => {}
+            Err(e) => warn!(
+                "视频「{}」第 {} 页音频转码失败，保留原始 m4a: {:#}",
+                &video_model.name, page_model.pid, e
+            ),
+        }
+    }
+
+    // 输出 mkv 时把字幕、弹幕和封面封装进视频文件，失败时保留 mp4 和外挂文件
+    if video_refreshed && status.get(1) == STATUS_OK && !audio_only && video_source.output_container() == "mkv" {
+        match mux_page_mkv(&video_path).await {
+            Ok(mkv_path) => {
+                // 校验记录需要对应最终的 mkv 文件
+                if verified_file.is_some() {
+                    verified_file = verify_final_file(&mkv_path, video_model, &page_model, &mut status).await;
+                    if verified_file.is_none() {
+                        // 外挂字幕和弹幕已随封装删除，重新下载时需要一并重新获取
+                        status.set(3, 0);
+                        status.set(4, 0);
+                    }
+                }
+                final_video_path = mkv_path;
+            }
+            Err(e) => warn!(
+                "视频「{}」第 {} 页封装 mkv 失败，保留 mp4 和外挂文件: {:#}",
+                &video_model.name, page_model.pid, e
+            ),
+        }
+    }
+
+    // 新下载的视频交给独立的后处理工作池，不等待处理完成
+    let postprocess_profile = video_source.postprocess_profile();
+    if video_refreshed && status.get(1) == STATUS_OK && !audio_only && !postprocess_profile.is_empty() {
+        if let Err(e) =
+            crate::task::postprocess::enqueue(connection, page_model.id, &final_video_path, postprocess_profile).await
+        {
+            warn!(
+                "视频「{}」第 {} 页加入后处理队列失败: {:#}",
+                &video_model.name, page_model.pid, e
+            );
+        }
+    }
+
+    // 只有本轮真正下载了媒体文件的目录才需要通知媒体服务器刷新
+    if video_refreshed && status.get(1) == STATUS_OK {
+        crate::utils::media_server::record_downloaded_folder(&base_path);
+    }
 
     let mut page_active_model: page::ActiveModel = page_model.into();
     page_active_model.download_status = Set(status.into());
     page_active_model.path = Set(Some(final_video_path.to_string_lossy().to_string()));
+    // 重新下载过的文件需要覆盖旧的校验记录，未校验时清空，避免全库校验拿旧值比对
+    if video_refreshed {
+        let verified_at = verified_file.as_ref().map(|_| now_standard_string());
+        page_active_model.file_size = Set(verified_file.as_ref().map(|v| v.size as i64));
+        page_active_model.file_hash = Set(verified_file.and_then(|v| v.hash));
+        page_active_model.verified_at = Set(verified_at);
+    }
     Ok(page_active_model)
 }
 
+/// 校验转码或封装后的最终文件，失败时删除该文件并把视频子任务记为失败一次，交由重试流程重新下载
+async fn verify_final_file(
+    path: &Path,
+    video_model: &video::Model,
+    page_model: &page::Model,
+    status: &mut PageStatus,
+) -> Option<VerifiedFile> {
+    let verify_config = crate::config::reload_config().media_verify;
+    match verify_media_file(path, page_model.duration, &verify_config).await {
+        Ok(verified) => Some(verified),
+        Err(e) => {
+            error!(
+                "视频「{}」第 {} 页最终文件校验失败，将重新下载: {:#}",
+                &video_model.name, page_model.pid, e
+            );
+            let _ = fs::remove_file(path).await;
+            // 本轮开始前视频子任务必然未完成，在原失败次数上加一
+            let failures = PageStatus::from(page_model.download_status).get(1);
+            status.set(1, failures + 1);
+            None
+        }
+    }
+}
+
+fn page_cover_url<'a>(single_page: bool, video_model: &'a video::Model, page_model: &'a page::Model) -> &'a str {
+    if single_page {
+        // 单页视频直接用视频的封面
+        video_model.cover.as_str()
+    } else {
+        // 多页视频，如果单页没有封面，就使用视频的封面
+        match &page_model.image {
+            Some(url) => url.as_str(),
+            None => video_model.cover.as_str(),
+        }
+    }
+}
+
+/// 向刚下载完成的 mp4/m4a 写入标题、UP主、发布日期、简介、标签和封面，数据来源与 NFO 一致
+///
+/// 分段章节不受元数据开关控制，只要存在就写入；封面优先读取本地文件，仅音频模式跳过了封面文件时才单独下载；
+/// 多P视频的音轨编号同时写入总分页数；
+/// 写入失败只记录警告，不影响下载结果
+#[allow(clippy::too_many_arguments)]
+async fn embed_page_tags(
+    bili_client: &BiliClient,
+    video_model: &video::Model,
+    page_model: &page::Model,
+    chapters: &[Chapter],
+    video_path: &Path,
+    poster_path: &Path,
+    connection: &DatabaseConnection,
+    token: CancellationToken,
+) {
+    let config = crate::config::reload_config().metadata_embed;
+    if !config.enabled && chapters.is_empty() {
+        return;
+    }
+    let mut tags = MediaTags {
+        chapters: chapters
+            .iter()
+            .map(|chapter| (u64::from(chapter.from) * 1000, chapter.content.clone()))
+            .collect(),
+        ..Default::default()
+    };
+    if !config.enabled {
+        if let Err(e) = embed_tags(video_path, tags).await {
+            warn!(
+                "视频「{}」第 {} 页写入分段章节失败: {:#}",
+                &video_model.name, page_model.pid, e
+            );
+        }
+        return;
+    }
+    let movie = Movie::from(video_model);
+    tags.title = movie.name.to_string();
+    tags.artist = Some(decode_html_entities(movie.upper_name).to_string());
+    // 发布日期固定使用投稿时间，不跟随 NFO 的时间类型设置
+    tags.date = Some(video_model.pubtime.format("%Y-%m-%d").to_string());
+    tags.description = Some(movie.intro.to_string());
+    tags.genres = movie.tags.unwrap_or_default();
+    let single_page = video_model.single_page.unwrap_or(true);
+    if !single_page {
+        // 多P视频的分页以分页标题为标题，视频标题作为专辑
+        tags.album = Some(std::mem::replace(&mut tags.title, page_model.name.clone()));
+        let total = page::Entity::find()
+            .filter(page::Column::VideoId.eq(video_model.id))
+            .count(connection)
+            .await
+            .unwrap_or_default();
+        let total = u16::try_from(total).unwrap_or_default();
+        tags.track = u16::try_from(page_model.pid).ok().map(|pid| (pid, total));
+    }
+    if config.embed_cover {
+        let cover = async {
+            if poster_path.exists() {
+                return Ok(fs::read(poster_path).await?);
+            }
+            let url = page_cover_url(single_page, video_model, page_model);
+            let response = bili_client.get(url, token).await?.error_for_status()?;
+            Ok::<_, anyhow::Error>(response.bytes().await?.to_vec())
+        };
+        match cover.await {
+            Ok(cover) => tags.cover = Some(cover),
+            Err(e) => warn!(
+                "视频「{}」第 {} 页下载封面失败，仅写入文字标签: {:#}",
+                &video_model.name, page_model.pid, e
+            ),
+        }
+    }
+    if let Err(e) = embed_tags(video_path, tags).await {
+        warn!(
+            "视频「{}」第 {} 页写入元数据失败: {:#}",
+            &video_model.name, page_model.pid, e
+        );
+    }
+}
+
+/// 生成进度条预览图，优先使用 B 站的视频快照，没有快照或配置为本地生成时用 ffmpeg 从视频中截取
+async fn generate_page_trickplay(
+    should_run: bool,
+    bili_client: &BiliClient,
+    video_model: &video::Model,
+    page_info: &PageInfo,
+    video_path: &Path,
+    token: CancellationToken,
+) -> Result<ExecutionStatus> {
+    if !should_run {
+        return Ok(ExecutionStatus::Skipped);
+    }
+    let config = crate::config::reload_config().trickplay;
+    let shot = if config.source == TrickplaySource::Bilibili {
+        match Video::new(bili_client, video_model.bvid.clone())
+            .get_videoshot(page_info)
+            .await
+        {
+            Ok(shot) => shot,
+            Err(e) => {
+                warn!(
+                    "视频「{}」获取视频快照失败，改为本地生成预览图: {:#}",
+                    &video_model.name, e
+                );
+                None
+            }
+        }
+    } else {
+        None
+    };
+    let source = match &shot {
+        Some(shot) => {
+            let mut sheets = Vec::with_capacity(shot.image.len());
+            for url in &shot.image {
+                let response = bili_client.get(url, token.clone()).await?.error_for_status()?;
+                sheets.push(response.bytes().await?.to_vec());
+            }
+            FrameSource::Sprites { shot, sheets }
+        }
+        None => {
+            // 重新执行预览图任务时传入的是数据库记录的路径，已封装的 mkv 或接管的文件都直接从这里截取
+            if !video_path.exists() {
+                bail!("视频文件不存在，无法生成预览图: {}", video_path.display());
+            }
+            FrameSource::Video(video_path)
+        }
+    };
+    let output = generate_trickplay(&config, source, page_info.duration, video_path).await?;
+    debug!("已生成预览图: {}", output.display());
+    Ok(ExecutionStatus::Succeeded)
+}
+
+/// 将分页的视频与同目录下的各语言字幕、弹幕和封面封装为 mkv，成功后删除 mp4 和已封装的字幕文件
+///
+/// 外挂文件的命名与 `download_page` 一致：`{名称}.{语言}.srt`、`{名称}.zh-CN.default.ass`、`{名称}-thumb.jpg`
+async fn mux_page_mkv(video_path: &Path) -> Result<PathBuf> {
+    let parent = video_path.parent().context("视频路径没有上级目录")?;
+    let base_name = video_path
+        .file_stem()
+        .context("视频路径没有文件名")?
+        .to_string_lossy()
+        .into_owned();
+
+    let mut subtitles = Vec::new();
+    let prefix = format!("{}.", base_name);
+    let mut entries = fs::read_dir(parent).await?;
+    while let Some(entry) = entries.next_entry().await? {
+        let name = entry.file_name().to_string_lossy().into_owned();
+        let Some(lan) = name.strip_prefix(&prefix).and_then(|rest| rest.strip_suffix(".srt")) else {
+            continue;
+        };
+        if !lan.is_empty() && !lan.contains('.') {
+            subtitles.push((lan.to_string(), entry.path()));
+        }
+    }
+    // 人工字幕排在 AI 字幕之前，第一条字幕会成为默认轨道
+    subtitles.sort_by_key(|(lan, _)| (lan.starts_with("ai-"), lan.clone()));
+
+    let danmaku = parent.join(format!("{}.zh-CN.default.ass", base_name));
+    let cover = parent.join(format!("{}-thumb.jpg", base_name));
+    let inputs = MkvInputs {
+        video: video_path.to_path_buf(),
+        subtitles,
+        danmaku: danmaku.exists().then_some(danmaku),
+        cover: cover.exists().then_some(cover),
+    };
+    let mkv_path = video_path.with_extension("mkv");
+    mux_mkv(&inputs, &mkv_path).await?;
+
+    let embedded = inputs.subtitles.iter().map(|(_, path)| path).chain(&inputs.danmaku);
+    for path in std::iter::once(&inputs.video).chain(embedded) {
+        if let Err(e) = fs::remove_file(path).await {
+            warn!("删除已封装进 mkv 的文件 {} 失败: {:#}", path.display(), e);
+        }
+    }
+    Ok(mkv_path)
+}
+
 pub async fn fetch_page_poster(
     should_run: bool,
     video_model: &video::Model,
@@ -4177,17 +4673,7 @@ pub async fn fetch_page_poster(
         return Ok(ExecutionStatus::Skipped);
     }
     let single_page = video_model.single_page.context("single_page is null")?;
-    let url = if single_page {
-        // 单页视频直接用视频的封面
-        video_model.cover.as_str()
-    } else {
-        // 多页视频，如果单页没有封面，就使用视频的封面
-        match &page_model.image {
-            Some(url) => url.as_str(),
-            None => video_model.cover.as_str(),
-        }
-    };
-    let urls = vec![url];
+    let urls = vec![page_cover_url(single_page, video_model, page_model)];
     tokio::select! {
         biased;
         _ = token.cancelled() => return Ok(ExecutionStatus::Cancelled),
@@ -4203,11 +4689,17 @@ pub async fn fetch_page_poster(
 /// 下载单个流文件并返回文件大小（使用UnifiedDownloader智能选择下载方式）
 ///
 /// 同时会把本次下载的 bytes 与耗时写入内存「入库事件」统计，用于首页展示平均下载速度。
-async fn download_stream(downloader: &UnifiedDownloader, video_id: i32, urls: &[&str], path: &Path) -> Result<u64> {
+async fn download_stream(
+    downloader: &UnifiedDownloader,
+    video_id: i32,
+    urls: &[&str],
+    path: &Path,
+    throttle: &Throttle,
+) -> Result<u64> {
     // 直接使用UnifiedDownloader，它会智能选择aria2或原生下载器
     // aria2本身就支持多线程，原生下载器作为备选方案使用单线程
     let start = std::time::Instant::now();
-    let download_result = downloader.fetch_with_fallback(urls, path).await;
+    let download_result = downloader.fetch_with_throttle(urls, path, throttle).await;
 
     match download_result {
         Ok(_) => {
@@ -4256,6 +4748,7 @@ pub async fn fetch_page_video(
     page_info: &PageInfo,
     page_path: &Path,
     audio_only: bool,
+    throttle: &Throttle,
     token: CancellationToken,
 ) -> Result<ExecutionStatus> {
     if !should_run {
@@ -4411,7 +4904,7 @@ pub async fn fetch_page_video(
                 // 混合流无法提取纯音频，警告并使用混合流
                 warn!("混合流不支持纯音频提取，将下载完整内容");
                 let urls = mix_stream.urls();
-                download_stream(downloader, video_model.id, &urls, page_path).await?
+                download_stream(downloader, video_model.id, &urls, page_path, throttle).await?
             }
             BestStream::VideoAudio {
                 audio: Some(audio_stream),
@@ -4419,7 +4912,7 @@ pub async fn fetch_page_video(
             } => {
                 // 直接下载音频流
                 let audio_urls = audio_stream.urls();
-                download_stream(downloader, video_model.id, &audio_urls, page_path).await?
+                download_stream(downloader, video_model.id, &audio_urls, page_path, throttle).await?
             }
             BestStream::VideoAudio {
                 audio: None,
@@ -4428,7 +4921,7 @@ pub async fn fetch_page_video(
                 // 没有独立音频流，警告并使用视频流（可能包含音频）
                 warn!("未找到独立音频流，将下载视频流");
                 let urls = video_stream.urls();
-                download_stream(downloader, video_model.id, &urls, page_path).await?
+                download_stream(downloader, video_model.id, &urls, page_path, throttle).await?
             }
         }
     } else {
@@ -4440,7 +4933,8 @@ pub async fn fetch_page_video(
                     crate::bilibili::Stream::Flv(_) => {
                         let tmp_mix_path = page_path.with_extension("tmp_flv");
                         let urls = mix_stream.urls();
-                        let downloaded_size = download_stream(downloader, video_model.id, &urls, &tmp_mix_path).await?;
+                        let downloaded_size =
+                            download_stream(downloader, video_model.id, &urls, &tmp_mix_path, throttle).await?;
 
                         match crate::downloader::remux_with_ffmpeg(&tmp_mix_path, page_path).await {
                             Ok(()) => {
@@ -4463,7 +4957,7 @@ pub async fn fetch_page_video(
                     }
                     _ => {
                         let urls = mix_stream.urls();
-                        download_stream(downloader, video_model.id, &urls, page_path).await?
+                        download_stream(downloader, video_model.id, &urls, page_path, throttle).await?
                     }
                 }
             }
@@ -4472,7 +4966,7 @@ pub async fn fetch_page_video(
                 audio: None,
             } => {
                 let urls = video_stream.urls();
-                download_stream(downloader, video_model.id, &urls, page_path).await?
+                download_stream(downloader, video_model.id, &urls, page_path, throttle).await?
             }
             BestStream::VideoAudio {
                 video: video_stream,
@@ -4484,7 +4978,7 @@ pub async fn fetch_page_video(
                 );
 
                 let video_urls = video_stream.urls();
-                let video_size = download_stream(downloader, video_model.id, &video_urls, &tmp_video_path)
+                let video_size = download_stream(downloader, video_model.id, &video_urls, &tmp_video_path, throttle)
                     .await
                     .map_err(|e| {
                         // 使用错误分类器进行统一处理
@@ -4501,7 +4995,7 @@ pub async fn fetch_page_video(
                     })?;
 
                 let audio_urls = audio_stream.urls();
-                let audio_size = download_stream(downloader, video_model.id, &audio_urls, &tmp_audio_path)
+                let audio_size = download_stream(downloader, video_model.id, &audio_urls, &tmp_audio_path, throttle)
                     .await
                     .map_err(|e| {
                         // 使用错误分类器进行统一处理
@@ -4657,25 +5151,31 @@ pub async fn fetch_page_subtitle(
     should_run: bool,
     bili_client: &BiliClient,
     video_model: &video::Model,
-    page_info: &PageInfo,
+    player_info: Option<&Result<PlayerInfo>>,
     subtitle_path: &Path,
     token: CancellationToken,
 ) -> Result<ExecutionStatus> {
     if !should_run {
         return Ok(ExecutionStatus::Skipped);
     }
+    let player_info = match player_info {
+        Some(Ok(player_info)) => player_info,
+        Some(Err(e)) => bail!("获取播放器信息失败: {:#}", e),
+        None => bail!("分页CID无效，无法获取字幕"),
+    };
     let bili_video = Video::new(bili_client, video_model.bvid.clone());
     let subtitles = tokio::select! {
         biased;
         _ = token.cancelled() => return Err(anyhow!("Download cancelled")),
-        res = bili_video.get_subtitles(page_info) => res?,
+        res = bili_video.get_subtitles(player_info) => res?,
     };
     let tasks = subtitles
         .into_iter()
         .map(|subtitle| async move {
             let path = subtitle_path.with_extension(format!("{}.srt", subtitle.lan));
-            ensure_parent_dir_for_file(&path).await.map_err(std::io::Error::other)?;
-            tokio::fs::write(path, subtitle.body.to_string()).await
+            write_atomic(&path, subtitle.body.to_string())
+                .await
+                .map_err(std::io::Error::other)
         })
         .collect::<FuturesUnordered<_>>();
     tasks.try_collect::<Vec<()>>().await?;
@@ -4686,6 +5186,8 @@ pub async fn generate_page_nfo(
     should_run: bool,
     video_model: &video::Model,
     page_model: &page::Model,
+    chapters: &[Chapter],
+    collection_set: Option<CollectionSet>,
     nfo_path: PathBuf,
     _connection: &DatabaseConnection,
 ) -> Result<ExecutionStatus> {
@@ -4702,6 +5204,8 @@ pub async fn generate_page_nfo(
                     // 番剧单页或合集视频应使用Episode格式，符合Emby标准
                     use crate::utils::nfo::Episode;
                     let mut episode = Episode::from_video_and_page(video_model, page_model);
+                    episode.chapters = chapters;
+                    episode.collection_set = collection_set;
                     // 对于合集视频，如果数据库中尚未带有 episode_number，按合集顺序编号
                     if video_model.collection_id.is_some() && video_model.episode_number.is_none() {
                         if let Some(col_id) = video_model.collection_id {
@@ -4714,19 +5218,23 @@ pub async fn generate_page_nfo(
                     }
                     NFO::Episode(episode)
                 } else {
-                    // 普通单页视频生成Movie
-                    use crate::utils::nfo::Movie;
-                    NFO::Movie(Movie::from_video_with_pages(video_model, &[page_model.clone()]))
+                    // 普通单页视频：按输出方案生成Movie或Episode
+                    let profile = crate::config::with_config(|bundle| bundle.config.nfo_config.profile);
+                    NFO::single_page(video_model, page_model, chapters, collection_set, profile)
                 }
             } else {
                 use crate::utils::nfo::Episode;
-                let episode = Episode::from_video_and_page(video_model, page_model);
+                let mut episode = Episode::from_video_and_page(video_model, page_model);
+                episode.chapters = chapters;
+                episode.collection_set = collection_set;
                 NFO::Episode(episode)
             }
         }
         None => {
             use crate::utils::nfo::Episode;
             let mut episode = Episode::from_video_and_page(video_model, page_model);
+            episode.chapters = chapters;
+            episode.collection_set = collection_set;
             // 非番剧但属于合集的视频：按合集顺序编号，避免固定为1
             if video_model.category != 1 {
                 if let Some(col_id) = video_model.collection_id {
@@ -5023,12 +5531,15 @@ pub async fn fetch_bangumi_poster(
 pub async fn generate_video_nfo(
     should_run: bool,
     video_model: &video::Model,
+    collection_set: Option<CollectionSet>,
     nfo_path: PathBuf,
 ) -> Result<ExecutionStatus> {
     if !should_run {
         return Ok(ExecutionStatus::Skipped);
     }
-    generate_nfo(NFO::TVShow(video_model.into()), nfo_path).await?;
+    let mut tvshow = TVShow::from(video_model);
+    tvshow.collection_set = collection_set;
+    generate_nfo(NFO::TVShow(tvshow), nfo_path).await?;
     Ok(ExecutionStatus::Succeeded)
 }
 
@@ -5038,13 +5549,14 @@ pub async fn generate_collection_video_nfo(
     video_model: &video::Model,
     collection_name: Option<&str>,
     collection_cover: Option<&str>,
+    collection_set: Option<CollectionSet>,
     nfo_path: PathBuf,
 ) -> Result<ExecutionStatus> {
     if !should_run {
         return Ok(ExecutionStatus::Skipped);
     }
-    use crate::utils::nfo::TVShow;
-    let tvshow = TVShow::from_video_with_collection(video_model, collection_name, collection_cover);
+    let mut tvshow = TVShow::from_video_with_collection(video_model, collection_name, collection_cover);
+    tvshow.collection_set = collection_set;
     generate_nfo(NFO::TVShow(tvshow), nfo_path).await?;
     Ok(ExecutionStatus::Succeeded)
 }
@@ -5107,7 +5619,8 @@ async fn ensure_parent_dir_for_file(file_path: &std::path::Path) -> Result<()> {
 async fn generate_nfo(nfo: NFO<'_>, nfo_path: PathBuf) -> Result<()> {
     // 只在实际写入NFO文件时才创建父目录
     ensure_parent_dir_for_file(&nfo_path).await?;
-    fs::write(nfo_path, nfo.generate_nfo().await?.as_bytes()).await?;
+    // 先写临时文件再重命名，避免中断时留下半截的 NFO
+    write_atomic(&nfo_path, nfo.generate_nfo().await?.as_bytes()).await?;
     Ok(())
 }
 
@@ -5967,11 +6480,11 @@ pub async fn auto_reset_risk_control_failures(connection: &DatabaseConnection) -
         let mut page_resetted = false;
 
         // 检查是否为完全成功的状态（所有任务都是1）
-        let is_fully_completed = (0..5).all(|task_index| page_status.get(task_index) == 1);
+        let is_fully_completed = (0..6).all(|task_index| page_status.get(task_index) == 1);
 
         if !is_fully_completed {
             // 如果不是完全成功，检查所有任务索引，将失败状态(3)、正在进行状态(2)和未开始状态(0)重置为未开始(0)
-            for task_index in 0..5 {
+            for task_index in 0..6 {
                 let status_value = page_status.get(task_index);
                 if status_value == 3 || status_value == 2 || status_value == 0 {
                     page_status.set(task_index, 0); // 重置为未开始
@@ -6762,6 +7275,7 @@ fn is_same_video_folder(folder_path: &std::path::Path, video_model: &video::Mode
             if file_name_str.contains(&video_model.bvid.to_lowercase()) {
                 if file_name_str.ends_with(".tmp_video")
                     || file_name_str.ends_with(".tmp_audio")
+                    || file_name_str.ends_with(".part")
                     || file_name_str.ends_with(".mp4")
                     || file_name_str.ends_with(".mkv")
                     || file_name_str.ends_with(".flv")
@@ -6908,10 +7422,50 @@ pub fn generate_unique_folder_name(
 
 #[cfg(test)]
 mod tests {
+    use std::path::Path;
+
+    use bili_sync_entity::page;
     use handlebars::handlebars_helper;
     use serde_json::json;
 
+    use crate::adapter::VideoSourceEnum;
     use crate::config::PathSafeTemplate;
+    use crate::test_utils::{rerun_downloaded_test_page, test_watch_later_source};
+    use crate::utils::status::{PageStatus, STATUS_OK};
+
+    /// 以只缺 NFO 的状态重新处理已下载完成的分页，返回写回数据库的分页
+    async fn rerun_page_missing_nfo(source: &VideoSourceEnum, media: &Path) -> page::ActiveModel {
+        let status = PageStatus::from([STATUS_OK, STATUS_OK, 0, STATUS_OK, STATUS_OK, STATUS_OK]);
+        rerun_downloaded_test_page(source, media, status).await
+    }
+
+    #[tokio::test]
+    async fn test_rerun_muxed_page_keeps_mkv_path() {
+        let dir = std::env::temp_dir().join(format!("bili-sync-rerun-mkv-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let media = dir.join("视频.mkv");
+        std::fs::write(&media, b"").unwrap();
+
+        // 封装后 mp4 已删除，重新执行其它子任务时路径仍指向 mkv
+        let updated = rerun_page_missing_nfo(&test_watch_later_source(false, "mkv"), &media).await;
+        assert_eq!(updated.path.unwrap(), Some(media.to_string_lossy().to_string()));
+        assert!(dir.join("视频.nfo").exists());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_rerun_transcoded_page_keeps_audio_path() {
+        let dir = std::env::temp_dir().join(format!("bili-sync-rerun-audio-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let media = dir.join("音频.mp3");
+        std::fs::write(&media, b"").unwrap();
+
+        // 转码后原始 m4a 已删除，重新执行其它子任务时路径仍指向转码结果
+        let updated = rerun_page_missing_nfo(&test_watch_later_source(true, "mp4"), &media).await;
+        assert_eq!(updated.path.unwrap(), Some(media.to_string_lossy().to_string()));
+        assert!(dir.join("音频.nfo").exists());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
 
     #[test]
     fn test_template_usage() {
@@ -21,6 +21,18 @@ pub struct Model {
     /// 是否已被 AI 重命名
     #[sea_orm(default_value = "0")]
     pub ai_renamed: Option<i32>,
+    /// 校验通过时记录的文件大小（字节）
+    pub file_size: Option<i64>,
+    /// 校验通过时记录的文件哈希（md5）
+    pub file_hash: Option<String>,
+    /// 最近一次校验通过的时间
+    pub verified_at: Option<String>,
+    /// 后处理任务状态，取值见 `PostProcessStatus`
+    pub postprocess_status: i32,
+    /// 后处理失败时的错误信息
+    pub postprocess_error: Option<String>,
+    /// 后处理保留原文件时另存的兼容副本路径
+    pub postprocess_copy_path: Option<String>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
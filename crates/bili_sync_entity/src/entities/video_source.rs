@@ -43,6 +43,22 @@ pub struct Model {
     pub flat_folder: bool,
     pub download_danmaku: bool,
     pub download_subtitle: bool,
+    /// 该视频源的下载限速（KB/s），0 表示不单独限速
+    pub download_speed_limit: i32,
+    /// 下载优先级，数值越大越先扫描和下载
+    pub priority: i32,
+    /// 下载完成后使用的后处理配置名称，空字符串表示不处理
+    pub postprocess_profile: String,
+    /// 输出容器格式：mp4，或将字幕、弹幕和封面一并封装的 mkv
+    pub output_container: String,
+    /// 仅音频模式的输出格式：m4a（保留原始音频）、mp3、opus 或 flac
+    pub audio_format: String,
+    /// 仅音频模式是否进行 EBU R128 响度标准化
+    pub audio_normalize: bool,
+    /// 播客订阅的访问令牌，未生成时为空
+    pub feed_token: Option<String>,
+    /// 是否在 WebDAV 目录中显示
+    pub webdav_visible: bool,
     pub ai_rename: bool,
     pub ai_rename_video_prompt: String,
     pub ai_rename_audio_prompt: String,
@@ -52,6 +52,16 @@ mod m20260125_000001_migrate_legacy_config;
 mod m20260125_000002_add_use_dynamic_api;
 mod m20260125_000003_add_dynamic_api_full_synced;
 mod m20260127_000001_add_submission_scan_state;
+mod m20261016_000001_add_page_verification;
+mod m20261016_000002_add_download_speed_limit;
+mod m20261016_000003_add_download_priority;
+mod m20261017_000001_add_postprocess;
+mod m20261017_000002_add_output_container;
+mod m20261017_000003_add_audio_output;
+mod m20261017_000004_add_feed_token;
+mod m20261017_000005_add_webdav_visible;
+mod m20261017_000006_add_source_overview;
+mod m20261017_000007_add_postprocess_copy_path;
 
 pub struct Migrator;
 
@@ -111,6 +121,16 @@ impl MigratorTrait for Migrator {
             Box::new(m20260125_000002_add_use_dynamic_api::Migration),
             Box::new(m20260125_000003_add_dynamic_api_full_synced::Migration),
             Box::new(m20260127_000001_add_submission_scan_state::Migration),
+            Box::new(m20261016_000001_add_page_verification::Migration),
+            Box::new(m20261016_000002_add_download_speed_limit::Migration),
+            Box::new(m20261016_000003_add_download_priority::Migration),
+            Box::new(m20261017_000001_add_postprocess::Migration),
+            Box::new(m20261017_000002_add_output_container::Migration),
+            Box::new(m20261017_000003_add_audio_output::Migration),
+            Box::new(m20261017_000004_add_feed_token::Migration),
+            Box::new(m20261017_000005_add_webdav_visible::Migration),
+            Box::new(m20261017_000006_add_source_overview::Migration),
+            Box::new(m20261017_000007_add_postprocess_copy_path::Migration),
         ]
     }
 }
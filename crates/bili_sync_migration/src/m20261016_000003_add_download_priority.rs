@@ -0,0 +1,103 @@
+use sea_orm::Statement;
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+/// 需要添加优先级字段的表（视频表与各视频源表）
+fn priority_tables() -> Vec<(DynIden, &'static str)> {
+    vec![
+        (Video::Table.into_iden(), "video"),
+        (Collection::Table.into_iden(), "collection"),
+        (Favorite::Table.into_iden(), "favorite"),
+        (Submission::Table.into_iden(), "submission"),
+        (WatchLater::Table.into_iden(), "watch_later"),
+        (VideoSource::Table.into_iden(), "video_source"),
+    ]
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        for (table, table_name) in priority_tables() {
+            if !table_has_column(manager, table_name, "priority").await? {
+                manager
+                    .alter_table(
+                        Table::alter()
+                            .table(table)
+                            .add_column(ColumnDef::new(PriorityColumn::Priority).integer().not_null().default(0))
+                            .to_owned(),
+                    )
+                    .await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        for (table, table_name) in priority_tables() {
+            if table_has_column(manager, table_name, "priority").await? {
+                manager
+                    .alter_table(
+                        Table::alter()
+                            .table(table)
+                            .drop_column(PriorityColumn::Priority)
+                            .to_owned(),
+                    )
+                    .await?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum Video {
+    Table,
+}
+
+#[derive(DeriveIden)]
+enum Collection {
+    Table,
+}
+
+#[derive(DeriveIden)]
+enum Favorite {
+    Table,
+}
+
+#[derive(DeriveIden)]
+enum Submission {
+    Table,
+}
+
+#[derive(DeriveIden)]
+enum WatchLater {
+    Table,
+}
+
+#[derive(DeriveIden)]
+enum VideoSource {
+    Table,
+}
+
+#[derive(DeriveIden)]
+enum PriorityColumn {
+    Priority,
+}
+
+async fn table_has_column(manager: &SchemaManager<'_>, table: &str, column: &str) -> Result<bool, DbErr> {
+    let backend = manager.get_connection().get_database_backend();
+    let sql = format!(
+        "SELECT COUNT(*) FROM pragma_table_info('{}') WHERE name = '{}'",
+        table.replace('\'', "''"),
+        column.replace('\'', "''")
+    );
+    let result = manager
+        .get_connection()
+        .query_one(Statement::from_string(backend, sql))
+        .await?;
+    Ok(result.and_then(|row| row.try_get_by_index(0).ok()).unwrap_or(0) >= 1)
+}
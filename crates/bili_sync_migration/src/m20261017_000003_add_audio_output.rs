@@ -0,0 +1,118 @@
+use sea_orm::Statement;
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+/// 需要添加音频输出字段的视频源表
+fn source_tables() -> Vec<(DynIden, &'static str)> {
+    vec![
+        (Collection::Table.into_iden(), "collection"),
+        (Favorite::Table.into_iden(), "favorite"),
+        (Submission::Table.into_iden(), "submission"),
+        (WatchLater::Table.into_iden(), "watch_later"),
+        (VideoSource::Table.into_iden(), "video_source"),
+    ]
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        for (table, table_name) in source_tables() {
+            if !table_has_column(manager, table_name, "audio_format").await? {
+                manager
+                    .alter_table(
+                        Table::alter()
+                            .table(table.clone())
+                            .add_column(
+                                ColumnDef::new(SourceColumn::AudioFormat)
+                                    .string()
+                                    .not_null()
+                                    .default("m4a"),
+                            )
+                            .to_owned(),
+                    )
+                    .await?;
+            }
+            if !table_has_column(manager, table_name, "audio_normalize").await? {
+                manager
+                    .alter_table(
+                        Table::alter()
+                            .table(table)
+                            .add_column(
+                                ColumnDef::new(SourceColumn::AudioNormalize)
+                                    .boolean()
+                                    .not_null()
+                                    .default(false),
+                            )
+                            .to_owned(),
+                    )
+                    .await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        for (table, table_name) in source_tables() {
+            for (column, column_name) in [
+                (SourceColumn::AudioFormat, "audio_format"),
+                (SourceColumn::AudioNormalize, "audio_normalize"),
+            ] {
+                if table_has_column(manager, table_name, column_name).await? {
+                    manager
+                        .alter_table(Table::alter().table(table.clone()).drop_column(column).to_owned())
+                        .await?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum Collection {
+    Table,
+}
+
+#[derive(DeriveIden)]
+enum Favorite {
+    Table,
+}
+
+#[derive(DeriveIden)]
+enum Submission {
+    Table,
+}
+
+#[derive(DeriveIden)]
+enum WatchLater {
+    Table,
+}
+
+#[derive(DeriveIden)]
+enum VideoSource {
+    Table,
+}
+
+#[derive(DeriveIden)]
+enum SourceColumn {
+    AudioFormat,
+    AudioNormalize,
+}
+
+async fn table_has_column(manager: &SchemaManager<'_>, table: &str, column: &str) -> Result<bool, DbErr> {
+    let backend = manager.get_connection().get_database_backend();
+    let sql = format!(
+        "SELECT COUNT(*) FROM pragma_table_info('{}') WHERE name = '{}'",
+        table.replace('\'', "''"),
+        column.replace('\'', "''")
+    );
+    let result = manager
+        .get_connection()
+        .query_one(Statement::from_string(backend, sql))
+        .await?;
+    Ok(result.and_then(|row| row.try_get_by_index(0).ok()).unwrap_or(0) >= 1)
+}
@@ -0,0 +1,92 @@
+use sea_orm::Statement;
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+/// 需要添加的字段：(表, 表名, 字段, 字段名)
+fn source_columns() -> Vec<(DynIden, &'static str, DynIden, &'static str)> {
+    vec![
+        (
+            Favorite::Table.into_iden(),
+            "favorite",
+            SourceColumn::Description.into_iden(),
+            "description",
+        ),
+        (
+            Favorite::Table.into_iden(),
+            "favorite",
+            SourceColumn::Cover.into_iden(),
+            "cover",
+        ),
+        (
+            Collection::Table.into_iden(),
+            "collection",
+            SourceColumn::Description.into_iden(),
+            "description",
+        ),
+    ]
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // 收藏夹和合集的简介、收藏夹封面，用于生成媒体服务器的合集信息
+        for (table, table_name, column, column_name) in source_columns() {
+            if !table_has_column(manager, table_name, column_name).await? {
+                manager
+                    .alter_table(
+                        Table::alter()
+                            .table(table)
+                            .add_column(ColumnDef::new(column).text().null())
+                            .to_owned(),
+                    )
+                    .await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        for (table, table_name, column, column_name) in source_columns() {
+            if table_has_column(manager, table_name, column_name).await? {
+                manager
+                    .alter_table(Table::alter().table(table).drop_column(column).to_owned())
+                    .await?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum Collection {
+    Table,
+}
+
+#[derive(DeriveIden)]
+enum Favorite {
+    Table,
+}
+
+#[derive(DeriveIden)]
+enum SourceColumn {
+    Description,
+    Cover,
+}
+
+async fn table_has_column(manager: &SchemaManager<'_>, table: &str, column: &str) -> Result<bool, DbErr> {
+    let backend = manager.get_connection().get_database_backend();
+    let sql = format!(
+        "SELECT COUNT(*) FROM pragma_table_info('{}') WHERE name = '{}'",
+        table.replace('\'', "''"),
+        column.replace('\'', "''")
+    );
+    let result = manager
+        .get_connection()
+        .query_one(Statement::from_string(backend, sql))
+        .await?;
+    Ok(result.and_then(|row| row.try_get_by_index(0).ok()).unwrap_or(0) >= 1)
+}
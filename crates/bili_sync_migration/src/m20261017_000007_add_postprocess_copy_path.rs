@@ -0,0 +1,59 @@
+use sea_orm::Statement;
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // 后处理保留原文件时另存的兼容副本，重新处理或删除视频时据此清理
+        if !table_has_column(manager, "page", "postprocess_copy_path").await? {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(Page::Table)
+                        .add_column(ColumnDef::new(Page::PostprocessCopyPath).string().null())
+                        .to_owned(),
+                )
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        if table_has_column(manager, "page", "postprocess_copy_path").await? {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(Page::Table)
+                        .drop_column(Page::PostprocessCopyPath)
+                        .to_owned(),
+                )
+                .await?;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum Page {
+    Table,
+    PostprocessCopyPath,
+}
+
+async fn table_has_column(manager: &SchemaManager<'_>, table: &str, column: &str) -> Result<bool, DbErr> {
+    let backend = manager.get_connection().get_database_backend();
+    let sql = format!(
+        "SELECT COUNT(*) FROM pragma_table_info('{}') WHERE name = '{}'",
+        table.replace('\'', "''"),
+        column.replace('\'', "''")
+    );
+    let result = manager
+        .get_connection()
+        .query_one(Statement::from_string(backend, sql))
+        .await?;
+    Ok(result.and_then(|row| row.try_get_by_index(0).ok()).unwrap_or(0) >= 1)
+}
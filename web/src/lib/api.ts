@@ -26,6 +26,7 @@ import type {
 	UpdateVideoSourceEnabledResponse,
 	ResetVideoSourcePathRequest,
 	ResetVideoSourcePathResponse,
+	VideoSourceFeedResponse,
 	UpdateSubmissionSelectedVideosResponse,
 	UpdateKeywordFiltersResponse,
 	GetKeywordFiltersResponse,
@@ -46,7 +47,8 @@ import type {
 	VideoBvidResponse,
 	KeywordFilterMode,
 	LatestIngestResponse,
-	BetaImageUpdateStatusResponse
+	BetaImageUpdateStatusResponse,
+	CdnHealthReport
 } from './types';
 import { ErrorType } from './types';
 import { wsManager } from './ws';
@@ -415,6 +417,30 @@ class ApiClient {
 		);
 	}
 
+	/**
+	 * 获取视频源的播客订阅地址
+	 * @param sourceType 视频源类型
+	 * @param id 视频源ID
+	 */
+	async getVideoSourceFeed(
+		sourceType: string,
+		id: number
+	): Promise<ApiResponse<VideoSourceFeedResponse>> {
+		return this.get<VideoSourceFeedResponse>(`/video-sources/${sourceType}/${id}/feed`);
+	}
+
+	/**
+	 * 重新生成视频源的播客订阅令牌
+	 * @param sourceType 视频源类型
+	 * @param id 视频源ID
+	 */
+	async resetVideoSourceFeed(
+		sourceType: string,
+		id: number
+	): Promise<ApiResponse<VideoSourceFeedResponse>> {
+		return this.post<VideoSourceFeedResponse>(`/video-sources/${sourceType}/${id}/feed/reset`);
+	}
+
 	/**
 	 * 更新投稿源选中视频列表
 	 * @param id 投稿源ID
@@ -640,6 +666,20 @@ class ApiClient {
 		return this.get<QueueStatusResponse>('/queue-status');
 	}
 
+	/**
+	 * 获取 CDN 节点统计
+	 */
+	async getCdnStats(): Promise<ApiResponse<CdnHealthReport>> {
+		return this.get<CdnHealthReport>('/cdn/stats');
+	}
+
+	/**
+	 * 清空 CDN 节点统计并解除黑名单
+	 */
+	async resetCdnStats(): Promise<ApiResponse<CdnHealthReport>> {
+		return this.post<CdnHealthReport>('/cdn/stats/reset');
+	}
+
 	/**
 	 * 更新视频状态
 	 * @param id 视频ID
@@ -733,6 +773,17 @@ class ApiClient {
 		return `${this.baseURL}/videos/proxy-stream?url=${encodedUrl}${transmuxParam}`;
 	}
 
+	/**
+	 * 获取本地视频的 HLS 转码播放地址
+	 * @param videoId 视频或分页ID
+	 * @param codecs 浏览器可直接播放的视频编码，其余编码会被转码
+	 */
+	getHlsStreamUrl(videoId: string | number, codecs: string[]): string {
+		// 原生 HLS 播放器无法附加请求头，令牌通过查询参数传递
+		const token = this.defaultHeaders['Authorization'] ?? '';
+		return `${this.baseURL}/videos/stream/${videoId}/hls/master.m3u8?codecs=${codecs.join(',')}&token=${encodeURIComponent(token)}`;
+	}
+
 	/**
 	 * 获取UP主投稿列表
 	 * @param params 查询参数
@@ -1011,6 +1062,16 @@ export const api = {
 	 */
 	getQueueStatus: () => apiClient.getQueueStatus(),
 
+	/**
+	 * 获取 CDN 节点统计
+	 */
+	getCdnStats: () => apiClient.getCdnStats(),
+
+	/**
+	 * 清空 CDN 节点统计并解除黑名单
+	 */
+	resetCdnStats: () => apiClient.resetCdnStats(),
+
 	/**
 	 * 更新视频状态
 	 */
@@ -1057,6 +1118,18 @@ export const api = {
 	resetVideoSourcePath: (sourceType: string, id: number, params: ResetVideoSourcePathRequest) =>
 		apiClient.resetVideoSourcePath(sourceType, id, params),
 
+	/**
+	 * 获取视频源的播客订阅地址
+	 */
+	getVideoSourceFeed: (sourceType: string, id: number) =>
+		apiClient.getVideoSourceFeed(sourceType, id),
+
+	/**
+	 * 重新生成视频源的播客订阅令牌
+	 */
+	resetVideoSourceFeed: (sourceType: string, id: number) =>
+		apiClient.resetVideoSourceFeed(sourceType, id),
+
 	/**
 	 * 更新投稿源选中视频列表
 	 */
@@ -1176,6 +1249,12 @@ export const api = {
 	getProxyStreamUrl: (streamUrl: string, options?: { transmux?: boolean }) =>
 		apiClient.getProxyStreamUrl(streamUrl, options),
 
+	/**
+	 * 获取本地视频的 HLS 转码播放地址
+	 */
+	getHlsStreamUrl: (videoId: string | number, codecs: string[]) =>
+		apiClient.getHlsStreamUrl(videoId, codecs),
+
 	/**
 	 * 获取UP主投稿列表
 	 */
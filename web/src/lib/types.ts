@@ -102,8 +102,11 @@ export interface PageInfo {
 	id: number;
 	pid: number;
 	name: string;
-	download_status: [number, number, number, number, number];
+	download_status: [number, number, number, number, number, number];
 	path?: string;
+	// 后处理状态：0 无，1 排队中，2 处理中，3 完成，4 失败，5 无需处理
+	postprocess_status: number;
+	postprocess_error?: string;
 }
 
 // 单个视频响应类型
@@ -705,6 +708,13 @@ export interface ResetVideoSourcePathResponse {
 	message: string;
 }
 
+// 视频源播客订阅响应类型
+export interface VideoSourceFeedResponse {
+	source_id: number;
+	source_type: string;
+	feed_path: string;
+}
+
 // 更新投稿源选中视频列表响应类型
 export interface UpdateSubmissionSelectedVideosResponse {
 	success: boolean;
@@ -801,6 +811,7 @@ export interface VideoPlayInfoResponse {
 	video_streams: VideoStreamInfo[];
 	audio_streams: AudioStreamInfo[];
 	subtitle_streams: SubtitleStreamInfo[];
+	chapters: ChapterInfo[];
 	video_title: string;
 	video_duration?: number;
 	video_quality_description: string;
@@ -842,6 +853,13 @@ export interface SubtitleStreamInfo {
 	url: string;
 }
 
+// 分段章节信息类型（时间单位为秒）
+export interface ChapterInfo {
+	title: string;
+	start: number;
+	end: number;
+}
+
 // 验证收藏夹响应类型
 export interface ValidateFavoriteResponse {
 	valid: boolean;
@@ -902,6 +920,34 @@ export interface MonitoringStatus {
 	last_scan_time: string | null;
 	next_scan_time: string | null;
 	is_scanning: boolean;
+	schedule: ScheduleStatus | null;
+}
+
+// 时间窗口状态类型
+export interface ScheduleStatus {
+	scan_allowed: boolean;
+	download_allowed: boolean;
+	next_scan_window: string | null;
+	next_download_window: string | null;
+}
+
+// CDN 节点统计类型
+export interface CdnHostStats {
+	host: string;
+	successes: number;
+	failures: number;
+	error_rate: number;
+	throughput_kbps: number | null;
+	ttfb_ms: number | null;
+	score: number;
+	blacklisted: boolean;
+	blacklisted_until: string | null;
+	last_error: string | null;
+}
+
+export interface CdnHealthReport {
+	enabled: boolean;
+	hosts: CdnHostStats[];
 }
 
 // 每日视频计数类型